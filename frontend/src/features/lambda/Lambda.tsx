@@ -8,7 +8,9 @@ import {
   PublishRequest,
   SubscribeRequest,
 } from "redis-grpc/gen-js/redis_grpc_pb";
-import { LamabdaParamEntry, ParamType } from "./lambda.types";
+import { BlotterRow, FillRow, LamabdaParamEntry, ParamType } from "./lambda.types";
+import { signCommand } from "./signCommand";
+import { useTable } from "./useTable";
 import { entryValuePaser } from "./valueParser";
 
 interface Props {
@@ -16,6 +18,34 @@ interface Props {
   instance: string;
 }
 
+const orderColDefs: ColDef[] = [
+  { field: "market" },
+  { field: "side" },
+  { field: "type" },
+  { field: "price" },
+  { field: "size" },
+  { field: "filled_size" },
+  { field: "avg_fill_px" },
+  { field: "status" },
+  {
+    field: "updated_ms",
+    sort: "desc",
+    valueFormatter: (params) => new Date(params.value).toISOString(),
+  },
+  { field: "client_id" },
+];
+
+const fillColDefs: ColDef[] = [
+  { field: "time", sort: "desc" },
+  { field: "market" },
+  { field: "side" },
+  { field: "price" },
+  { field: "size" },
+  { field: "fee" },
+  { field: "liquidity" },
+  { field: "id" },
+];
+
 const Lambda = ({ host, instance }: Props) => {
   const [stateEntries, setStateEntries] = useState<LamabdaParamEntry[]>([]);
   const [paramEntries, setParamEntries] = useState<LamabdaParamEntry[]>([]);
@@ -59,14 +89,16 @@ const Lambda = ({ host, instance }: Props) => {
     };
   }, [host, instance]);
 
-  const updateParam = useCallback((entry: LamabdaParamEntry) => {
+  const updateParam = useCallback(async (entry: LamabdaParamEntry) => {
     const service = new RedisGrpcPromiseClient(host);
     const pub_request = new PublishRequest();
-    pub_request.setChannel(`UpdateParam:${instance}`);
-    pub_request.setMessage(JSON.stringify(entry));
+    const channel = `UpdateParam:${instance}`;
+    const command = await signCommand(channel, JSON.stringify(entry));
+    pub_request.setChannel(channel);
+    pub_request.setMessage(JSON.stringify(command));
     console.log(pub_request);
     service.publish(pub_request).then(console.log).catch(console.error);
-  }, []);
+  }, [host, instance]);
 
   const colDefs = useMemo((): ColDef[] => {
     return [
@@ -83,6 +115,9 @@ const Lambda = ({ host, instance }: Props) => {
 
   const getRowId = (entry: LamabdaParamEntry) => entry.key;
 
+  const orders = useTable<BlotterRow>(host, instance, "OrderBlotter", (row) => row.client_id);
+  const fills = useTable<FillRow>(host, instance, "Fills", (row) => row.id);
+
   const onCellEditingStopped = useCallback(
     (params: CellEditingStoppedEvent) => {
       console.log(params.data);
@@ -124,14 +159,32 @@ const Lambda = ({ host, instance }: Props) => {
           />
         </NavbarGroup>
       </Navbar>
-      <AgGridReact
-        immutableData
-        columnDefs={colDefs}
-        rowData={[...stateEntries, ...paramEntries]}
-        getRowNodeId={getRowId}
-        groupDefaultExpanded={1}
-        onCellEditingStopped={onCellEditingStopped}
-      />
+      <div style={{ flex: 2 }}>
+        <AgGridReact
+          immutableData
+          columnDefs={colDefs}
+          rowData={[...stateEntries, ...paramEntries]}
+          getRowNodeId={getRowId}
+          groupDefaultExpanded={1}
+          onCellEditingStopped={onCellEditingStopped}
+        />
+      </div>
+      <div style={{ flex: 1 }}>
+        <AgGridReact
+          immutableData
+          columnDefs={orderColDefs}
+          rowData={orders}
+          getRowNodeId={(row: BlotterRow) => row.client_id}
+        />
+      </div>
+      <div style={{ flex: 1 }}>
+        <AgGridReact
+          immutableData
+          columnDefs={fillColDefs}
+          rowData={fills}
+          getRowNodeId={(row: FillRow) => row.id}
+        />
+      </div>
     </>
   );
 };
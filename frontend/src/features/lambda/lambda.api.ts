@@ -1,10 +1,12 @@
 import ky from "ky";
 import { LamabdaParamEntry, ParamType } from "./lambda.types";
+import { apiKey } from "./signCommand";
 
 const LambdaApi = (baseURL: string) => {
   const client = ky.extend({
     prefixUrl: baseURL,
     timeout: 1000,
+    headers: { "x-api-key": apiKey() },
   });
 
   const getStates = () => client.get(`states`);
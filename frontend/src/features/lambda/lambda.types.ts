@@ -9,4 +9,43 @@ export type LamabdaParamEntry = {
   key: string,
   type: ParamType
   value: any,
-}
\ No newline at end of file
+}
+export type StatusChange = {
+  status: string,
+  time_ms: number,
+}
+
+export type BlotterRow = {
+  client_id: string,
+  exchange: string,
+  market: string,
+  side: string,
+  type: string,
+  price: number,
+  size: number,
+  filled_size: number,
+  avg_fill_px: number | null,
+  status: string,
+  updated_ms: number,
+  history: StatusChange[],
+}
+
+export type FillRow = {
+  id: string,
+  exchange: string,
+  market: string,
+  side: string,
+  price: number,
+  size: number,
+  fee: number,
+  liquidity: string,
+  time: string,
+}
+
+// rows changed since the previous update of a table, or every row when `snapshot`
+export type TableUpdate<T> = {
+  seq: number,
+  snapshot: boolean,
+  upsert: T[],
+  remove: string[],
+}
@@ -0,0 +1,31 @@
+export type SignedCommand = {
+  api_key: string,
+  time: number,
+  payload: string,
+  sign: string,
+}
+
+export const apiKey = () => localStorage.getItem("apiKey") ?? "";
+
+const apiSecret = () => localStorage.getItem("apiSecret") ?? "";
+
+const toHex = (buffer: ArrayBuffer) =>
+  Array.from(new Uint8Array(buffer))
+    .map((b) => b.toString(16).padStart(2, "0"))
+    .join("");
+
+// HMAC-SHA256 of `${channel}${time}${payload}` with the secret of the api key, checked by the
+// engine. The channel the command is published on is signed, so it is accepted on no other one.
+export const signCommand = async (channel: string, payload: string): Promise<SignedCommand> => {
+  const encoder = new TextEncoder();
+  const key = await crypto.subtle.importKey(
+    "raw",
+    encoder.encode(apiSecret()),
+    { name: "HMAC", hash: "SHA-256" },
+    false,
+    ["sign"]
+  );
+  const time = Date.now();
+  const sign = await crypto.subtle.sign("HMAC", key, encoder.encode(`${channel}${time}${payload}`));
+  return { api_key: apiKey(), time, payload, sign: toHex(sign) };
+};
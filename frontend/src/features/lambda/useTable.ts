@@ -0,0 +1,58 @@
+import { useEffect, useRef, useState } from "react";
+import { RedisGrpcPromiseClient } from "redis-grpc/gen-js/redis_grpc_grpc_web_pb";
+import {
+  PublishRequest,
+  SubscribeRequest,
+} from "redis-grpc/gen-js/redis_grpc_pb";
+import { TableUpdate } from "./lambda.types";
+
+// Rows of a blotter table of `instance`, kept from the `TableUpdate`s published on `channel`. A
+// snapshot is queried on subscribe and whenever a `seq` is missed.
+export const useTable = <T>(
+  host: string,
+  instance: string,
+  channel: string,
+  rowId: (row: T) => string
+): T[] => {
+  const [rows, setRows] = useState<Map<string, T>>(new Map());
+  const seq = useRef<number | undefined>(undefined);
+  const rowIdRef = useRef(rowId);
+  rowIdRef.current = rowId;
+
+  useEffect(() => {
+    setRows(new Map());
+    seq.current = undefined;
+
+    const service = new RedisGrpcPromiseClient(host);
+    const querySnapshot = () => {
+      const pub_request = new PublishRequest();
+      pub_request.setChannel(`QueryBlotter:${instance}`);
+      pub_request.setMessage("{}");
+      service.publish(pub_request).catch(console.error);
+    };
+
+    let sub_request = new SubscribeRequest();
+    sub_request.setChannelsList([`${channel}:${instance}`]);
+    let pubsub = service.subscribe(sub_request);
+    pubsub.on("data", (data) => {
+      const update: TableUpdate<T> = JSON.parse(data.getMessage());
+      if (!update.snapshot && (seq.current === undefined || update.seq !== seq.current + 1)) {
+        querySnapshot();
+      }
+      seq.current = update.seq;
+      setRows((previous) => {
+        const next = new Map(update.snapshot ? [] : previous);
+        update.upsert.forEach((row) => next.set(rowIdRef.current(row), row));
+        update.remove.forEach((id) => next.delete(id));
+        return next;
+      });
+    });
+    querySnapshot();
+
+    return () => {
+      pubsub.cancel();
+    };
+  }, [host, instance, channel]);
+
+  return Array.from(rows.values());
+};
@@ -0,0 +1,8 @@
+pub mod report;
+pub mod source;
+
+mod runner;
+
+pub use report::{BacktestReport, InventoryPoint, ReplayClock, ReportCollector};
+pub use runner::{Backtest, BacktestParams};
+pub use source::{load_sources, MarketDataSource};
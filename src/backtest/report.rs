@@ -0,0 +1,205 @@
+use crate::cache::OrderUpdateCache;
+use crate::core::clock::Clock;
+use crate::model::constants::{Exchanges, PublishChannel};
+use crate::model::market_data_model::MarketDepth;
+use crate::model::{OrderFill, OrderSide, OrderStatus};
+use crate::pubsub::simple_message_bus::{MessageConsumer, RedisBackedMessageBus};
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::time::Instant;
+
+/// Maps replayed event timestamps onto tokio time, which the backtest runs paused
+#[derive(Debug, Clone, Copy)]
+pub struct ReplayClock {
+    start: Instant,
+    start_time: i64,
+}
+impl ReplayClock {
+    pub fn new(start_time: i64) -> Self {
+        ReplayClock {
+            start: Instant::now(),
+            start_time,
+        }
+    }
+
+    pub fn instant_at(&self, time_ms: i64) -> Instant {
+        self.start + Duration::from_millis((time_ms - self.start_time).max(0) as u64)
+    }
+}
+#[async_trait]
+impl Clock for ReplayClock {
+    fn now_ns(&self) -> i64 {
+        self.start_time * 1_000_000 + self.start.elapsed().as_nanos() as i64
+    }
+
+    async fn sleep(&self, duration: Duration) {
+        tokio::time::sleep(duration).await
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct InventoryPoint {
+    pub time: i64,
+    pub market: String,
+    pub position: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct BacktestReport {
+    pub start_time: i64,
+    pub end_time: i64,
+    pub market_depth_count: usize,
+    pub order_count: usize,
+    pub cancel_count: usize,
+    pub modify_count: usize,
+    pub fills: Vec<OrderFill>,
+    pub fees: f64,
+    /// cash plus positions marked to the last mid price, net of fees
+    pub pnl: f64,
+    pub positions: HashMap<String, f64>,
+    pub inventory_path: Vec<InventoryPoint>,
+    /// share of samples with at least one open order, per market
+    pub quote_uptime: HashMap<String, f64>,
+}
+
+#[derive(Default)]
+struct CollectorState {
+    report: BacktestReport,
+    cash: f64,
+    marks: HashMap<String, f64>,
+    samples: usize,
+    quoted_samples: HashMap<String, usize>,
+}
+
+pub struct ReportCollector {
+    replay_clock: ReplayClock,
+    markets: Vec<String>,
+    state: Mutex<CollectorState>,
+}
+
+impl ReportCollector {
+    pub fn new(replay_clock: ReplayClock, markets: Vec<String>) -> Self {
+        let mut state = CollectorState::default();
+        state.report.start_time = replay_clock.now_ms();
+        ReportCollector {
+            replay_clock,
+            markets,
+            state: Mutex::new(state),
+        }
+    }
+
+    pub fn on_market_depth(&self, market_depth: &MarketDepth) {
+        let mut state = self.state.lock().unwrap();
+        state.report.market_depth_count += 1;
+        if let (Some(bid), Some(ask)) = (market_depth.bids.first(), market_depth.asks.first()) {
+            state
+                .marks
+                .insert(market_depth.market.clone(), (bid.price + ask.price) / 2.0);
+        }
+    }
+
+    /// count order requests leaving the lambda
+    pub fn on_publish(&self, channel: &str) {
+        let mut state = self.state.lock().unwrap();
+        match PublishChannel::from_str(channel) {
+            Ok(PublishChannel::OrderRequest) => state.report.order_count += 1,
+            Ok(PublishChannel::CancelOrder) => state.report.cancel_count += 1,
+            Ok(PublishChannel::ModifyOrder) => state.report.modify_count += 1,
+            _ => {}
+        }
+    }
+
+    fn on_fill(&self, order_fill: OrderFill) {
+        let mut state = self.state.lock().unwrap();
+        let (delta, cash) = match order_fill.side {
+            OrderSide::Buy => (order_fill.size, -order_fill.price * order_fill.size),
+            OrderSide::Sell => (-order_fill.size, order_fill.price * order_fill.size),
+        };
+        state.cash += cash;
+        state.report.fees += order_fill.fee;
+        let position = {
+            let position = state
+                .report
+                .positions
+                .entry(order_fill.market.clone())
+                .or_insert(0.0);
+            *position += delta;
+            *position
+        };
+        state.report.inventory_path.push(InventoryPoint {
+            time: self.replay_clock.now_ms(),
+            market: order_fill.market.clone(),
+            position,
+        });
+        state.report.fills.push(order_fill);
+    }
+
+    pub async fn subscribe_fills(&self) -> anyhow::Result<()> {
+        RedisBackedMessageBus::subscribe_channels(vec![PublishChannel::OrderFill.as_ref()], self)
+            .await
+    }
+
+    pub async fn sample_quotes(
+        &self,
+        order_update_cache: &OrderUpdateCache,
+        sample_interval_ms: u64,
+    ) -> anyhow::Result<()> {
+        loop {
+            let mut quoted = vec![];
+            for market in self.markets.iter() {
+                let has_open_order = order_update_cache.cache.iter().any(|order| {
+                    order.exchange == Exchanges::SIM
+                        && &order.market == market
+                        && matches!(order.status, OrderStatus::New | OrderStatus::Open)
+                });
+                if has_open_order {
+                    quoted.push(market.clone());
+                }
+            }
+            {
+                let mut state = self.state.lock().unwrap();
+                state.samples += 1;
+                for market in quoted {
+                    *state.quoted_samples.entry(market).or_insert(0) += 1;
+                }
+            }
+            tokio::time::sleep(Duration::from_millis(sample_interval_ms)).await;
+        }
+    }
+
+    pub fn report(&self) -> BacktestReport {
+        let state = self.state.lock().unwrap();
+        let mut report = state.report.clone();
+        report.end_time = self.replay_clock.now_ms();
+        let inventory_value: f64 = report
+            .positions
+            .iter()
+            .map(|(market, position)| position * state.marks.get(market).cloned().unwrap_or(0.0))
+            .sum();
+        report.pnl = state.cash + inventory_value - report.fees;
+        for market in self.markets.iter() {
+            let quoted_samples = state.quoted_samples.get(market).cloned().unwrap_or(0);
+            let uptime = if state.samples > 0 {
+                quoted_samples as f64 / state.samples as f64
+            } else {
+                0.0
+            };
+            report.quote_uptime.insert(market.clone(), uptime);
+        }
+        report
+    }
+}
+
+#[async_trait::async_trait]
+impl MessageConsumer for ReportCollector {
+    async fn consume(&self, msg: &[u8]) -> anyhow::Result<()> {
+        let order_fill = serde_json::from_slice::<OrderFill>(msg)?;
+        if order_fill.exchange == Exchanges::SIM {
+            self.on_fill(order_fill);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,206 @@
+use crate::backtest::{
+    load_sources, BacktestReport, MarketDataSource, ReplayClock, ReportCollector,
+};
+use crate::cache::{MarketDepthCache, OrderUpdateCache, ValueCache, ValueCacheKey};
+use crate::core::clock::SharedClock;
+use crate::core::kill_switch::KillSwitch;
+use crate::core::OrderGateway;
+use crate::lambda::strategy::{StrategyContext, StrategyRegistry, StrategyRunner};
+use crate::lambda::{GenericLambdaInstanceConfig, LambdaInstanceConfig, LambdaState};
+use crate::model::constants::{Exchanges, PublishChannel};
+use crate::model::market_data_model::MarketDepth;
+use crate::model::MeasurementCache;
+use crate::pubsub::local_message_bus::LocalMessageBus;
+use crate::pubsub::{PublishPayload, SubscribeMarketDepthRequest};
+use crate::sim::SimOrderGateway;
+use serde_json::Value;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// time for every component to subscribe before the first event is replayed
+const WARMUP_MS: i64 = 1000;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct BacktestParams {
+    /// lambda instance to run. every market depth of the instance has to be a SIM market
+    pub instance: String,
+    pub sources: Vec<MarketDataSource>,
+    pub sample_interval_ms: u64,
+    /// keep running after the last event so resting orders and hedges settle
+    pub tail_ms: u64,
+}
+impl Default for BacktestParams {
+    fn default() -> Self {
+        BacktestParams {
+            instance: "".to_string(),
+            sources: vec![],
+            sample_interval_ms: 100,
+            tail_ms: 1000,
+        }
+    }
+}
+
+/// Replays recorded market data into an unchanged strategy, matching its orders with the SIM
+/// matching engine. Message bus traffic stays in process through a `LocalMessageBus` of the run.
+///
+/// Every component runs on the `ReplayClock`, which is backed by tokio time, so `run` has to be
+/// called on a runtime with paused time (`start_paused = true`): tokio then advances the clock to
+/// the next timer whenever every task is idle and the replay runs as fast as the lambda can
+/// process it.
+pub struct Backtest {
+    params: BacktestParams,
+}
+
+impl Backtest {
+    pub fn new(params: BacktestParams) -> Self {
+        Backtest { params }
+    }
+
+    pub async fn run(&self) -> anyhow::Result<BacktestReport> {
+        let sources = self.params.sources.clone();
+        let market_depths =
+            tokio::task::spawn_blocking(move || load_sources(sources.as_slice())).await??;
+        let first_market_depth = market_depths
+            .first()
+            .ok_or_else(|| anyhow!("no market data to replay"))?;
+
+        let local_message_bus = LocalMessageBus::new();
+
+        let instance_config = GenericLambdaInstanceConfig::load(self.params.instance.as_str());
+        let market_depth_requests: Vec<SubscribeMarketDepthRequest> = instance_config
+            .lambda_params
+            .market_depths
+            .iter()
+            .map(|token| SubscribeMarketDepthRequest::from_token(token.as_str()))
+            .collect();
+        if market_depth_requests
+            .iter()
+            .any(|request| request.exchange != Exchanges::SIM)
+        {
+            return Err(anyhow!(
+                "backtest instance {} must only use SIM markets",
+                instance_config.name
+            ));
+        }
+        let markets: Vec<String> = market_depth_requests
+            .iter()
+            .map(|request| request.market.clone())
+            .collect();
+
+        let replay_clock = ReplayClock::new(first_market_depth.timestamp - WARMUP_MS);
+        let clock: SharedClock = Arc::new(replay_clock);
+
+        let market_depth_cache = Arc::new(MarketDepthCache::with_clock(clock.clone()));
+        let order_update_cache = Arc::new(OrderUpdateCache::new());
+        let (message_bus_sender, message_bus_receiver) =
+            tokio::sync::mpsc::channel::<PublishPayload>(1000);
+        // a backtest never reads nor overwrites the caches of the live instance in redis
+        let measurement_cache = Arc::new(MeasurementCache::in_memory(clock.clone()));
+        let value_cache = Arc::new(ValueCache::in_memory(instance_config.clone()));
+
+        let lambda_instance_config = LambdaInstanceConfig::load(instance_config.name.as_str());
+        let mut strategy_params = lambda_instance_config.strategy_params.clone();
+        strategy_params["state"] = Value::String(LambdaState::Live.to_string());
+        value_cache.insert(ValueCacheKey::StrategyParams, strategy_params);
+
+        let context = StrategyContext {
+            instance_config: instance_config.clone(),
+            market_depth_cache: market_depth_cache.clone(),
+            order_update_cache: order_update_cache.clone(),
+            message_bus_sender: message_bus_sender.clone(),
+            measurement_cache: measurement_cache.clone(),
+            value_cache: value_cache.clone(),
+            ftx_client: None,
+            clock: clock.clone(),
+        };
+        let strategy = StrategyRegistry::create(instance_config.registry.as_str(), &context)?;
+        let strategy_runner = StrategyRunner::new(strategy, context);
+
+        // the replay publishes SIM market depth itself
+        let mut sim_params = instance_config.lambda_params.sim.clone();
+        sim_params.source_exchange = Exchanges::SIM;
+        let sim_order_gateway = SimOrderGateway::new(
+            message_bus_sender.clone(),
+            sim_params,
+            markets.clone(),
+            Arc::new(KillSwitch::new()),
+            clock,
+        );
+
+        let collector = ReportCollector::new(replay_clock, markets);
+
+        let run = async {
+            tokio::select! {
+                result = self.replay(&local_message_bus, &market_depths, &collector, replay_clock) => {
+                    result?;
+                }
+                result = Self::route_messages(&local_message_bus, message_bus_receiver, &collector) => {
+                    return Err(anyhow!("backtest message routing completed: {:?}", result));
+                }
+                result = market_depth_cache.subscribe(&market_depth_requests) => {
+                    return Err(anyhow!("backtest market_depth_cache completed: {:?}", result));
+                }
+                result = order_update_cache.subscribe() => {
+                    return Err(anyhow!("backtest order_update_cache completed: {:?}", result));
+                }
+                result = sim_order_gateway.subscribe() => {
+                    return Err(anyhow!("backtest sim_order_gateway completed: {:?}", result));
+                }
+                result = collector.subscribe_fills() => {
+                    return Err(anyhow!("backtest report collector completed: {:?}", result));
+                }
+                result = collector.sample_quotes(&order_update_cache, self.params.sample_interval_ms) => {
+                    return Err(anyhow!("backtest quote sampling completed: {:?}", result));
+                }
+                result = strategy_runner.run() => {
+                    return Err(anyhow!("backtest strategy completed: {:?}", result));
+                }
+            }
+            Ok::<(), anyhow::Error>(())
+        };
+        // every subscription of the run goes to the local bus, nothing else in the process does
+        LocalMessageBus::scope(local_message_bus.clone(), run).await?;
+        Ok(collector.report())
+    }
+
+    async fn replay(
+        &self,
+        local_message_bus: &LocalMessageBus,
+        market_depths: &[MarketDepth],
+        collector: &ReportCollector,
+        replay_clock: ReplayClock,
+    ) -> anyhow::Result<()> {
+        for market_depth in market_depths {
+            tokio::time::sleep_until(replay_clock.instant_at(market_depth.timestamp)).await;
+            let mut market_depth = market_depth.clone();
+            market_depth.exchange = Exchanges::SIM;
+            collector.on_market_depth(&market_depth);
+            let channel = format!(
+                "{}:{}:{}",
+                PublishChannel::MarketDepth,
+                Exchanges::SIM,
+                market_depth.market
+            );
+            local_message_bus.publish(
+                channel.as_str(),
+                serde_json::to_vec(&market_depth)?.as_slice(),
+            );
+        }
+        tokio::time::sleep(Duration::from_millis(self.params.tail_ms)).await;
+        Ok(())
+    }
+
+    /// stands in for `RedisBackedMessageBus::subscribe`, publishing the lambda's messages locally
+    async fn route_messages(
+        local_message_bus: &LocalMessageBus,
+        mut message_bus_receiver: tokio::sync::mpsc::Receiver<PublishPayload>,
+        collector: &ReportCollector,
+    ) -> anyhow::Result<()> {
+        while let Some(payload) = message_bus_receiver.recv().await {
+            collector.on_publish(payload.channel.as_str());
+            local_message_bus.publish(payload.channel.as_str(), payload.payload.as_bytes());
+        }
+        Err(anyhow!("backtest message_bus_receiver closed"))
+    }
+}
@@ -0,0 +1,121 @@
+use crate::model::constants::Exchanges;
+use crate::model::market_data_model::{MarketDepth, PriceLevel};
+use postgres::NoTls;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::str::FromStr;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(tag = "type")]
+pub enum MarketDataSource {
+    /// JSON lines of `MarketDepth`, as written by the `record_market_depth` binary
+    Recording { path: String },
+    /// Trades of the postgres `trades` table filled by `fetch_trades`. Each trade is replayed as a
+    /// synthetic book of `levels` levels of `level_size` spaced `tick_size` around the trade price.
+    Trades {
+        db_url: String,
+        exchange: String,
+        market: String,
+        start_time: i64,
+        end_time: i64,
+        tick_size: f64,
+        levels: usize,
+        level_size: f64,
+    },
+}
+
+impl MarketDataSource {
+    /// blocking. load before the replay starts
+    pub fn load(&self) -> anyhow::Result<Vec<MarketDepth>> {
+        match self {
+            MarketDataSource::Recording { path } => Self::load_recording(path),
+            MarketDataSource::Trades {
+                db_url,
+                exchange,
+                market,
+                start_time,
+                end_time,
+                tick_size,
+                levels,
+                level_size,
+            } => {
+                let exchange = Exchanges::from_str(exchange.to_uppercase().as_str())
+                    .map_err(|_| anyhow!("Unknown exchange {}", exchange))?;
+                let mut db = postgres::Client::connect(db_url, NoTls)?;
+                let rows = db.query(
+                    "select price, size, time from trades where exchange = $1 and market = $2 and time >= $3 and time < $4 order by time, id",
+                    &[
+                        &exchange.to_string(),
+                        market,
+                        &(UNIX_EPOCH + Duration::from_millis(*start_time as u64)),
+                        &(UNIX_EPOCH + Duration::from_millis(*end_time as u64)),
+                    ],
+                )?;
+                let mut market_depths = vec![];
+                for row in rows {
+                    let price: f32 = row.get(0);
+                    let time: SystemTime = row.get(2);
+                    let timestamp = time.duration_since(UNIX_EPOCH)?.as_millis() as i64;
+                    market_depths.push(Self::synthetic_market_depth(
+                        timestamp,
+                        exchange.clone(),
+                        market,
+                        price as f64,
+                        *tick_size,
+                        *levels,
+                        *level_size,
+                    ));
+                }
+                Ok(market_depths)
+            }
+        }
+    }
+
+    fn load_recording(path: &str) -> anyhow::Result<Vec<MarketDepth>> {
+        let reader = BufReader::new(File::open(path)?);
+        let mut market_depths = vec![];
+        for line in reader.lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            market_depths.push(serde_json::from_str::<MarketDepth>(line.as_str())?);
+        }
+        Ok(market_depths)
+    }
+
+    pub fn synthetic_market_depth(
+        timestamp: i64,
+        exchange: Exchanges,
+        market: &str,
+        price: f64,
+        tick_size: f64,
+        levels: usize,
+        level_size: f64,
+    ) -> MarketDepth {
+        let level = |i: usize, sign: f64| PriceLevel {
+            price: price + sign * tick_size * i as f64,
+            size: level_size,
+        };
+        MarketDepth {
+            timestamp,
+            exchange,
+            market: market.to_string(),
+            bids: (1..=levels).map(|i| level(i, -1.0)).collect(),
+            asks: (1..=levels).map(|i| level(i, 1.0)).collect(),
+        }
+    }
+}
+
+/// events of every source merged in time order
+pub fn load_sources(sources: &[MarketDataSource]) -> anyhow::Result<Vec<MarketDepth>> {
+    let mut market_depths = vec![];
+    for source in sources {
+        let mut loaded = source.load()?;
+        info!("loaded {} market depths from {:?}", loaded.len(), source);
+        market_depths.append(&mut loaded);
+    }
+    market_depths.sort_by_key(|market_depth| market_depth.timestamp);
+    Ok(market_depths)
+}
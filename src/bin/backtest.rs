@@ -0,0 +1,13 @@
+use rust_quant::backtest::{Backtest, BacktestParams};
+
+/// usage: cargo run --features backtest --bin backtest <backtest config path>
+#[tokio::main(flavor = "current_thread", start_paused = true)]
+async fn main() -> anyhow::Result<()> {
+    env_logger::init();
+    let args: Vec<String> = std::env::args().collect();
+    let path = args.get(1).expect("missing argument: backtest config path");
+    let params: BacktestParams = confy::load_path(path)?;
+    let report = Backtest::new(params).run().await?;
+    println!("{}", serde_json::to_string_pretty(&report)?);
+    Ok(())
+}
@@ -2,6 +2,7 @@
 extern crate log;
 
 use std::error::Error;
+use std::sync::Arc;
 
 use rust_quant::lambda::{GenericLambdaInstanceConfig, LambdaEngine};
 
@@ -9,13 +10,17 @@ use rust_quant::lambda::{GenericLambdaInstanceConfig, LambdaEngine};
 async fn main() -> Result<(), Box<dyn Error>> {
     env_logger::init();
     let args: Vec<String> = std::env::args().collect();
-    let instance_name = args
-        .get(1)
-        .expect("Missing parameter: instance")
-        .to_string();
-    let config = GenericLambdaInstanceConfig::load(instance_name.as_str());
-    let engine = LambdaEngine::init(config).await;
-    // rust_quant::lambda::engine(config).await;
-    engine.subscribe().await;
+    if args.len() < 2 {
+        panic!("Missing parameter: instance [instance ...]");
+    }
+    let configs: Vec<GenericLambdaInstanceConfig> = args[1..]
+        .iter()
+        .map(|instance_name| GenericLambdaInstanceConfig::load(instance_name.as_str()))
+        .collect();
+    let engine = Arc::new(LambdaEngine::init(configs).await);
+    if let Err(err) = engine.subscribe().await {
+        error!("lambda engine: {}", err);
+        std::process::exit(1);
+    }
     Ok(())
 }
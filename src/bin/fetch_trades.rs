@@ -1,20 +1,13 @@
 use chrono::prelude::*;
 use postgres::NoTls;
 use reqwest::blocking::Client;
+use rust_quant::ftx::ApiResponse;
 use serde::{Deserialize, Serialize};
 
 use std::collections::HashMap;
 use std::error::Error;
 use std::time::SystemTime;
 
-#[derive(Serialize, Deserialize, Debug)]
-struct ApiResponse<T> {
-    success: bool,
-    #[serde(default)]
-    result: T,
-    #[serde(default)]
-    error: String,
-}
 #[derive(Serialize, Deserialize, Debug, Clone)]
 struct TradeHistory {
     id: i64,
@@ -86,7 +79,7 @@ fn get_trades(
     let resp = req.send()?;
     let json = resp.json::<ApiResponse<Vec<TradeHistory>>>()?;
     match &json.success {
-        true => Ok(json.result),
+        true => Ok(json.result.unwrap_or_default()),
         false => {
             Err(anyhow::anyhow!(format!("{:?}", json)))
         }
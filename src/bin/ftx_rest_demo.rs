@@ -1,6 +1,3 @@
-#[macro_use]
-extern crate log;
-
 use rust_quant::ftx::FtxRestClient;
 use rust_quant::model::constants::Exchanges;
 use rust_quant::model::{OrderRequest, OrderSide, OrderType};
@@ -29,13 +26,11 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                 post_only: true,
                 client_id: None,
             };
-            let json = client.place_order(order_request).await.unwrap();
-            log::info!("{}", json);
+            let order = client.place_order(order_request).await.unwrap();
+            log::info!("{:?}", order);
             tokio::time::sleep(Duration::from_millis(10)).await;
-            if let Some(clientId) = json["result"]["id"].as_i64() {
-                let json = client.cancel_order(clientId).await.unwrap();
-                log::info!("{}", json);
-            }
+            let result = client.cancel_order(order.id).await.unwrap();
+            log::info!("{}", result);
         });
         counter += 1;
     }
@@ -0,0 +1,51 @@
+use rust_quant::core::kill_switch::{KillSwitchAction, KillSwitchCommand};
+use rust_quant::model::constants::{Exchanges, PublishChannel};
+use rust_quant::pubsub::simple_message_bus::RedisBackedMessageBus;
+use std::str::FromStr;
+
+/// usage: kill_switch <engage|rearm> [exchange] [market]
+///
+/// With `API_KEY` and `API_SECRET` set the command is signed, re-arming needs the key of an admin.
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    env_logger::init();
+    let args: Vec<String> = std::env::args().collect();
+    let action = match args
+        .get(1)
+        .expect("missing argument: engage|rearm")
+        .to_lowercase()
+        .as_str()
+    {
+        "engage" => KillSwitchAction::Engage,
+        "rearm" => KillSwitchAction::Rearm,
+        other => panic!("Unsupported action: {}", other),
+    };
+    let exchange = args.get(2).map(|exchange| {
+        Exchanges::from_str(exchange.to_uppercase().as_str()).expect("Unknown exchange")
+    });
+    let market = args.get(3).map(|market| market.to_uppercase());
+
+    let command = KillSwitchCommand {
+        action,
+        exchange,
+        market,
+    };
+    let message_bus = RedisBackedMessageBus::new().await?;
+    match (std::env::var("API_KEY"), std::env::var("API_SECRET")) {
+        (Ok(api_key), Ok(api_secret)) => {
+            let time = chrono::Utc::now().timestamp_millis();
+            let signed = command.sign(api_key.as_str(), api_secret.as_str(), time)?;
+            message_bus
+                .publish(PublishChannel::KillSwitch.as_ref(), &signed)
+                .await?;
+        }
+        _ => {
+            if command.action == KillSwitchAction::Rearm {
+                return Err(anyhow::anyhow!("rearm needs API_KEY and API_SECRET of an admin"));
+            }
+            command.publish(&message_bus).await?;
+        }
+    }
+    log::info!("published {:?}", command);
+    Ok(())
+}
@@ -5,8 +5,10 @@ use std::error::Error;
 
 use std::time::Duration;
 
+use rust_quant::core::clock::WallClock;
 use rust_quant::ftx::market_depth::market_depth;
 use rust_quant::ftx::ticker::ticker;
+use rust_quant::ftx::trades::trades;
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn Error>> {
@@ -40,7 +42,12 @@ async fn main() -> Result<(), Box<dyn Error>> {
                     }
                 }
                 "MARKETDEPTH" => {
-                    if let Err(err) = market_depth(market.as_str()).await {
+                    if let Err(err) = market_depth(market.as_str(), WallClock::shared()).await {
+                        handle_error(err)
+                    }
+                }
+                "TRADES" => {
+                    if let Err(err) = trades(market.as_str(), WallClock::shared()).await {
                         handle_error(err)
                     }
                 }
@@ -0,0 +1,36 @@
+use rust_quant::model::constants::PublishChannel;
+use rust_quant::pubsub::simple_message_bus::{MessageConsumer, RedisBackedMessageBus};
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::sync::Mutex;
+
+/// Appends every MarketDepth of the channel as a JSON line, for replay in a backtest
+struct MarketDepthRecorder(Mutex<File>);
+
+#[async_trait::async_trait]
+impl MessageConsumer for MarketDepthRecorder {
+    async fn consume(&self, msg: &[u8]) -> anyhow::Result<()> {
+        let mut file = self.0.lock().unwrap();
+        file.write_all(msg)?;
+        file.write_all(b"\n")?;
+        Ok(())
+    }
+}
+
+/// usage: record_market_depth <exchange> <market> <path>
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    env_logger::init();
+    let args: Vec<String> = std::env::args().collect();
+    let exchange = args
+        .get(1)
+        .expect("missing argument: exchange")
+        .to_uppercase();
+    let market = args.get(2).expect("missing argument: market").to_uppercase();
+    let path = args.get(3).expect("missing argument: path");
+
+    let file = OpenOptions::new().create(true).append(true).open(path)?;
+    let recorder = MarketDepthRecorder(Mutex::new(file));
+    let channel = format!("{}:{}:{}", PublishChannel::MarketDepth, exchange, market);
+    RedisBackedMessageBus::subscribe_channels(vec![channel.as_str()], &recorder).await
+}
@@ -1,3 +1,4 @@
+use crate::core::clock::{SharedClock, WallClock};
 use crate::model::constants::PublishChannel;
 use crate::model::market_data_model::MarketDepth;
 use crate::pubsub::simple_message_bus::{MessageConsumer, RedisBackedMessageBus};
@@ -7,23 +8,28 @@ use dashmap::DashMap;
 
 use std::sync::Arc;
 
-use tokio_stream::StreamExt;
-
 type Cache = DashMap<String, MarketDepth>;
 
 pub struct MarketDepthCache {
     pub cache: Arc<Cache>,
-    tx: tokio::sync::mpsc::Sender<String>,
-    rx: tokio::sync::mpsc::Receiver<String>,
+    clock: SharedClock,
+}
+
+impl Default for MarketDepthCache {
+    fn default() -> Self {
+        MarketDepthCache::new()
+    }
 }
 
 impl MarketDepthCache {
     pub fn new() -> MarketDepthCache {
-        let (tx, rx) = tokio::sync::mpsc::channel::<String>(1000);
+        Self::with_clock(WallClock::shared())
+    }
+
+    pub fn with_clock(clock: SharedClock) -> MarketDepthCache {
         MarketDepthCache {
             cache: Arc::new(DashMap::new()),
-            tx,
-            rx,
+            clock,
         }
     }
 
@@ -33,7 +39,7 @@ impl MarketDepthCache {
         return match self.cache.get(key) {
             None => None,
             Some(md) => {
-                let now = chrono::Utc::now().timestamp_millis();
+                let now = self.clock.now_ms();
                 if now - md.timestamp > 1000 {
                     // ref must be dropped before calling remove to prevent deadlock
                     drop(md);
@@ -54,8 +60,8 @@ impl MarketDepthCache {
             .map(|request| {
                 format!(
                     "{}:{}:{}",
-                    PublishChannel::MarketDepth.to_string(),
-                    request.exchange.to_string(),
+                    PublishChannel::MarketDepth,
+                    request.exchange,
                     request.market
                 )
             })
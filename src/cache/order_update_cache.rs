@@ -2,25 +2,44 @@ use async_trait::async_trait;
 use dashmap::DashMap;
 
 use crate::model::constants::PublishChannel;
-use crate::model::{OrderStatus, OrderUpdate};
+use crate::model::{ModifyOrderRequest, OrderStatus, OrderUpdate};
 use crate::pubsub::simple_message_bus::{MessageConsumer, RedisBackedMessageBus};
 
 use std::sync::Arc;
+use tokio::sync::broadcast;
 
 type Cache = Arc<DashMap<String, OrderUpdate>>;
 
+/// updates applied to the cache a slow listener may fall behind by before it misses some
+const APPLIED_CAPACITY: usize = 1024;
+
 #[derive(Debug)]
 pub struct OrderUpdateCache {
     pub cache: Cache,
+    applied: broadcast::Sender<OrderUpdate>,
+}
+
+impl Default for OrderUpdateCache {
+    fn default() -> Self {
+        OrderUpdateCache::new()
+    }
 }
 
 impl OrderUpdateCache {
     pub fn new() -> OrderUpdateCache {
+        let (applied, _) = broadcast::channel(APPLIED_CAPACITY);
         OrderUpdateCache {
             cache: Arc::new(DashMap::new()),
+            applied,
         }
     }
 
+    /// Order updates in the order they were applied, each one sent once the cache reflects it.
+    /// Lets a strategy react to updates without subscribing to the bus a second time.
+    pub fn subscribe_applied(&self) -> broadcast::Receiver<OrderUpdate> {
+        self.applied.subscribe()
+    }
+
     pub async fn subscribe(&self) -> anyhow::Result<()> {
         RedisBackedMessageBus::subscribe_channels(vec![PublishChannel::OrderUpdate.as_ref()], self)
             .await
@@ -40,17 +59,13 @@ impl OrderUpdateCache {
                         let cached_order = cache
                             .get(cache_key.as_str())
                             .expect("Failed to get cached_order");
-                        match cached_order.status {
-                            OrderStatus::PendingCancel => match order_update.status {
-                                OrderStatus::New | OrderStatus::Open => {
-                                    order_update.status = OrderStatus::PendingCancel;
-                                    drop(cached_order);
-                                    cache.insert(cache_key, order_update);
-                                    return ();
-                                }
-                                _ => {}
-                            },
-                            _ => {}
+                        if cached_order.status == OrderStatus::PendingCancel {
+                            if let OrderStatus::New | OrderStatus::Open = order_update.status {
+                                order_update.status = OrderStatus::PendingCancel;
+                                drop(cached_order);
+                                cache.insert(cache_key, order_update);
+                                return;
+                            }
                         }
                     }
                     cache.insert(cache_key, order_update);
@@ -63,15 +78,46 @@ impl OrderUpdateCache {
             );
         }
     }
+
+    /// A modify replaces the order on the exchange under a new client id. The original order is
+    /// marked PendingCancel until its Closed update arrives and the replacement is tracked as
+    /// PendingNew under the new client id. Returns false if the original order is not modifiable.
+    pub fn accept_modify_order(
+        cache: &DashMap<String, OrderUpdate>,
+        modify_order_request: &ModifyOrderRequest,
+    ) -> bool {
+        let replacement = match cache.get_mut(modify_order_request.client_id.as_str()) {
+            None => return false,
+            Some(mut order_update) => match order_update.status {
+                OrderStatus::New | OrderStatus::Open => {
+                    order_update.status = OrderStatus::PendingCancel;
+                    let mut replacement = order_update.clone();
+                    replacement.id = -1;
+                    replacement.client_id = Some(modify_order_request.new_client_id.clone());
+                    replacement.price = modify_order_request.price;
+                    replacement.size = modify_order_request.size;
+                    replacement.status = OrderStatus::PendingNew;
+                    replacement.filledSize = 0.0;
+                    replacement.remainingSize = modify_order_request.size;
+                    replacement.avgFillPrice = None;
+                    replacement
+                }
+                _ => return false,
+            },
+        };
+        cache.insert(modify_order_request.new_client_id.clone(), replacement);
+        true
+    }
 }
 
 #[async_trait]
 impl MessageConsumer for OrderUpdateCache {
     async fn consume(&self, msg: &[u8]) -> anyhow::Result<()> {
         let order_update = serde_json::from_slice::<OrderUpdate>(msg)?;
-        // log::debug!("{:?}", msg);
-        let cache = self.cache.clone();
-        tokio::spawn(async move { Self::accept_order_update(cache, order_update) });
+        // applied in arrival order, a spawned task per update could reorder them
+        Self::accept_order_update(self.cache.clone(), order_update.clone());
+        // no receiver while no strategy listens
+        let _ = self.applied.send(order_update);
         Ok(())
     }
 }
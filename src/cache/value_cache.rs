@@ -1,11 +1,12 @@
-use crate::lambda::GenericLambdaInstanceConfig;
-use crate::pubsub::simple_message_bus::RedisBackedMessageBus;
+use crate::lambda::{GenericLambdaInstanceConfig, LambdaState};
 use dashmap::DashMap;
 use serde_json::Value;
 use std::sync::Arc;
 use redis::aio::MultiplexedConnection;
 use crate::core::config::ConfigStore;
+use crate::core::in_flight::InFlight;
 use redis::AsyncCommands;
+use std::str::FromStr;
 
 type Cache = Arc<DashMap<String, Value>>;
 
@@ -18,7 +19,9 @@ pub enum ValueCacheKey {
 pub struct ValueCache {
     cache: Cache,
     instance_config: GenericLambdaInstanceConfig,
-    redis_conn: MultiplexedConnection,
+    /// None for a cache kept in memory only, e.g. in a backtest
+    redis_conn: Option<MultiplexedConnection>,
+    in_flight: InFlight,
 }
 
 impl ValueCache {
@@ -31,31 +34,123 @@ impl ValueCache {
         ValueCache {
             cache: Arc::new(DashMap::new()),
             instance_config,
-            redis_conn,
+            redis_conn: Some(redis_conn),
+            in_flight: InFlight::new(),
+        }
+    }
+
+    /// values of a single run, never written to nor hydrated from redis
+    pub fn in_memory(instance_config: GenericLambdaInstanceConfig) -> Self {
+        ValueCache {
+            cache: Arc::new(DashMap::new()),
+            instance_config,
+            redis_conn: None,
+            in_flight: InFlight::new(),
         }
     }
 
     pub fn get_clone(&self, key: ValueCacheKey) -> Option<Value> {
         let key = key.to_string();
-        match self.cache.get(&key) {
-            None => None,
-            Some(value) => Some(value.value().clone()),
-        }
+        self.cache.get(&key).map(|value| value.value().clone())
     }
 
     fn get_instance_value_cache_key(&self, value_cache_key: ValueCacheKey) -> String {
-        format!("ValueCache:{}:{}", value_cache_key.to_string(), self.instance_config.name.as_str())
+        format!("ValueCache:{}:{}", value_cache_key, self.instance_config.name.as_str())
     }
 
     pub fn insert(&self, key: ValueCacheKey, value: Value) -> Option<Value> {
         let old_value = self.cache.insert(key.to_string(), value.clone());
+        self.write(key, value);
+        old_value
+    }
+
+    /// Read-modify-write of a cached value under its entry lock, so concurrent writers never lose
+    /// each other's changes. The value is written to redis when `f` changed it. None when the key
+    /// is not cached.
+    pub fn update<T, F>(&self, key: ValueCacheKey, f: F) -> Option<T>
+    where
+        F: FnOnce(&mut Value) -> T,
+    {
+        let (result, value) = {
+            let mut entry = self.cache.get_mut(&key.to_string())?;
+            let before = entry.value().clone();
+            let result = f(entry.value_mut());
+            if *entry.value() == before {
+                return Some(result);
+            }
+            (result, entry.value().clone())
+        };
+        self.write(key, value);
+        Some(result)
+    }
+
+    /// Move the `state` of the StrategyParams to `to` if it is one of `from`, any state when `from`
+    /// is empty. Returns the state found, None without StrategyParams.
+    pub fn transition_state(&self, from: &[LambdaState], to: LambdaState) -> Option<LambdaState> {
+        let found = self.update(ValueCacheKey::StrategyParams, |params| {
+            let state = params["state"]
+                .as_str()
+                .and_then(|state| LambdaState::from_str(state).ok());
+            let allowed = match state {
+                Some(ref state) => from.is_empty() || from.contains(state),
+                None => from.is_empty(),
+            };
+            if allowed {
+                params["state"] = Value::String(to.to_string());
+            }
+            state
+        });
+        if found.is_none() {
+            error!("Cannot get StrategyParams from ValueCache");
+        }
+        found.flatten()
+    }
+
+    fn write(&self, key: ValueCacheKey, value: Value) {
+        let mut conn = match self.redis_conn {
+            None => return,
+            Some(ref conn) => conn.clone(),
+        };
         let set_key = self.get_instance_value_cache_key(key);
-        let mut conn = self.redis_conn.clone();
+        let in_flight = self.in_flight.start();
         tokio::spawn(async move {
+            let _in_flight = in_flight;
             let json = value.to_string();
             conn.set::<&str, &str, redis::Value>(set_key.as_str(), json.as_str()).await.unwrap();
         });
-        old_value
+    }
+
+    /// Load the `StrategyStates` last written by this instance back from redis, e.g. after a crash,
+    /// without writing them again. Params are not hydrated, the instance config is their source.
+    /// Returns the number of values found.
+    pub async fn hydrate(&self) -> anyhow::Result<usize> {
+        let redis_conn = match self.redis_conn {
+            None => return Ok(0),
+            Some(ref redis_conn) => redis_conn,
+        };
+        let mut hydrated = 0;
+        for key in [ValueCacheKey::StrategyStates] {
+            let cache_key = key.to_string();
+            let get_key = self.get_instance_value_cache_key(key);
+            let mut conn = redis_conn.clone();
+            let json = match conn.get::<&str, Option<String>>(get_key.as_str()).await? {
+                None => continue,
+                Some(json) => json,
+            };
+            match serde_json::from_str::<Value>(json.as_str()) {
+                Ok(value) => {
+                    self.cache.insert(cache_key, value);
+                    hydrated += 1;
+                }
+                Err(err) => warn!("Cannot hydrate {}: {}", get_key, err),
+            }
+        }
+        Ok(hydrated)
+    }
+
+    /// wait for the writes of `insert` to reach redis, returns the number still unwritten
+    pub async fn flush(&self, timeout: std::time::Duration) -> usize {
+        self.in_flight.wait(timeout).await
     }
 
     pub async fn subscribe(&self) {
@@ -0,0 +1,159 @@
+use crate::core::config::ConfigStore;
+use crate::ftx::{sign_hmac_sha256, verify_hmac_sha256};
+use crate::lambda::LambdaState;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use thiserror::Error;
+
+/// Roles in increasing order of access, each role may do everything of the roles before it.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub enum Role {
+    /// reads params, states and the audit log
+    Viewer,
+    /// changes params and pauses, stops or resumes a lambda
+    #[default]
+    Trader,
+    /// changes risk limits and restarts stopped lambdas
+    Admin,
+}
+
+impl Role {
+    /// Traders may always reduce risk but only resume a paused lambda, a lambda stopped by the kill
+    /// switch or not yet started needs an admin.
+    pub fn may_set_state(&self, from: &LambdaState, to: &LambdaState) -> bool {
+        match self {
+            Role::Admin => true,
+            Role::Viewer => false,
+            Role::Trader => match to {
+                LambdaState::Paused | LambdaState::Stopped => true,
+                LambdaState::Live => matches!(
+                    from,
+                    LambdaState::Live | LambdaState::Paused | LambdaState::AutoPaused
+                ),
+                LambdaState::Init | LambdaState::AutoPaused => false,
+            },
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ApiUser {
+    pub name: String,
+    pub api_key: String,
+    pub api_secret: String,
+    pub role: Role,
+}
+
+/// A command signed with the secret of `api_key`.
+///
+/// `sign` is the hex HMAC-SHA256 of `"{channel}{time}{payload}"`, `channel` the one the command is
+/// published on, e.g. `UpdateParam:{instance}`, so a command cannot be replayed to another channel
+/// or instance. `payload` is the JSON of the command as the client serialized it, so the signature
+/// never depends on how the JSON is re-encoded.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SignedCommand {
+    pub api_key: String,
+    pub time: i64,
+    pub payload: String,
+    pub sign: String,
+}
+
+impl SignedCommand {
+    /// `payload` signed for `channel` with the secret of `api_key`
+    pub fn new(channel: &str, api_key: &str, api_secret: &str, time: i64, payload: String) -> Self {
+        let sign = sign_hmac_sha256(
+            api_secret,
+            Self::message(channel, time, payload.as_str()).as_str(),
+        );
+        SignedCommand {
+            api_key: api_key.to_string(),
+            time,
+            payload,
+            sign,
+        }
+    }
+
+    pub fn message(channel: &str, time: i64, payload: &str) -> String {
+        format!("{}{}{}", channel, time, payload)
+    }
+}
+
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum AuthError {
+    #[error("unknown api key")]
+    UnknownKey,
+    #[error("invalid signature")]
+    InvalidSignature,
+    #[error("command time {0} is outside of the accepted window")]
+    Expired(i64),
+    #[error("{0} is not permitted")]
+    Forbidden(String),
+    #[error("command was already accepted")]
+    Replayed,
+}
+
+/// max distance of a command's time from ours, bounds replaying a captured command
+pub const MAX_COMMAND_AGE_MS: i64 = 5000;
+
+/// Verifies `SignedCommand`s. Every command is accepted once, each consumer of a channel keeps its
+/// own `Authenticator`.
+pub struct Authenticator {
+    users: Vec<ApiUser>,
+    /// time of the accepted commands by signature, while they are inside `MAX_COMMAND_AGE_MS`
+    accepted: Mutex<HashMap<String, i64>>,
+}
+
+impl Authenticator {
+    pub fn new(users: Vec<ApiUser>) -> Self {
+        if users.is_empty() {
+            warn!("no api users configured, every command is rejected");
+        }
+        Authenticator {
+            users,
+            accepted: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// users of the environment config
+    pub fn load() -> Self {
+        Self::new(ConfigStore::load().api_users)
+    }
+
+    pub fn user(&self, api_key: &str) -> Result<&ApiUser, AuthError> {
+        self.users
+            .iter()
+            .find(|user| user.api_key == api_key)
+            .ok_or(AuthError::UnknownKey)
+    }
+
+    /// the user `command` received on `channel` is signed by, checked against the time `now_ms`
+    pub fn verify(
+        &self,
+        channel: &str,
+        command: &SignedCommand,
+        now_ms: i64,
+    ) -> Result<&ApiUser, AuthError> {
+        let user = self.user(command.api_key.as_str())?;
+        let message = SignedCommand::message(channel, command.time, command.payload.as_str());
+        if !verify_hmac_sha256(
+            user.api_secret.as_str(),
+            message.as_str(),
+            command.sign.as_str(),
+        ) {
+            return Err(AuthError::InvalidSignature);
+        }
+        if (now_ms - command.time).abs() > MAX_COMMAND_AGE_MS {
+            return Err(AuthError::Expired(command.time));
+        }
+        let mut accepted = self.accepted.lock().unwrap();
+        // an expired command is rejected above, its signature need not be kept
+        accepted.retain(|_, time| now_ms - *time <= MAX_COMMAND_AGE_MS);
+        if accepted
+            .insert(command.sign.clone(), command.time)
+            .is_some()
+        {
+            return Err(AuthError::Replayed);
+        }
+        Ok(user)
+    }
+}
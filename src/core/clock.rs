@@ -0,0 +1,85 @@
+use async_trait::async_trait;
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::sync::Notify;
+
+/// Source of time for everything that timestamps events or waits on timers. Live components run
+/// on `WallClock`; backtests, replays and tests inject a clock they control.
+#[async_trait]
+pub trait Clock: Send + Sync {
+    fn now_ns(&self) -> i64;
+
+    fn now_ms(&self) -> i64 {
+        self.now_ns() / 1_000_000
+    }
+
+    async fn sleep(&self, duration: Duration);
+}
+
+pub type SharedClock = Arc<dyn Clock>;
+
+pub struct WallClock;
+impl WallClock {
+    pub fn shared() -> SharedClock {
+        Arc::new(WallClock)
+    }
+}
+#[async_trait]
+impl Clock for WallClock {
+    fn now_ns(&self) -> i64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("system time before unix epoch")
+            .as_nanos() as i64
+    }
+
+    async fn sleep(&self, duration: Duration) {
+        tokio::time::sleep(duration).await
+    }
+}
+
+/// Clock that only moves when advanced. Sleepers wake once the clock passes their deadline.
+pub struct ManualClock {
+    now_ns: AtomicI64,
+    notify: Notify,
+}
+impl ManualClock {
+    pub fn new(start_ms: i64) -> Self {
+        ManualClock {
+            now_ns: AtomicI64::new(start_ms * 1_000_000),
+            notify: Notify::new(),
+        }
+    }
+
+    pub fn advance(&self, duration: Duration) {
+        self.now_ns
+            .fetch_add(duration.as_nanos() as i64, Ordering::SeqCst);
+        self.notify.notify_waiters();
+    }
+
+    /// move the clock to `time_ms`. the clock never moves backwards
+    pub fn set_ms(&self, time_ms: i64) {
+        self.now_ns
+            .fetch_max(time_ms * 1_000_000, Ordering::SeqCst);
+        self.notify.notify_waiters();
+    }
+}
+#[async_trait]
+impl Clock for ManualClock {
+    fn now_ns(&self) -> i64 {
+        self.now_ns.load(Ordering::SeqCst)
+    }
+
+    async fn sleep(&self, duration: Duration) {
+        let deadline = self.now_ns() + duration.as_nanos() as i64;
+        loop {
+            // register before checking so an advance in between is not missed
+            let notified = self.notify.notified();
+            if self.now_ns() >= deadline {
+                return;
+            }
+            notified.await;
+        }
+    }
+}
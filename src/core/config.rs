@@ -1,3 +1,4 @@
+use crate::core::auth::ApiUser;
 use serde::{Deserialize, Serialize};
 #[derive(Debug, Serialize, Deserialize, Default)]
 pub struct Config {
@@ -6,12 +7,21 @@ pub struct Config {
     pub(crate) ftx_api_key: String,
     pub(crate) ftx_api_secret: String,
     pub(crate) ftx_sub_account: String,
+    /// who may read and change lambdas through `UpdateParam` and the api
+    #[serde(default)]
+    pub(crate) api_users: Vec<ApiUser>,
 }
 
 pub struct ConfigStore {
     cfg: Config,
 }
 
+impl Default for ConfigStore {
+    fn default() -> Self {
+        ConfigStore::new()
+    }
+}
+
 impl ConfigStore {
     pub fn new() -> ConfigStore {
         let environment = std::env::var("ENV").expect("ENV is not defined");
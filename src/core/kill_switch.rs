@@ -0,0 +1,237 @@
+use crate::core::auth::{Authenticator, Role, SignedCommand};
+use crate::model::constants::{Exchanges, PublishChannel};
+use crate::pubsub::simple_message_bus::RedisBackedMessageBus;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+#[derive(Serialize, Deserialize, Debug, Clone, strum_macros::Display, PartialEq)]
+pub enum KillSwitchAction {
+    Engage,
+    Rearm,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct KillSwitchCommand {
+    pub action: KillSwitchAction,
+    /// None applies the command to every exchange
+    pub exchange: Option<Exchanges>,
+    /// None cancels all open orders of the account, otherwise only orders of this market
+    pub market: Option<String>,
+}
+
+impl KillSwitchCommand {
+    pub fn applies_to(&self, exchange: &Exchanges) -> bool {
+        match self.exchange {
+            None => true,
+            Some(ref target) => target == exchange,
+        }
+    }
+
+    /// the orders the command engages or re-arms the switch for
+    pub fn scope(&self) -> KillSwitchScope {
+        KillSwitchScope {
+            exchange: self.exchange.clone(),
+            market: self.market.clone(),
+        }
+    }
+
+    pub async fn publish(&self, message_bus: &RedisBackedMessageBus) -> anyhow::Result<()> {
+        message_bus
+            .publish(PublishChannel::KillSwitch.as_ref(), self)
+            .await
+    }
+
+    /// `self` signed for `KillSwitch` with the secret of `api_key`
+    pub fn sign(
+        &self,
+        api_key: &str,
+        api_secret: &str,
+        time: i64,
+    ) -> anyhow::Result<SignedCommand> {
+        Ok(SignedCommand::new(
+            PublishChannel::KillSwitch.as_ref(),
+            api_key,
+            api_secret,
+            time,
+            serde_json::to_string(self)?,
+        ))
+    }
+
+    /// The command of a message on `KillSwitch`. A bare `KillSwitchCommand` may only engage the
+    /// switch, re-arming takes a `SignedCommand` of an admin.
+    pub fn authorize(
+        msg: &[u8],
+        authenticator: &Authenticator,
+        now_ms: i64,
+    ) -> anyhow::Result<KillSwitchCommand> {
+        if let Ok(signed) = serde_json::from_slice::<SignedCommand>(msg) {
+            let user =
+                authenticator.verify(PublishChannel::KillSwitch.as_ref(), &signed, now_ms)?;
+            let command = serde_json::from_str::<KillSwitchCommand>(signed.payload.as_str())?;
+            if command.action == KillSwitchAction::Rearm && user.role < Role::Admin {
+                return Err(anyhow!("{} may not re-arm the kill switch", user.name));
+            }
+            return Ok(command);
+        }
+        let command = serde_json::from_slice::<KillSwitchCommand>(msg)?;
+        if command.action != KillSwitchAction::Engage {
+            return Err(anyhow!("{} must be signed by an admin", command.action));
+        }
+        Ok(command)
+    }
+}
+
+/// Orders of an exchange and market, None for any.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct KillSwitchScope {
+    pub exchange: Option<Exchanges>,
+    pub market: Option<String>,
+}
+
+impl KillSwitchScope {
+    /// every order of every exchange
+    pub fn all() -> Self {
+        KillSwitchScope {
+            exchange: None,
+            market: None,
+        }
+    }
+
+    pub fn exchange(exchange: Exchanges) -> Self {
+        KillSwitchScope {
+            exchange: Some(exchange),
+            market: None,
+        }
+    }
+
+    pub fn covers(&self, exchange: &Exchanges, market: &str) -> bool {
+        let exchange = match self.exchange {
+            None => true,
+            Some(ref target) => target == exchange,
+        };
+        let market = match self.market {
+            None => true,
+            Some(ref target) => target == market,
+        };
+        exchange && market
+    }
+
+    /// every order `other` covers is covered by `self`
+    fn contains(&self, other: &KillSwitchScope) -> bool {
+        let exchange = match (&self.exchange, &other.exchange) {
+            (None, _) => true,
+            (Some(target), Some(exchange)) => target == exchange,
+            (Some(_), None) => false,
+        };
+        let market = match (&self.market, &other.market) {
+            (None, _) => true,
+            (Some(target), Some(market)) => target == market,
+            (Some(_), None) => false,
+        };
+        exchange && market
+    }
+}
+
+/// where the engine keeps an engaged switch, so a restart comes back engaged
+pub const KILL_SWITCH_PATH: &str = "./kill_switch.engaged";
+
+/// Once engaged for a scope, order gateways reject every `OrderRequest` it covers until an
+/// operator re-arms it with an explicit `KillSwitchAction::Rearm` command.
+///
+/// Re-arming clears the engaged scopes within the re-armed one, e.g. re-arming FTX clears FTX and
+/// its markets but not a switch engaged for every exchange.
+///
+/// A persistent switch keeps its engaged scopes as JSON in its file, the file is removed once
+/// nothing is engaged.
+#[derive(Default)]
+pub struct KillSwitch {
+    engaged: Mutex<Vec<KillSwitchScope>>,
+    path: Option<PathBuf>,
+}
+
+impl KillSwitch {
+    pub fn new() -> Self {
+        KillSwitch::default()
+    }
+
+    /// the switch as persisted at `path`, engaged as it was before the restart
+    pub fn persistent<P: AsRef<Path>>(path: P) -> Self {
+        let path = path.as_ref().to_path_buf();
+        let engaged = match std::fs::read_to_string(&path) {
+            Err(_) => vec![],
+            Ok(content) => match serde_json::from_str::<Vec<KillSwitchScope>>(content.as_str()) {
+                Ok(engaged) => engaged,
+                Err(err) => {
+                    // unreadable, stay on the safe side
+                    error!("Cannot read kill switch {:?}: {}", path, err);
+                    vec![KillSwitchScope::all()]
+                }
+            },
+        };
+        if !engaged.is_empty() {
+            warn!("kill switch engaged since the last run: {:?}", engaged);
+        }
+        KillSwitch {
+            engaged: Mutex::new(engaged),
+            path: Some(path),
+        }
+    }
+
+    /// whether orders of `market` on `exchange` are blocked
+    pub fn is_engaged(&self, exchange: &Exchanges, market: &str) -> bool {
+        self.engaged
+            .lock()
+            .unwrap()
+            .iter()
+            .any(|scope| scope.covers(exchange, market))
+    }
+
+    /// scopes engaged, empty while armed
+    pub fn engaged(&self) -> Vec<KillSwitchScope> {
+        self.engaged.lock().unwrap().clone()
+    }
+
+    pub fn engage(&self, scope: &KillSwitchScope) {
+        let mut engaged = self.engaged.lock().unwrap();
+        if engaged.iter().any(|engaged| engaged.contains(scope)) {
+            return;
+        }
+        warn!("kill switch engaged for {:?}", scope);
+        engaged.retain(|engaged| !scope.contains(engaged));
+        engaged.push(scope.clone());
+        self.persist(&engaged);
+    }
+
+    pub fn rearm(&self, scope: &KillSwitchScope) {
+        let mut engaged = self.engaged.lock().unwrap();
+        let before = engaged.len();
+        engaged.retain(|engaged| !scope.contains(engaged));
+        if engaged.len() < before {
+            warn!("kill switch re-armed for {:?}", scope);
+        }
+        if !engaged.is_empty() {
+            warn!("kill switch still engaged for {:?}", engaged);
+        }
+        self.persist(&engaged);
+    }
+
+    fn persist(&self, engaged: &[KillSwitchScope]) {
+        let path = match self.path {
+            None => return,
+            Some(ref path) => path,
+        };
+        if engaged.is_empty() {
+            match std::fs::remove_file(path) {
+                Err(err) if err.kind() != std::io::ErrorKind::NotFound => {
+                    error!("Cannot remove kill switch {:?}: {}", path, err);
+                }
+                _ => {}
+            }
+            return;
+        }
+        let content = serde_json::to_string(engaged).unwrap();
+        if let Err(err) = std::fs::write(path, content) {
+            error!("Cannot persist kill switch to {:?}: {}", path, err);
+        }
+    }
+}
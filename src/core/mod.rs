@@ -1,4 +1,9 @@
+pub mod auth;
+pub mod clock;
 pub mod config;
+pub mod in_flight;
+pub mod kill_switch;
+pub mod supervisor;
 
 #[async_trait::async_trait]
 pub trait OrderGateway {
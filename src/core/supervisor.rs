@@ -0,0 +1,266 @@
+use crate::core::clock::SharedClock;
+use crate::model::constants::PublishChannel;
+use crate::pubsub::simple_message_bus::MessageBusSender;
+use crate::pubsub::PublishPayload;
+use futures_util::future::BoxFuture;
+use futures_util::FutureExt;
+use std::collections::VecDeque;
+use std::panic::AssertUnwindSafe;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// events kept for `Supervisor::events`
+const MAX_EVENTS: usize = 100;
+
+/// Delay before a restart, doubling with every attempt from `initial_ms` up to `max_ms`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct Backoff {
+    pub initial_ms: u64,
+    pub max_ms: u64,
+    /// a task running at least this long counts as healthy, its next failure starts over
+    pub reset_after_ms: u64,
+}
+
+impl Default for Backoff {
+    fn default() -> Self {
+        Backoff {
+            initial_ms: 500,
+            max_ms: 30_000,
+            reset_after_ms: 60_000,
+        }
+    }
+}
+
+impl Backoff {
+    /// delay before restart `attempt`, starting at 1
+    pub fn delay_ms(&self, attempt: u32) -> u64 {
+        let factor = 1u64 << attempt.saturating_sub(1).min(32);
+        self.initial_ms.saturating_mul(factor).min(self.max_ms)
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum RestartPolicy {
+    /// restart the component on its own
+    Restart(Backoff),
+    /// give up and end `Supervisor::run` with the error
+    Escalate,
+    /// pause the world through `WorldPause` before restarting the component
+    StopTheWorld(Backoff),
+}
+
+/// What the world has to do before a `StopTheWorld` component restarts, e.g. pause every lambda
+/// and cancel its orders before the order gateway comes back.
+#[async_trait::async_trait]
+pub trait WorldPause: Send + Sync {
+    async fn pause_world(&self, component: &str) -> anyhow::Result<()>;
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, strum_macros::Display)]
+pub enum SupervisorEventKind {
+    Started,
+    Exited,
+    WorldPaused,
+    Restarting,
+    Escalated,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SupervisorEvent {
+    pub time_ms: i64,
+    pub component: String,
+    pub kind: SupervisorEventKind,
+    /// restart attempt since the component was last healthy, 0 for the first start
+    pub attempt: u32,
+    pub reason: Option<String>,
+    pub delay_ms: Option<u64>,
+}
+
+pub type TaskFactory = Box<dyn Fn() -> BoxFuture<'static, anyhow::Result<()>> + Send + Sync>;
+
+struct Child {
+    name: String,
+    policy: RestartPolicy,
+    factory: TaskFactory,
+}
+
+/// Runs long-lived components and applies their `RestartPolicy` when one of them returns or
+/// panics, instead of letting the first one to finish end everything.
+///
+/// Every start, exit and restart is logged, kept for `events` and published on `SupervisorEvent`.
+pub struct Supervisor {
+    children: Vec<Child>,
+    world: Option<Arc<dyn WorldPause>>,
+    message_bus_sender: Option<MessageBusSender>,
+    events: Mutex<VecDeque<SupervisorEvent>>,
+    clock: SharedClock,
+}
+
+impl Supervisor {
+    pub fn new(clock: SharedClock, message_bus_sender: Option<MessageBusSender>) -> Self {
+        Supervisor {
+            children: vec![],
+            world: None,
+            message_bus_sender,
+            events: Mutex::new(VecDeque::new()),
+            clock,
+        }
+    }
+
+    pub fn set_world_pause(&mut self, world: Arc<dyn WorldPause>) {
+        self.world = Some(world);
+    }
+
+    /// `factory` builds a fresh future for every start of the component
+    pub fn supervise<F>(&mut self, name: &str, policy: RestartPolicy, factory: F)
+    where
+        F: Fn() -> BoxFuture<'static, anyhow::Result<()>> + Send + Sync + 'static,
+    {
+        self.children.push(Child {
+            name: name.to_string(),
+            policy,
+            factory: Box::new(factory),
+        });
+    }
+
+    /// most recent events, oldest first
+    pub fn events(&self) -> Vec<SupervisorEvent> {
+        self.events.lock().unwrap().iter().cloned().collect()
+    }
+
+    /// Runs until a component escalates, dropping every other component with it.
+    pub async fn run(&self) -> anyhow::Result<()> {
+        if self.children.is_empty() {
+            return futures_util::future::pending().await;
+        }
+        let children = self
+            .children
+            .iter()
+            .map(|child| self.run_child(child).boxed());
+        let (result, _, _) = futures_util::future::select_all(children).await;
+        result
+    }
+
+    async fn run_child(&self, child: &Child) -> anyhow::Result<()> {
+        let name = child.name.as_str();
+        let mut attempt = 0;
+        loop {
+            self.emit(name, SupervisorEventKind::Started, attempt, None, None);
+            let started_ms = self.clock.now_ms();
+            let reason = match AssertUnwindSafe((child.factory)()).catch_unwind().await {
+                Ok(Ok(())) => "completed".to_string(),
+                Ok(Err(err)) => err.to_string(),
+                Err(panic) => format!("panicked: {}", panic_message(panic)),
+            };
+            self.emit(
+                name,
+                SupervisorEventKind::Exited,
+                attempt,
+                Some(reason.clone()),
+                None,
+            );
+
+            let backoff = match child.policy {
+                RestartPolicy::Escalate => {
+                    self.emit(
+                        name,
+                        SupervisorEventKind::Escalated,
+                        attempt,
+                        Some(reason.clone()),
+                        None,
+                    );
+                    return Err(anyhow!("{} escalated: {}", name, reason));
+                }
+                RestartPolicy::Restart(ref backoff) => backoff,
+                RestartPolicy::StopTheWorld(ref backoff) => {
+                    if let Some(ref world) = self.world {
+                        // restarting without pausing the world is unsafe, escalate instead
+                        if let Err(err) = world.pause_world(name).await {
+                            let reason = format!("pause world failed: {}", err);
+                            self.emit(
+                                name,
+                                SupervisorEventKind::Escalated,
+                                attempt,
+                                Some(reason.clone()),
+                                None,
+                            );
+                            return Err(anyhow!("{} escalated: {}", name, reason));
+                        }
+                    }
+                    self.emit(name, SupervisorEventKind::WorldPaused, attempt, None, None);
+                    backoff
+                }
+            };
+            if self.clock.now_ms() - started_ms >= backoff.reset_after_ms as i64 {
+                attempt = 0;
+            }
+            attempt += 1;
+            let delay_ms = backoff.delay_ms(attempt);
+            self.emit(
+                name,
+                SupervisorEventKind::Restarting,
+                attempt,
+                None,
+                Some(delay_ms),
+            );
+            self.clock.sleep(Duration::from_millis(delay_ms)).await;
+        }
+    }
+
+    fn emit(
+        &self,
+        component: &str,
+        kind: SupervisorEventKind,
+        attempt: u32,
+        reason: Option<String>,
+        delay_ms: Option<u64>,
+    ) {
+        let event = SupervisorEvent {
+            time_ms: self.clock.now_ms(),
+            component: component.to_string(),
+            kind,
+            attempt,
+            reason,
+            delay_ms,
+        };
+        match event.kind {
+            SupervisorEventKind::Started | SupervisorEventKind::Restarting => {
+                info!("supervisor: {:?}", event)
+            }
+            SupervisorEventKind::Exited | SupervisorEventKind::WorldPaused => {
+                warn!("supervisor: {:?}", event)
+            }
+            SupervisorEventKind::Escalated => error!("supervisor: {:?}", event),
+        }
+        if let Some(ref sender) = self.message_bus_sender {
+            match serde_json::to_string(&event) {
+                Ok(payload) => {
+                    let payload = PublishPayload {
+                        channel: PublishChannel::SupervisorEvent.to_string(),
+                        payload,
+                    };
+                    if let Err(err) = sender.try_send(payload) {
+                        warn!("Cannot publish SupervisorEvent: {}", err);
+                    }
+                }
+                Err(err) => error!("{}", err),
+            }
+        }
+        let mut events = self.events.lock().unwrap();
+        events.push_back(event);
+        while events.len() > MAX_EVENTS {
+            events.pop_front();
+        }
+    }
+}
+
+fn panic_message(panic: Box<dyn std::any::Any + Send>) -> String {
+    if let Some(message) = panic.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = panic.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "unknown panic".to_string()
+    }
+}
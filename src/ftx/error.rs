@@ -0,0 +1,64 @@
+use reqwest::StatusCode;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum FtxApiError {
+    #[error("post only order would cross: {0}")]
+    PostOnlyWouldCross(String),
+    #[error("insufficient margin: {0}")]
+    InsufficientMargin(String),
+    #[error("order already closed: {0}")]
+    OrderAlreadyClosed(String),
+    #[error("rate limited: {0}")]
+    RateLimited(String),
+    #[error("authentication failed: {0}")]
+    AuthFailure(String),
+    /// turned down by the caller once the rate limiter let it through, never sent
+    #[error("request not admitted: {0}")]
+    NotAdmitted(String),
+    #[error("network error: {0}")]
+    Network(#[from] reqwest::Error),
+    #[error("invalid response: {0}")]
+    InvalidResponse(#[from] serde_json::Error),
+    #[error("ftx error ({0}): {1}")]
+    Other(StatusCode, String),
+}
+
+/// FTX's messages of an order rejected for lack of collateral, without the trailing period
+const INSUFFICIENT_MARGIN_MESSAGES: [&str; 2] = [
+    "Not enough balances",
+    "Account does not have enough margin for order",
+];
+
+impl FtxApiError {
+    /// classify the `error` message of an unsuccessful FTX response
+    pub fn from_response(status: StatusCode, message: &str) -> Self {
+        let lower = message.to_lowercase();
+        let message = message.to_string();
+        if status == StatusCode::TOO_MANY_REQUESTS || lower.contains("do not send more than") {
+            FtxApiError::RateLimited(message)
+        } else if status == StatusCode::UNAUTHORIZED
+            || lower.contains("not logged in")
+            || lower.contains("invalid signature")
+            || lower.contains("invalid api key")
+        {
+            FtxApiError::AuthFailure(message)
+        } else if lower.contains("would cross") {
+            FtxApiError::PostOnlyWouldCross(message)
+        } else if INSUFFICIENT_MARGIN_MESSAGES.contains(&message.trim().trim_end_matches('.')) {
+            FtxApiError::InsufficientMargin(message)
+        } else if lower.contains("already closed")
+            || lower.contains("already queued for cancellation")
+            || lower.contains("order not found")
+        {
+            FtxApiError::OrderAlreadyClosed(message)
+        } else {
+            FtxApiError::Other(status, message)
+        }
+    }
+
+    /// whether sending the same request again may succeed
+    pub fn is_retryable(&self) -> bool {
+        matches!(self, FtxApiError::RateLimited(_) | FtxApiError::Network(_))
+    }
+}
@@ -1,32 +1,88 @@
+use crate::core::auth::Authenticator;
+use crate::core::clock::SharedClock;
+use crate::core::kill_switch::{KillSwitch, KillSwitchAction, KillSwitchCommand, KillSwitchScope};
 use crate::core::OrderGateway;
-use crate::ftx::types::{FtxOrderData, FtxOrderFill, WebSocketResponse, WebSocketResponseType};
+use crate::ftx::types::{
+    FtxOrderData, FtxOrderFill, FtxOrderStatus, WebSocketResponse, WebSocketResponseType,
+};
 use crate::ftx::utils::{connect_ftx_authed, ping_pong};
-use crate::ftx::FtxRestClient;
+use crate::ftx::{FtxApiError, FtxRestClient};
 use crate::model::constants::{Exchanges, PublishChannel};
-use crate::model::{CancelOrderRequest, Measurement, MeasurementCache, OrderRequest, OrderStatus, OrderUpdate, TSOptions};
+use crate::model::{
+    CancelOrderRequest, MeasurementCache, ModifyOrderRequest, OrderFill, OrderRequest, OrderStatus,
+    OrderUpdate,
+};
 use crate::pubsub::simple_message_bus::{MessageBusSender, MessageConsumer, RedisBackedMessageBus};
 use async_trait::async_trait;
 
 use futures_util::stream::SplitStream;
 use futures_util::{SinkExt, StreamExt};
-use redis::Commands;
 use serde_json::json;
 
-use std::sync::Arc;
+use dashmap::DashMap;
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
 
 use crate::pubsub::PublishPayload;
 use thiserror::Error;
 use tokio::net::TcpStream;
 
+use crate::model::global_measurement::{ORDER_LATENCY, RATE_LIMIT_SATURATION, TO_ACK};
 use tokio_stream::wrappers::ReceiverStream;
 use tokio_tungstenite::tungstenite::Message;
 use tokio_tungstenite::{MaybeTlsStream, WebSocketStream};
-use crate::model::global_measurement::{ORDER_LATENCY, TO_ACK};
 
 pub struct FtxOrderGateway {
     message_bus_sender: MessageBusSender,
     client: Arc<FtxRestClient>,
     measurement_cache: Arc<MeasurementCache>,
+    kill_switch: Arc<KillSwitch>,
+    client_ids: Arc<ClientIds>,
+    clock: SharedClock,
+}
+
+const MAX_CANCEL_ATTEMPTS: u64 = 3;
+/// closed orders whose client id is still kept for fills arriving after the close
+const MAX_CLOSED_CLIENT_IDS: usize = 10000;
+
+/// Client ids by FTX order id. FTX fills only carry the order id, the lambda owning a fill is
+/// found through the client id of its order, learned from place order responses and order updates.
+#[derive(Default)]
+struct ClientIds {
+    client_ids: DashMap<i64, String>,
+    /// order ids of closed orders, oldest first
+    closed: Mutex<VecDeque<i64>>,
+}
+impl ClientIds {
+    fn on_order(&self, order: &FtxOrderData) {
+        let client_id = match order.clientId {
+            None => return,
+            Some(ref client_id) => client_id.clone(),
+        };
+        self.client_ids.insert(order.id, client_id);
+        if let FtxOrderStatus::closed = order.status {
+            let mut closed = self.closed.lock().unwrap();
+            closed.push_back(order.id);
+            while closed.len() > MAX_CLOSED_CLIENT_IDS {
+                if let Some(order_id) = closed.pop_front() {
+                    self.client_ids.remove(&order_id);
+                }
+            }
+        }
+    }
+
+    fn to_order_fill(&self, fill: &FtxOrderFill) -> OrderFill {
+        let mut order_fill = fill.to_order_fill();
+        order_fill.client_id = self
+            .client_ids
+            .get(&fill.orderId)
+            .map(|client_id| client_id.value().clone());
+        if order_fill.client_id.is_none() {
+            log::warn!("no client id for the fill of order {}", fill.orderId);
+        }
+        order_fill
+    }
 }
 
 #[derive(Error, Debug)]
@@ -40,11 +96,16 @@ impl FtxOrderGateway {
         message_bus_sender: MessageBusSender,
         client: Arc<FtxRestClient>,
         measurement_cache: Arc<MeasurementCache>,
+        kill_switch: Arc<KillSwitch>,
+        clock: SharedClock,
     ) -> FtxOrderGateway {
         FtxOrderGateway {
             message_bus_sender,
             client,
             measurement_cache,
+            kill_switch,
+            client_ids: Arc::new(ClientIds::default()),
+            clock,
         }
     }
 }
@@ -52,12 +113,34 @@ impl FtxOrderGateway {
 #[async_trait]
 impl OrderGateway for FtxOrderGateway {
     async fn subscribe(&self) -> anyhow::Result<()> {
-        let order_update_service = FtxOrderUpdateService::new();
-        let order_fill_service = FtxOrderFillService::new();
-        let order_request_service =
-            FtxOrderRequestService::new(self.message_bus_sender.clone(), self.client.clone(), self.measurement_cache.clone());
-        let cancel_order_service =
-            FtxCancelOrderService::new(self.message_bus_sender.clone(), self.client.clone());
+        let order_update_service =
+            FtxOrderUpdateService::new(self.client_ids.clone(), self.clock.clone());
+        let order_fill_service =
+            FtxOrderFillService::new(self.client_ids.clone(), self.clock.clone());
+        let order_request_service = FtxOrderRequestService::new(
+            self.message_bus_sender.clone(),
+            self.client.clone(),
+            self.measurement_cache.clone(),
+            self.kill_switch.clone(),
+            self.client_ids.clone(),
+        );
+        let cancel_order_service = FtxCancelOrderService::new(
+            self.message_bus_sender.clone(),
+            self.client.clone(),
+            self.measurement_cache.clone(),
+        );
+        let modify_order_service = FtxModifyOrderService::new(
+            self.message_bus_sender.clone(),
+            self.client.clone(),
+            self.measurement_cache.clone(),
+            self.kill_switch.clone(),
+        );
+        let kill_switch_service = FtxKillSwitchService::new(
+            self.client.clone(),
+            self.kill_switch.clone(),
+            Authenticator::load(),
+            self.clock.clone(),
+        );
         tokio::select! {
             Err(err) = order_update_service.subscribe() => {
                 log::error!("order_update_service panic: {}", err)
@@ -71,16 +154,25 @@ impl OrderGateway for FtxOrderGateway {
             Err(err) = cancel_order_service.subscribe() => {
                 log::error!("cancel_order_service panic: {}", err)
             },
+            Err(err) = modify_order_service.subscribe() => {
+                log::error!("modify_order_service panic: {}", err)
+            },
+            Err(err) = kill_switch_service.subscribe() => {
+                log::error!("kill_switch_service panic: {}", err)
+            },
         }
         // panic!("FtxOrderGateway subscribe uncaught")
         Err(anyhow!("FtxOrderGateway subscribe uncaught"))
     }
 }
 
-struct FtxOrderUpdateService {}
+struct FtxOrderUpdateService {
+    client_ids: Arc<ClientIds>,
+    clock: SharedClock,
+}
 impl FtxOrderUpdateService {
-    pub fn new() -> Self {
-        FtxOrderUpdateService {}
+    pub fn new(client_ids: Arc<ClientIds>, clock: SharedClock) -> Self {
+        FtxOrderUpdateService { client_ids, clock }
     }
 
     pub async fn process_stream(
@@ -111,13 +203,17 @@ impl FtxOrderUpdateService {
                 WebSocketResponseType::update => {
                     log::debug!("{:?}", response);
                     if let Some(data) = response.data {
+                        self.client_ids.on_order(&data);
                         let order_update = data.to_order_update();
-                        redis
+                        if let Err(err) = redis
                             .publish(
                                 PublishChannel::OrderUpdate.to_string().as_str(),
                                 &order_update,
                             )
-                            .await;
+                            .await
+                        {
+                            log::error!("Cannot publish {:?}: {}", order_update, err);
+                        }
                     }
                 }
                 _ => {
@@ -129,14 +225,14 @@ impl FtxOrderUpdateService {
     }
 
     pub async fn subscribe(&self) -> anyhow::Result<()> {
-        let (mut write, mut read) = connect_ftx_authed().await?;
+        let (mut write, mut read) = connect_ftx_authed(&self.clock).await?;
         let (tx, rx) = tokio::sync::mpsc::channel(32);
         let init_message = json!({
             "op": "subscribe",
             "channel": "orders",
         });
         log::info!("{}", init_message);
-        write.send(Message::Text(init_message.to_string())).await;
+        write.send(Message::Text(init_message.to_string())).await?;
         let forward_write_to_ws = ReceiverStream::new(rx).map(Ok).forward(write);
         tokio::select! {
             _ = forward_write_to_ws => {},
@@ -149,11 +245,14 @@ impl FtxOrderUpdateService {
     }
 }
 
-struct FtxOrderFillService {}
+struct FtxOrderFillService {
+    client_ids: Arc<ClientIds>,
+    clock: SharedClock,
+}
 
 impl FtxOrderFillService {
-    pub fn new() -> Self {
-        FtxOrderFillService {}
+    pub fn new(client_ids: Arc<ClientIds>, clock: SharedClock) -> Self {
+        FtxOrderFillService { client_ids, clock }
     }
 
     pub async fn process_stream(
@@ -184,13 +283,16 @@ impl FtxOrderFillService {
                 WebSocketResponseType::update => {
                     log::debug!("{:?}", response);
                     if let Some(data) = response.data {
-                        let order_update = data.to_order_fill();
-                        redis
+                        let order_update = self.client_ids.to_order_fill(&data);
+                        if let Err(err) = redis
                             .publish(
                                 PublishChannel::OrderFill.to_string().as_str(),
                                 &order_update,
                             )
-                            .await;
+                            .await
+                        {
+                            log::error!("Cannot publish {:?}: {}", order_update, err);
+                        }
                     }
                 }
                 _ => {
@@ -204,14 +306,14 @@ impl FtxOrderFillService {
     }
 
     pub async fn subscribe(&self) -> anyhow::Result<()> {
-        let (mut write, mut read) = connect_ftx_authed().await?;
+        let (mut write, mut read) = connect_ftx_authed(&self.clock).await?;
         let (tx, rx) = tokio::sync::mpsc::channel(32);
         let init_message = json!({
             "op": "subscribe",
             "channel": "fills",
         });
         info!("{}", init_message);
-        write.send(Message::Text(init_message.to_string())).await;
+        write.send(Message::Text(init_message.to_string())).await?;
         let forward_write_to_ws = ReceiverStream::new(rx).map(Ok).forward(write);
         tokio::select! {
             Err(err) = self.process_stream(&mut read) => {
@@ -228,13 +330,73 @@ struct FtxOrderRequestService {
     client: Arc<FtxRestClient>,
     message_bus_sender: MessageBusSender,
     measurement_cache: Arc<MeasurementCache>,
+    kill_switch: Arc<KillSwitch>,
+    client_ids: Arc<ClientIds>,
 }
 impl FtxOrderRequestService {
-    pub fn new(message_bus_sender: MessageBusSender, client: Arc<FtxRestClient>, measurement_cache: Arc<MeasurementCache>) -> Self {
+    pub fn new(
+        message_bus_sender: MessageBusSender,
+        client: Arc<FtxRestClient>,
+        measurement_cache: Arc<MeasurementCache>,
+        kill_switch: Arc<KillSwitch>,
+        client_ids: Arc<ClientIds>,
+    ) -> Self {
         FtxOrderRequestService {
             client,
             message_bus_sender,
             measurement_cache,
+            kill_switch,
+            client_ids,
+        }
+    }
+
+    fn failed_order_update(order_request: &OrderRequest) -> OrderUpdate {
+        OrderUpdate {
+            exchange: Exchanges::FTX,
+            id: -1,
+            client_id: order_request.client_id.clone(),
+            market: order_request.market.clone(),
+            type_: order_request.type_.clone(),
+            side: order_request.side.clone(),
+            size: order_request.size,
+            price: order_request.price,
+            reduceOnly: false,
+            ioc: order_request.ioc,
+            postOnly: order_request.post_only,
+            status: OrderStatus::Failed,
+            filledSize: 0.0,
+            remainingSize: 0.0,
+            avgFillPrice: None,
+        }
+    }
+
+    async fn publish_failed_order_update(
+        message_bus_sender: &MessageBusSender,
+        order_request: &OrderRequest,
+    ) {
+        let failed_order_update = Self::failed_order_update(order_request);
+        let payload = PublishPayload {
+            channel: PublishChannel::OrderUpdate.to_string(),
+            payload: RedisBackedMessageBus::pack_json(&failed_order_update).unwrap(),
+        };
+        if let Err(err) = message_bus_sender.send(payload).await {
+            log::error!("Cannot publish {:?}: {}", failed_order_update, err);
+        }
+    }
+
+    /// engage the kill switch of every consumer, stopping the lambdas and cancelling FTX orders
+    async fn publish_kill_switch(message_bus_sender: &MessageBusSender) {
+        let command = KillSwitchCommand {
+            action: KillSwitchAction::Engage,
+            exchange: Some(Exchanges::FTX),
+            market: None,
+        };
+        let payload = PublishPayload {
+            channel: PublishChannel::KillSwitch.to_string(),
+            payload: RedisBackedMessageBus::pack_json(&command).unwrap(),
+        };
+        if let Err(err) = message_bus_sender.send(payload).await {
+            log::error!("Cannot publish {:?}: {}", command, err);
         }
     }
 
@@ -248,10 +410,14 @@ impl FtxOrderRequestService {
         client: Arc<FtxRestClient>,
         order_request: OrderRequest,
         measurement_cache: Arc<MeasurementCache>,
+        kill_switch: Arc<KillSwitch>,
+        client_ids: Arc<ClientIds>,
     ) {
         // measure toAct
         if let Some(ref client_id) = order_request.client_id {
-            if let Some(to_ack) = measurement_cache.time_end(format!("toAck:{}", client_id).as_str()) {
+            if let Some(to_ack) =
+                measurement_cache.time_end(format!("toAck:{}", client_id).as_str())
+            {
                 measurement_cache.add_point_now(&TO_ACK, to_ack as f64);
             } else {
                 panic!("no act");
@@ -260,37 +426,43 @@ impl FtxOrderRequestService {
         let event_id = order_request.client_id.clone().unwrap();
         measurement_cache.time_start(event_id.as_str());
         let original_request = order_request.clone();
-        let api_result = client.place_order(order_request).await;
+        // the switch may have engaged while the request waited on the rate limiter
+        let market = order_request.market.clone();
+        let api_result = client
+            .place_order_admitted(order_request, || {
+                !kill_switch.is_engaged(&Exchanges::FTX, market.as_str())
+            })
+            .await;
         if let Some(latency) = measurement_cache.time_end(event_id.as_str()) {
             measurement_cache.add_point_now(&ORDER_LATENCY, latency as f64);
         }
+        measurement_cache.add_point_now(&RATE_LIMIT_SATURATION, client.rate_limit_saturation());
         match api_result {
-            Ok(_response) => {}
-            Err(_) => {
+            Ok(response) => client_ids.on_order(&response),
+            Err(err) => {
+                match err {
+                    FtxApiError::PostOnlyWouldCross(_) => {
+                        log::info!("place order rejected: {}", err)
+                    }
+                    FtxApiError::NotAdmitted(_) => {
+                        warn!(
+                            "kill switch engaged. rejecting {:?}",
+                            original_request.client_id
+                        )
+                    }
+                    FtxApiError::RateLimited(_) | FtxApiError::Network(_) => {
+                        log::warn!("place order failed: {}", err)
+                    }
+                    FtxApiError::AuthFailure(_) => {
+                        // every following request would fail as well, stop the lambdas with it
+                        log::error!("place order failed: {}. engaging kill switch", err);
+                        kill_switch.engage(&KillSwitchScope::exchange(Exchanges::FTX));
+                        Self::publish_kill_switch(&message_bus_sender).await;
+                    }
+                    _ => log::error!("place order failed: {}", err),
+                }
                 // set OrderUpdate to Failed
-                let failed_order_update = OrderUpdate {
-                    exchange: Exchanges::FTX,
-                    id: -1,
-                    client_id: original_request.client_id.clone(),
-                    market: original_request.market.clone(),
-                    type_: original_request.type_.clone(),
-                    side: original_request.side.clone(),
-                    size: original_request.size.clone(),
-                    price: original_request.price.clone(),
-                    reduceOnly: false,
-                    ioc: original_request.ioc.clone(),
-                    postOnly: original_request.post_only.clone(),
-                    status: OrderStatus::Failed,
-                    filledSize: 0.0,
-                    remainingSize: 0.0,
-                    avgFillPrice: None,
-                };
-                message_bus_sender
-                    .send(PublishPayload {
-                        channel: PublishChannel::OrderUpdate.to_string(),
-                        payload: RedisBackedMessageBus::pack_json(&failed_order_update).unwrap(),
-                    })
-                    .await;
+                Self::publish_failed_order_update(&message_bus_sender, &original_request).await;
             }
         };
     }
@@ -302,10 +474,31 @@ impl MessageConsumer for FtxOrderRequestService {
             Ok(order_request) => {
                 if order_request.exchange == Exchanges::FTX {
                     log::info!("FtxOrderRequestService: {:?}", order_request);
+                    if self
+                        .kill_switch
+                        .is_engaged(&Exchanges::FTX, order_request.market.as_str())
+                    {
+                        warn!(
+                            "kill switch engaged. rejecting {:?}",
+                            order_request.client_id
+                        );
+                        Self::publish_failed_order_update(&self.message_bus_sender, &order_request)
+                            .await;
+                        return Ok(());
+                    }
                     let mbs = self.message_bus_sender.clone();
                     let client = self.client.clone();
                     let measurement_cache = self.measurement_cache.clone();
-                    tokio::spawn(Self::accept_order_request(mbs, client, order_request, measurement_cache));
+                    let kill_switch = self.kill_switch.clone();
+                    let client_ids = self.client_ids.clone();
+                    tokio::spawn(Self::accept_order_request(
+                        mbs,
+                        client,
+                        order_request,
+                        measurement_cache,
+                        kill_switch,
+                        client_ids,
+                    ));
                 }
             }
             Err(err) => {
@@ -319,12 +512,18 @@ impl MessageConsumer for FtxOrderRequestService {
 struct FtxCancelOrderService {
     message_bus_sender: MessageBusSender,
     client: Arc<FtxRestClient>,
+    measurement_cache: Arc<MeasurementCache>,
 }
 impl FtxCancelOrderService {
-    pub fn new(message_bus_sender: MessageBusSender, client: Arc<FtxRestClient>) -> Self {
+    pub fn new(
+        message_bus_sender: MessageBusSender,
+        client: Arc<FtxRestClient>,
+        measurement_cache: Arc<MeasurementCache>,
+    ) -> Self {
         FtxCancelOrderService {
             message_bus_sender,
             client,
+            measurement_cache,
         }
     }
     pub async fn subscribe(&self) -> anyhow::Result<()> {
@@ -336,27 +535,54 @@ impl FtxCancelOrderService {
         client: Arc<FtxRestClient>,
         cancel_order_request: CancelOrderRequest,
         message_bus_sender: MessageBusSender,
+        measurement_cache: Arc<MeasurementCache>,
     ) {
-        match client
-            .cancel_order_cid(cancel_order_request.client_id.as_str())
-            .await
-        {
-            Ok(_response) => {}
-            Err(err) => {
-                // FIXME: need to manual clean up order cache for pending cancel orders
-                // check if err = 400
-                log::error!("{}", err);
-                // let mut order_update = OrderUpdate::default();
-                // order_update.client_id = Some(cancel_order_request.client_id);
-                // order_update.status = OrderStatus::Failed;
-                // order_update.exchange = cancel_order_request.exchange.clone();
-                // order_update.market = cancel_order_request.market.clone();
-                // let publish_payload = PublishPayload {
-                //     channel: PublishChannel::OrderUpdate.to_string(),
-                //     payload: serde_json::to_string(&order_update).unwrap(),
-                // };
-                // message_bus_sender.send(publish_payload).await;
+        let mut attempt = 0;
+        loop {
+            attempt += 1;
+            let api_result = client
+                .cancel_order_cid(cancel_order_request.client_id.as_str())
+                .await;
+            measurement_cache.add_point_now(&RATE_LIMIT_SATURATION, client.rate_limit_saturation());
+            match api_result {
+                Ok(_response) => {}
+                Err(FtxApiError::OrderAlreadyClosed(message)) => {
+                    // the order is gone on the exchange, clean up the PendingCancel entry in cache
+                    log::info!(
+                        "cancel {}: {}. closing order",
+                        cancel_order_request.client_id,
+                        message
+                    );
+                    let order_update = OrderUpdate {
+                        client_id: Some(cancel_order_request.client_id.clone()),
+                        status: OrderStatus::Closed,
+                        exchange: cancel_order_request.exchange.clone(),
+                        market: cancel_order_request.market.clone(),
+                        ..Default::default()
+                    };
+                    let publish_payload = PublishPayload {
+                        channel: PublishChannel::OrderUpdate.to_string(),
+                        payload: serde_json::to_string(&order_update).unwrap(),
+                    };
+                    if let Err(err) = message_bus_sender.send(publish_payload).await {
+                        log::error!("Cannot publish {:?}: {}", order_update, err);
+                    }
+                }
+                Err(err) if err.is_retryable() && attempt < MAX_CANCEL_ATTEMPTS => {
+                    log::warn!(
+                        "cancel {} attempt {} failed: {}",
+                        cancel_order_request.client_id,
+                        attempt,
+                        err
+                    );
+                    tokio::time::sleep(Duration::from_millis(100 * attempt)).await;
+                    continue;
+                }
+                Err(err) => {
+                    log::error!("cancel {} failed: {}", cancel_order_request.client_id, err);
+                }
             }
+            break;
         }
     }
 }
@@ -369,18 +595,229 @@ impl MessageConsumer for FtxCancelOrderService {
                     log::info!("FtxCancelOrderService: {:?}", order_request);
                     let client = self.client.clone();
                     let message_bus_sender = self.message_bus_sender.clone();
+                    let measurement_cache = self.measurement_cache.clone();
                     tokio::spawn(Self::accept_cancel_order_request(
                         client,
                         order_request,
                         message_bus_sender,
+                        measurement_cache,
                     ));
                 }
             }
             Err(err) => {
                 log::error!("{}", err);
-                return Err(anyhow!(err))
+                return Err(anyhow!(err));
             }
         };
         Ok(())
     }
 }
+
+struct FtxModifyOrderService {
+    message_bus_sender: MessageBusSender,
+    client: Arc<FtxRestClient>,
+    measurement_cache: Arc<MeasurementCache>,
+    kill_switch: Arc<KillSwitch>,
+}
+impl FtxModifyOrderService {
+    pub fn new(
+        message_bus_sender: MessageBusSender,
+        client: Arc<FtxRestClient>,
+        measurement_cache: Arc<MeasurementCache>,
+        kill_switch: Arc<KillSwitch>,
+    ) -> Self {
+        FtxModifyOrderService {
+            message_bus_sender,
+            client,
+            measurement_cache,
+            kill_switch,
+        }
+    }
+    pub async fn subscribe(&self) -> anyhow::Result<()> {
+        RedisBackedMessageBus::subscribe_channels(vec![PublishChannel::ModifyOrder.as_ref()], self)
+            .await?;
+        Err(anyhow!("FtxModifyOrderService subscribe uncaught"))
+    }
+
+    /// The replacement order never made it to the exchange. Fail it in cache and cancel the
+    /// original order so the lambda does not keep quoting a stale price.
+    async fn reject_modify_order_request(
+        message_bus_sender: &MessageBusSender,
+        modify_order_request: &ModifyOrderRequest,
+    ) {
+        let order_update = OrderUpdate {
+            exchange: modify_order_request.exchange.clone(),
+            id: -1,
+            client_id: Some(modify_order_request.new_client_id.clone()),
+            market: modify_order_request.market.clone(),
+            price: modify_order_request.price,
+            size: modify_order_request.size,
+            status: OrderStatus::Failed,
+            ..Default::default()
+        };
+        let payload = PublishPayload {
+            channel: PublishChannel::OrderUpdate.to_string(),
+            payload: RedisBackedMessageBus::pack_json(&order_update).unwrap(),
+        };
+        if let Err(err) = message_bus_sender.send(payload).await {
+            log::error!("Cannot publish {:?}: {}", order_update, err);
+        }
+        let cancel_order_request = CancelOrderRequest {
+            exchange: modify_order_request.exchange.clone(),
+            market: modify_order_request.market.clone(),
+            client_id: modify_order_request.client_id.clone(),
+        };
+        let payload = PublishPayload {
+            channel: PublishChannel::CancelOrder.to_string(),
+            payload: RedisBackedMessageBus::pack_json(&cancel_order_request).unwrap(),
+        };
+        if let Err(err) = message_bus_sender.send(payload).await {
+            log::error!("Cannot publish {:?}: {}", cancel_order_request, err);
+        }
+    }
+
+    async fn accept_modify_order_request(
+        message_bus_sender: MessageBusSender,
+        client: Arc<FtxRestClient>,
+        modify_order_request: ModifyOrderRequest,
+        measurement_cache: Arc<MeasurementCache>,
+        kill_switch: Arc<KillSwitch>,
+    ) {
+        let event_id = modify_order_request.new_client_id.clone();
+        measurement_cache.time_start(event_id.as_str());
+        // the switch may have engaged while the request waited on the rate limiter
+        let api_result = client
+            .modify_order_cid_admitted(
+                modify_order_request.client_id.as_str(),
+                modify_order_request.price,
+                modify_order_request.size,
+                modify_order_request.new_client_id.as_str(),
+                || !kill_switch.is_engaged(&Exchanges::FTX, modify_order_request.market.as_str()),
+            )
+            .await;
+        if let Some(latency) = measurement_cache.time_end(event_id.as_str()) {
+            measurement_cache.add_point_now(&ORDER_LATENCY, latency as f64);
+        }
+        measurement_cache.add_point_now(&RATE_LIMIT_SATURATION, client.rate_limit_saturation());
+        match api_result {
+            Ok(_response) => {}
+            Err(err) => {
+                match err {
+                    FtxApiError::PostOnlyWouldCross(_) | FtxApiError::OrderAlreadyClosed(_) => {
+                        log::info!(
+                            "modify {} rejected: {}",
+                            modify_order_request.client_id,
+                            err
+                        )
+                    }
+                    FtxApiError::NotAdmitted(_) => warn!(
+                        "kill switch engaged. rejecting modify {}",
+                        modify_order_request.client_id
+                    ),
+                    _ => log::error!("modify {} failed: {}", modify_order_request.client_id, err),
+                }
+                Self::reject_modify_order_request(&message_bus_sender, &modify_order_request).await;
+            }
+        }
+    }
+}
+#[async_trait]
+impl MessageConsumer for FtxModifyOrderService {
+    async fn consume(&self, msg: &[u8]) -> anyhow::Result<()> {
+        match serde_json::from_slice::<ModifyOrderRequest>(msg) {
+            Ok(modify_order_request) => {
+                if modify_order_request.exchange == Exchanges::FTX {
+                    log::info!("FtxModifyOrderService: {:?}", modify_order_request);
+                    if self
+                        .kill_switch
+                        .is_engaged(&Exchanges::FTX, modify_order_request.market.as_str())
+                    {
+                        warn!(
+                            "kill switch engaged. rejecting modify {}",
+                            modify_order_request.client_id
+                        );
+                        Self::reject_modify_order_request(
+                            &self.message_bus_sender,
+                            &modify_order_request,
+                        )
+                        .await;
+                        return Ok(());
+                    }
+                    tokio::spawn(Self::accept_modify_order_request(
+                        self.message_bus_sender.clone(),
+                        self.client.clone(),
+                        modify_order_request,
+                        self.measurement_cache.clone(),
+                        self.kill_switch.clone(),
+                    ));
+                }
+            }
+            Err(err) => {
+                log::error!("{}", err)
+            }
+        };
+        Ok(())
+    }
+}
+
+struct FtxKillSwitchService {
+    client: Arc<FtxRestClient>,
+    kill_switch: Arc<KillSwitch>,
+    /// its own, the engine accepts the same commands
+    authenticator: Authenticator,
+    clock: SharedClock,
+}
+impl FtxKillSwitchService {
+    pub fn new(
+        client: Arc<FtxRestClient>,
+        kill_switch: Arc<KillSwitch>,
+        authenticator: Authenticator,
+        clock: SharedClock,
+    ) -> Self {
+        FtxKillSwitchService {
+            client,
+            kill_switch,
+            authenticator,
+            clock,
+        }
+    }
+    pub async fn subscribe(&self) -> anyhow::Result<()> {
+        RedisBackedMessageBus::subscribe_channels(vec![PublishChannel::KillSwitch.as_ref()], self)
+            .await?;
+        Err(anyhow!("FtxKillSwitchService subscribe uncaught"))
+    }
+}
+#[async_trait]
+impl MessageConsumer for FtxKillSwitchService {
+    async fn consume(&self, msg: &[u8]) -> anyhow::Result<()> {
+        let now_ms = self.clock.now_ms();
+        let command = match KillSwitchCommand::authorize(msg, &self.authenticator, now_ms) {
+            Ok(command) => command,
+            Err(err) => {
+                log::warn!("Rejected KillSwitch: {}", err);
+                return Ok(());
+            }
+        };
+        if !command.applies_to(&Exchanges::FTX) {
+            return Ok(());
+        }
+        log::warn!("FtxKillSwitchService: {:?}", command);
+        let scope = command.scope();
+        match command.action {
+            KillSwitchAction::Engage => {
+                // block new orders before cancelling so nothing slips in behind cancel-all
+                self.kill_switch.engage(&scope);
+                match self
+                    .client
+                    .cancel_all_orders(command.market.as_deref())
+                    .await
+                {
+                    Ok(response) => log::warn!("cancel all orders: {}", response),
+                    Err(err) => log::error!("cancel all orders failed: {}", err),
+                }
+            }
+            KillSwitchAction::Rearm => self.kill_switch.rearm(&scope),
+        }
+        Ok(())
+    }
+}
@@ -8,6 +8,7 @@ use tokio_stream::wrappers::ReceiverStream;
 use tokio_tungstenite::tungstenite::Message;
 use tokio_tungstenite::{MaybeTlsStream, WebSocketStream};
 
+use crate::core::clock::SharedClock;
 use crate::ftx::types::{FtxOrderBookData, WebSocketResponse};
 use crate::ftx::utils::{connect_ftx, format_float, ping_pong};
 use crate::model::constants::{Exchanges, PublishChannel};
@@ -93,20 +94,21 @@ pub fn validate_checksum(checksum: u32, md: &MarketDepth) -> bool {
     hasher.update(sign.as_bytes());
     let crc = hasher.finalize();
     log::debug!("checksum: {}, crc: {}, sign: {}", checksum, crc, sign);
-    return checksum == crc;
+    checksum == crc
 }
 
 pub async fn subscribe_message(
     stream: &mut SplitStream<WebSocketStream<MaybeTlsStream<TcpStream>>>,
     message_bus_sender: &tokio::sync::mpsc::Sender<PublishPayload>,
+    clock: &SharedClock,
 ) -> anyhow::Result<()> {
     let mut bids_ob = PriceMap::new();
     let mut asks_ob = PriceMap::new();
     while let Some(msg) = stream.next().await {
         let msg = msg?;
-        let time_start_ns = chrono::Utc::now().timestamp_nanos();
+        let time_start_ns = clock.now_ns();
         let parse_result =
-            serde_json::from_slice::<WebSocketResponse<FtxOrderBookData>>(&mut *msg.into_data());
+            serde_json::from_slice::<WebSocketResponse<FtxOrderBookData>>(&msg.into_data());
         match parse_result {
             Ok(response) => {
                 log::debug!("{:?}", response);
@@ -116,7 +118,7 @@ pub async fn subscribe_message(
                     let snapshot = MarketDepth {
                         exchange: Exchanges::FTX,
                         market: market.clone(),
-                        timestamp: chrono::Utc::now().timestamp_millis(),
+                        timestamp: clock.now_ms(),
                         bids: keys_of_btree(&bids_ob, true),
                         asks: keys_of_btree(&asks_ob, false),
                     };
@@ -127,8 +129,8 @@ pub async fn subscribe_message(
                     let payload = PublishPayload {
                         channel: format!(
                             "{}:{}:{}",
-                            PublishChannel::MarketDepth.to_string(),
-                            Exchanges::FTX.to_string(),
+                            PublishChannel::MarketDepth,
+                            Exchanges::FTX,
                             market
                         ),
                         payload: serde_json::to_string(&snapshot)?,
@@ -138,7 +140,7 @@ pub async fn subscribe_message(
                         log::error!("md process msg error: {}", err);
                     }
 
-                    let time_end_ns = chrono::Utc::now().timestamp_nanos();
+                    let time_end_ns = clock.now_ns();
                     log::info!(
                         "process marketdepth after publish: {} nanos, {} ms",
                         time_end_ns - time_start_ns,
@@ -160,13 +162,16 @@ pub async fn subscribe_message(
     Ok(())
 }
 
-pub async fn market_depth(market: &str) -> Result<(), Box<dyn std::error::Error>> {
+pub async fn market_depth(
+    market: &str,
+    clock: SharedClock,
+) -> Result<(), Box<dyn std::error::Error>> {
     let (write, mut sub) = connect_ftx().await?;
     let (msg_tx, rx) = tokio::sync::mpsc::channel(32);
     let forward_write_to_ws = ReceiverStream::new(rx)
         .map(|x| {
             log::info!("send {}", x);
-            return x;
+            x
         })
         .map(Ok)
         .forward(write);
@@ -180,13 +185,13 @@ pub async fn market_depth(market: &str) -> Result<(), Box<dyn std::error::Error>
         "channel": "orderbook",
         "market": market,
     });
-    msg_tx.send(Message::Text(init_message.to_string())).await;
+    msg_tx.send(Message::Text(init_message.to_string())).await?;
 
     // polling message bus publisher
     let message_bus_poll = message_bus.subscribe();
 
     tokio::select! {
-        Err(err) = subscribe_message(&mut sub, &message_bus_sender) => {
+        Err(err) = subscribe_message(&mut sub, &message_bus_sender, &clock) => {
             log::error!("subscribe_message error: {}", err);
         },
         Err(err) = forward_write_to_ws => {
@@ -1,11 +1,19 @@
+mod error;
 pub mod ftx_order_gateway;
 pub mod market_depth;
+pub mod rate_limiter;
 mod rest;
 mod rest_tests;
 pub mod ticker;
+pub mod trades;
 mod types;
 mod utils;
 
+pub use error::FtxApiError;
 pub use rest::FtxRestClient;
+pub use utils::{sign_hmac_sha256, verify_hmac_sha256};
 
-pub use types::{FtxPlaceOrder, FtxOrderType, FtxOrderSide, FtxOrderStatus};
\ No newline at end of file
+pub use types::{
+    ApiResponse, FtxAccountInfo, FtxFutureStats, FtxOrderData, FtxOrderFill, FtxOrderSide, FtxOrderStatus,
+    FtxOrderType, FtxPlaceOrder, FtxPosition,
+};
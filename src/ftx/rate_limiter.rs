@@ -0,0 +1,234 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::sync::Notify;
+use tokio::time::Instant;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, strum_macros::Display)]
+pub enum EndpointClass {
+    PlaceOrder,
+    CancelOrder,
+    Other,
+}
+
+#[derive(Debug, Clone)]
+pub struct BucketParams {
+    pub capacity: f64,
+    pub refill_per_sec: f64,
+}
+
+#[derive(Debug, Clone)]
+pub struct RateLimiterParams {
+    /// account wide budget shared by every endpoint class
+    pub global: BucketParams,
+    pub place_order: BucketParams,
+    pub cancel_order: BucketParams,
+    pub other: BucketParams,
+    /// global tokens only cancels may consume, so a burst of new orders can never starve cancels
+    pub cancel_reserve: f64,
+}
+impl Default for RateLimiterParams {
+    fn default() -> Self {
+        // FTX allows roughly 30 requests per 200ms
+        RateLimiterParams {
+            global: BucketParams {
+                capacity: 30.0,
+                refill_per_sec: 150.0,
+            },
+            place_order: BucketParams {
+                capacity: 20.0,
+                refill_per_sec: 100.0,
+            },
+            cancel_order: BucketParams {
+                capacity: 30.0,
+                refill_per_sec: 150.0,
+            },
+            other: BucketParams {
+                capacity: 10.0,
+                refill_per_sec: 20.0,
+            },
+            cancel_reserve: 5.0,
+        }
+    }
+}
+
+struct TokenBucket {
+    params: BucketParams,
+    tokens: f64,
+    last_refill: Instant,
+}
+impl TokenBucket {
+    fn new(params: BucketParams) -> Self {
+        TokenBucket {
+            tokens: params.capacity,
+            params,
+            last_refill: Instant::now(),
+        }
+    }
+
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now
+            .saturating_duration_since(self.last_refill)
+            .as_secs_f64();
+        self.tokens =
+            (self.tokens + elapsed * self.params.refill_per_sec).min(self.params.capacity);
+        self.last_refill = now;
+    }
+
+    /// time until `tokens` are available
+    fn wait_for(&self, tokens: f64) -> Duration {
+        if self.tokens >= tokens {
+            return Duration::from_millis(0);
+        }
+        Duration::from_secs_f64((tokens - self.tokens) / self.params.refill_per_sec)
+    }
+}
+
+struct RateLimiterInner {
+    global: TokenBucket,
+    classes: HashMap<EndpointClass, TokenBucket>,
+    blocked_until: Option<Instant>,
+    waiting_cancels: usize,
+}
+
+/// why a token could not be taken
+enum Wait {
+    /// until the waiting cancels are through
+    Cancels,
+    For(Duration),
+}
+
+/// Counts a cancel as waiting while alive. Dropped with the `acquire` future, so a cancelled
+/// acquire never blocks the other classes.
+struct WaitingCancel<'a>(&'a RateLimiter);
+impl<'a> WaitingCancel<'a> {
+    fn new(rate_limiter: &'a RateLimiter) -> Self {
+        rate_limiter.inner.lock().unwrap().waiting_cancels += 1;
+        WaitingCancel(rate_limiter)
+    }
+}
+impl Drop for WaitingCancel<'_> {
+    fn drop(&mut self) {
+        let mut inner = self.0.inner.lock().unwrap();
+        inner.waiting_cancels -= 1;
+        if inner.waiting_cancels == 0 {
+            self.0.cancels_done.notify_waiters();
+        }
+    }
+}
+
+/// Token bucket rate limiter with a global budget and a budget per endpoint class.
+/// Cancels take priority over every other class: while a cancel is waiting no other request is
+/// admitted, and the last `cancel_reserve` global tokens are kept for cancels.
+pub struct RateLimiter {
+    params: RateLimiterParams,
+    inner: Mutex<RateLimiterInner>,
+    /// wakes the other classes once no cancel is waiting
+    cancels_done: Notify,
+}
+
+impl RateLimiter {
+    pub fn new(params: RateLimiterParams) -> Self {
+        let mut classes = HashMap::new();
+        classes.insert(
+            EndpointClass::PlaceOrder,
+            TokenBucket::new(params.place_order.clone()),
+        );
+        classes.insert(
+            EndpointClass::CancelOrder,
+            TokenBucket::new(params.cancel_order.clone()),
+        );
+        classes.insert(EndpointClass::Other, TokenBucket::new(params.other.clone()));
+        RateLimiter {
+            inner: Mutex::new(RateLimiterInner {
+                global: TokenBucket::new(params.global.clone()),
+                classes,
+                blocked_until: None,
+                waiting_cancels: 0,
+            }),
+            params,
+            cancels_done: Notify::new(),
+        }
+    }
+
+    /// try to take a token, returning what to wait for before the next attempt if none is available
+    fn try_acquire(&self, class: EndpointClass) -> Result<(), Wait> {
+        let now = Instant::now();
+        let mut inner = self.inner.lock().unwrap();
+        if let Some(blocked_until) = inner.blocked_until {
+            if blocked_until > now {
+                return Err(Wait::For(blocked_until - now));
+            }
+            inner.blocked_until = None;
+        }
+        if class != EndpointClass::CancelOrder && inner.waiting_cancels > 0 {
+            return Err(Wait::Cancels);
+        }
+        let reserve = match class {
+            EndpointClass::CancelOrder => 0.0,
+            _ => self.params.cancel_reserve,
+        };
+        inner.global.refill(now);
+        let global_wait = inner.global.wait_for(1.0 + reserve);
+        let bucket = inner.classes.get_mut(&class).unwrap();
+        bucket.refill(now);
+        let class_wait = bucket.wait_for(1.0);
+        if global_wait.as_nanos() > 0 || class_wait.as_nanos() > 0 {
+            return Err(Wait::For(global_wait.max(class_wait)));
+        }
+        bucket.tokens -= 1.0;
+        inner.global.tokens -= 1.0;
+        Ok(())
+    }
+
+    pub async fn acquire(&self, class: EndpointClass) {
+        let mut waiting = false;
+        let mut _waiting_cancel = None;
+        loop {
+            // created before the check so a cancel finishing in between still wakes us
+            let cancels_done = self.cancels_done.notified();
+            match self.try_acquire(class) {
+                Ok(_) => return,
+                Err(Wait::Cancels) => {
+                    if !waiting {
+                        debug!("rate limiter: {} waits for cancels", class);
+                    }
+                    waiting = true;
+                    cancels_done.await;
+                }
+                Err(Wait::For(wait)) => {
+                    if !waiting {
+                        debug!("rate limiter saturated: {} waits {:?}", class, wait);
+                        if class == EndpointClass::CancelOrder {
+                            _waiting_cancel = Some(WaitingCancel::new(self));
+                        }
+                    }
+                    waiting = true;
+                    tokio::time::sleep(wait).await;
+                }
+            }
+        }
+    }
+
+    /// block every endpoint class until `retry_after` elapsed, e.g. after an HTTP 429
+    pub fn on_rate_limited(&self, retry_after: Duration) {
+        let until = Instant::now() + retry_after;
+        let mut inner = self.inner.lock().unwrap();
+        match inner.blocked_until {
+            Some(blocked_until) if blocked_until >= until => {}
+            _ => inner.blocked_until = Some(until),
+        }
+    }
+
+    /// share of the global budget in use, from 0.0 (idle) to 1.0 (saturated)
+    pub fn saturation(&self) -> f64 {
+        let mut inner = self.inner.lock().unwrap();
+        if let Some(blocked_until) = inner.blocked_until {
+            if blocked_until > Instant::now() {
+                return 1.0;
+            }
+        }
+        inner.global.refill(Instant::now());
+        1.0 - (inner.global.tokens / inner.global.params.capacity).max(0.0)
+    }
+}
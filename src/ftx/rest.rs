@@ -1,20 +1,37 @@
 use crate::core::config::ConfigStore;
-use crate::ftx::types::FtxPlaceOrder;
+use crate::ftx::error::FtxApiError;
+use crate::ftx::rate_limiter::{EndpointClass, RateLimiter, RateLimiterParams};
+use crate::ftx::types::{
+    ApiResponse, FtxAccountInfo, FtxFutureStats, FtxOrderData, FtxOrderFill, FtxPlaceOrder,
+};
 use crate::model::OrderRequest;
 use hmac::{Hmac, Mac, NewMac};
-use reqwest::header::{HeaderMap, CONTENT_TYPE};
-use reqwest::RequestBuilder;
+use reqwest::header::{HeaderMap, CONTENT_TYPE, RETRY_AFTER};
+use reqwest::{RequestBuilder, Response, StatusCode};
+use serde::de::DeserializeOwned;
+use serde_json::json;
 use sha2::Sha256;
 
 use std::collections::HashMap;
+use std::time::Duration;
 
 pub struct FtxRestClient {
     base_url: String,
     client: reqwest::Client,
     secret: String,
+    rate_limiter: RateLimiter,
 }
 
-type ApiResult = anyhow::Result<serde_json::Value>;
+type ApiResult<T> = Result<T, FtxApiError>;
+
+/// retries of a request answered with HTTP 429 before giving up
+const MAX_RATE_LIMITED_RETRIES: u32 = 3;
+
+impl Default for FtxRestClient {
+    fn default() -> Self {
+        FtxRestClient::new()
+    }
+}
 
 impl FtxRestClient {
     pub fn new() -> FtxRestClient {
@@ -29,6 +46,61 @@ impl FtxRestClient {
             client,
             base_url,
             secret: config.ftx_api_secret,
+            rate_limiter: RateLimiter::new(RateLimiterParams::default()),
+        }
+    }
+
+    pub fn rate_limit_saturation(&self) -> f64 {
+        self.rate_limiter.saturation()
+    }
+
+    /// send a request through the rate limiter, retrying on HTTP 429 after the Retry-After delay.
+    /// `build_request` is called for every attempt since the signature depends on the timestamp
+    async fn send<F>(&self, class: EndpointClass, build_request: F) -> ApiResult<Response>
+    where
+        F: Fn() -> RequestBuilder,
+    {
+        self.send_admitted(class, || true, build_request).await
+    }
+
+    /// `send` asking `admit` after every wait on the rate limiter, a request it turns down fails
+    /// with `NotAdmitted` without being sent
+    async fn send_admitted<A, F>(
+        &self,
+        class: EndpointClass,
+        admit: A,
+        build_request: F,
+    ) -> ApiResult<Response>
+    where
+        A: Fn() -> bool,
+        F: Fn() -> RequestBuilder,
+    {
+        let mut retries = 0;
+        loop {
+            self.rate_limiter.acquire(class).await;
+            if !admit() {
+                return Err(FtxApiError::NotAdmitted(class.to_string()));
+            }
+            let response = build_request().send().await?;
+            if response.status() != StatusCode::TOO_MANY_REQUESTS {
+                return Ok(response);
+            }
+            let retry_after = response
+                .headers()
+                .get(RETRY_AFTER)
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| value.parse::<f64>().ok())
+                .map(Duration::from_secs_f64)
+                .unwrap_or_else(|| Duration::from_millis(500 * 2u64.pow(retries)));
+            warn!("{} rate limited. retry after {:?}", class, retry_after);
+            self.rate_limiter.on_rate_limited(retry_after);
+            retries += 1;
+            if retries > MAX_RATE_LIMITED_RETRIES {
+                return Err(FtxApiError::RateLimited(format!(
+                    "{} retries exhausted for {}",
+                    MAX_RATE_LIMITED_RETRIES, class
+                )));
+            }
         }
     }
 
@@ -53,20 +125,27 @@ impl FtxRestClient {
         // const sign = CryptoJS.HmacSHA256(`${ts}websocket_login`, secret).toString();
         let mut mac = HmacSha256::new_from_slice(secret.as_bytes()).unwrap();
         let payload = match method {
-            "GET" | "DELETE" => match params {
-                None => format!(
+            "GET" | "DELETE" => match (params, json_body) {
+                (None, None) => format!(
                     "{ts}{method}{request_path}",
                     ts = ts,
                     method = method,
                     request_path = request_path
                 ),
-                Some(params) => format!(
+                (Some(params), _) => format!(
                     "{ts}{method}{request_path}?{params}",
                     ts = ts,
                     method = method,
                     request_path = request_path,
                     params = params
                 ),
+                (None, Some(json_body)) => format!(
+                    "{ts}{method}{request_path}{json_body}",
+                    ts = ts,
+                    method = method,
+                    request_path = request_path,
+                    json_body = json_body
+                ),
             },
             "POST" => match json_body {
                 None => format!(
@@ -92,7 +171,7 @@ impl FtxRestClient {
 
         let result = mac.finalize().into_bytes();
 
-        return hex::encode(result);
+        hex::encode(result)
     }
 
     pub fn get(&self, path: &str, params: Option<HashMap<String, String>>) -> RequestBuilder {
@@ -110,12 +189,10 @@ impl FtxRestClient {
             Some(_) => url.query(),
         };
         let sign = Self::generate_signature(&self.secret, ts, "GET", url.path(), query, None);
-        let request = self
-            .client
+        self.client
             .get(url)
             .header("FTX-TS", ts)
-            .header("FTX-SIGN", sign);
-        request
+            .header("FTX-SIGN", sign)
     }
 
     pub fn post(&self, path: &str, json: serde_json::Value) -> RequestBuilder {
@@ -132,14 +209,12 @@ impl FtxRestClient {
             None,
             Option::from(json_body.as_str()),
         );
-        let request = self
-            .client
+        self.client
             .post(url)
             .body(json_body)
             .header(CONTENT_TYPE, "application/json")
             .header("FTX-TS", ts)
-            .header("FTX-SIGN", sign);
-        request
+            .header("FTX-SIGN", sign)
     }
 
     pub fn delete(&self, path: &str, params: Option<HashMap<String, String>>) -> RequestBuilder {
@@ -157,73 +232,186 @@ impl FtxRestClient {
             Some(_) => url.query(),
         };
         let sign = Self::generate_signature(&self.secret, ts, "DELETE", url.path(), query, None);
-        let request = self
-            .client
+        self.client
+            .delete(url)
+            .header("FTX-TS", ts)
+            .header("FTX-SIGN", sign)
+    }
+
+    pub fn delete_json(&self, path: &str, json: serde_json::Value) -> RequestBuilder {
+        let ts = chrono::Utc::now().timestamp_millis();
+        let request_path = format!("{base_url}api{path}", base_url = self.base_url, path = path);
+        let url = reqwest::Url::parse(request_path.as_str()).unwrap();
+        let json_body = serde_json::to_string(&json).unwrap();
+        let sign = Self::generate_signature(
+            &self.secret,
+            ts,
+            "DELETE",
+            url.path(),
+            None,
+            Option::from(json_body.as_str()),
+        );
+        self.client
             .delete(url)
+            .body(json_body)
+            .header(CONTENT_TYPE, "application/json")
             .header("FTX-TS", ts)
-            .header("FTX-SIGN", sign);
-        request
+            .header("FTX-SIGN", sign)
     }
 }
 
 // API methods
 impl FtxRestClient {
-    pub async fn get_account(&self) -> Result<String, Box<dyn std::error::Error>> {
-        let request = self.get("/account", None);
-        let response = request.send().await?;
-        let json = response.text().await?;
-        Ok(json)
+    async fn parse_response<T: DeserializeOwned>(response: Response) -> ApiResult<T> {
+        let status = response.status();
+        let body = response.text().await?;
+        let api_response = match serde_json::from_str::<ApiResponse<T>>(body.as_str()) {
+            Ok(api_response) => api_response,
+            Err(err) => {
+                if status.is_success() {
+                    return Err(FtxApiError::InvalidResponse(err));
+                }
+                return Err(FtxApiError::from_response(status, body.as_str()));
+            }
+        };
+        match (api_response.success, api_response.result) {
+            (true, Some(result)) => Ok(result),
+            (true, None) => Err(FtxApiError::Other(status, "missing result".to_string())),
+            (false, _) => Err(FtxApiError::from_response(
+                status,
+                api_response.error.unwrap_or_default().as_str(),
+            )),
+        }
+    }
+
+    pub async fn get_account(&self) -> ApiResult<FtxAccountInfo> {
+        let response = self
+            .send(EndpointClass::Other, || self.get("/account", None))
+            .await?;
+        Self::parse_response(response).await
     }
 
-    pub async fn get_fills(&self, market: &str) -> Result<String, Box<dyn std::error::Error>> {
+    pub async fn get_fills(&self, market: &str) -> ApiResult<Vec<FtxOrderFill>> {
         let mut params = HashMap::new();
         params.insert(String::from("market"), market.to_string());
-        let request = self.get("/fills", Option::from(params));
-        let response = request.send().await?;
-        let json = response.text().await?;
-        Ok(json)
+        let response = self
+            .send(EndpointClass::Other, || {
+                self.get("/fills", Option::from(params.clone()))
+            })
+            .await?;
+        Self::parse_response(response).await
+    }
+
+    /// open orders of the account, or only those of `market` if given
+    pub async fn get_open_orders(&self, market: Option<&str>) -> ApiResult<Vec<FtxOrderData>> {
+        let params = market.map(|market| {
+            let mut params = HashMap::new();
+            params.insert(String::from("market"), market.to_string());
+            params
+        });
+        let response = self
+            .send(EndpointClass::Other, || self.get("/orders", params.clone()))
+            .await?;
+        Self::parse_response(response).await
+    }
+
+    pub async fn get_future_stats(&self, future: &str) -> ApiResult<FtxFutureStats> {
+        let path = format!("/futures/{}/stats", future);
+        let response = self
+            .send(EndpointClass::Other, || self.get(path.as_str(), None))
+            .await?;
+        Self::parse_response(response).await
     }
 
-    pub async fn place_order(&self, order: OrderRequest) -> ApiResult {
+    pub async fn place_order(&self, order: OrderRequest) -> ApiResult<FtxOrderData> {
+        self.place_order_admitted(order, || true).await
+    }
+
+    /// `place_order` unless `admit` turns it down once the rate limiter let it through, e.g. as
+    /// the kill switch engaged while it waited
+    pub async fn place_order_admitted<A>(
+        &self,
+        order: OrderRequest,
+        admit: A,
+    ) -> ApiResult<FtxOrderData>
+    where
+        A: Fn() -> bool,
+    {
         let ftx_request = FtxPlaceOrder::from_order_request(order);
         let json = serde_json::to_value(ftx_request)?;
-        let request = self.post("/orders", json);
-        let response = request.send().await?;
-        let json = response.json::<serde_json::Value>().await?;
-        match json["success"].as_bool() {
-            None => {}
-            Some(success) => {
-                if !success {
-                    error!("{}", json);
-                    return Err(anyhow::anyhow!("{}", json))
-                }
-            }
-        }
-        Ok(json)
+        let response = self
+            .send_admitted(EndpointClass::PlaceOrder, admit, || {
+                self.post("/orders", json.clone())
+            })
+            .await?;
+        Self::parse_response(response).await
     }
 
-    pub async fn cancel_order(&self, order_id: i64) -> ApiResult {
-        let request = self.delete(format!("/orders/{}", order_id).as_str(), None);
-        let response = request.send().await?;
-        let json = response.json::<serde_json::Value>().await?;
-        Ok(json)
+    pub async fn cancel_order(&self, order_id: i64) -> ApiResult<String> {
+        let path = format!("/orders/{}", order_id);
+        let response = self
+            .send(EndpointClass::CancelOrder, || {
+                self.delete(path.as_str(), None)
+            })
+            .await?;
+        Self::parse_response(response).await
     }
 
-    pub async fn cancel_order_cid(&self, cid: &str) -> ApiResult {
-        let request = self.delete(format!("/orders/by_client_id/{}", cid).as_str(), None);
-        let response = request.send().await?;
-        let json = response.json::<serde_json::Value>().await?;
-        match json["success"].as_bool() {
-            None => {
-                return Err(anyhow!("Missing success field"))
-            }
-            Some(success) => {
-                if (success) {
-                    return Ok(json)
-                } else {
-                    return Err(anyhow!(format!("{:?}", json["error"].as_str())))
-                }
-            }
-        }
+    pub async fn cancel_order_cid(&self, cid: &str) -> ApiResult<String> {
+        let path = format!("/orders/by_client_id/{}", cid);
+        let response = self
+            .send(EndpointClass::CancelOrder, || {
+                self.delete(path.as_str(), None)
+            })
+            .await?;
+        Self::parse_response(response).await
+    }
+
+    /// FTX closes the order `cid` and opens a replacement order under `new_cid`
+    pub async fn modify_order_cid(
+        &self,
+        cid: &str,
+        price: f64,
+        size: f64,
+        new_cid: &str,
+    ) -> ApiResult<FtxOrderData> {
+        self.modify_order_cid_admitted(cid, price, size, new_cid, || true)
+            .await
+    }
+
+    /// `modify_order_cid` unless `admit` turns it down once the rate limiter let it through
+    pub async fn modify_order_cid_admitted<A>(
+        &self,
+        cid: &str,
+        price: f64,
+        size: f64,
+        new_cid: &str,
+        admit: A,
+    ) -> ApiResult<FtxOrderData>
+    where
+        A: Fn() -> bool,
+    {
+        let path = format!("/orders/by_client_id/{}/modify", cid);
+        let body = json!({ "price": price, "size": size, "clientId": new_cid });
+        let response = self
+            .send_admitted(EndpointClass::PlaceOrder, admit, || {
+                self.post(path.as_str(), body.clone())
+            })
+            .await?;
+        Self::parse_response(response).await
+    }
+
+    /// cancel all open orders of the account, or only those of `market` if given
+    pub async fn cancel_all_orders(&self, market: Option<&str>) -> ApiResult<String> {
+        let body = match market {
+            None => json!({}),
+            Some(market) => json!({ "market": market }),
+        };
+        let response = self
+            .send(EndpointClass::CancelOrder, || {
+                self.delete_json("/orders", body.clone())
+            })
+            .await?;
+        Self::parse_response(response).await
     }
 }
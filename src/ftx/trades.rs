@@ -0,0 +1,83 @@
+use futures_util::stream::SplitStream;
+use futures_util::StreamExt;
+use serde_json::json;
+
+use tokio::net::TcpStream;
+use tokio_stream::wrappers::ReceiverStream;
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::{MaybeTlsStream, WebSocketStream};
+
+use crate::core::clock::SharedClock;
+use crate::ftx::types::{FtxTrade, WebSocketResponse};
+use crate::ftx::utils::{connect_ftx, ping_pong};
+use crate::model::constants::Exchanges;
+use crate::model::market_data_model::Trade;
+use crate::pubsub::simple_message_bus::RedisBackedMessageBus;
+use crate::pubsub::PublishPayload;
+
+pub async fn subscribe_message(
+    stream: &mut SplitStream<WebSocketStream<MaybeTlsStream<TcpStream>>>,
+    message_bus_sender: &tokio::sync::mpsc::Sender<PublishPayload>,
+    clock: &SharedClock,
+) -> anyhow::Result<()> {
+    while let Some(msg) = stream.next().await {
+        let msg = msg?;
+        let response =
+            match serde_json::from_slice::<WebSocketResponse<Vec<FtxTrade>>>(&msg.into_data()) {
+                Ok(response) => response,
+                Err(err) => {
+                    log::error!("Error parsing trades. Error: {}", err);
+                    continue;
+                }
+            };
+        let (market, trades) = match (response.market, response.data) {
+            (Some(market), Some(trades)) => (market, trades),
+            _ => continue,
+        };
+        let channel = Trade::channel(&Exchanges::FTX, market.as_str());
+        for trade in trades {
+            let payload = PublishPayload {
+                channel: channel.clone(),
+                payload: serde_json::to_string(&trade.to_trade(market.as_str(), clock.now_ms()))?,
+            };
+            if let Err(err) = message_bus_sender.send(payload).await {
+                log::error!("trades process msg error: {}", err);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// publish the trades of `market` on `Trades:FTX:{market}`
+pub async fn trades(market: &str, clock: SharedClock) -> Result<(), Box<dyn std::error::Error>> {
+    let (write, mut sub) = connect_ftx().await?;
+    let (msg_tx, rx) = tokio::sync::mpsc::channel(32);
+    let forward_write_to_ws = ReceiverStream::new(rx).map(Ok).forward(write);
+
+    let message_bus = RedisBackedMessageBus::new().await?;
+    let message_bus_sender = message_bus.publish_tx.clone();
+    let init_message = json!({
+        "op": "subscribe",
+        "channel": "trades",
+        "market": market,
+    });
+    msg_tx.send(Message::Text(init_message.to_string())).await?;
+
+    let message_bus_poll = message_bus.subscribe();
+
+    tokio::select! {
+        Err(err) = subscribe_message(&mut sub, &message_bus_sender, &clock) => {
+            log::error!("subscribe_message error: {}", err);
+        },
+        Err(err) = forward_write_to_ws => {
+            log::error!("forward_write_to_ws error: {}", err);
+        }
+        Err(err) = ping_pong(msg_tx) => {
+            log::error!("ping_pong error: {}", err);
+        },
+        Err(err) = message_bus_poll => {
+            log::error!("message_bus_poll error: {}", err);
+        },
+    }
+    Ok(())
+}
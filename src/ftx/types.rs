@@ -1,4 +1,5 @@
 use crate::model::constants::Exchanges;
+use crate::model::market_data_model::Trade;
 use crate::model::{OrderFill, OrderRequest, OrderSide, OrderStatus, OrderType, OrderUpdate};
 use serde::{Deserialize, Serialize};
 
@@ -27,13 +28,20 @@ pub enum FtxOrderStatus {
     open,
     closed,
 }
-#[derive(Deserialize, Serialize, Debug, PartialEq)]
+#[derive(Deserialize, Serialize, Debug, PartialEq, Clone)]
 #[allow(non_camel_case_types)]
 pub enum FtxOrderSide {
     buy,
     sell,
 }
 
+#[derive(Deserialize, Serialize, Debug)]
+pub struct ApiResponse<T> {
+    pub success: bool,
+    pub result: Option<T>,
+    pub error: Option<String>,
+}
+
 #[derive(Deserialize, Serialize, Debug)]
 pub struct WebSocketResponse<DataType> {
     pub channel: Option<String>,
@@ -55,6 +63,32 @@ pub struct FtxOrderBookData {
     pub time: f64,
 }
 
+/// a trade of the `trades` channel
+#[derive(Deserialize, Serialize, Debug)]
+pub struct FtxTrade {
+    pub id: i64,
+    pub price: f64,
+    pub size: f64,
+    pub side: FtxOrderSide,
+    pub liquidation: bool,
+    pub time: String,
+}
+impl FtxTrade {
+    pub fn to_trade(&self, market: &str, timestamp: i64) -> Trade {
+        Trade {
+            timestamp,
+            exchange: Exchanges::FTX,
+            market: market.to_string(),
+            side: match self.side {
+                FtxOrderSide::buy => OrderSide::Buy,
+                FtxOrderSide::sell => OrderSide::Sell,
+            },
+            price: self.price,
+            size: self.size,
+        }
+    }
+}
+
 #[derive(Deserialize, Serialize, Debug)]
 #[allow(non_camel_case_types, non_snake_case)]
 pub struct FtxOrderData {
@@ -79,7 +113,7 @@ impl FtxOrderData {
     pub fn to_order_update(&self) -> OrderUpdate {
         OrderUpdate {
             exchange: Exchanges::FTX,
-            id: self.id.clone(),
+            id: self.id,
             client_id: self.clientId.clone(),
             market: self.market.clone(),
             type_: match self.type_ {
@@ -144,6 +178,7 @@ impl FtxOrderFill {
             size: self.size,
             time: self.time.clone(),
             type_: self.type_.clone(),
+            client_id: None,
         }
     }
 }
@@ -193,7 +228,46 @@ impl FtxPlaceOrder {
                 OrderType::Limit => or.post_only,
                 OrderType::Market => false,
             },
-            clientId: Option::from(or.client_id),
+            clientId: or.client_id,
         }
     }
 }
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[allow(non_camel_case_types, non_snake_case)]
+pub struct FtxPosition {
+    pub future: String,
+    pub size: f64,
+    pub side: FtxOrderSide,
+    pub netSize: f64,
+    pub longOrderSize: f64,
+    pub shortOrderSize: f64,
+    pub cost: f64,
+    pub entryPrice: Option<f64>,
+    pub unrealizedPnl: f64,
+    pub realizedPnl: f64,
+    pub estimatedLiquidationPrice: Option<f64>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[allow(non_camel_case_types, non_snake_case)]
+pub struct FtxAccountInfo {
+    pub username: String,
+    pub collateral: f64,
+    pub freeCollateral: f64,
+    pub totalAccountValue: f64,
+    pub totalPositionSize: f64,
+    pub leverage: f64,
+    pub marginFraction: Option<f64>,
+    pub positions: Vec<FtxPosition>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[allow(non_camel_case_types, non_snake_case)]
+pub struct FtxFutureStats {
+    pub volume: f64,
+    /// predicted rate of the next hourly funding, paid by longs when positive
+    pub nextFundingRate: Option<f64>,
+    pub nextFundingTime: Option<String>,
+    pub openInterest: Option<f64>,
+}
@@ -11,12 +11,13 @@ use hmac::{Hmac, Mac, NewMac};
 use sha2::Sha256;
 
 use crate::conn::websocket::connect_wss_async;
+use crate::core::clock::SharedClock;
 use crate::core::config::ConfigStore;
 
 pub(crate) async fn ping_pong(write: tokio::sync::mpsc::Sender<Message>) -> anyhow::Result<()> {
     loop {
         let ping = json!({"op": "ping"});
-        write.send(Message::Text(ping.to_string())).await;
+        write.send(Message::Text(ping.to_string())).await?;
         tokio::time::sleep(Duration::from_secs(10)).await;
     }
 }
@@ -26,18 +27,20 @@ pub async fn connect_ftx() -> anyhow::Result<(
     SplitStream<WebSocketStream<MaybeTlsStream<TcpStream>>>,
 )> {
     let url = "wss://ftx.com/ws/";
-    let socket = connect_wss_async(&url).await?;
+    let socket = connect_wss_async(url).await?;
     let (write, read) = socket.split();
     Ok((write, read))
 }
 
-pub async fn connect_ftx_authed() -> anyhow::Result<(
+pub async fn connect_ftx_authed(
+    clock: &SharedClock,
+) -> anyhow::Result<(
     SplitSink<WebSocketStream<MaybeTlsStream<TcpStream>>, Message>,
     SplitStream<WebSocketStream<MaybeTlsStream<TcpStream>>>,
 )> {
     let cfg = ConfigStore::load();
     let (mut write, read) = connect_ftx().await?;
-    let ts = chrono::Utc::now().timestamp_millis();
+    let ts = clock.now_ms();
     let sign = generate_signature(cfg.ftx_api_secret.as_str(), ts);
 
     let auth_message = json!({
@@ -55,15 +58,32 @@ pub async fn connect_ftx_authed() -> anyhow::Result<(
 }
 
 pub fn generate_signature(secret: &str, ts: i64) -> String {
-    type HmacSha256 = Hmac<Sha256>;
     // TS implementation
     // const sign = CryptoJS.HmacSHA256(`${ts}websocket_login`, secret).toString();
+    sign_hmac_sha256(secret, format!("{}websocket_login", ts).as_str())
+}
+
+/// hex encoded HMAC-SHA256 of `message`
+pub fn sign_hmac_sha256(secret: &str, message: &str) -> String {
+    type HmacSha256 = Hmac<Sha256>;
     let mut mac = HmacSha256::new_from_slice(secret.as_bytes()).unwrap();
-    mac.update(format!("{}websocket_login", ts).as_bytes());
+    mac.update(message.as_bytes());
 
     let result = mac.finalize().into_bytes();
 
-    return hex::encode(result);
+    hex::encode(result)
+}
+
+/// constant time check of a hex encoded HMAC-SHA256 of `message`
+pub fn verify_hmac_sha256(secret: &str, message: &str, sign: &str) -> bool {
+    type HmacSha256 = Hmac<Sha256>;
+    let sign = match hex::decode(sign) {
+        Ok(sign) => sign,
+        Err(_) => return false,
+    };
+    let mut mac = HmacSha256::new_from_slice(secret.as_bytes()).unwrap();
+    mac.update(message.as_bytes());
+    mac.verify(sign.as_slice()).is_ok()
 }
 
 /// 1   ->  1.0
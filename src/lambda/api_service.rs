@@ -0,0 +1,516 @@
+use crate::cache::{MarketDepthCache, OrderUpdateCache, ValueCache, ValueCacheKey};
+use crate::core::auth::{ApiUser, AuthError, Role};
+use crate::core::clock::SharedClock;
+use crate::lambda::execution::{ExecutionReport, Executor, ParentOrder};
+use crate::lambda::GenericLambdaInstanceConfig;
+use crate::model::constants::{Exchanges, PublishChannel};
+use crate::model::{OrderFill, OrderSide, OrderUpdate};
+use crate::pubsub::simple_message_bus::{MessageConsumer, RedisBackedMessageBus};
+use crate::view::audit_log::AuditQuery;
+use crate::view::utils::{value_to_entries, KeyValueEntry};
+use crate::view::view_service::ParamUpdater;
+
+use dashmap::DashMap;
+use futures_util::{SinkExt, StreamExt};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use warp::http::StatusCode;
+use warp::ws::{Message, WebSocket};
+use warp::{Filter, Rejection, Reply};
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct ApiParams {
+    pub enabled: bool,
+    pub host: String,
+    pub port: u16,
+    /// period of pushing states and params to websocket clients
+    pub ws_interval_ms: u64,
+}
+
+impl Default for ApiParams {
+    fn default() -> Self {
+        ApiParams {
+            enabled: true,
+            host: "0.0.0.0".to_string(),
+            port: 6008,
+            ws_interval_ms: 500,
+        }
+    }
+}
+
+/// Net position per market of the instance, built from its fills since the lambda started. Every
+/// start begins flat, so this is the position of the session, not the one of the account.
+pub struct PositionBook {
+    owner: String,
+    markets: Vec<(Exchanges, String)>,
+    positions: DashMap<String, f64>,
+}
+
+impl PositionBook {
+    pub fn new(owner: &str, markets: Vec<(Exchanges, String)>) -> Self {
+        PositionBook {
+            owner: owner.to_string(),
+            markets,
+            positions: DashMap::new(),
+        }
+    }
+
+    pub fn is_instance_market(&self, exchange: &Exchanges, market: &str) -> bool {
+        self.markets
+            .iter()
+            .any(|(e, m)| e == exchange && m == market)
+    }
+
+    /// returns false for fills of other instances or of markets the instance does not trade
+    pub fn apply(&self, order_fill: &OrderFill) -> bool {
+        if !self.is_instance_market(&order_fill.exchange, order_fill.market.as_str())
+            || !order_fill.is_owned_by(self.owner.as_str())
+        {
+            return false;
+        }
+        let delta = match order_fill.side {
+            OrderSide::Buy => order_fill.size,
+            OrderSide::Sell => -order_fill.size,
+        };
+        *self
+            .positions
+            .entry(order_fill.market.clone())
+            .or_insert(0.0) += delta;
+        true
+    }
+
+    pub fn snapshot(&self) -> HashMap<String, f64> {
+        self.positions
+            .iter()
+            .map(|position| (position.key().clone(), *position.value()))
+            .collect()
+    }
+}
+
+#[async_trait::async_trait]
+impl MessageConsumer for PositionBook {
+    async fn consume(&self, msg: &[u8]) -> anyhow::Result<()> {
+        let order_fill = serde_json::from_slice::<OrderFill>(msg)?;
+        self.apply(&order_fill);
+        Ok(())
+    }
+}
+
+/// parent order an operator sends on one market of the instance
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ExecutionRequest {
+    pub market: String,
+    pub parent: ParentOrder,
+}
+
+/// Embedded HTTP api of a lambda.
+///
+/// REST endpoints for params, states, open orders, session positions, operator executions and
+/// health, and a websocket at `/ws`
+/// pushing the same `StrategyStates` / `StrategyParams` entries the view service publishes to Redis.
+pub struct ApiService {
+    params: ApiParams,
+    instance_config: GenericLambdaInstanceConfig,
+    value_cache: Arc<ValueCache>,
+    market_depth_cache: Arc<MarketDepthCache>,
+    order_update_cache: Arc<OrderUpdateCache>,
+    param_updater: Arc<ParamUpdater>,
+    positions: PositionBook,
+    /// executor per market, for the trades of operators, run by the engine next to the strategy
+    executors: HashMap<String, Arc<Executor>>,
+    started_ms: i64,
+    clock: SharedClock,
+}
+
+impl ApiService {
+    pub fn new(
+        instance_config: GenericLambdaInstanceConfig,
+        value_cache: Arc<ValueCache>,
+        market_depth_cache: Arc<MarketDepthCache>,
+        order_update_cache: Arc<OrderUpdateCache>,
+        param_updater: Arc<ParamUpdater>,
+        executors: HashMap<String, Arc<Executor>>,
+        clock: SharedClock,
+    ) -> Self {
+        let positions = PositionBook::new(instance_config.name.as_str(), instance_config.markets());
+        ApiService {
+            params: instance_config.lambda_params.api.clone(),
+            instance_config,
+            value_cache,
+            market_depth_cache,
+            order_update_cache,
+            param_updater,
+            positions,
+            executors,
+            started_ms: clock.now_ms(),
+            clock,
+        }
+    }
+
+    fn channel(&self, channel: PublishChannel) -> String {
+        format!("{}:{}", channel, self.instance_config.name)
+    }
+
+    fn entries(&self, key: ValueCacheKey, group: &str) -> Vec<KeyValueEntry> {
+        self.value_cache
+            .get_clone(key)
+            .map(|value| value_to_entries(&value, group))
+            .unwrap_or_default()
+    }
+
+    pub fn params(&self) -> Vec<KeyValueEntry> {
+        self.entries(ValueCacheKey::StrategyParams, "params")
+    }
+
+    pub fn states(&self) -> Vec<KeyValueEntry> {
+        self.entries(ValueCacheKey::StrategyStates, "states")
+    }
+
+    pub fn open_orders(&self) -> Vec<OrderUpdate> {
+        self.order_update_cache
+            .cache
+            .iter()
+            .filter(|order| order.is_owned_by(self.instance_config.name.as_str()))
+            .map(|order| order.value().clone())
+            .collect()
+    }
+
+    /// positions from the fills since `since_ms`, see `PositionBook`
+    pub fn session_positions(&self) -> Value {
+        json!({
+            "since_ms": self.started_ms,
+            "positions": self.positions.snapshot(),
+        })
+    }
+
+    pub fn executions(&self) -> Vec<ExecutionReport> {
+        self.executors
+            .values()
+            .flat_map(|executor| executor.reports())
+            .collect()
+    }
+
+    /// start working `request`, returns the id of the execution
+    pub async fn submit_execution(
+        &self,
+        request: ExecutionRequest,
+        user: &ApiUser,
+    ) -> anyhow::Result<String> {
+        let executor = self.executors.get(request.market.as_str()).ok_or_else(|| {
+            anyhow!(
+                "{} does not trade {}",
+                self.instance_config.name,
+                request.market
+            )
+        })?;
+        let id = executor.submit(request.parent).await?;
+        info!(
+            "execution {} on {} submitted by {}",
+            id, request.market, user.name
+        );
+        Ok(id)
+    }
+
+    pub async fn cancel_execution(&self, id: &str, user: &ApiUser) -> anyhow::Result<()> {
+        let executor = self
+            .executors
+            .values()
+            .find(|executor| executor.report(id).is_some())
+            .ok_or_else(|| anyhow!("unknown execution {}", id))?;
+        info!("execution {} cancelled by {}", id, user.name);
+        executor.cancel(id).await
+    }
+
+    /// healthy while every market depth of the instance is fresh
+    pub fn health(&self) -> (bool, Value) {
+        let stale_markets: Vec<String> = self
+            .positions
+            .markets
+            .iter()
+            .filter(|(_, market)| self.market_depth_cache.get_clone(market).is_none())
+            .map(|(_, market)| market.clone())
+            .collect();
+        let state = self
+            .value_cache
+            .get_clone(ValueCacheKey::StrategyParams)
+            .map(|params| params["state"].clone())
+            .unwrap_or(Value::Null);
+        let healthy = stale_markets.is_empty();
+        let health = json!({
+            "name": self.instance_config.name,
+            "registry": self.instance_config.registry,
+            "state": state,
+            "healthy": healthy,
+            "stale_markets": stale_markets,
+            "uptime_ms": self.clock.now_ms() - self.started_ms,
+        });
+        (healthy, health)
+    }
+
+    /// the user of `api_key` if it has at least `role`
+    pub fn authorize(&self, api_key: Option<String>, role: &Role) -> Result<ApiUser, AuthError> {
+        let api_key = api_key.ok_or(AuthError::UnknownKey)?;
+        let user = self.param_updater.authenticator().user(api_key.as_str())?;
+        if user.role < *role {
+            return Err(AuthError::Forbidden(format!(
+                "{:?} access of {}",
+                role, user.name
+            )));
+        }
+        Ok(user.clone())
+    }
+
+    async fn update_params(&self, entries: Vec<KeyValueEntry>, user: &ApiUser) -> impl Reply {
+        let mut results = vec![];
+        for entry in entries {
+            results.push(self.param_updater.update(entry, user).await);
+        }
+        let status = match results.iter().all(|result| result.accepted) {
+            true => StatusCode::OK,
+            false => StatusCode::BAD_REQUEST,
+        };
+        warp::reply::with_status(warp::reply::json(&results), status)
+    }
+
+    async fn execute(&self, request: ExecutionRequest, user: &ApiUser) -> impl Reply {
+        match self.submit_execution(request, user).await {
+            Ok(id) => {
+                warp::reply::with_status(warp::reply::json(&json!({ "id": id })), StatusCode::OK)
+            }
+            Err(err) => warp::reply::with_status(
+                warp::reply::json(&json!({ "error": err.to_string() })),
+                StatusCode::BAD_REQUEST,
+            ),
+        }
+    }
+
+    async fn cancel(&self, id: &str, user: &ApiUser) -> impl Reply {
+        match self.cancel_execution(id, user).await {
+            Ok(()) => {
+                warp::reply::with_status(warp::reply::json(&json!({ "id": id })), StatusCode::OK)
+            }
+            Err(err) => warp::reply::with_status(
+                warp::reply::json(&json!({ "error": err.to_string() })),
+                StatusCode::BAD_REQUEST,
+            ),
+        }
+    }
+
+    fn audit_log(&self, query: AuditQuery) -> impl Reply {
+        match self.param_updater.audit_log().query(&query) {
+            Ok(entries) => warp::reply::with_status(warp::reply::json(&entries), StatusCode::OK),
+            Err(err) => warp::reply::with_status(
+                warp::reply::json(&json!({ "error": err.to_string() })),
+                StatusCode::INTERNAL_SERVER_ERROR,
+            ),
+        }
+    }
+
+    /// push states and params until the client goes away
+    async fn stream(self: Arc<Self>, socket: WebSocket) {
+        let (mut tx, mut rx) = socket.split();
+        let states_channel = self.channel(PublishChannel::StrategyStates);
+        let params_channel = self.channel(PublishChannel::StrategyParams);
+        let interval = Duration::from_millis(self.params.ws_interval_ms.max(1));
+        loop {
+            let messages = vec![
+                json!({ "channel": states_channel, "data": self.states() }),
+                json!({ "channel": params_channel, "data": self.params() }),
+            ];
+            for message in messages {
+                if let Err(err) = tx.send(Message::text(message.to_string())).await {
+                    debug!("websocket closed: {}", err);
+                    return;
+                }
+            }
+            tokio::select! {
+                _ = self.clock.sleep(interval) => {}
+                msg = rx.next() => match msg {
+                    None => return,
+                    Some(Err(err)) => {
+                        debug!("websocket closed: {}", err);
+                        return;
+                    }
+                    Some(Ok(msg)) if msg.is_close() => return,
+                    Some(Ok(_)) => {}
+                },
+            }
+        }
+    }
+
+    pub fn routes(
+        self: Arc<Self>,
+    ) -> impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone {
+        let service = warp::any().map(move || self.clone());
+        // browsers cannot set headers on a websocket, which passes the key in the query instead
+        let user = |role: Role| {
+            service
+                .clone()
+                .and(warp::header::optional::<String>("x-api-key"))
+                .and(warp::query::<HashMap<String, String>>())
+                .and_then(
+                    move |service: Arc<Self>,
+                          header: Option<String>,
+                          query: HashMap<String, String>| {
+                        let api_key = header.or_else(|| query.get("api_key").cloned());
+                        let result = service
+                            .authorize(api_key, &role)
+                            .map_err(|err| warp::reject::custom(AuthRejection(err)));
+                        async move { result }
+                    },
+                )
+        };
+
+        let health = warp::path("health")
+            .and(warp::get())
+            .and(service.clone())
+            .map(|service: Arc<Self>| {
+                let (healthy, health) = service.health();
+                let status = match healthy {
+                    true => StatusCode::OK,
+                    false => StatusCode::SERVICE_UNAVAILABLE,
+                };
+                warp::reply::with_status(warp::reply::json(&health), status)
+            });
+        let get_params = warp::path("params")
+            .and(warp::get())
+            .and(service.clone())
+            .and(user(Role::Viewer))
+            .map(|service: Arc<Self>, _: ApiUser| warp::reply::json(&service.params()));
+        let update_params = warp::path("params")
+            .and(warp::post())
+            .and(service.clone())
+            .and(user(Role::Trader))
+            .and(warp::body::json())
+            .and_then(
+                |service: Arc<Self>, user: ApiUser, entries: Vec<KeyValueEntry>| async move {
+                    Ok::<_, Rejection>(service.update_params(entries, &user).await)
+                },
+            );
+        let schema = warp::path("schema")
+            .and(warp::get())
+            .and(service.clone())
+            .and(user(Role::Viewer))
+            .map(|service: Arc<Self>, _: ApiUser| {
+                warp::reply::json(&service.param_updater.param_schema().params)
+            });
+        let states = warp::path("states")
+            .and(warp::get())
+            .and(service.clone())
+            .and(user(Role::Viewer))
+            .map(|service: Arc<Self>, _: ApiUser| warp::reply::json(&service.states()));
+        let orders = warp::path("orders")
+            .and(warp::get())
+            .and(service.clone())
+            .and(user(Role::Viewer))
+            .map(|service: Arc<Self>, _: ApiUser| warp::reply::json(&service.open_orders()));
+        let positions = warp::path("session_positions")
+            .and(warp::get())
+            .and(service.clone())
+            .and(user(Role::Viewer))
+            .map(|service: Arc<Self>, _: ApiUser| warp::reply::json(&service.session_positions()));
+        let executions = warp::path!("executions")
+            .and(warp::get())
+            .and(service.clone())
+            .and(user(Role::Viewer))
+            .map(|service: Arc<Self>, _: ApiUser| warp::reply::json(&service.executions()));
+        let execute = warp::path!("executions")
+            .and(warp::post())
+            .and(service.clone())
+            .and(user(Role::Trader))
+            .and(warp::body::json())
+            .and_then(
+                |service: Arc<Self>, user: ApiUser, request: ExecutionRequest| async move {
+                    Ok::<_, Rejection>(service.execute(request, &user).await)
+                },
+            );
+        let cancel = warp::path!("executions" / String / "cancel")
+            .and(warp::post())
+            .and(service.clone())
+            .and(user(Role::Trader))
+            .and_then(|id: String, service: Arc<Self>, user: ApiUser| async move {
+                Ok::<_, Rejection>(service.cancel(id.as_str(), &user).await)
+            });
+        let audit = warp::path("audit")
+            .and(warp::get())
+            .and(service.clone())
+            .and(user(Role::Viewer))
+            .and(warp::query::<AuditQuery>())
+            .map(|service: Arc<Self>, _: ApiUser, query: AuditQuery| service.audit_log(query));
+        let ws = warp::path("ws")
+            .and(warp::ws())
+            .and(service.clone())
+            .and(user(Role::Viewer))
+            .map(|ws: warp::ws::Ws, service: Arc<Self>, _: ApiUser| {
+                ws.on_upgrade(move |socket| service.stream(socket))
+            });
+
+        let cors = warp::cors()
+            .allow_any_origin()
+            .allow_methods(vec!["GET", "POST", "OPTIONS"])
+            .allow_headers(vec!["content-type", "x-api-key"]);
+
+        health
+            .or(get_params)
+            .or(update_params)
+            .or(schema)
+            .or(states)
+            .or(orders)
+            .or(positions)
+            .or(executions)
+            .or(execute)
+            .or(cancel)
+            .or(audit)
+            .or(ws)
+            .recover(handle_rejection)
+            .with(cors)
+    }
+
+    async fn serve(self: Arc<Self>) -> anyhow::Result<()> {
+        let addr: SocketAddr = format!("{}:{}", self.params.host, self.params.port).parse()?;
+        let (addr, server) = warp::serve(self.clone().routes()).try_bind_ephemeral(addr)?;
+        info!("api listening on {}", addr);
+        server.await;
+        Err(anyhow!("api server completed"))
+    }
+
+    pub async fn subscribe(self: Arc<Self>) -> anyhow::Result<()> {
+        if !self.params.enabled {
+            info!("api disabled");
+            return futures_util::future::pending().await;
+        }
+        tokio::select! {
+            result = self.clone().serve() => {
+                error!("api serve completed: {:?}", result);
+            }
+            result = RedisBackedMessageBus::subscribe_channels(vec![PublishChannel::OrderFill.as_ref()], &self.positions) => {
+                error!("api positions completed: {:?}", result);
+            }
+        }
+        Err(anyhow!("ApiService subscribe uncaught"))
+    }
+}
+
+#[derive(Debug)]
+struct AuthRejection(AuthError);
+
+impl warp::reject::Reject for AuthRejection {}
+
+async fn handle_rejection(rejection: Rejection) -> Result<impl Reply, Rejection> {
+    let err = match rejection.find::<AuthRejection>() {
+        None => return Err(rejection),
+        Some(AuthRejection(err)) => err,
+    };
+    let status = match err {
+        AuthError::Forbidden(_) => StatusCode::FORBIDDEN,
+        _ => StatusCode::UNAUTHORIZED,
+    };
+    Ok(warp::reply::with_status(
+        warp::reply::json(&json!({ "error": err.to_string() })),
+        status,
+    ))
+}
@@ -1,29 +1,52 @@
-use std::sync::Arc;
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
 
 use crate::cache::OrderUpdateCache;
 use crate::cache::{MarketDepthCache, ValueCache, ValueCacheKey};
 
+use crate::core::auth::{Authenticator, Role, SignedCommand};
+use crate::core::clock::{SharedClock, WallClock};
+use crate::core::kill_switch::{KillSwitch, KillSwitchAction, KillSwitchCommand, KILL_SWITCH_PATH};
+use crate::core::supervisor::{Backoff, RestartPolicy, Supervisor, WorldPause};
 use crate::core::OrderGateway;
 use crate::ftx::ftx_order_gateway::FtxOrderGateway;
 
 use crate::ftx::FtxRestClient;
+use crate::lambda::execution::{execution_owner, Executor};
 use crate::lambda::lambda_instance::GenericLambdaInstanceConfig;
-use crate::lambda::strategy::swap_mm::lambda::Lambda;
-use crate::lambda::strategy::LambdaRegistry;
-use crate::lambda::LambdaInstanceConfig;
-use crate::model::MeasurementCache;
-use crate::pubsub::simple_message_bus::{MessageBusSender, RedisBackedMessageBus};
-use crate::pubsub::SubscribeMarketDepthRequest;
+use crate::lambda::restore::RestoredOrders;
+use crate::lambda::shutdown::{
+    shutdown_signal, working_orders, ShutdownReport, CANCEL_TIMEOUT_MS, FLUSH_TIMEOUT_MS,
+};
+use crate::lambda::strategy::{StrategyContext, StrategyRegistry, StrategyRunner};
+use crate::lambda::{ApiService, LambdaInstanceConfig, LambdaState};
+use crate::model::constants::{Exchanges, PublishChannel};
+use crate::model::{CancelOrderRequest, MeasurementCache, OrderUpdate};
+use crate::pubsub::local_message_bus::LocalMessageBus;
+use crate::pubsub::simple_message_bus::{MessageBusSender, MessageConsumer, RedisBackedMessageBus};
+use crate::pubsub::{PublishPayload, SubscribeMarketDepthRequest};
+use crate::sim::{SimOrderGateway, SimParams};
 use crate::view::view_service::ViewService;
+use dashmap::DashMap;
+use futures_util::FutureExt;
+use serde::Serialize;
+use serde_json::Value;
+use std::future::Future;
 use std::time::Duration;
+use tokio::sync::mpsc::Receiver;
+use tokio::task::JoinHandle;
+
+/// time a stopped lambda gets to cancel its orders in `on_stop` before its tasks are dropped
+const STOP_GRACE_MS: u64 = 1000;
+
+const FTX_ORDER_GATEWAY: &str = "ftx_order_gateway";
+const SIM_ORDER_GATEWAY: &str = "sim_order_gateway";
 
 pub async fn thread_order_update_cache(
     order_update_cache: Arc<OrderUpdateCache>,
 ) -> anyhow::Result<()> {
-    tokio::spawn(async move {
-        order_update_cache.subscribe().await;
-    })
-    .await;
+    order_update_cache.subscribe().await?;
     Err(anyhow!("thread_order_update_cache uncaught error",))
 }
 
@@ -31,51 +54,158 @@ pub async fn thread_market_depth(
     market_depth_cache: Arc<MarketDepthCache>,
     market_depth_requests: Vec<SubscribeMarketDepthRequest>,
 ) -> anyhow::Result<()> {
-    tokio::spawn(async move {
-        market_depth_cache.subscribe(&market_depth_requests).await;
-    })
-    .await;
+    market_depth_cache.subscribe(&market_depth_requests).await?;
     Err(anyhow!("thread_market_depth uncaught error"))
 }
 
+/// The gateway is restarted by the supervisor only after the world is paused, see
+/// `LambdaEngine::pause_world`. `kill_switch` outlives the gateway, a restart keeps it engaged.
 pub async fn thread_order_gateway(
     message_bus_sender: MessageBusSender,
+    client: Arc<FtxRestClient>,
     measurement_cache: Arc<MeasurementCache>,
+    kill_switch: Arc<KillSwitch>,
+    clock: SharedClock,
 ) -> anyhow::Result<()> {
-    // order gateway
-    tokio::spawn(async move {
-        let client = Arc::new(FtxRestClient::new());
-        let ftx_order_gateway = FtxOrderGateway::new(
-            message_bus_sender.clone(),
-            client.clone(),
-            measurement_cache.clone(),
-        );
-        tokio::select! {
-            Err(err) = ftx_order_gateway.subscribe() => {
-                error!("ftx_order_gateway: {}", err);
+    let ftx_order_gateway = FtxOrderGateway::new(
+        message_bus_sender,
+        client,
+        measurement_cache,
+        kill_switch,
+        clock,
+    );
+    ftx_order_gateway.subscribe().await?;
+    Err(anyhow!("thread_order_gateway uncaught error"))
+}
+
+pub async fn thread_sim_order_gateway(
+    message_bus_sender: MessageBusSender,
+    sim_params: SimParams,
+    markets: Vec<String>,
+    kill_switch: Arc<KillSwitch>,
+    clock: SharedClock,
+) -> anyhow::Result<()> {
+    let sim_order_gateway =
+        SimOrderGateway::new(message_bus_sender, sim_params, markets, kill_switch, clock);
+    sim_order_gateway.subscribe().await?;
+    Err(anyhow!("thread_sim_order_gateway uncaught error"))
+}
+
+/// Exchanges and markets served by the shared gateways. They are fixed when the engine starts, a
+/// lambda started later has to trade within them.
+#[derive(Debug, Clone, Default)]
+pub struct EngineMarkets {
+    pub ftx: bool,
+    pub sim_markets: Vec<String>,
+    /// `SimParams` of the first instance trading on SIM, the sim gateway is shared by all of them
+    pub sim_params: Option<SimParams>,
+}
+
+impl EngineMarkets {
+    pub fn from_configs(instance_configs: &[GenericLambdaInstanceConfig]) -> Self {
+        let mut markets = EngineMarkets::default();
+        for instance_config in instance_configs {
+            for (exchange, market) in instance_config.markets() {
+                match exchange {
+                    Exchanges::FTX => markets.ftx = true,
+                    Exchanges::SIM => {
+                        if markets.sim_params.is_none() {
+                            markets.sim_params = Some(instance_config.lambda_params.sim.clone());
+                        }
+                        if !markets.sim_markets.contains(&market) {
+                            markets.sim_markets.push(market);
+                        }
+                    }
+                    _ => {}
+                }
             }
         }
-        // restarting order gateway without pause-the-world is unsafe
-        // tokio::time::sleep(Duration::from_millis(1000)).await;
-    })
-    .await;
-    Err(anyhow!("thread_order_gateway uncaught error"))
+        markets
+    }
+
+    /// `Err` naming the first market of the instance no gateway serves
+    pub fn covers(&self, instance_config: &GenericLambdaInstanceConfig) -> Result<(), String> {
+        for (exchange, market) in instance_config.markets() {
+            let covered = match exchange {
+                Exchanges::FTX => self.ftx,
+                Exchanges::SIM => self.sim_markets.contains(&market),
+                // no gateway trades anywhere else
+                _ => false,
+            };
+            if !covered {
+                return Err(format!("no gateway for {} on {}", market, exchange));
+            }
+        }
+        Ok(())
+    }
 }
 
-pub struct LambdaEngine {
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, strum_macros::Display)]
+pub enum LambdaControlAction {
+    Start,
+    Stop,
+}
+
+/// payload of a `SignedCommand` on `LambdaControl`, only admins may start and stop lambdas
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct LambdaControlCommand {
+    pub action: LambdaControlAction,
+    pub instance: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct LambdaControlResult {
+    pub action: LambdaControlAction,
+    pub instance: String,
+    pub accepted: bool,
+    pub reason: Option<String>,
+    /// lambdas running after the command
+    pub running: Vec<String>,
+}
+
+/// One lambda of the engine, with its own `ValueCache` and tasks.
+struct Lambda {
     instance_config: GenericLambdaInstanceConfig,
-    message_bus: Arc<RedisBackedMessageBus>,
+    value_cache: Arc<ValueCache>,
+    finished: Arc<AtomicBool>,
+    handle: JoinHandle<()>,
+}
+
+impl Lambda {
+    fn is_running(&self) -> bool {
+        !self.finished.load(Ordering::SeqCst)
+    }
+}
+
+/// Runs the lambdas of several instances on one set of caches, gateways and message bus.
+pub struct LambdaEngine {
+    instance_configs: Vec<GenericLambdaInstanceConfig>,
+    markets: EngineMarkets,
+    /// None for an in-memory engine, whose lambdas run on `local_message_bus`
+    message_bus: Option<Arc<RedisBackedMessageBus>>,
+    local_message_bus: Option<Arc<LocalMessageBus>>,
     message_bus_sender: MessageBusSender,
     market_depth_cache: Arc<MarketDepthCache>,
     order_update_cache: Arc<OrderUpdateCache>,
     measurement_cache: Arc<MeasurementCache>,
-    value_cache: Arc<ValueCache>,
+    ftx_client: Option<Arc<FtxRestClient>>,
+    authenticator: Arc<Authenticator>,
+    /// shared by the gateways of every start, persisted so a restart does not re-arm it
+    kill_switch: Arc<KillSwitch>,
+    lambdas: DashMap<String, Lambda>,
+    /// market depth tokens the `MarketDepthCache` is subscribed to
+    market_depths: Mutex<HashSet<String>>,
+    /// orders found open on start-up, not yet offered to a lambda
+    restored_orders: RestoredOrders,
+    clock: SharedClock,
 }
 
 impl LambdaEngine {
-    pub async fn init(instance_config: GenericLambdaInstanceConfig) -> Self {
+    pub async fn init(instance_configs: Vec<GenericLambdaInstanceConfig>) -> Self {
+        let clock = WallClock::shared();
+
         // market depth request
-        let market_depth_cache = Arc::new(MarketDepthCache::new());
+        let market_depth_cache = Arc::new(MarketDepthCache::with_clock(clock.clone()));
 
         // order update cache
         let order_update_cache = Arc::new(OrderUpdateCache::new());
@@ -85,82 +215,777 @@ impl LambdaEngine {
         let message_bus_sender = message_bus.publish_tx.clone();
 
         // measurement cache
-        let measurement_cache = Arc::new(MeasurementCache::new().await);
-
-        // value cache
-        let value_cache = Arc::new(ValueCache::new(instance_config.clone()).await);
-
-        // get lambda params
-        let lambda_instance_config = LambdaInstanceConfig::load(instance_config.name.as_str());
-        value_cache.insert(
-            ValueCacheKey::StrategyParams,
-            lambda_instance_config.strategy_params.clone(),
-        );
+        let measurement_cache = Arc::new(MeasurementCache::with_clock(clock.clone()).await);
 
-        return LambdaEngine {
-            instance_config,
-            message_bus,
+        LambdaEngine {
+            markets: EngineMarkets::from_configs(&instance_configs),
+            instance_configs,
+            message_bus: Some(message_bus),
+            local_message_bus: None,
             message_bus_sender,
             market_depth_cache,
             order_update_cache,
             measurement_cache,
-            value_cache,
+            ftx_client: Some(Arc::new(FtxRestClient::new())),
+            authenticator: Arc::new(Authenticator::load()),
+            kill_switch: Arc::new(KillSwitch::persistent(KILL_SWITCH_PATH)),
+            lambdas: DashMap::new(),
+            market_depths: Mutex::new(HashSet::new()),
+            restored_orders: RestoredOrders::new(),
+            clock,
+        }
+    }
+
+    /// Engine of SIM lambdas without redis, FTX nor files written, e.g. for tests. Its lambdas
+    /// subscribe to `local_message_bus` and run without view and api services, what they publish
+    /// comes out of the returned receiver. `subscribe` is not available, the caller starts the
+    /// lambdas and drives the clock.
+    pub fn in_memory(
+        instance_configs: Vec<GenericLambdaInstanceConfig>,
+        local_message_bus: Arc<LocalMessageBus>,
+        clock: SharedClock,
+    ) -> (Self, Receiver<PublishPayload>) {
+        let (message_bus_sender, receiver) = tokio::sync::mpsc::channel(1000);
+        let engine = LambdaEngine {
+            markets: EngineMarkets::from_configs(&instance_configs),
+            instance_configs,
+            message_bus: None,
+            local_message_bus: Some(local_message_bus),
+            message_bus_sender,
+            market_depth_cache: Arc::new(MarketDepthCache::with_clock(clock.clone())),
+            order_update_cache: Arc::new(OrderUpdateCache::new()),
+            measurement_cache: Arc::new(MeasurementCache::in_memory(clock.clone())),
+            ftx_client: None,
+            authenticator: Arc::new(Authenticator::new(vec![])),
+            kill_switch: Arc::new(KillSwitch::new()),
+            lambdas: DashMap::new(),
+            market_depths: Mutex::new(HashSet::new()),
+            restored_orders: RestoredOrders::new(),
+            clock,
         };
+        (engine, receiver)
+    }
+
+    pub fn order_update_cache(&self) -> Arc<OrderUpdateCache> {
+        self.order_update_cache.clone()
     }
 
-    pub async fn subscribe_lambda(&self) -> anyhow::Result<()> {
-        match self.instance_config.registry {
-            LambdaRegistry::SwapMM => {
-                // lambda
-                let lambda = Lambda::new(
-                    self.instance_config.clone(),
-                    self.market_depth_cache.clone(),
-                    self.order_update_cache.clone(),
-                    self.message_bus_sender.clone(),
-                    self.measurement_cache.clone(),
-                    self.value_cache.clone(),
-                );
+    fn ftx_client(&self) -> anyhow::Result<Arc<FtxRestClient>> {
+        self.ftx_client
+            .clone()
+            .ok_or_else(|| anyhow!("no FTX client in an in-memory engine"))
+    }
+
+    /// publish through the message bus sender, like the lambdas
+    async fn publish<T: Serialize>(&self, channel: &str, message: &T) -> anyhow::Result<()> {
+        let payload = PublishPayload {
+            channel: channel.to_string(),
+            payload: RedisBackedMessageBus::pack_json(message)?,
+        };
+        self.message_bus_sender
+            .send(payload)
+            .await
+            .map_err(|err| anyhow!("publish on {} failed: {}", channel, err))
+    }
 
-                lambda.subscribe().await?;
+    /// spawn `future`, on the local message bus for an in-memory engine
+    fn spawn<F>(&self, future: F) -> JoinHandle<F::Output>
+    where
+        F: Future + Send + 'static,
+        F::Output: Send + 'static,
+    {
+        match self.local_message_bus.clone() {
+            None => tokio::spawn(future),
+            Some(local_message_bus) => {
+                tokio::spawn(LocalMessageBus::scope(local_message_bus, future))
             }
-            LambdaRegistry::LatencyMM => {}
         }
-        Ok(())
     }
 
-    pub async fn subscribe(&self) -> anyhow::Result<()> {
-        let market_depth_tokens = &self.instance_config.lambda_params.market_depths;
+    fn strategy_context(
+        &self,
+        instance_config: GenericLambdaInstanceConfig,
+        value_cache: Arc<ValueCache>,
+    ) -> StrategyContext {
+        StrategyContext {
+            instance_config,
+            market_depth_cache: self.market_depth_cache.clone(),
+            order_update_cache: self.order_update_cache.clone(),
+            message_bus_sender: self.message_bus_sender.clone(),
+            measurement_cache: self.measurement_cache.clone(),
+            value_cache,
+            ftx_client: self.ftx_client.clone(),
+            clock: self.clock.clone(),
+        }
+    }
+
+    /// names of the running lambdas, sorted
+    pub fn running(&self) -> Vec<String> {
+        let mut running: Vec<String> = self
+            .lambdas
+            .iter()
+            .filter(|lambda| lambda.is_running())
+            .map(|lambda| lambda.key().clone())
+            .collect();
+        running.sort();
+        running
+    }
+
+    /// Subscribe the `MarketDepthCache` to the tokens it does not receive yet, restarted by its own
+    /// supervisor like a lambda. The subscription outlives the lambda asking for it, other lambdas
+    /// may share the market.
+    fn subscribe_market_depths(&self, market_depth_tokens: &[String]) {
+        let mut market_depths = self.market_depths.lock().unwrap();
         let market_depth_requests: Vec<SubscribeMarketDepthRequest> = market_depth_tokens
             .iter()
+            .filter(|token| market_depths.insert(token.to_string()))
             .map(|token| SubscribeMarketDepthRequest::from_token(token.as_str()))
             .collect();
-
-        let view_service = ViewService::new(
-            self.instance_config.clone(),
-            self.message_bus.clone(),
-            self.value_cache.clone(),
+        if market_depth_requests.is_empty() {
+            return;
+        }
+        let name = format!(
+            "market_depth:{}",
+            market_depth_requests
+                .iter()
+                .map(|request| format!("{}:{}", request.exchange, request.market))
+                .collect::<Vec<String>>()
+                .join(",")
         );
-
-        tokio::select! {
-            Err(err) = thread_market_depth(self.market_depth_cache.clone(), market_depth_requests) => {
-                log::error!("market_depth_cache panic: {}", err)
+        let market_depth_cache = self.market_depth_cache.clone();
+        let mut supervisor =
+            Supervisor::new(self.clock.clone(), Some(self.message_bus_sender.clone()));
+        supervisor.supervise(
+            name.as_str(),
+            RestartPolicy::Restart(Backoff::default()),
+            move || {
+                thread_market_depth(market_depth_cache.clone(), market_depth_requests.clone())
+                    .boxed()
             },
-            Err(err) = thread_order_gateway(self.message_bus_sender.clone(), self.measurement_cache.clone()) => {
-                log::error!("order_gateway panic: {}", err);
+        );
+        self.spawn(async move {
+            if let Err(err) = supervisor.run().await {
+                error!("market depth supervisor: {}", err);
+            }
+        });
+    }
+
+    /// Load the instance config from disk and run its lambda next to the running ones.
+    pub async fn start_lambda(&self, instance_name: &str) -> anyhow::Result<()> {
+        if let Some(lambda) = self.lambdas.get(instance_name) {
+            if lambda.is_running() {
+                return Err(anyhow!("lambda {} is already running", instance_name));
+            }
+        }
+        let instance_config = GenericLambdaInstanceConfig::read(instance_name)
+            .map_err(|err| anyhow!("Cannot read instance {}: {}", instance_name, err))?;
+        if instance_config.name != instance_name {
+            return Err(anyhow!("config name != instance_name"));
+        }
+        self.markets
+            .covers(&instance_config)
+            .map_err(|err| anyhow!("Cannot start lambda {}: {}", instance_name, err))?;
+        let api = &instance_config.lambda_params.api;
+        if api.enabled {
+            let port_taken = self.lambdas.iter().any(|lambda| {
+                let other = &lambda.instance_config.lambda_params.api;
+                lambda.is_running() && other.enabled && other.port == api.port
+            });
+            if port_taken {
+                return Err(anyhow!("api port {} is taken by another lambda", api.port));
+            }
+        }
+
+        let value_cache = match self.message_bus {
+            None => Arc::new(ValueCache::in_memory(instance_config.clone())),
+            Some(_) => Arc::new(ValueCache::new(instance_config.clone()).await),
+        };
+        match value_cache.hydrate().await {
+            Ok(hydrated) => info!("hydrated {} values of {}", hydrated, instance_name),
+            Err(err) => error!("Cannot hydrate {}: {}", instance_name, err),
+        }
+        // only the states are hydrated, params come from the instance config with state Init, a
+        // restart never resumes trading
+        let mut strategy_params = LambdaInstanceConfig::load(instance_name).strategy_params;
+        strategy_params["state"] = Value::String(LambdaState::Init.to_string());
+        value_cache.insert(ValueCacheKey::StrategyParams, strategy_params);
+
+        let context = self.strategy_context(instance_config.clone(), value_cache.clone());
+        let strategy = StrategyRegistry::create(instance_config.registry.as_str(), &context)?;
+        let unadopted = self
+            .restored_orders
+            .restore_lambda_orders(&instance_config, &strategy)
+            .await;
+        self.cancel_restored_orders(unadopted).await;
+        // built once, the api submits to the executors the lambda runs
+        let executors = context.executors();
+        let services = self.message_bus.as_ref().map(|message_bus| {
+            let view_service = Arc::new(ViewService::new(
+                instance_config.clone(),
+                message_bus.clone(),
+                value_cache.clone(),
+                strategy.param_schema(),
+                self.authenticator.clone(),
+                self.clock.clone(),
+            ));
+            // the unadopted orders are cancelled, their closing updates reach the blotter
+            view_service.seed_blotter(&self.order_update_cache);
+            let api_service = Arc::new(ApiService::new(
+                instance_config.clone(),
+                value_cache.clone(),
+                self.market_depth_cache.clone(),
+                self.order_update_cache.clone(),
+                view_service.param_updater(),
+                executors.clone(),
+                self.clock.clone(),
+            ));
+            (view_service, api_service)
+        });
+        self.subscribe_market_depths(&instance_config.lambda_params.market_depths);
+
+        // a failing lambda restarts on its own, without touching the other lambdas
+        let mut supervisor =
+            Supervisor::new(self.clock.clone(), Some(self.message_bus_sender.clone()));
+        supervisor.supervise(
+            format!("lambda:{}", instance_name).as_str(),
+            RestartPolicy::Restart(Backoff::default()),
+            move || {
+                run_lambda(
+                    StrategyRunner::new(strategy.clone(), context.clone()),
+                    executors.values().cloned().collect(),
+                    services.clone(),
+                )
+                .boxed()
             },
-            Err(err) = thread_order_update_cache(self.order_update_cache.clone()) => {
-                log::error!("order_update_service panic: {}", err);
+        );
+
+        info!("starting lambda {}", instance_name);
+        let finished = Arc::new(AtomicBool::new(false));
+        let handle = {
+            let finished = finished.clone();
+            self.spawn(async move {
+                if let Err(err) = supervisor.run().await {
+                    error!("lambda supervisor: {}", err);
+                }
+                finished.store(true, Ordering::SeqCst);
+            })
+        };
+        self.lambdas.insert(
+            instance_name.to_string(),
+            Lambda {
+                instance_config,
+                value_cache,
+                finished,
+                handle,
             },
-            result = self.message_bus.subscribe() => {
-                log::error!("message_bus completed: {:?}", result)
+        );
+        Ok(())
+    }
+
+    /// Rebuild the `OrderUpdateCache` from the orders open on FTX, kept for the lambdas to adopt or
+    /// cancel when they start.
+    async fn restore_open_orders(&self) {
+        if !self.markets.ftx {
+            return;
+        }
+        let orders = match self.ftx_client() {
+            Ok(ftx_client) => ftx_client
+                .get_open_orders(None)
+                .await
+                .map_err(|err| anyhow!("{}", err)),
+            Err(err) => Err(err),
+        };
+        match orders {
+            Ok(orders) => {
+                let restored = self
+                    .restored_orders
+                    .restore_open_orders(&self.order_update_cache, &orders);
+                info!("restored {} open orders", restored);
+            }
+            Err(err) => error!("Cannot restore open orders: {}", err),
+        }
+    }
+
+    /// cancel restored orders on FTX which their lambda did not adopt
+    async fn cancel_restored_orders(&self, orders: Vec<OrderUpdate>) {
+        if orders.is_empty() {
+            return;
+        }
+        let ftx_client = match self.ftx_client() {
+            Ok(ftx_client) => ftx_client,
+            Err(err) => {
+                error!("Cannot cancel restored orders: {}", err);
+                return;
+            }
+        };
+        for order in orders {
+            let client_id = order.cache_key();
+            match ftx_client.cancel_order_cid(client_id.as_str()).await {
+                Ok(_) => {
+                    warn!("cancelled restored order {}", client_id);
+                    self.order_update_cache.cache.remove(&client_id);
+                }
+                Err(err) => error!("cancel restored order {} failed: {}", client_id, err),
+            }
+        }
+    }
+
+    /// Move the lambda to `Stopped`, give `on_stop` time to cancel its orders and drop its tasks.
+    pub async fn stop_lambda(&self, instance_name: &str) -> anyhow::Result<()> {
+        let (_, lambda) = self
+            .lambdas
+            .remove(instance_name)
+            .ok_or_else(|| anyhow!("lambda {} is not running", instance_name))?;
+        info!("stopping lambda {}", instance_name);
+        if lambda.is_running() {
+            set_state(&lambda.value_cache, LambdaState::Stopped);
+            self.clock.sleep(Duration::from_millis(STOP_GRACE_MS)).await;
+        }
+        lambda.handle.abort();
+        Ok(())
+    }
+
+    async fn subscribe_kill_switch(&self) -> anyhow::Result<()> {
+        let consumer = KillSwitchConsumer(self);
+        RedisBackedMessageBus::subscribe_channels(
+            vec![PublishChannel::KillSwitch.as_ref()],
+            &consumer,
+        )
+        .await?;
+        Err(anyhow!("thread_kill_switch uncaught error"))
+    }
+
+    async fn subscribe_lambda_control(&self) -> anyhow::Result<()> {
+        let consumer = LambdaControlConsumer(self);
+        RedisBackedMessageBus::subscribe_channels(
+            vec![PublishChannel::LambdaControl.as_ref()],
+            &consumer,
+        )
+        .await?;
+        Err(anyhow!("LambdaControlConsumer subscribe uncaught"))
+    }
+
+    /// Start the configured lambdas and supervise the shared components until one escalates.
+    pub async fn subscribe(self: Arc<Self>) -> anyhow::Result<()> {
+        let message_bus = self
+            .message_bus
+            .clone()
+            .ok_or_else(|| anyhow!("an in-memory engine is driven by its caller"))?;
+        self.restore_open_orders().await;
+        for instance_config in &self.instance_configs {
+            let instance_name = instance_config.name.as_str();
+            if let Err(err) = self.start_lambda(instance_name).await {
+                error!("Failed to start lambda {}: {}", instance_name, err);
+            }
+        }
+        let unclaimed = self.restored_orders.unclaimed().len();
+        if unclaimed > 0 {
+            warn!("{} restored orders of no lambda, left open", unclaimed);
+        }
+
+        let mut supervisor =
+            Supervisor::new(self.clock.clone(), Some(self.message_bus_sender.clone()));
+        supervisor.set_world_pause(self.clone());
+        if self.markets.ftx {
+            let engine = self.clone();
+            supervisor.supervise(
+                FTX_ORDER_GATEWAY,
+                RestartPolicy::StopTheWorld(Backoff::default()),
+                move || {
+                    let ftx_client = match engine.ftx_client() {
+                        Ok(ftx_client) => ftx_client,
+                        Err(err) => return futures_util::future::err(err).boxed(),
+                    };
+                    thread_order_gateway(
+                        engine.message_bus_sender.clone(),
+                        ftx_client,
+                        engine.measurement_cache.clone(),
+                        engine.kill_switch.clone(),
+                        engine.clock.clone(),
+                    )
+                    .boxed()
+                },
+            );
+        }
+        if !self.markets.sim_markets.is_empty() {
+            let engine = self.clone();
+            supervisor.supervise(
+                SIM_ORDER_GATEWAY,
+                RestartPolicy::StopTheWorld(Backoff::default()),
+                move || {
+                    thread_sim_order_gateway(
+                        engine.message_bus_sender.clone(),
+                        engine.markets.sim_params.clone().unwrap_or_default(),
+                        engine.markets.sim_markets.clone(),
+                        engine.kill_switch.clone(),
+                        engine.clock.clone(),
+                    )
+                    .boxed()
+                },
+            );
+        }
+        let engine = self.clone();
+        supervisor.supervise(
+            "order_update_cache",
+            RestartPolicy::Restart(Backoff::default()),
+            move || thread_order_update_cache(engine.order_update_cache.clone()).boxed(),
+        );
+        let engine = self.clone();
+        supervisor.supervise(
+            "kill_switch",
+            RestartPolicy::Restart(Backoff::default()),
+            move || {
+                let engine = engine.clone();
+                async move { engine.subscribe_kill_switch().await }.boxed()
             },
-            result = self.subscribe_lambda() => {
-                log::error!("lambda completed: {:?}", result)
+        );
+        let engine = self.clone();
+        supervisor.supervise(
+            "lambda_control",
+            RestartPolicy::Restart(Backoff::default()),
+            move || {
+                let engine = engine.clone();
+                async move { engine.subscribe_lambda_control().await }.boxed()
             },
-            result = view_service.subscribe() => {
-                log::error!("view_serivce completed: {:?}", result)
+        );
+        // without the publish loop no order reaches a gateway, leave the restart to the container
+        supervisor.supervise("message_bus", RestartPolicy::Escalate, move || {
+            let message_bus = message_bus.clone();
+            async move { message_bus.subscribe().await }.boxed()
+        });
+
+        // the shared components keep running while shutting down, cancels need the gateways
+        let run = supervisor.run();
+        tokio::pin!(run);
+        let signal = tokio::select! {
+            result = &mut run => return result,
+            signal = shutdown_signal() => signal,
+        };
+        warn!("{} received, shutting down", signal);
+        let shutdown = self.shutdown(signal);
+        tokio::pin!(shutdown);
+        let report = tokio::select! {
+            report = &mut shutdown => report,
+            result = &mut run => {
+                error!("supervisor ended while shutting down: {:?}", result);
+                shutdown.await
+            }
+        };
+        if report.is_clean() {
+            info!("{}", report.summary());
+            Ok(())
+        } else {
+            Err(anyhow!("{}", report.summary()))
+        }
+    }
+
+    /// Stop every lambda, cancel the orders they and their executions still have working and wait
+    /// for their confirmation, then persist the params and flush the value-cache and measurement writes.
+    /// Orders of no lambda of this engine, e.g. restored and never claimed, are left open.
+    pub async fn shutdown(&self, signal: &str) -> ShutdownReport {
+        let mut report = ShutdownReport {
+            signal: signal.to_string(),
+            ..Default::default()
+        };
+        for lambda in self.lambdas.iter() {
+            set_state(&lambda.value_cache, LambdaState::Stopped);
+            report.stopped.push(lambda.key().clone());
+        }
+        report.stopped.sort();
+        // on_stop cancels the lambdas' own orders first
+        self.clock.sleep(Duration::from_millis(STOP_GRACE_MS)).await;
+
+        let orders: Vec<OrderUpdate> = working_orders(&self.order_update_cache)
+            .into_iter()
+            .filter(|order| {
+                report.stopped.iter().any(|instance_name| {
+                    order.is_owned_by(instance_name)
+                        || order.is_owned_by(execution_owner(instance_name).as_str())
+                })
+            })
+            .collect();
+        for order in &orders {
+            let cancel_order_request = CancelOrderRequest {
+                exchange: order.exchange.clone(),
+                market: order.market.clone(),
+                client_id: order.cache_key(),
+            };
+            if let Err(err) = self
+                .publish(PublishChannel::CancelOrder.as_ref(), &cancel_order_request)
+                .await
+            {
+                error!("cancel {} failed: {}", cancel_order_request.client_id, err);
+            }
+        }
+        let deadline = self.clock.now_ms() + CANCEL_TIMEOUT_MS as i64;
+        while self.clock.now_ms() < deadline
+            && orders.iter().any(|order| {
+                self.order_update_cache
+                    .cache
+                    .contains_key(&order.cache_key())
+            })
+        {
+            self.clock.sleep(Duration::from_millis(50)).await;
+        }
+        for order in &orders {
+            if self
+                .order_update_cache
+                .cache
+                .contains_key(&order.cache_key())
+            {
+                report.open_orders.push(format!(
+                    "{} {} {}",
+                    order.exchange,
+                    order.market,
+                    order.cache_key()
+                ));
+            } else {
+                report.cancelled.push(order.cache_key());
+            }
+        }
+
+        let mut value_caches = vec![];
+        for instance_name in report.stopped.iter() {
+            let (_, lambda) = match self.lambdas.remove(instance_name) {
+                None => continue,
+                Some(lambda) => lambda,
+            };
+            lambda.handle.abort();
+            // an in-memory engine leaves the instance configs alone
+            if self.message_bus.is_some() {
+                if let Err(err) = persist_params(instance_name, &lambda.value_cache).await {
+                    report
+                        .unpersisted
+                        .push(format!("{}: {}", instance_name, err));
+                }
+            }
+            value_caches.push(lambda.value_cache);
+        }
+        let flush_timeout = Duration::from_millis(FLUSH_TIMEOUT_MS);
+        let (unflushed_values, unflushed_measurements) = tokio::join!(
+            futures_util::future::join_all(
+                value_caches
+                    .iter()
+                    .map(|value_cache| value_cache.flush(flush_timeout))
+            ),
+            self.measurement_cache.flush(flush_timeout)
+        );
+        report.unflushed_values = unflushed_values.iter().sum();
+        report.unflushed_measurements = unflushed_measurements;
+        report
+    }
+}
+
+/// write the params of the lambda back to its instance config, on the blocking pool
+async fn persist_params(instance_name: &str, value_cache: &ValueCache) -> anyhow::Result<()> {
+    let params = match value_cache.get_clone(ValueCacheKey::StrategyParams) {
+        None => return Ok(()),
+        Some(params) => params,
+    };
+    let instance_name = instance_name.to_string();
+    tokio::task::spawn_blocking(move || {
+        LambdaInstanceConfig::save_strategy_params(instance_name.as_str(), params)
+    })
+    .await??;
+    Ok(())
+}
+
+#[async_trait::async_trait]
+impl WorldPause for LambdaEngine {
+    /// Pause every live lambda, and cancel their orders on FTX before its gateway restarts. SIM
+    /// orders live in the sim gateway and go with it.
+    async fn pause_world(&self, component: &str) -> anyhow::Result<()> {
+        let mut ftx_markets: Vec<String> = vec![];
+        for lambda in self.lambdas.iter() {
+            if !lambda.is_running() {
+                continue;
+            }
+            pause(lambda.key(), &lambda.value_cache);
+            for (exchange, market) in lambda.instance_config.markets() {
+                if exchange == Exchanges::FTX && !ftx_markets.contains(&market) {
+                    ftx_markets.push(market);
+                }
             }
         }
+        if component != FTX_ORDER_GATEWAY {
+            return Ok(());
+        }
+        if ftx_markets.is_empty() {
+            return Ok(());
+        }
+        let ftx_client = self.ftx_client()?;
+        for market in ftx_markets {
+            match ftx_client.cancel_all_orders(Some(market.as_str())).await {
+                Ok(response) => warn!("cancel all orders of {}: {}", market, response),
+                Err(err) => return Err(anyhow!("cancel all orders of {} failed: {}", market, err)),
+            }
+        }
+        Ok(())
+    }
+}
+
+/// view and api service of a lambda, an in-memory engine runs its lambdas without
+type LambdaServices = (Arc<ViewService>, Arc<ApiService>);
+
+async fn run_lambda(
+    runner: StrategyRunner,
+    executors: Vec<Arc<Executor>>,
+    services: Option<LambdaServices>,
+) -> anyhow::Result<()> {
+    let (view_service, api_service) = match services {
+        None => {
+            tokio::select! {
+                result = runner.run() => {
+                    log::error!("lambda completed: {:?}", result)
+                },
+                result = run_executors(&executors) => {
+                    log::error!("lambda executors completed: {:?}", result)
+                }
+            }
+            return Err(anyhow!("lambda uncaught"));
+        }
+        Some(services) => services,
+    };
+    tokio::select! {
+        result = runner.run() => {
+            log::error!("lambda completed: {:?}", result)
+        },
+        result = run_executors(&executors) => {
+            log::error!("lambda executors completed: {:?}", result)
+        }
+        result = view_service.subscribe() => {
+            log::error!("lambda view_service completed: {:?}", result)
+        }
+        result = api_service.subscribe() => {
+            log::error!("lambda api_service completed: {:?}", result)
+        }
+    }
+    Err(anyhow!("lambda uncaught"))
+}
+
+/// the executors of a lambda's operator trades, pending for none
+async fn run_executors(executors: &[Arc<Executor>]) -> anyhow::Result<()> {
+    if executors.is_empty() {
+        return futures_util::future::pending().await;
+    }
+    let (result, _, _) = futures_util::future::select_all(
+        executors
+            .iter()
+            .map(|executor| executor.subscribe().boxed()),
+    )
+    .await;
+    result
+}
+
+/// `Paused` for a live lambda, a stopped lambda stays stopped
+fn pause(instance_name: &str, value_cache: &ValueCache) {
+    let paused = value_cache.transition_state(
+        &[LambdaState::Live, LambdaState::AutoPaused],
+        LambdaState::Paused,
+    );
+    if let Some(LambdaState::Live) | Some(LambdaState::AutoPaused) = paused {
+        warn!("paused lambda {}", instance_name);
+    }
+}
+
+fn set_state(value_cache: &ValueCache, state: LambdaState) {
+    value_cache.transition_state(&[], state);
+}
+
+/// Engages the engine's kill switch for the command's scope and moves the lambdas trading in it to
+/// `Stopped`, or re-arms the scope on an admin's signed command. Re-arming does not restart the
+/// lambdas; the operator has to set the state explicitly through `UpdateParam`.
+struct KillSwitchConsumer<'e>(&'e LambdaEngine);
+#[async_trait::async_trait]
+impl MessageConsumer for KillSwitchConsumer<'_> {
+    async fn consume(&self, msg: &[u8]) -> anyhow::Result<()> {
+        let now_ms = self.0.clock.now_ms();
+        let command = match KillSwitchCommand::authorize(msg, &self.0.authenticator, now_ms) {
+            Ok(command) => command,
+            Err(err) => {
+                warn!("Rejected KillSwitch: {}", err);
+                return Ok(());
+            }
+        };
+        let scope = command.scope();
+        match command.action {
+            KillSwitchAction::Engage => {
+                self.0.kill_switch.engage(&scope);
+                for lambda in self.0.lambdas.iter() {
+                    let markets = lambda.instance_config.markets();
+                    if markets
+                        .iter()
+                        .any(|(exchange, market)| scope.covers(exchange, market))
+                    {
+                        warn!("kill switch engaged. stopping lambda {}", lambda.key());
+                        set_state(&lambda.value_cache, LambdaState::Stopped);
+                    }
+                }
+            }
+            KillSwitchAction::Rearm => self.0.kill_switch.rearm(&scope),
+        }
+        Ok(())
+    }
+}
+
+struct LambdaControlConsumer<'e>(&'e LambdaEngine);
+
+impl LambdaControlConsumer<'_> {
+    async fn apply(
+        &self,
+        command: &SignedCommand,
+        control: &LambdaControlCommand,
+    ) -> anyhow::Result<()> {
+        let now_ms = self.0.clock.now_ms();
+        let user =
+            self.0
+                .authenticator
+                .verify(PublishChannel::LambdaControl.as_ref(), command, now_ms)?;
+        if user.role < Role::Admin {
+            return Err(anyhow!("{} may not start or stop lambdas", user.name));
+        }
+        let instance_name = control.instance.as_str();
+        match control.action {
+            LambdaControlAction::Start => self.0.start_lambda(instance_name).await,
+            LambdaControlAction::Stop => self.0.stop_lambda(instance_name).await,
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl MessageConsumer for LambdaControlConsumer<'_> {
+    async fn consume(&self, msg: &[u8]) -> anyhow::Result<()> {
+        let command = match serde_json::from_slice::<SignedCommand>(msg) {
+            Ok(command) => command,
+            Err(err) => {
+                warn!("Rejected LambdaControl, not a signed command: {}", err);
+                return Ok(());
+            }
+        };
+        let control = match serde_json::from_str::<LambdaControlCommand>(command.payload.as_str()) {
+            Ok(control) => control,
+            Err(err) => {
+                warn!("Rejected LambdaControl, invalid command: {}", err);
+                return Ok(());
+            }
+        };
+        let reason = match self.apply(&command, &control).await {
+            Ok(()) => None,
+            Err(err) => {
+                warn!("Rejected LambdaControl: {}", err);
+                Some(err.to_string())
+            }
+        };
+        let result = LambdaControlResult {
+            action: control.action,
+            instance: control.instance,
+            accepted: reason.is_none(),
+            reason,
+            running: self.0.running(),
+        };
+        self.0
+            .publish(PublishChannel::LambdaControlResult.as_ref(), &result)
+            .await?;
         Ok(())
     }
 }
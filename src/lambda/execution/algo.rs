@@ -0,0 +1,103 @@
+use crate::model::OrderSide;
+
+/// sizes below this are treated as zero, e.g. the rest of a fully filled parent order
+pub const SIZE_EPSILON: f64 = 1e-9;
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub enum ExecutionAlgo {
+    /// `slices` equal child orders spread over `duration_ms`, the first one sent immediately
+    Twap { duration_ms: i64, slices: u32 },
+    /// keeps the executed size at `participation_rate` of the volume the market traded since the
+    /// start, so the fills follow the volume profile and average out near the market's VWAP
+    Vwap { participation_rate: f64 },
+    /// a single resting child of at most `display_size`, replenished as it fills
+    Iceberg { display_size: f64 },
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ParentOrder {
+    pub side: OrderSide,
+    pub size: f64,
+    /// worst price of any child, None to take the touch
+    pub limit_px: Option<f64>,
+    /// distance through the touch of taking children, capped at `limit_px`
+    pub max_slippage_bp: f64,
+    /// children below this are not sent, the rest is carried into the next one
+    pub min_child_size: f64,
+    pub algo: ExecutionAlgo,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub enum ExecutionStatus {
+    Working,
+    Done,
+    Cancelled,
+}
+
+/// size the parent order should have executed `elapsed_ms` after its start, for schedule driven
+/// algos. None for algos that are not driven by time.
+pub fn scheduled_size(parent: &ParentOrder, elapsed_ms: i64) -> Option<f64> {
+    match parent.algo {
+        ExecutionAlgo::Twap {
+            duration_ms,
+            slices,
+        } => {
+            let slices = slices.max(1) as i64;
+            let slice_ms = (duration_ms / slices).max(1);
+            let released = (elapsed_ms.max(0) / slice_ms + 1).min(slices);
+            Some(parent.size * released as f64 / slices as f64)
+        }
+        ExecutionAlgo::Vwap { .. } | ExecutionAlgo::Iceberg { .. } => None,
+    }
+}
+
+/// Size of the next child order, None while nothing is due.
+///
+/// `filled_size` and `open_size` are the filled and the still open size of the children so far,
+/// `traded_size` the volume the market traded since the parent started.
+pub fn next_child_size(
+    parent: &ParentOrder,
+    elapsed_ms: i64,
+    filled_size: f64,
+    open_size: f64,
+    traded_size: f64,
+) -> Option<f64> {
+    let working_size = filled_size + open_size;
+    let rest = parent.size - working_size;
+    if rest <= SIZE_EPSILON {
+        return None;
+    }
+    let size = match parent.algo {
+        ExecutionAlgo::Twap { .. } => scheduled_size(parent, elapsed_ms)? - working_size,
+        ExecutionAlgo::Vwap { participation_rate } => {
+            traded_size * participation_rate - working_size
+        }
+        // tops the displayed size back up as the resting children fill
+        ExecutionAlgo::Iceberg { display_size } => display_size - open_size,
+    };
+    let size = size.min(rest);
+    // the last child takes whatever is left, even below the min size
+    if size <= SIZE_EPSILON || (size < parent.min_child_size && size < rest) {
+        return None;
+    }
+    Some(size)
+}
+
+/// limit of a child taking the touch, capped at the parent's limit
+pub fn child_price(parent: &ParentOrder, touch_px: f64) -> f64 {
+    let slippage = parent.max_slippage_bp / 10000.0;
+    match parent.side {
+        OrderSide::Buy => {
+            let price = touch_px * (1.0 + slippage);
+            parent
+                .limit_px
+                .map_or(price, |limit_px| price.min(limit_px))
+        }
+        OrderSide::Sell => {
+            let price = touch_px * (1.0 - slippage);
+            parent
+                .limit_px
+                .map_or(price, |limit_px| price.max(limit_px))
+        }
+    }
+}
@@ -0,0 +1,446 @@
+use crate::cache::MarketDepthCache;
+use crate::core::clock::SharedClock;
+use crate::lambda::execution::algo::{
+    child_price, next_child_size, ExecutionAlgo, ExecutionStatus, ParentOrder, SIZE_EPSILON,
+};
+use crate::model::market_data_model::Trade;
+use crate::model::{Instrument, OrderSide, OrderStatus, OrderType, OrderUpdate};
+use crate::pubsub::simple_message_bus::{
+    MessageConsumer, RedisBackedMessageBus, TypedMessageConsumer,
+};
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use uuid::Uuid;
+
+/// period of working the schedules of all parent orders
+const WORK_INTERVAL_MS: u64 = 200;
+
+/// Owner of the children an operator's executions send for `instance_name`. Kept apart from the
+/// instance's own orders, which its strategy would otherwise take for its quotes.
+pub fn execution_owner(instance_name: &str) -> String {
+    format!("{}.exec", instance_name)
+}
+
+#[derive(Debug, Clone)]
+struct ChildOrder {
+    size: f64,
+    filled_size: f64,
+    avg_fill_px: Option<f64>,
+    open: bool,
+}
+
+#[derive(Debug, Clone)]
+struct Execution {
+    parent: ParentOrder,
+    status: ExecutionStatus,
+    started_ms: i64,
+    /// volume the market traded since the start, our own children included
+    traded_size: f64,
+    children: HashMap<String, ChildOrder>,
+}
+
+impl Execution {
+    fn filled_size(&self) -> f64 {
+        self.children.values().map(|child| child.filled_size).sum()
+    }
+
+    fn open_size(&self) -> f64 {
+        self.children
+            .values()
+            .filter(|child| child.open)
+            .map(|child| child.size - child.filled_size)
+            .sum()
+    }
+
+    fn has_open_children(&self) -> bool {
+        self.children.values().any(|child| child.open)
+    }
+
+    fn report(&self, id: &str) -> ExecutionReport {
+        let filled_size = self.filled_size();
+        let notional: f64 = self
+            .children
+            .values()
+            .filter_map(|child| child.avg_fill_px.map(|px| px * child.filled_size))
+            .sum();
+        ExecutionReport {
+            id: id.to_string(),
+            side: self.parent.side.clone(),
+            size: self.parent.size,
+            filled_size,
+            avg_fill_px: match filled_size > SIZE_EPSILON {
+                true => Some(notional / filled_size),
+                false => None,
+            },
+            open_size: self.open_size(),
+            child_cnt: self.children.len(),
+            status: self.status.clone(),
+        }
+    }
+}
+
+/// progress of a parent order
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ExecutionReport {
+    pub id: String,
+    pub side: OrderSide,
+    pub size: f64,
+    pub filled_size: f64,
+    pub avg_fill_px: Option<f64>,
+    /// size of children sent but not yet filled or closed
+    pub open_size: f64,
+    pub child_cnt: usize,
+    pub status: ExecutionStatus,
+}
+
+/// Works parent orders on one instrument with child orders.
+///
+/// TWAP and VWAP children take the touch as IOC orders, capped at the parent's limit. VWAP children
+/// follow the market's trades, see `on_trade`. Iceberg children rest at the limit or join our side
+/// of the book, topped back up to the display size as they fill. Children are filled from
+/// `OrderUpdate`s, either forwarded by the owning strategy through `on_order_update` or consumed by
+/// `subscribe`.
+pub struct Executor {
+    instrument: Arc<Instrument>,
+    market_depth: Arc<MarketDepthCache>,
+    executions: Mutex<HashMap<String, Execution>>,
+    /// serializes sending children with order updates, so an update never misses its child
+    send_lock: tokio::sync::Mutex<()>,
+    clock: SharedClock,
+}
+
+impl Executor {
+    pub fn new(
+        instrument: Arc<Instrument>,
+        market_depth: Arc<MarketDepthCache>,
+        clock: SharedClock,
+    ) -> Self {
+        Executor {
+            instrument,
+            market_depth,
+            executions: Mutex::new(HashMap::new()),
+            send_lock: tokio::sync::Mutex::new(()),
+            clock,
+        }
+    }
+
+    /// start working `parent`, returns the id reports and `cancel` refer to
+    pub async fn submit(&self, parent: ParentOrder) -> anyhow::Result<String> {
+        if parent.size <= SIZE_EPSILON {
+            return Err(anyhow!(
+                "parent order size must be positive: {}",
+                parent.size
+            ));
+        }
+        match parent.algo {
+            ExecutionAlgo::Iceberg { display_size } if display_size <= SIZE_EPSILON => {
+                return Err(anyhow!("iceberg display_size must be positive"));
+            }
+            ExecutionAlgo::Vwap { participation_rate }
+                if participation_rate <= 0.0 || participation_rate > 1.0 =>
+            {
+                return Err(anyhow!(
+                    "vwap participation_rate must be in (0, 1]: {}",
+                    participation_rate
+                ));
+            }
+            _ => {}
+        }
+        let id = Uuid::new_v4().to_string();
+        info!(
+            "execution {} {} {} {} {:?}",
+            id, self.instrument.market, parent.side, parent.size, parent.algo
+        );
+        self.executions.lock().unwrap().insert(
+            id.clone(),
+            Execution {
+                parent,
+                status: ExecutionStatus::Working,
+                started_ms: self.clock.now_ms(),
+                traded_size: 0.0,
+                children: HashMap::new(),
+            },
+        );
+        self.work_execution(id.as_str()).await?;
+        Ok(id)
+    }
+
+    /// stop working `id` and cancel its open children
+    pub async fn cancel(&self, id: &str) -> anyhow::Result<()> {
+        let _guard = self.send_lock.lock().await;
+        let open_children: Vec<String> = {
+            let mut executions = self.executions.lock().unwrap();
+            let execution = executions
+                .get_mut(id)
+                .ok_or_else(|| anyhow!("unknown execution {}", id))?;
+            if execution.status == ExecutionStatus::Working {
+                execution.status = ExecutionStatus::Cancelled;
+            }
+            execution
+                .children
+                .iter()
+                .filter(|(_, child)| child.open)
+                .map(|(client_id, _)| client_id.clone())
+                .collect()
+        };
+        for client_id in open_children {
+            self.instrument.cancel_order(client_id.as_str()).await?;
+        }
+        Ok(())
+    }
+
+    pub fn report(&self, id: &str) -> Option<ExecutionReport> {
+        self.executions
+            .lock()
+            .unwrap()
+            .get(id)
+            .map(|execution| execution.report(id))
+    }
+
+    pub fn reports(&self) -> Vec<ExecutionReport> {
+        self.executions
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(id, execution)| execution.report(id))
+            .collect()
+    }
+
+    /// drop finished executions from the reports
+    pub fn clear_finished(&self) {
+        self.executions.lock().unwrap().retain(|_, execution| {
+            execution.status == ExecutionStatus::Working || execution.has_open_children()
+        });
+    }
+
+    /// touch price of the side a child of `side` takes
+    fn touch_px(&self, side: &OrderSide) -> Option<f64> {
+        let md = self
+            .market_depth
+            .get_clone(self.instrument.market.as_str())?;
+        let level = match side {
+            OrderSide::Buy => md.asks.first(),
+            OrderSide::Sell => md.bids.first(),
+        };
+        level.map(|level| level.price)
+    }
+
+    /// best price on our own side, where an iceberg without a limit joins
+    fn join_px(&self, side: &OrderSide) -> Option<f64> {
+        let md = self
+            .market_depth
+            .get_clone(self.instrument.market.as_str())?;
+        let level = match side {
+            OrderSide::Buy => md.bids.first(),
+            OrderSide::Sell => md.asks.first(),
+        };
+        level.map(|level| level.price)
+    }
+
+    /// send the next child of `id` if one is due
+    async fn work_execution(&self, id: &str) -> anyhow::Result<()> {
+        let _guard = self.send_lock.lock().await;
+        let now_ms = self.clock.now_ms();
+        let execution = match self.executions.lock().unwrap().get(id) {
+            None => return Ok(()),
+            Some(execution) => execution.clone(),
+        };
+        if execution.status != ExecutionStatus::Working {
+            return Ok(());
+        }
+        let parent = &execution.parent;
+        match parent.algo {
+            // an iceberg works next to its resting children
+            ExecutionAlgo::Iceberg { .. } => {}
+            _ if execution.has_open_children() => return Ok(()),
+            ExecutionAlgo::Twap { .. } | ExecutionAlgo::Vwap { .. } => {}
+        }
+        let touch_px = match self.touch_px(&parent.side) {
+            None => return Ok(()),
+            Some(touch_px) => touch_px,
+        };
+        let size = match next_child_size(
+            parent,
+            now_ms - execution.started_ms,
+            execution.filled_size(),
+            execution.open_size(),
+            execution.traded_size,
+        ) {
+            None => return Ok(()),
+            Some(size) => size,
+        };
+        let (price, ioc) = match parent.algo {
+            ExecutionAlgo::Iceberg { .. } => {
+                match parent.limit_px.or_else(|| self.join_px(&parent.side)) {
+                    None => return Ok(()),
+                    Some(price) => (price, false),
+                }
+            }
+            ExecutionAlgo::Twap { .. } | ExecutionAlgo::Vwap { .. } => {
+                (child_price(parent, touch_px), true)
+            }
+        };
+        debug!(
+            "execution {} child {} {} @ {}",
+            id, parent.side, size, price
+        );
+        let client_id = self
+            .instrument
+            .send_order_request(
+                parent.side.clone(),
+                price,
+                size,
+                OrderType::Limit,
+                ioc,
+                false,
+            )
+            .await?;
+        if let Some(client_id) = client_id {
+            if let Some(execution) = self.executions.lock().unwrap().get_mut(id) {
+                execution.children.insert(
+                    client_id,
+                    ChildOrder {
+                        size,
+                        filled_size: 0.0,
+                        avg_fill_px: None,
+                        open: true,
+                    },
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// send the children due of all working parent orders
+    pub async fn work(&self) -> anyhow::Result<()> {
+        let ids: Vec<String> = self
+            .executions
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(_, execution)| execution.status == ExecutionStatus::Working)
+            .map(|(id, _)| id.clone())
+            .collect();
+        for id in ids {
+            if let Err(err) = self.work_execution(id.as_str()).await {
+                error!("execution {}: {}", id, err);
+            }
+        }
+        Ok(())
+    }
+
+    /// apply an update of one of our children, returns whether it belonged to a parent order
+    pub async fn on_order_update(&self, order_update: &OrderUpdate) -> bool {
+        let client_id = match order_update.client_id {
+            None => return false,
+            Some(ref client_id) => client_id,
+        };
+        let _guard = self.send_lock.lock().await;
+        let mut executions = self.executions.lock().unwrap();
+        for (id, execution) in executions.iter_mut() {
+            let child = match execution.children.get_mut(client_id.as_str()) {
+                None => continue,
+                Some(child) => child,
+            };
+            child.filled_size = child.filled_size.max(order_update.filledSize);
+            if order_update.avgFillPrice.is_some() {
+                child.avg_fill_px = order_update.avgFillPrice;
+            }
+            match order_update.status {
+                OrderStatus::Closed | OrderStatus::Failed => child.open = false,
+                _ => {}
+            }
+            if execution.status == ExecutionStatus::Working
+                && execution.filled_size() >= execution.parent.size - SIZE_EPSILON
+            {
+                info!("execution {} done", id);
+                execution.status = ExecutionStatus::Done;
+            }
+            return true;
+        }
+        false
+    }
+
+    /// count a trade of the market into the volume the working VWAP parents follow, returns
+    /// whether one of them follows it
+    pub fn on_trade(&self, trade: &Trade) -> bool {
+        if trade.exchange != self.instrument.exchange || trade.market != self.instrument.market {
+            return false;
+        }
+        let mut following = false;
+        for execution in self.executions.lock().unwrap().values_mut() {
+            if execution.status != ExecutionStatus::Working {
+                continue;
+            }
+            if let ExecutionAlgo::Vwap { .. } = execution.parent.algo {
+                execution.traded_size += trade.size;
+                following = true;
+            }
+        }
+        following
+    }
+
+    async fn period_work(&self) -> anyhow::Result<()> {
+        loop {
+            self.clock
+                .sleep(Duration::from_millis(WORK_INTERVAL_MS))
+                .await;
+            self.work().await?;
+        }
+    }
+
+    /// work the parent orders on a timer, next to the hooks the owning strategy forwards
+    pub async fn run(&self) -> anyhow::Result<()> {
+        self.period_work().await
+    }
+
+    async fn subscribe_trades(&self) -> anyhow::Result<()> {
+        let channel = Trade::channel(&self.instrument.exchange, self.instrument.market.as_str());
+        RedisBackedMessageBus::subscribe_channels(vec![channel.as_str()], &TradeConsumer(self))
+            .await?;
+        Err(anyhow!("Executor trades subscribe uncaught"))
+    }
+
+    /// `run` for an executor not owned by a strategy, consuming its own order updates and the
+    /// trades of its market
+    pub async fn subscribe(&self) -> anyhow::Result<()> {
+        tokio::select! {
+            Err(err) = self.instrument.subscribe_order_update(self) => {
+                error!("Executor subscribe_order_update: {}", err);
+            }
+            Err(err) = self.subscribe_trades() => {
+                error!("Executor subscribe_trades: {}", err);
+            }
+            Err(err) = self.period_work() => {
+                error!("Executor period_work: {}", err);
+            }
+        }
+        Err(anyhow!("Executor subscribe uncaught"))
+    }
+}
+
+#[async_trait::async_trait]
+impl TypedMessageConsumer<OrderUpdate> for Executor {
+    async fn consume(&self, order_update: OrderUpdate) -> anyhow::Result<()> {
+        if self.on_order_update(&order_update).await {
+            // an iceberg is topped up as soon as its child filled
+            self.work().await?;
+        }
+        Ok(())
+    }
+}
+
+struct TradeConsumer<'r>(&'r Executor);
+
+#[async_trait::async_trait]
+impl<'r> MessageConsumer for TradeConsumer<'r> {
+    async fn consume(&self, msg: &[u8]) -> anyhow::Result<()> {
+        let trade: Trade = serde_json::from_slice(msg)?;
+        if self.0.on_trade(&trade) {
+            self.0.work().await?;
+        }
+        Ok(())
+    }
+}
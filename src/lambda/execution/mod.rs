@@ -0,0 +1,5 @@
+pub use algo::{ExecutionAlgo, ExecutionStatus, ParentOrder};
+pub use executor::{execution_owner, ExecutionReport, Executor};
+
+pub mod algo;
+mod executor;
@@ -1,29 +1,30 @@
-// use crate::lambda::param_service::LambdaStrategyParamService;
-
 use confy::ConfyError;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
-use dashmap::DashMap;
-
-use crate::cache::{ValueCache, ValueCacheKey};
-use crate::lambda::LambdaState;
+use crate::lambda::{ApiParams, WatchdogParams};
+use crate::model::constants::Exchanges;
+use crate::model::Instrument;
+use crate::sim::SimParams;
 use std::fmt::Debug;
-use std::sync::Arc;
-use tokio::sync::RwLock;
-use tokio::sync::mpsc::error::SendError;
-use crate::lambda::strategy::LambdaRegistry;
 
 #[derive(Debug, Serialize, Deserialize, Default, Clone)]
 pub struct LambdaParams {
     pub book: String,
     pub market_depths: Vec<String>,
+    #[serde(default)]
+    pub watchdog: WatchdogParams,
+    #[serde(default)]
+    pub sim: SimParams,
+    #[serde(default)]
+    pub api: ApiParams,
 }
 
 #[derive(Debug, Serialize, Deserialize, Default, Clone)]
 pub struct LambdaInstanceConfig {
     pub name: String,
-    pub registry: LambdaRegistry,
+    /// name of the strategy in the `StrategyRegistry`
+    pub registry: String,
     pub lambda_params: LambdaParams,
     pub init_params: Value,
     pub strategy_params: Value,
@@ -31,244 +32,78 @@ pub struct LambdaInstanceConfig {
 #[derive(Debug, Serialize, Deserialize, Default, Clone)]
 pub struct GenericLambdaInstanceConfig {
     pub name: String,
-    pub registry: LambdaRegistry,
+    /// name of the strategy in the `StrategyRegistry`
+    pub registry: String,
     pub lambda_params: LambdaParams,
 }
 
 impl LambdaInstanceConfig {
-    pub fn load(instance_name: &str) -> Self {
-        let mut config: LambdaInstanceConfig =
-            confy::load_path(format!("./instance/{}.toml", instance_name)).unwrap();
-        if config.name != instance_name {
-            panic!("config name != instance_name")
-        }
-        config.name = instance_name.to_string();
-        config.strategy_params["state"] = Value::String(LambdaState::Init.to_string());
-        config.save().expect("Failed to save config_store");
-        config
+    pub fn path(instance_name: &str) -> String {
+        format!("./instance/{}.toml", instance_name)
     }
-    pub fn save(&self) -> Result<(), ConfyError> {
-        let config = LambdaInstanceConfig {
-            name: self.name.clone(),
-            registry: self.registry.clone(),
-            lambda_params: self.lambda_params.clone(),
-            init_params: self.init_params.clone(),
-            strategy_params: self.strategy_params.clone(),
-        };
-        confy::store_path(format!("./instance/{}.toml", self.name), config)
+
+    /// the instance config as stored, without resetting `state`
+    pub fn read(instance_name: &str) -> Result<Self, ConfyError> {
+        confy::load_path(Self::path(instance_name))
     }
-}
 
-impl GenericLambdaInstanceConfig {
+    /// the stored instance config, read only, panics when it is missing or named differently
     pub fn load(instance_name: &str) -> Self {
-        let mut config: GenericLambdaInstanceConfig =
-            confy::load_path(format!("./instance/{}.toml", instance_name)).unwrap();
+        let mut config = Self::read(instance_name).unwrap();
         if config.name != instance_name {
             panic!("config name != instance_name")
         }
         config.name = instance_name.to_string();
         config
     }
-}
 
-pub type LambdaStrategyParamsRequestSender = tokio::sync::mpsc::Sender<LambdaStrategyParamsRequest>;
-
-#[derive(
-    Serialize,
-    Deserialize,
-    Debug,
-    strum_macros::EnumString,
-    strum_macros::Display,
-    Clone,
-    PartialOrd,
-    PartialEq,
-)]
-pub enum LambdaInstanceValueCacheKey {
-    StrategyState,
-    StrategyParam,
-}
-
-pub struct LambdaInstance {
-    pub name: String,
-    pub lambda_params: LambdaParams,
-    pub init_params: Value,
-    pub strategy_params_request_sender: LambdaStrategyParamsRequestSender,
-    strategy_params_receiver: RwLock<tokio::sync::mpsc::Receiver<LambdaStrategyParamsRequest>>,
-    pub value_cache: Arc<ValueCache>,
-}
-
-#[derive(Debug)]
-pub enum RequestType {
-    ParamSnapshot {
-        result: tokio::sync::oneshot::Sender<Value>,
-    },
-    UpdateParam {
-        value: Value,
-    },
-    SetState {
-        value: Value,
-    },
-    StateSnapshot {
-        result: tokio::sync::oneshot::Sender<Option<Value>>,
-    },
-}
-
-#[derive(Debug)]
-pub struct LambdaStrategyParamsRequest(RequestType);
-
-impl LambdaStrategyParamsRequest {
-    pub async fn request_strategy_params_snapshot(
-        sender: &LambdaStrategyParamsRequestSender,
-    ) -> anyhow::Result<Value> {
-        let (result, rx) = tokio::sync::oneshot::channel::<Value>();
-        let request = LambdaStrategyParamsRequest(RequestType::ParamSnapshot { result });
-        sender.send(request).await?;
-        let snapshot = rx.await?;
-        Ok(snapshot)
-    }
-
-    pub async fn request_update_strategy_params(
-        sender: &LambdaStrategyParamsRequestSender,
-        value: Value,
-    ) -> Result<(), SendError<LambdaStrategyParamsRequest>> {
-        let request = LambdaStrategyParamsRequest(RequestType::UpdateParam { value });
-        sender.send(request).await
+    pub fn save(&self) -> Result<(), ConfyError> {
+        self.save_to(Self::path(self.name.as_str()).as_str())
     }
 
-    pub async fn request_set_state(
-        sender: &LambdaStrategyParamsRequestSender,
-        value: Value,
-    ) -> Result<(), SendError<LambdaStrategyParamsRequest>> {
-        let request = LambdaStrategyParamsRequest(RequestType::SetState { value });
-        sender.send(request).await
+    /// Write to a temporary file next to `path` and rename it over `path`, so a crash never leaves
+    /// a truncated config behind.
+    pub fn save_to(&self, path: &str) -> Result<(), ConfyError> {
+        let tmp_path = format!("{}.tmp", path);
+        confy::store_path(tmp_path.as_str(), self.clone())?;
+        std::fs::rename(tmp_path.as_str(), path).map_err(ConfyError::WriteConfigurationFileError)
     }
 
-    pub async fn request_lambda_state_snapshot(
-        sender: &LambdaStrategyParamsRequestSender,
-    ) -> anyhow::Result<Option<Value>> {
-        let (result, rx) = tokio::sync::oneshot::channel::<Option<Value>>();
-        let request = LambdaStrategyParamsRequest(RequestType::StateSnapshot { result });
-        sender.send(request).await?;
-        let snapshot = rx.await?;
-        Ok(snapshot)
+    /// persist `strategy_params` into the stored config of `instance_name`
+    pub fn save_strategy_params(
+        instance_name: &str,
+        strategy_params: Value,
+    ) -> Result<(), ConfyError> {
+        let mut config = Self::read(instance_name)?;
+        config.strategy_params = strategy_params;
+        config.save()
     }
 }
 
-impl LambdaInstance {
-    pub fn new(config: LambdaInstanceConfig, value_cache: Arc<ValueCache>) -> LambdaInstance {
-        let (tx, rx) = tokio::sync::mpsc::channel::<LambdaStrategyParamsRequest>(100);
-        // let value_cache = DashMap::new();
-        // value_cache.insert(LambdaInstanceValueCacheKey::StrategyState.to_string(), None);
-        // value_cache.insert(
-        //     LambdaInstanceValueCacheKey::StrategyParam.to_string(),
-        //     Some(config.strategy_params),
-        // );
-        LambdaInstance {
-            name: config.name.to_string(),
-            lambda_params: config.lambda_params,
-            init_params: config.init_params,
-            strategy_params_request_sender: tx,
-            strategy_params_receiver: RwLock::new(rx),
-            value_cache,
-        }
+impl GenericLambdaInstanceConfig {
+    /// the instance config as stored, `Err` when it is missing or does not parse
+    pub fn read(instance_name: &str) -> Result<Self, ConfyError> {
+        confy::load_path(LambdaInstanceConfig::path(instance_name))
     }
 
-    async fn save_instance_config(&self) -> Result<(), ConfyError> {
-        let strategy_params = self
-            .value_cache
-            .get_clone(ValueCacheKey::StrategyParams)
-            .unwrap();
-        let config = LambdaInstanceConfig {
-            name: self.name.clone(),
-            registry: LambdaRegistry::SwapMM,
-            lambda_params: self.lambda_params.clone(),
-            init_params: self.init_params.clone(),
-            strategy_params,
-        };
-        confy::store_path(format!("./instance/{}.toml", self.name), config)
+    pub fn load(instance_name: &str) -> Self {
+        let mut config = Self::read(instance_name).unwrap();
+        if config.name != instance_name {
+            panic!("config name != instance_name")
+        }
+        config.name = instance_name.to_string();
+        config
     }
 
-    pub fn get_strategy_params_clone(&self) -> Option<Value> {
-        self.value_cache.get_clone(ValueCacheKey::StrategyParams)
+    /// exchange and market of every market depth the instance subscribes
+    pub fn markets(&self) -> Vec<(Exchanges, String)> {
+        self.lambda_params
+            .market_depths
+            .iter()
+            .map(|token| {
+                let symbol = Instrument::instrument_symbol(token.as_str());
+                (symbol.0, symbol.1)
+            })
+            .collect()
     }
-
-    // pub async fn subscribe_strategy_params_requests(
-    //     &self,
-    // ) -> Result<(), Box<dyn std::error::Error>> {
-    //     let mut strategy_params_receiver = self.strategy_params_receiver.write().await;
-    //     while let Some(msg) = strategy_params_receiver.recv().await {
-    //         match msg.0 {
-    //             RequestType::ParamSnapshot { result } => {
-    //                 if let Some(value) = self.value_cache.get(
-    //                     LambdaInstanceValueCacheKey::StrategyParam
-    //                         .to_string()
-    //                         .as_str(),
-    //                 ) {
-    //                     match value.value().clone() {
-    //                         None => {
-    //                             panic!("Request Strategy params but it's nil");
-    //                         }
-    //                         Some(value) => {
-    //                             result.send(value).unwrap();
-    //                         }
-    //                     }
-    //                 } else {
-    //                     panic!("Uncaught: getting ParamSnapshot should not be nil")
-    //                 }
-    //             }
-    //             RequestType::UpdateParam { value } => {
-    //                 info!("UpdateParam: {}", value);
-    //                 self.value_cache.insert(
-    //                     LambdaInstanceValueCacheKey::StrategyParam.to_string(),
-    //                     Some(value),
-    //                 );
-    //                 self.save_instance_config().await?;
-    //             }
-    //             RequestType::SetState { value } => {
-    //                 self.value_cache.insert(
-    //                     LambdaInstanceValueCacheKey::StrategyState.to_string(),
-    //                     Some(value),
-    //                 );
-    //             }
-    //             RequestType::StateSnapshot { result } => {
-    //                 if let Some(value) = self.value_cache.get(
-    //                     LambdaInstanceValueCacheKey::StrategyState
-    //                         .to_string()
-    //                         .as_str(),
-    //                 ) {
-    //                     match value.value().clone() {
-    //                         None => {
-    //                             result.send(None);
-    //                         }
-    //                         Some(value) => {
-    //                             result.send(Some(value));
-    //                         }
-    //                     }
-    //                 }
-    //             }
-    //         }
-    //     }
-    //     Ok(())
-    // }
-
-    // pub async fn subscribe_rest(&self) -> anyhow::Result<()> {
-    //     let sender = self.strategy_params_request_sender.clone();
-    //     tokio::spawn(async move {
-    //         info!("spawning subscribe_rest");
-    //         let param_service = LambdaStrategyParamService::new(sender);
-    //         param_service.subscribe().await;
-    //     })
-    //     .await;
-    //     Err(anyhow!("subscribe_rest uncaught"))
-    // }
-    //
-    // pub async fn subscribe(&self) -> Result<(), Box<dyn std::error::Error>> {
-    //     tokio::select! {
-    //         Err(_err) = self.subscribe_strategy_params_requests() => {},
-    //         result = self.subscribe_rest() => {
-    //             error!("subscribe_rest error: {:?}", result)
-    //         }
-    //     }
-    //     Ok(())
-    // }
 }
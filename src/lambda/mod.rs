@@ -1,34 +1,37 @@
-pub use engine::LambdaEngine;
+pub use api_service::{ApiParams, ApiService, PositionBook};
+pub use engine::{
+    EngineMarkets, LambdaControlAction, LambdaControlCommand, LambdaControlResult, LambdaEngine,
+};
 pub use lambda_instance::{GenericLambdaInstanceConfig, LambdaInstanceConfig, LambdaParams};
+pub use restore::RestoredOrders;
+pub use shutdown::{working_orders, ShutdownReport};
+pub use watchdog::{Watchdog, WatchdogParams, WatchdogTrigger};
 
+mod api_service;
 mod engine;
 mod lambda_instance;
-mod param_service;
+mod restore;
+mod shutdown;
+mod watchdog;
 
-pub mod strategy {
-    pub mod swap_mm;
-    #[derive(Debug, Serialize, Deserialize, Clone)]
-    pub enum LambdaRegistry {
-        SwapMM,
-        LatencyMM,
-    }
-    impl Default for LambdaRegistry {
-        fn default() -> Self {
-            panic!("Should not call default for LambdaRegistry")
-        }
-    }
-}
+pub mod execution;
+pub mod strategy;
 
-#[derive(Debug, Serialize, Deserialize, Clone, strum_macros::Display)]
+#[derive(
+    Debug,
+    Serialize,
+    Deserialize,
+    Clone,
+    PartialEq,
+    Default,
+    strum_macros::Display,
+    strum_macros::EnumString,
+)]
 pub enum LambdaState {
+    #[default]
     Init,
     Live,
     Paused,
     Stopped,
     AutoPaused,
 }
-impl Default for LambdaState {
-    fn default() -> Self {
-        LambdaState::Init
-    }
-}
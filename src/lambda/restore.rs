@@ -0,0 +1,88 @@
+use crate::cache::OrderUpdateCache;
+use crate::ftx::FtxOrderData;
+use crate::lambda::strategy::Strategy;
+use crate::lambda::GenericLambdaInstanceConfig;
+use crate::model::OrderUpdate;
+use std::sync::{Arc, Mutex};
+
+/// Orders found open on start-up, kept for the lambdas which sent them to adopt or cancel when
+/// they start.
+#[derive(Default)]
+pub struct RestoredOrders {
+    orders: Mutex<Vec<OrderUpdate>>,
+}
+
+impl RestoredOrders {
+    pub fn new() -> Self {
+        RestoredOrders::default()
+    }
+
+    /// Rebuild `order_update_cache` from the open orders of the exchange. Orders without a client
+    /// id were not placed by a lambda and are left alone. Returns the number restored.
+    pub fn restore_open_orders(
+        &self,
+        order_update_cache: &OrderUpdateCache,
+        orders: &[FtxOrderData],
+    ) -> usize {
+        let mut restored_orders = self.orders.lock().unwrap();
+        let mut restored = 0;
+        for order in orders.iter().filter(|order| order.clientId.is_some()) {
+            let order_update = order.to_order_update();
+            OrderUpdateCache::accept_order_update(
+                order_update_cache.cache.clone(),
+                order_update.clone(),
+            );
+            restored_orders.push(order_update);
+            restored += 1;
+        }
+        restored
+    }
+
+    /// Offer the restored orders sent by the instance on its markets to `strategy`. Returns the
+    /// orders it did not adopt, for the caller to cancel.
+    pub async fn restore_lambda_orders(
+        &self,
+        instance_config: &GenericLambdaInstanceConfig,
+        strategy: &Arc<dyn Strategy>,
+    ) -> Vec<OrderUpdate> {
+        let markets = instance_config.markets();
+        let orders: Vec<OrderUpdate> = {
+            let mut restored_orders = self.orders.lock().unwrap();
+            let (orders, others) = restored_orders.drain(..).partition(|order| {
+                order.is_owned_by(instance_config.name.as_str())
+                    && markets.contains(&(order.exchange.clone(), order.market.clone()))
+            });
+            *restored_orders = others;
+            orders
+        };
+        if orders.is_empty() {
+            return vec![];
+        }
+        let adopted = match strategy.on_restore(&orders).await {
+            Ok(adopted) => adopted,
+            Err(err) => {
+                error!("strategy on_restore: {}", err);
+                vec![]
+            }
+        };
+        orders
+            .into_iter()
+            .filter(|order| {
+                let adopt = adopted.contains(&order.cache_key());
+                if adopt {
+                    info!(
+                        "{} adopted order {}",
+                        instance_config.name,
+                        order.cache_key()
+                    );
+                }
+                !adopt
+            })
+            .collect()
+    }
+
+    /// orders no lambda has claimed yet
+    pub fn unclaimed(&self) -> Vec<OrderUpdate> {
+        self.orders.lock().unwrap().clone()
+    }
+}
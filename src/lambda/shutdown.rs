@@ -0,0 +1,85 @@
+use crate::cache::OrderUpdateCache;
+use crate::model::{OrderStatus, OrderUpdate};
+use tokio::signal::unix::{signal, SignalKind};
+
+/// time given to the cancels of working orders to be confirmed
+pub const CANCEL_TIMEOUT_MS: u64 = 5000;
+/// time given to the value-cache and measurement writes to land
+pub const FLUSH_TIMEOUT_MS: u64 = 2000;
+
+/// Resolves on SIGTERM or SIGINT with the name of the signal.
+pub async fn shutdown_signal() -> &'static str {
+    let mut terminate = match signal(SignalKind::terminate()) {
+        Ok(terminate) => terminate,
+        Err(err) => {
+            error!("Cannot listen for SIGTERM: {}", err);
+            return match tokio::signal::ctrl_c().await {
+                Ok(()) => "SIGINT",
+                Err(_) => futures_util::future::pending().await,
+            };
+        }
+    };
+    tokio::select! {
+        _ = terminate.recv() => "SIGTERM",
+        Ok(()) = tokio::signal::ctrl_c() => "SIGINT",
+    }
+}
+
+/// orders not yet confirmed closed, `OrderUpdateCache` drops Closed and Failed orders
+pub fn working_orders(order_update_cache: &OrderUpdateCache) -> Vec<OrderUpdate> {
+    order_update_cache
+        .cache
+        .iter()
+        .filter(|order| !matches!(order.status, OrderStatus::Closed | OrderStatus::Failed))
+        .map(|order| order.value().clone())
+        .collect()
+}
+
+/// What a graceful shutdown did and what it could not clean up.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct ShutdownReport {
+    pub signal: String,
+    pub stopped: Vec<String>,
+    /// client ids of orders confirmed closed after their cancel
+    pub cancelled: Vec<String>,
+    /// `exchange market client_id` of orders still working when the cancel timeout passed
+    pub open_orders: Vec<String>,
+    /// instances whose params could not be persisted, with the error
+    pub unpersisted: Vec<String>,
+    /// writes still in flight when the flush timeout passed
+    pub unflushed_values: usize,
+    pub unflushed_measurements: usize,
+}
+
+impl ShutdownReport {
+    pub fn is_clean(&self) -> bool {
+        self.open_orders.is_empty()
+            && self.unpersisted.is_empty()
+            && self.unflushed_values == 0
+            && self.unflushed_measurements == 0
+    }
+
+    pub fn summary(&self) -> String {
+        let mut summary = format!(
+            "shutdown on {}: stopped {:?}, cancelled {} orders",
+            self.signal,
+            self.stopped,
+            self.cancelled.len()
+        );
+        if !self.open_orders.is_empty() {
+            summary.push_str(format!(", orders left open {:?}", self.open_orders).as_str());
+        }
+        if !self.unpersisted.is_empty() {
+            summary.push_str(format!(", params not persisted {:?}", self.unpersisted).as_str());
+        }
+        if self.unflushed_values > 0 {
+            summary.push_str(format!(", {} value writes lost", self.unflushed_values).as_str());
+        }
+        if self.unflushed_measurements > 0 {
+            summary.push_str(
+                format!(", {} measurement writes lost", self.unflushed_measurements).as_str(),
+            );
+        }
+        summary
+    }
+}
@@ -0,0 +1,402 @@
+use crate::cache::MarketDepthCache;
+use crate::core::clock::SharedClock;
+use crate::lambda::strategy::basis_arb::params::{
+    self, BasisArbInitParams, BasisArbStrategyParams, BasisArbStrategyStateStruct,
+};
+use crate::lambda::strategy::basis_arb::pricing::{
+    edge, find_opportunity, unwind_order, update_fill_ratio, Leg, Opportunity,
+};
+use crate::lambda::strategy::{ParamSchema, Strategy, StrategyContext};
+use crate::lambda::{LambdaState, Watchdog};
+use crate::model::constants::Exchanges;
+use crate::model::market_data_model::MarketDepth;
+use crate::model::{Instrument, OrderSide, OrderStatus, OrderType, OrderUpdate};
+
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+type InitParams = BasisArbInitParams;
+type StrategyParams = BasisArbStrategyParams;
+type StrategyState = BasisArbStrategyStateStruct;
+
+#[derive(Debug, Clone, PartialEq)]
+enum ArbOrderKind {
+    Entry,
+    Unwind,
+}
+
+#[derive(Debug, Clone)]
+struct ArbOrder {
+    leg: Leg,
+    kind: ArbOrderKind,
+    size: f64,
+    filled_size: f64,
+    sent_ms: i64,
+}
+
+/// Arbitrage of the same asset quoted on two venues.
+///
+/// When the VWAP of `size` on one venue's asks is below the VWAP on the other venue's bids by more
+/// than both taker fees plus `min_basis`, both legs are sent as simultaneous IOC orders capped at
+/// the worst level needed. A leg that fills less than the other leaves a net exposure, which is
+/// unwound with an IOC on the overfilled leg before the next arb.
+pub struct Lambda {
+    market_depth: Arc<MarketDepthCache>,
+    instrument_a: Arc<Instrument>,
+    instrument_b: Arc<Instrument>,
+    strategy_state: Mutex<StrategyState>,
+    orders: Mutex<HashMap<String, ArbOrder>>,
+    /// one arb or unwind is sent at a time
+    send_lock: tokio::sync::Mutex<()>,
+    context: StrategyContext,
+    watchdog: Watchdog,
+    clock: SharedClock,
+}
+
+impl Lambda {
+    pub fn new(context: &StrategyContext) -> anyhow::Result<Self> {
+        let init_params = context.init_params::<InitParams>()?;
+        let instrument_a = context.instrument(init_params.symbol_a.as_str())?;
+        let instrument_b = context.instrument(init_params.symbol_b.as_str())?;
+
+        let watchdog = context.watchdog(vec![instrument_a.clone(), instrument_b.clone()]);
+
+        Ok(Lambda {
+            market_depth: context.market_depth_cache.clone(),
+            instrument_a,
+            instrument_b,
+            strategy_state: Mutex::new(context.restored_state()),
+            orders: Mutex::new(HashMap::new()),
+            send_lock: tokio::sync::Mutex::new(()),
+            context: context.clone(),
+            watchdog,
+            clock: context.clock.clone(),
+        })
+    }
+
+    pub fn get_strategy_params(&self) -> anyhow::Result<StrategyParams> {
+        self.context.strategy_params()
+    }
+
+    pub fn get_strategy_state(&self) -> StrategyState {
+        self.strategy_state.lock().unwrap().clone()
+    }
+
+    fn instrument(&self, leg: Leg) -> &Arc<Instrument> {
+        match leg {
+            Leg::A => &self.instrument_a,
+            Leg::B => &self.instrument_b,
+        }
+    }
+
+    fn leg_of(&self, exchange: &Exchanges, market: &str) -> Option<Leg> {
+        if *exchange == self.instrument_a.exchange && market == self.instrument_a.market {
+            Some(Leg::A)
+        } else if *exchange == self.instrument_b.exchange && market == self.instrument_b.market {
+            Some(Leg::B)
+        } else {
+            None
+        }
+    }
+
+    /// fresh book of a leg. MarketDepthCache is keyed by market only, a book of the same market
+    /// name on another venue is not ours
+    fn book(&self, leg: Leg) -> Option<MarketDepth> {
+        let instrument = self.instrument(leg);
+        self.market_depth
+            .get_clone(instrument.market.as_str())
+            .filter(|md| md.exchange == instrument.exchange)
+    }
+
+    fn has_orders(&self, kind: ArbOrderKind) -> bool {
+        self.orders
+            .lock()
+            .unwrap()
+            .values()
+            .any(|order| order.kind == kind)
+    }
+
+    fn within_position_limit(&self, params: &StrategyParams, opportunity: &Opportunity) -> bool {
+        let state = self.strategy_state.lock().unwrap();
+        let (buy_position, sell_position) = match opportunity.buy_leg {
+            Leg::A => (state.position_a, state.position_b),
+            Leg::B => (state.position_b, state.position_a),
+        };
+        buy_position + params.size <= params.max_position
+            && sell_position - params.size >= -params.max_position
+    }
+
+    async fn send_ioc(
+        &self,
+        leg: Leg,
+        kind: ArbOrderKind,
+        side: OrderSide,
+        price: f64,
+        size: f64,
+    ) -> anyhow::Result<()> {
+        let client_id = self
+            .instrument(leg)
+            .send_order_request(side, price, size, OrderType::Limit, true, false)
+            .await?;
+        if let Some(client_id) = client_id {
+            self.orders.lock().unwrap().insert(
+                client_id,
+                ArbOrder {
+                    leg,
+                    kind,
+                    size,
+                    filled_size: 0.0,
+                    sent_ms: self.clock.now_ms(),
+                },
+            );
+        }
+        Ok(())
+    }
+
+    /// flatten the net exposure left by a legged arb, once no order is in flight
+    async fn unwind(&self, params: &StrategyParams) -> anyhow::Result<bool> {
+        let (position_a, position_b) = {
+            let state = self.strategy_state.lock().unwrap();
+            (state.position_a, state.position_b)
+        };
+        let (leg, side, size) = match unwind_order(params, position_a, position_b) {
+            None => return Ok(false),
+            Some(unwind) => unwind,
+        };
+        let touch_px = match self.book(leg) {
+            None => {
+                warn!("unwind {:?} {} {} waits for a fresh book", leg, side, size);
+                return Ok(true);
+            }
+            Some(md) => match side {
+                OrderSide::Buy => md.asks.first().map(|level| level.price),
+                OrderSide::Sell => md.bids.first().map(|level| level.price),
+            },
+        };
+        let touch_px = match touch_px {
+            None => return Ok(true),
+            Some(touch_px) => touch_px,
+        };
+        let slippage = params.max_unwind_slippage_bp / 10000.0;
+        let price = match side {
+            OrderSide::Buy => touch_px * (1.0 + slippage),
+            OrderSide::Sell => touch_px * (1.0 - slippage),
+        };
+        info!("unwind {:?} {} {} @ {}", leg, side, size, price);
+        self.strategy_state.lock().unwrap().unwind_cnt += 1;
+        self.send_ioc(leg, ArbOrderKind::Unwind, side, price, size)
+            .await?;
+        Ok(true)
+    }
+
+    /// unwind any legging, otherwise fire an arb if the books allow
+    async fn trade(&self) -> anyhow::Result<()> {
+        let _guard = self.send_lock.lock().await;
+        let params = self.get_strategy_params()?;
+        let (book_a, book_b) = match (self.book(Leg::A), self.book(Leg::B)) {
+            (Some(book_a), Some(book_b)) => (book_a, book_b),
+            _ => {
+                let mut state = self.strategy_state.lock().unwrap();
+                state.edge_ab_bp = None;
+                state.edge_ba_bp = None;
+                return Ok(());
+            }
+        };
+        {
+            let mut state = self.strategy_state.lock().unwrap();
+            state.edge_ab_bp = edge(&params, &book_a, &book_b).map(|(edge_bp, _, _)| edge_bp);
+            state.edge_ba_bp = edge(&params, &book_b, &book_a).map(|(edge_bp, _, _)| edge_bp);
+        }
+        if !matches!(params.state, LambdaState::Live) {
+            return Ok(());
+        }
+        if self.has_orders(ArbOrderKind::Entry) || self.has_orders(ArbOrderKind::Unwind) {
+            return Ok(());
+        }
+        if self.unwind(&params).await? {
+            return Ok(());
+        }
+
+        let now_ms = self.clock.now_ms();
+        let last_arb_ms = self.strategy_state.lock().unwrap().last_arb_ms;
+        if let Some(last_arb_ms) = last_arb_ms {
+            if now_ms - last_arb_ms < params.cooldown_ms {
+                return Ok(());
+            }
+        }
+        let opportunity = match find_opportunity(&params, &book_a, &book_b) {
+            None => return Ok(()),
+            Some(opportunity) => opportunity,
+        };
+        if !self.within_position_limit(&params, &opportunity) {
+            return Ok(());
+        }
+        info!(
+            "arb buy {:?} @ {} sell {:?} @ {} edge {}bp",
+            opportunity.buy_leg,
+            opportunity.buy_px,
+            opportunity.sell_leg(),
+            opportunity.sell_px,
+            opportunity.edge_bp
+        );
+        {
+            let mut state = self.strategy_state.lock().unwrap();
+            state.arb_cnt += 1;
+            state.last_arb_ms = Some(now_ms);
+        }
+        let (buy, sell) = tokio::join!(
+            self.send_ioc(
+                opportunity.buy_leg,
+                ArbOrderKind::Entry,
+                OrderSide::Buy,
+                opportunity.buy_px,
+                params.size,
+            ),
+            self.send_ioc(
+                opportunity.sell_leg(),
+                ArbOrderKind::Entry,
+                OrderSide::Sell,
+                opportunity.sell_px,
+                params.size,
+            )
+        );
+        // a leg that failed to send is legging, unwound once the other leg closed
+        buy.and(sell)
+    }
+
+    /// cancel legs still open after `leg_timeout_ms`, their fills so far are unwound
+    async fn cancel_timed_out_orders(&self, params: &StrategyParams) {
+        let now_ms = self.clock.now_ms();
+        let timed_out: Vec<(String, Leg)> = self
+            .orders
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(_, order)| now_ms - order.sent_ms >= params.leg_timeout_ms)
+            .map(|(client_id, order)| (client_id.clone(), order.leg))
+            .collect();
+        for (client_id, leg) in timed_out {
+            warn!("{:?} leg {} timed out", leg, client_id);
+            if let Err(err) = self.instrument(leg).cancel_order(client_id.as_str()).await {
+                error!("cancel {} failed: {}", client_id, err);
+            }
+        }
+    }
+
+    /// Positions are taken from the filled size of order updates instead of fills, so the close of
+    /// a leg is never seen before its fills when deciding on an unwind.
+    fn apply_order_update(&self, params: &StrategyParams, order_update: &OrderUpdate) {
+        let client_id = match order_update.client_id {
+            None => return,
+            Some(ref client_id) => client_id,
+        };
+        let mut orders = self.orders.lock().unwrap();
+        let order = match orders.get_mut(client_id.as_str()) {
+            None => return,
+            Some(order) => order,
+        };
+        let filled = (order_update.filledSize - order.filled_size).max(0.0);
+        order.filled_size += filled;
+        let order = order.clone();
+
+        let mut state = self.strategy_state.lock().unwrap();
+        let signed_fill = match order_update.side {
+            OrderSide::Buy => filled,
+            OrderSide::Sell => -filled,
+        };
+        match order.leg {
+            Leg::A => state.position_a += signed_fill,
+            Leg::B => state.position_b += signed_fill,
+        }
+        state.net_exposure = state.position_a + state.position_b;
+
+        match order_update.status {
+            OrderStatus::Closed | OrderStatus::Failed => {
+                orders.remove(client_id.as_str());
+                if order.kind == ArbOrderKind::Entry {
+                    let fill_ratio = match order.leg {
+                        Leg::A => &mut state.fill_ratio_a,
+                        Leg::B => &mut state.fill_ratio_b,
+                    };
+                    *fill_ratio = Some(update_fill_ratio(
+                        *fill_ratio,
+                        order.filled_size,
+                        order.size,
+                        params.fill_ratio_alpha,
+                    ));
+                    let entry_done = !orders
+                        .values()
+                        .any(|order| order.kind == ArbOrderKind::Entry);
+                    if entry_done && state.net_exposure.abs() >= params.min_unwind_size {
+                        state.legged_cnt += 1;
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Strategy for Lambda {
+    async fn on_market_depth(&self, market_depth: &MarketDepth) -> anyhow::Result<()> {
+        if self
+            .leg_of(&market_depth.exchange, market_depth.market.as_str())
+            .is_none()
+        {
+            return Ok(());
+        }
+        self.trade().await
+    }
+
+    fn coalesce_market_depth(&self) -> bool {
+        true
+    }
+
+    async fn on_order_update(&self, order_update: &OrderUpdate) -> anyhow::Result<()> {
+        if self
+            .leg_of(&order_update.exchange, order_update.market.as_str())
+            .is_none()
+        {
+            return Ok(());
+        }
+        {
+            // an update never overtakes the order it belongs to being recorded
+            let _guard = self.send_lock.lock().await;
+            self.apply_order_update(&self.get_strategy_params()?, order_update);
+        }
+        self.trade().await
+    }
+
+    fn timer_interval(&self) -> Option<Duration> {
+        Some(Duration::from_millis(500))
+    }
+
+    async fn on_timer(&self) -> anyhow::Result<()> {
+        self.cancel_timed_out_orders(&self.get_strategy_params()?)
+            .await;
+        self.trade().await
+    }
+
+    fn param_schema(&self) -> ParamSchema {
+        params::param_schema()
+    }
+
+    async fn on_param_change(&self, _strategy_params: &Value) -> anyhow::Result<()> {
+        self.trade().await
+    }
+
+    async fn run(&self) -> anyhow::Result<()> {
+        tokio::select! {
+            result = self.context.period_publish_state(|| self.get_strategy_state()) => {
+                error!("period_publish_state completed: {:?}", result)
+            }
+            result = self.watchdog.subscribe() => {
+                error!("watchdog completed: {:?}", result)
+            }
+        }
+        Err(anyhow!("BasisArb run uncaught"))
+    }
+}
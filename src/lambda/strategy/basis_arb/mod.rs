@@ -0,0 +1,3 @@
+pub mod params;
+pub mod pricing;
+pub mod lambda;
@@ -0,0 +1,94 @@
+use crate::lambda::strategy::schema::{state_spec, ParamSchema, ParamSpec};
+use crate::lambda::LambdaState;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct BasisArbInitParams {
+    /// first venue of the asset, e.g. `BTC-PERP.FTX`
+    pub symbol_a: String,
+    /// second venue of the asset, e.g. `BTCUSDT.BINANCE`
+    pub symbol_b: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct BasisArbStrategyParams {
+    pub state: LambdaState,
+    /// size of each leg of an arb
+    pub size: f64,
+    /// edge in bp left after fees that fires an arb
+    pub min_basis: f64,
+    /// taker fee of the first venue
+    pub fee_a_bp: f64,
+    /// taker fee of the second venue
+    pub fee_b_bp: f64,
+    /// no new arb that would grow the position of either leg beyond this
+    pub max_position: f64,
+    /// net exposure between the legs below this is not unwound
+    pub min_unwind_size: f64,
+    /// limit price distance through the touch of unwind orders
+    pub max_unwind_slippage_bp: f64,
+    /// legs still open after this are cancelled and the legging is unwound
+    pub leg_timeout_ms: i64,
+    /// pause between two arbs
+    pub cooldown_ms: i64,
+    /// weight of the latest arb in the per leg fill ratio
+    pub fill_ratio_alpha: f64,
+}
+impl Default for BasisArbStrategyParams {
+    fn default() -> Self {
+        BasisArbStrategyParams {
+            state: LambdaState::Init,
+            size: 0.001,
+            min_basis: 5.0,
+            fee_a_bp: 7.0,
+            fee_b_bp: 4.0,
+            max_position: 0.01,
+            min_unwind_size: 0.0001,
+            max_unwind_slippage_bp: 20.0,
+            leg_timeout_ms: 2000,
+            cooldown_ms: 1000,
+            fill_ratio_alpha: 0.1,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct BasisArbStrategyStateStruct {
+    /// buy a / sell b edge after fees
+    pub edge_ab_bp: Option<f64>,
+    /// buy b / sell a edge after fees
+    pub edge_ba_bp: Option<f64>,
+    pub position_a: f64,
+    pub position_b: f64,
+    /// position_a + position_b, the legging not offset by the other venue
+    pub net_exposure: f64,
+    /// EWMA of filled / sent size of the legs, the risk of legging on each venue
+    pub fill_ratio_a: Option<f64>,
+    pub fill_ratio_b: Option<f64>,
+    pub arb_cnt: u64,
+    pub legged_cnt: u64,
+    pub unwind_cnt: u64,
+    pub last_arb_ms: Option<i64>,
+}
+
+pub fn param_schema() -> ParamSchema {
+    ParamSchema::new(vec![
+        state_spec(),
+        ParamSpec::float("size", "size of each leg").min(0.0),
+        ParamSpec::float("min_basis", "edge in bp after fees that fires an arb"),
+        ParamSpec::float("fee_a_bp", "taker fee of the first venue").min(0.0),
+        ParamSpec::float("fee_b_bp", "taker fee of the second venue").min(0.0),
+        ParamSpec::float("max_position", "position limit of either leg")
+            .min(0.0)
+            .admin(),
+        ParamSpec::float("min_unwind_size", "net exposure below this is not unwound").min(0.0),
+        ParamSpec::float("max_unwind_slippage_bp", "unwind price distance through the touch")
+            .min(0.0),
+        ParamSpec::int("leg_timeout_ms", "cancel legs still open after this").min(0.0),
+        ParamSpec::int("cooldown_ms", "pause between two arbs").min(0.0),
+        ParamSpec::float("fill_ratio_alpha", "weight of the latest arb in the fill ratio")
+            .min(0.0)
+            .max(1.0),
+    ])
+    .parsed_as::<BasisArbStrategyParams>()
+}
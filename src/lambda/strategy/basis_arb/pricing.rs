@@ -0,0 +1,130 @@
+use crate::lambda::strategy::basis_arb::params::BasisArbStrategyParams;
+use crate::model::market_data_model::{MarketDepth, PriceLevel};
+use crate::model::OrderSide;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Leg {
+    A,
+    B,
+}
+
+/// average price and worst level price of taking `size` from `levels`, None if the book is too thin
+pub fn vwap(levels: &[PriceLevel], size: f64) -> Option<(f64, f64)> {
+    if size <= 0.0 {
+        return None;
+    }
+    let mut rest = size;
+    let mut notional = 0.0;
+    for level in levels.iter() {
+        let take = level.size.min(rest);
+        notional += take * level.price;
+        rest -= take;
+        if rest <= size * 1e-9 {
+            return Some((notional / size, level.price));
+        }
+    }
+    None
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Opportunity {
+    pub buy_leg: Leg,
+    /// IOC limit of the buy leg, the worst ask level needed to fill the size
+    pub buy_px: f64,
+    /// IOC limit of the sell leg, the worst bid level needed to fill the size
+    pub sell_px: f64,
+    /// edge after fees of both legs
+    pub edge_bp: f64,
+}
+
+impl Opportunity {
+    pub fn sell_leg(&self) -> Leg {
+        match self.buy_leg {
+            Leg::A => Leg::B,
+            Leg::B => Leg::A,
+        }
+    }
+}
+
+/// edge of buying `size` on `buy` and selling it on `sell` in bp, after the taker fees of both
+pub fn edge(
+    params: &BasisArbStrategyParams,
+    buy: &MarketDepth,
+    sell: &MarketDepth,
+) -> Option<(f64, f64, f64)> {
+    let (buy_vwap, buy_px) = vwap(&buy.asks, params.size)?;
+    let (sell_vwap, sell_px) = vwap(&sell.bids, params.size)?;
+    let edge_bp =
+        (sell_vwap - buy_vwap) / buy_vwap * 10000.0 - params.fee_a_bp - params.fee_b_bp;
+    Some((edge_bp, buy_px, sell_px))
+}
+
+/// the better direction between the two books if its edge exceeds `min_basis`
+pub fn find_opportunity(
+    params: &BasisArbStrategyParams,
+    book_a: &MarketDepth,
+    book_b: &MarketDepth,
+) -> Option<Opportunity> {
+    let ab = edge(params, book_a, book_b).map(|(edge_bp, buy_px, sell_px)| Opportunity {
+        buy_leg: Leg::A,
+        buy_px,
+        sell_px,
+        edge_bp,
+    });
+    let ba = edge(params, book_b, book_a).map(|(edge_bp, buy_px, sell_px)| Opportunity {
+        buy_leg: Leg::B,
+        buy_px,
+        sell_px,
+        edge_bp,
+    });
+    let best = match (ab, ba) {
+        (Some(ab), Some(ba)) => {
+            if ab.edge_bp >= ba.edge_bp {
+                ab
+            } else {
+                ba
+            }
+        }
+        (Some(ab), None) => ab,
+        (None, Some(ba)) => ba,
+        (None, None) => return None,
+    };
+    match best.edge_bp >= params.min_basis {
+        true => Some(best),
+        false => None,
+    }
+}
+
+/// Order that flattens the net exposure of a legged arb, None below `min_unwind_size`.
+///
+/// While net long the leg holding the larger position sells, while net short the leg holding the
+/// smaller one buys, so the unwind takes back the excess fill instead of adding to the other venue.
+pub fn unwind_order(
+    params: &BasisArbStrategyParams,
+    position_a: f64,
+    position_b: f64,
+) -> Option<(Leg, OrderSide, f64)> {
+    let net = position_a + position_b;
+    if net.abs() < params.min_unwind_size.max(f64::EPSILON) {
+        return None;
+    }
+    if net > 0.0 {
+        let leg = if position_a >= position_b { Leg::A } else { Leg::B };
+        Some((leg, OrderSide::Sell, net))
+    } else {
+        let leg = if position_a <= position_b { Leg::A } else { Leg::B };
+        Some((leg, OrderSide::Buy, -net))
+    }
+}
+
+/// EWMA of the filled share of the size sent on a leg
+pub fn update_fill_ratio(fill_ratio: Option<f64>, filled_size: f64, size: f64, alpha: f64) -> f64 {
+    let ratio = match size > 0.0 {
+        true => (filled_size / size).min(1.0),
+        false => 0.0,
+    };
+    match fill_ratio {
+        None => ratio,
+        Some(fill_ratio) => fill_ratio + alpha * (ratio - fill_ratio),
+    }
+}
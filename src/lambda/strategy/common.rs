@@ -0,0 +1,9 @@
+use crate::model::market_data_model::MarketDepth;
+
+/// mid of the best bid and ask, None while a side of the book is empty
+pub fn mid_price(market_depth: &MarketDepth) -> Option<f64> {
+    match (market_depth.bids.first(), market_depth.asks.first()) {
+        (Some(bid), Some(ask)) => Some((bid.price + ask.price) / 2.0),
+        _ => None,
+    }
+}
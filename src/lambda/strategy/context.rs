@@ -0,0 +1,150 @@
+use crate::cache::{MarketDepthCache, OrderUpdateCache, ValueCache, ValueCacheKey};
+use crate::core::clock::SharedClock;
+use crate::ftx::FtxRestClient;
+use crate::lambda::execution::{execution_owner, Executor};
+use crate::lambda::{GenericLambdaInstanceConfig, LambdaInstanceConfig, Watchdog};
+use crate::model::{Instrument, InstrumentSymbol, MeasurementCache};
+use crate::pubsub::simple_message_bus::MessageBusSender;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Everything a strategy is built from. Shared by the engine, the backtest and the tests.
+#[derive(Clone)]
+pub struct StrategyContext {
+    pub instance_config: GenericLambdaInstanceConfig,
+    pub market_depth_cache: Arc<MarketDepthCache>,
+    pub order_update_cache: Arc<OrderUpdateCache>,
+    pub message_bus_sender: MessageBusSender,
+    pub measurement_cache: Arc<MeasurementCache>,
+    pub value_cache: Arc<ValueCache>,
+    /// the engine's FTX client, its rate limiter budgets the whole account. None without FTX,
+    /// e.g. in a backtest
+    pub ftx_client: Option<Arc<FtxRestClient>>,
+    pub clock: SharedClock,
+}
+
+impl StrategyContext {
+    /// `init_params` of the instance config, parsed into the strategy's own type
+    pub fn init_params<T: DeserializeOwned>(&self) -> anyhow::Result<T> {
+        let lambda_instance_config = LambdaInstanceConfig::load(self.instance_config.name.as_str());
+        Ok(serde_json::from_value::<T>(
+            lambda_instance_config.init_params,
+        )?)
+    }
+
+    /// current `StrategyParams` of the ValueCache, parsed into the strategy's own type
+    pub fn strategy_params<T: DeserializeOwned>(&self) -> anyhow::Result<T> {
+        let value = self
+            .value_cache
+            .get_clone(ValueCacheKey::StrategyParams)
+            .ok_or_else(|| anyhow!("StrategyParams not in ValueCache"))?;
+        Ok(serde_json::from_value::<T>(value)?)
+    }
+
+    /// `StrategyStates` hydrated from redis on a warm restart, the default state otherwise
+    pub fn restored_state<T: DeserializeOwned + Default>(&self) -> T {
+        restored_state(&self.value_cache)
+    }
+
+    /// instrument for a `MARKET.EXCHANGE` token, e.g. `ETH-PERP.FTX`, owned by this instance
+    pub fn instrument(&self, token: &str) -> anyhow::Result<Arc<Instrument>> {
+        let InstrumentSymbol(exchange, market) = InstrumentSymbol::from_str(token)
+            .map_err(|_| anyhow!("Cannot parse instrument from token {}", token))?;
+        Ok(Arc::new(Instrument::new(
+            self.instance_config.name.as_str(),
+            exchange,
+            market.as_str(),
+            self.order_update_cache.clone(),
+            self.message_bus_sender.clone(),
+            self.measurement_cache.clone(),
+        )))
+    }
+
+    /// watchdog over the instance's `market_depths`, cancelling the open orders of `instruments`
+    /// when it pauses the lambda
+    pub fn watchdog(&self, instruments: Vec<Arc<Instrument>>) -> Watchdog {
+        let market_depths = self
+            .instance_config
+            .lambda_params
+            .market_depths
+            .iter()
+            .map(|token| Instrument::instrument_symbol(token.as_str()).1)
+            .collect();
+        Watchdog::new(
+            self.instance_config.lambda_params.watchdog.clone(),
+            market_depths,
+            self.market_depth_cache.clone(),
+            self.measurement_cache.clone(),
+            self.value_cache.clone(),
+            instruments,
+            self.clock.clone(),
+        )
+    }
+
+    /// publish `state()` as the `StrategyStates` of the ValueCache every 500ms, never returns
+    pub async fn period_publish_state<S, F>(&self, state: F) -> anyhow::Result<()>
+    where
+        S: Serialize,
+        F: Fn() -> S,
+    {
+        loop {
+            match serde_json::to_value(state()) {
+                Ok(value) => {
+                    self.value_cache
+                        .insert(ValueCacheKey::StrategyStates, value);
+                }
+                Err(err) => error!("Error publishing lambda state: {}", err),
+            }
+            self.clock.sleep(Duration::from_millis(500)).await;
+        }
+    }
+
+    /// executor per market of the instance for operator trades, see `execution_owner`. Every call
+    /// builds new executors, the engine builds them once per start and runs them next to the
+    /// strategy
+    pub fn executors(&self) -> HashMap<String, Arc<Executor>> {
+        let owner = execution_owner(self.instance_config.name.as_str());
+        self.instance_config
+            .markets()
+            .into_iter()
+            .map(|(exchange, market)| {
+                let instrument = Arc::new(Instrument::new(
+                    owner.as_str(),
+                    exchange,
+                    market.as_str(),
+                    self.order_update_cache.clone(),
+                    self.message_bus_sender.clone(),
+                    self.measurement_cache.clone(),
+                ));
+                let executor = Executor::new(
+                    instrument,
+                    self.market_depth_cache.clone(),
+                    self.clock.clone(),
+                );
+                (market, Arc::new(executor))
+            })
+            .collect()
+    }
+}
+
+/// `StrategyStates` of `value_cache` as `T`, the default when there is none or it does not parse,
+/// e.g. after the state struct changed
+pub fn restored_state<T: DeserializeOwned + Default>(value_cache: &ValueCache) -> T {
+    match value_cache.get_clone(ValueCacheKey::StrategyStates) {
+        None => T::default(),
+        Some(value) => match serde_json::from_value::<T>(value) {
+            Ok(state) => {
+                info!("restored strategy state");
+                state
+            }
+            Err(err) => {
+                warn!("Cannot restore strategy state: {}", err);
+                T::default()
+            }
+        },
+    }
+}
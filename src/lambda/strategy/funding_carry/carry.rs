@@ -0,0 +1,53 @@
+use crate::lambda::strategy::funding_carry::params::FundingCarryStrategyParams;
+use crate::model::OrderSide;
+
+/// premium of the perp over the spot in bp
+pub fn basis_bp(perp_mid: f64, spot_mid: f64) -> f64 {
+    (perp_mid - spot_mid) / spot_mid * 10000.0
+}
+
+/// Carry in bp of holding short perp / long spot over `horizon_hours`.
+///
+/// The predicted funding is received every hour, a perp premium is earned as it converges, and
+/// both legs pay the taker fee on entry and exit.
+pub fn expected_carry_bp(params: &FundingCarryStrategyParams, funding_rate: f64, basis_bp: f64) -> f64 {
+    funding_rate * 10000.0 * params.horizon_hours + basis_bp
+        - 2.0 * (params.fee_perp_bp + params.fee_spot_bp)
+}
+
+/// Perp position to hold, zero or short.
+///
+/// A position opens at `min_carry_bp` and scales linearly up to `max_size` at `full_carry_bp`.
+/// Between `exit_carry_bp` and `min_carry_bp` an open position is kept as is, below it is closed.
+pub fn target_position(params: &FundingCarryStrategyParams, carry_bp: f64, position: f64) -> f64 {
+    if carry_bp < params.exit_carry_bp {
+        return 0.0;
+    }
+    if carry_bp < params.min_carry_bp {
+        return position.min(0.0);
+    }
+    let scale = match params.full_carry_bp > params.min_carry_bp {
+        true => ((carry_bp - params.min_carry_bp) / (params.full_carry_bp - params.min_carry_bp))
+            .clamp(0.0, 1.0),
+        false => 1.0,
+    };
+    // at least one clip once the carry is worth opening
+    -(params.max_size * scale).max(params.clip_size.min(params.max_size))
+}
+
+/// perp order moving `position` towards `target`, None within `rebalance_threshold`
+pub fn rebalance_order(
+    params: &FundingCarryStrategyParams,
+    position: f64,
+    target: f64,
+) -> Option<(OrderSide, f64)> {
+    let diff = target - position;
+    if diff.abs() < params.rebalance_threshold.max(f64::EPSILON) {
+        return None;
+    }
+    let size = diff.abs().min(params.clip_size);
+    match diff > 0.0 {
+        true => Some((OrderSide::Buy, size)),
+        false => Some((OrderSide::Sell, size)),
+    }
+}
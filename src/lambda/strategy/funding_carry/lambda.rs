@@ -0,0 +1,337 @@
+use crate::cache::{MarketDepthCache, ValueCacheKey};
+use crate::core::clock::SharedClock;
+use crate::ftx::FtxRestClient;
+use crate::lambda::strategy::common::mid_price;
+use crate::lambda::strategy::funding_carry::carry::{
+    basis_bp, expected_carry_bp, rebalance_order, target_position,
+};
+use crate::lambda::strategy::funding_carry::params::{
+    self, FundingCarryInitParams, FundingCarryStrategyParams, FundingCarryStrategyStateStruct,
+};
+use crate::lambda::strategy::swap_mm::hedger::Hedger;
+use crate::lambda::strategy::{ParamSchema, Strategy, StrategyContext};
+use crate::lambda::{LambdaState, Watchdog};
+use crate::model::constants::Exchanges;
+use crate::model::market_data_model::MarketDepth;
+use crate::model::{Instrument, OrderFill, OrderSide, OrderStatus, OrderType, OrderUpdate};
+
+use serde_json::Value;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+type InitParams = FundingCarryInitParams;
+type StrategyParams = FundingCarryStrategyParams;
+type StrategyState = FundingCarryStrategyStateStruct;
+
+/// Delta neutral short perp / long spot position sized by the expected carry.
+///
+/// The perp is moved towards `target_position` in IOC clips whenever it drifted more than
+/// `rebalance_threshold`, the spot leg follows every perp fill through the `Hedger`. Only the
+/// positive carry direction is traded, spot is never shorted.
+pub struct Lambda {
+    market_depth: Arc<MarketDepthCache>,
+    perp_instrument: Arc<Instrument>,
+    spot_instrument: Arc<Instrument>,
+    hedger: Hedger,
+    /// predicted funding of FTX perps, None for other venues which rely on `funding_rate_override`
+    rest_client: Option<Arc<FtxRestClient>>,
+    strategy_state: Mutex<StrategyState>,
+    /// client id and sent time of the perp order in flight
+    rebalance_order: Mutex<Option<(String, i64)>>,
+    send_lock: tokio::sync::Mutex<()>,
+    context: StrategyContext,
+    watchdog: Watchdog,
+    clock: SharedClock,
+}
+
+impl Lambda {
+    pub fn new(context: &StrategyContext) -> anyhow::Result<Self> {
+        let init_params = context.init_params::<InitParams>()?;
+        let perp_instrument = context.instrument(init_params.perp_symbol.as_str())?;
+        let spot_instrument = context.instrument(init_params.spot_symbol.as_str())?;
+
+        let watchdog = context.watchdog(vec![perp_instrument.clone(), spot_instrument.clone()]);
+        let hedger = Hedger::new(
+            perp_instrument.clone(),
+            spot_instrument.clone(),
+            context.market_depth_cache.clone(),
+            context.clock.clone(),
+        );
+        let strategy_state: StrategyState = context.restored_state();
+        hedger.restore_unhedged_delta(strategy_state.net_delta);
+        let rest_client = match perp_instrument.exchange {
+            // the engine's client, so the polls count against the account's rate limit
+            Exchanges::FTX => context.ftx_client.clone(),
+            _ => None,
+        };
+
+        Ok(Lambda {
+            market_depth: context.market_depth_cache.clone(),
+            perp_instrument,
+            spot_instrument,
+            hedger,
+            rest_client,
+            strategy_state: Mutex::new(strategy_state),
+            rebalance_order: Mutex::new(None),
+            send_lock: tokio::sync::Mutex::new(()),
+            context: context.clone(),
+            watchdog,
+            clock: context.clock.clone(),
+        })
+    }
+
+    pub fn get_strategy_params(&self) -> anyhow::Result<StrategyParams> {
+        self.context.strategy_params()
+    }
+
+    pub fn get_strategy_state(&self) -> StrategyState {
+        self.strategy_state.lock().unwrap().clone()
+    }
+
+    /// the state with the hedger's current delta
+    fn published_state(&self) -> StrategyState {
+        let mut state = self.strategy_state.lock().unwrap();
+        state.net_delta = self.hedger.unhedged_delta();
+        state.hedge_in_flight = self.hedger.in_flight();
+        state.clone()
+    }
+
+    async fn period_poll_funding(&self) -> anyhow::Result<()> {
+        let rest_client = match self.rest_client {
+            None => return futures_util::future::pending().await,
+            Some(ref rest_client) => rest_client,
+        };
+        loop {
+            match rest_client
+                .get_future_stats(self.perp_instrument.market.as_str())
+                .await
+            {
+                Ok(stats) => {
+                    self.strategy_state.lock().unwrap().funding_rate = stats.nextFundingRate
+                }
+                Err(err) => {
+                    // a stale rate must not keep sizing the position
+                    error!("get_future_stats {}: {}", self.perp_instrument.market, err);
+                    self.strategy_state.lock().unwrap().funding_rate = None;
+                }
+            }
+            let interval = self
+                .get_strategy_params()?
+                .funding_poll_interval_ms
+                .max(1000);
+            self.clock.sleep(Duration::from_millis(interval)).await;
+        }
+    }
+
+    fn is_instrument(instrument: &Instrument, exchange: &Exchanges, market: &str) -> bool {
+        *exchange == instrument.exchange && market == instrument.market
+    }
+
+    /// expected carry from the latest books and funding, None while either is missing
+    fn update_carry(&self, params: &StrategyParams) -> Option<f64> {
+        let perp_depth = self
+            .market_depth
+            .get_clone(self.perp_instrument.market.as_str());
+        let spot_depth = self
+            .market_depth
+            .get_clone(self.spot_instrument.market.as_str());
+        let mut state = self.strategy_state.lock().unwrap();
+        if params.funding_rate_override.is_some() {
+            state.funding_rate = params.funding_rate_override;
+        }
+        state.basis_bp = match (
+            perp_depth.as_ref().and_then(mid_price),
+            spot_depth.as_ref().and_then(mid_price),
+        ) {
+            (Some(perp_mid), Some(spot_mid)) => Some(basis_bp(perp_mid, spot_mid)),
+            _ => None,
+        };
+        state.expected_carry_bp = match (state.funding_rate, state.basis_bp) {
+            (Some(funding_rate), Some(basis_bp)) => {
+                Some(expected_carry_bp(params, funding_rate, basis_bp))
+            }
+            _ => None,
+        };
+        state.expected_carry_bp
+    }
+
+    fn touch_px(&self, side: &OrderSide) -> Option<f64> {
+        let md = self
+            .market_depth
+            .get_clone(self.perp_instrument.market.as_str())?;
+        let level = match side {
+            OrderSide::Buy => md.asks.first(),
+            OrderSide::Sell => md.bids.first(),
+        };
+        level.map(|level| level.price)
+    }
+
+    /// whether a perp order is in flight, one without a closing update past the timeout is
+    /// given up so a lost update does not block rebalancing
+    fn rebalance_in_flight(&self, params: &StrategyParams) -> bool {
+        let mut rebalance_order = self.rebalance_order.lock().unwrap();
+        let (client_id, sent_ms) = match *rebalance_order {
+            None => return false,
+            Some(ref order) => order,
+        };
+        if self.clock.now_ms() - sent_ms < params.rebalance_timeout_ms as i64 {
+            return true;
+        }
+        warn!("rebalance {} timed out without a closing update", client_id);
+        *rebalance_order = None;
+        false
+    }
+
+    /// move the perp one clip towards the target size of the current carry
+    async fn rebalance(&self) -> anyhow::Result<()> {
+        let _guard = self.send_lock.lock().await;
+        let params = self.get_strategy_params()?;
+        let carry_bp = self.update_carry(&params);
+        if !matches!(params.state, LambdaState::Live) {
+            return Ok(());
+        }
+        if self.rebalance_in_flight(&params) {
+            return Ok(());
+        }
+        let (target, position) = {
+            let mut state = self.strategy_state.lock().unwrap();
+            // without a carry estimate the position is held, not closed
+            if let Some(carry_bp) = carry_bp {
+                state.target_position = target_position(&params, carry_bp, state.perp_position);
+            }
+            (state.target_position, state.perp_position)
+        };
+        let (side, size) = match rebalance_order(&params, position, target) {
+            None => return Ok(()),
+            Some(order) => order,
+        };
+        let touch_px = match self.touch_px(&side) {
+            None => return Ok(()),
+            Some(touch_px) => touch_px,
+        };
+        let slippage = params.max_slippage_bp / 10000.0;
+        let price = match side {
+            OrderSide::Buy => touch_px * (1.0 + slippage),
+            OrderSide::Sell => touch_px * (1.0 - slippage),
+        };
+        info!(
+            "rebalance {} {} @ {}, position {} target {} carry {:?}bp",
+            side, size, price, position, target, carry_bp
+        );
+        let client_id = self
+            .perp_instrument
+            .send_order_request(side, price, size, OrderType::Limit, true, false)
+            .await?;
+        *self.rebalance_order.lock().unwrap() =
+            client_id.map(|client_id| (client_id, self.clock.now_ms()));
+        self.strategy_state.lock().unwrap().rebalance_cnt += 1;
+        Ok(())
+    }
+}
+
+#[async_trait::async_trait]
+impl Strategy for Lambda {
+    async fn on_init(&self) -> anyhow::Result<()> {
+        if let Some(value) = self
+            .context
+            .value_cache
+            .get_clone(ValueCacheKey::StrategyParams)
+        {
+            self.hedger
+                .set_params(serde_json::from_value::<StrategyParams>(value)?.hedger);
+        }
+        Ok(())
+    }
+
+    async fn on_market_depth(&self, market_depth: &MarketDepth) -> anyhow::Result<()> {
+        let exchange = &market_depth.exchange;
+        let market = market_depth.market.as_str();
+        if !Self::is_instrument(&self.perp_instrument, exchange, market)
+            && !Self::is_instrument(&self.spot_instrument, exchange, market)
+        {
+            return Ok(());
+        }
+        self.rebalance().await
+    }
+
+    fn coalesce_market_depth(&self) -> bool {
+        true
+    }
+
+    async fn on_order_update(&self, order_update: &OrderUpdate) -> anyhow::Result<()> {
+        if !Self::is_instrument(
+            &self.perp_instrument,
+            &order_update.exchange,
+            order_update.market.as_str(),
+        ) {
+            return Ok(());
+        }
+        match order_update.status {
+            OrderStatus::Closed | OrderStatus::Failed => {
+                let _guard = self.send_lock.lock().await;
+                let mut rebalance_order = self.rebalance_order.lock().unwrap();
+                let closed = match (rebalance_order.as_ref(), order_update.client_id.as_ref()) {
+                    (Some((client_id, _)), Some(closed_id)) => client_id == closed_id,
+                    _ => false,
+                };
+                if closed {
+                    *rebalance_order = None;
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    async fn on_fill(&self, order_fill: &OrderFill) -> anyhow::Result<()> {
+        if !Self::is_instrument(
+            &self.perp_instrument,
+            &order_fill.exchange,
+            order_fill.market.as_str(),
+        ) {
+            return Ok(());
+        }
+        let mut state = self.strategy_state.lock().unwrap();
+        match order_fill.side {
+            OrderSide::Buy => state.perp_position += order_fill.size,
+            OrderSide::Sell => state.perp_position -= order_fill.size,
+        }
+        Ok(())
+    }
+
+    fn timer_interval(&self) -> Option<Duration> {
+        Some(Duration::from_millis(1000))
+    }
+
+    async fn on_timer(&self) -> anyhow::Result<()> {
+        self.rebalance().await
+    }
+
+    fn param_schema(&self) -> ParamSchema {
+        params::param_schema()
+    }
+
+    async fn on_param_change(&self, strategy_params: &Value) -> anyhow::Result<()> {
+        self.hedger
+            .set_params(serde_json::from_value::<StrategyParams>(strategy_params.clone())?.hedger);
+        self.rebalance().await
+    }
+
+    async fn run(&self) -> anyhow::Result<()> {
+        tokio::select! {
+            result = self.context.period_publish_state(|| self.published_state()) => {
+                error!("period_publish_state completed: {:?}", result)
+            }
+            result = self.period_poll_funding() => {
+                error!("period_poll_funding completed: {:?}", result)
+            }
+            result = self.hedger.subscribe() => {
+                error!("hedger completed: {:?}", result)
+            }
+            result = self.watchdog.subscribe() => {
+                error!("watchdog completed: {:?}", result)
+            }
+        }
+        Err(anyhow!("FundingCarry run uncaught"))
+    }
+}
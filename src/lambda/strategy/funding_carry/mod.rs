@@ -0,0 +1,3 @@
+pub mod carry;
+pub mod params;
+pub mod lambda;
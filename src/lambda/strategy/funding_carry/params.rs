@@ -0,0 +1,106 @@
+use crate::lambda::strategy::schema::{state_spec, ParamSchema, ParamSpec};
+use crate::lambda::strategy::swap_mm::params::HedgerParams;
+use crate::lambda::LambdaState;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct FundingCarryInitParams {
+    /// perpetual paying the funding, e.g. `ETH-PERP.FTX`
+    pub perp_symbol: String,
+    /// spot hedging the perp, e.g. `ETH/USD.FTX`
+    pub spot_symbol: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct FundingCarryStrategyParams {
+    pub state: LambdaState,
+    /// short perp size at `full_carry_bp`
+    pub max_size: f64,
+    /// expected carry in bp to open a position
+    pub min_carry_bp: f64,
+    /// expected carry the position is scaled to `max_size` at
+    pub full_carry_bp: f64,
+    /// the position is closed once the expected carry falls below this
+    pub exit_carry_bp: f64,
+    /// hours of funding the expected carry is computed over
+    pub horizon_hours: f64,
+    /// taker fees of the perp and the spot, paid on entry and exit
+    pub fee_perp_bp: f64,
+    pub fee_spot_bp: f64,
+    /// perp drift from the target size that triggers a rebalance
+    pub rebalance_threshold: f64,
+    /// largest perp order of a rebalance
+    pub clip_size: f64,
+    /// limit price distance through the touch of perp orders
+    pub max_slippage_bp: f64,
+    /// a perp order without a closing update for this long is given up, its fills still count
+    pub rebalance_timeout_ms: u64,
+    /// predicted hourly funding rate used instead of the exchange's, e.g. in simulation
+    pub funding_rate_override: Option<f64>,
+    pub funding_poll_interval_ms: u64,
+    /// spot leg management, `threshold` is the delta drift between the legs that is rehedged
+    pub hedger: HedgerParams,
+}
+impl Default for FundingCarryStrategyParams {
+    fn default() -> Self {
+        FundingCarryStrategyParams {
+            state: LambdaState::Init,
+            max_size: 0.01,
+            min_carry_bp: 10.0,
+            full_carry_bp: 50.0,
+            exit_carry_bp: 0.0,
+            horizon_hours: 24.0,
+            fee_perp_bp: 7.0,
+            fee_spot_bp: 7.0,
+            rebalance_threshold: 0.001,
+            clip_size: 0.01,
+            max_slippage_bp: 10.0,
+            rebalance_timeout_ms: 10000,
+            funding_rate_override: None,
+            funding_poll_interval_ms: 60000,
+            hedger: HedgerParams::default(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct FundingCarryStrategyStateStruct {
+    /// predicted hourly funding rate, received by shorts when positive
+    pub funding_rate: Option<f64>,
+    /// perp premium over spot
+    pub basis_bp: Option<f64>,
+    pub expected_carry_bp: Option<f64>,
+    /// target perp position, zero or short
+    pub target_position: f64,
+    pub perp_position: f64,
+    /// perp and spot delta not yet offset by the spot leg
+    pub net_delta: f64,
+    pub hedge_in_flight: f64,
+    pub rebalance_cnt: u64,
+}
+
+pub fn param_schema() -> ParamSchema {
+    ParamSchema::new(vec![
+        state_spec(),
+        ParamSpec::float("max_size", "short perp size at full_carry_bp")
+            .min(0.0)
+            .admin(),
+        ParamSpec::float("min_carry_bp", "expected carry to open a position"),
+        ParamSpec::float("full_carry_bp", "expected carry of the full position"),
+        ParamSpec::float("exit_carry_bp", "close the position below this expected carry"),
+        ParamSpec::float("horizon_hours", "hours of funding in the expected carry").min(1.0),
+        ParamSpec::float("fee_perp_bp", "taker fee of the perp").min(0.0),
+        ParamSpec::float("fee_spot_bp", "taker fee of the spot").min(0.0),
+        ParamSpec::float("rebalance_threshold", "perp drift from the target to rebalance").min(0.0),
+        ParamSpec::float("clip_size", "largest perp order of a rebalance").min(0.0),
+        ParamSpec::float("max_slippage_bp", "perp order price distance through the touch").min(0.0),
+        ParamSpec::int("rebalance_timeout_ms", "give up a perp order without a closing update")
+            .min(1000.0),
+        ParamSpec::float("funding_rate_override", "hourly funding rate used instead of the exchange's")
+            .nullable(),
+        ParamSpec::int("funding_poll_interval_ms", "period of polling the predicted funding")
+            .min(1000.0),
+        ParamSpec::object("hedger", "spot leg order type, drift threshold and price cap"),
+    ])
+    .parsed_as::<FundingCarryStrategyParams>()
+}
@@ -0,0 +1,370 @@
+use crate::cache::MarketDepthCache;
+use crate::lambda::strategy::common::mid_price;
+use crate::lambda::strategy::latency_mm::params::{
+    self, LatencyMMInitParams, LatencyMMStrategyParams, LatencyMMStrategyStateStruct,
+};
+use crate::lambda::strategy::latency_mm::quoter::{
+    inventory_skew_bp, should_requote, target_quotes, update_lead_lag_offset,
+};
+use crate::lambda::strategy::{ParamSchema, Strategy, StrategyContext};
+use crate::lambda::{LambdaState, Watchdog};
+use crate::model::constants::Exchanges;
+use crate::model::market_data_model::MarketDepth;
+use crate::model::{
+    level_size, Instrument, OrderFill, OrderSide, OrderStatus, OrderType, OrderUpdate,
+    QueuePosition,
+};
+
+use dashmap::DashMap;
+use serde_json::Value;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+type InitParams = LatencyMMInitParams;
+type StrategyParams = LatencyMMStrategyParams;
+type StrategyState = LatencyMMStrategyStateStruct;
+
+/// Market maker quoting the quote instrument around a fair value led by a faster moving book.
+///
+/// The fair value is the lead mid plus a slow EWMA of the quote/lead mid difference, so quotes
+/// follow the lead book before the quote book catches up. Quotes are skewed against the position
+/// and resting orders are only moved when the target drifted far enough, see `should_requote`.
+pub struct Lambda {
+    market_depth: Arc<MarketDepthCache>,
+    quote_instrument: Arc<Instrument>,
+    lead_instrument: Arc<Instrument>,
+    strategy_state: Mutex<StrategyState>,
+    queue_positions: DashMap<String, QueuePosition>,
+    context: StrategyContext,
+    watchdog: Watchdog,
+}
+
+impl Lambda {
+    pub fn new(context: &StrategyContext) -> anyhow::Result<Self> {
+        let init_params = context.init_params::<InitParams>()?;
+        let quote_instrument = context.instrument(init_params.quote_symbol.as_str())?;
+        let lead_instrument = context.instrument(init_params.lead_symbol.as_str())?;
+
+        let watchdog = context.watchdog(vec![quote_instrument.clone()]);
+
+        Ok(Lambda {
+            market_depth: context.market_depth_cache.clone(),
+            quote_instrument,
+            lead_instrument,
+            strategy_state: Mutex::new(context.restored_state()),
+            queue_positions: DashMap::new(),
+            context: context.clone(),
+            watchdog,
+        })
+    }
+
+    pub fn get_strategy_params(&self) -> anyhow::Result<StrategyParams> {
+        self.context.strategy_params()
+    }
+
+    pub fn get_strategy_state(&self) -> StrategyState {
+        self.strategy_state.lock().unwrap().clone()
+    }
+
+    fn should_run_trading(&self, params: &StrategyParams) -> bool {
+        matches!(params.state, LambdaState::Live)
+    }
+
+    fn on_lead_depth(&self, market_depth: &MarketDepth) {
+        let mut state = self.strategy_state.lock().unwrap();
+        state.lead_mid_px = mid_price(market_depth);
+        state.fair_value = match (state.lead_mid_px, state.lead_lag_offset) {
+            (Some(lead_mid_px), Some(offset)) => Some(lead_mid_px + offset),
+            _ => None,
+        };
+    }
+
+    fn on_quote_depth(&self, market_depth: &MarketDepth, params: &StrategyParams) {
+        {
+            let mut state = self.strategy_state.lock().unwrap();
+            state.quote_mid_px = mid_price(market_depth);
+            state.depth_bid_px = market_depth.bids.first().map(|level| level.price);
+            state.depth_ask_px = market_depth.asks.first().map(|level| level.price);
+            if let (Some(quote_mid_px), Some(lead_mid_px)) = (state.quote_mid_px, state.lead_mid_px)
+            {
+                let offset = update_lead_lag_offset(
+                    state.lead_lag_offset,
+                    quote_mid_px,
+                    lead_mid_px,
+                    params.offset_ewma_alpha,
+                );
+                state.lead_lag_offset = Some(offset);
+                state.fair_value = Some(lead_mid_px + offset);
+            }
+        }
+        self.update_queue_positions(market_depth);
+    }
+
+    fn update_queue_positions(&self, market_depth: &MarketDepth) {
+        for order in self.quote_instrument.get_open_orders(false) {
+            let client_id = match order.client_id {
+                Some(ref client_id) => client_id.clone(),
+                None => continue,
+            };
+            match order.status {
+                OrderStatus::New | OrderStatus::Open => {}
+                _ => continue,
+            }
+            let level_size = level_size(market_depth, &order.side, order.price);
+            match self.queue_positions.get_mut(client_id.as_str()) {
+                Some(mut queue_position) => {
+                    queue_position.on_level_size(level_size, order.remainingSize);
+                }
+                None => {
+                    self.queue_positions.insert(
+                        client_id,
+                        QueuePosition::new(level_size, order.remainingSize),
+                    );
+                }
+            }
+        }
+    }
+
+    fn clear_fair_value(&self) {
+        let mut state = self.strategy_state.lock().unwrap();
+        state.fair_value = None;
+        state.target_bid_px = None;
+        state.target_ask_px = None;
+    }
+
+    /// move the quotes to the current targets
+    async fn quote(&self) -> anyhow::Result<()> {
+        let params = self.get_strategy_params()?;
+        let state = self.get_strategy_state();
+        let (target_bid_px, target_ask_px) =
+            match (state.fair_value, state.depth_bid_px, state.depth_ask_px) {
+                (Some(fair_value), Some(best_bid), Some(best_ask))
+                    if self.should_run_trading(&params) =>
+                {
+                    target_quotes(&params, fair_value, state.position, best_bid, best_ask)
+                }
+                _ => (None, None),
+            };
+        {
+            let mut state = self.strategy_state.lock().unwrap();
+            state.target_bid_px = target_bid_px;
+            state.target_ask_px = target_ask_px;
+            state.skew_bp = inventory_skew_bp(&params, state.position);
+        }
+        self.quote_side(&params, OrderSide::Buy, target_bid_px, state.fair_value)
+            .await?;
+        self.quote_side(&params, OrderSide::Sell, target_ask_px, state.fair_value)
+            .await?;
+        Ok(())
+    }
+
+    async fn quote_side(
+        &self,
+        params: &StrategyParams,
+        side: OrderSide,
+        target_px: Option<f64>,
+        fair_value: Option<f64>,
+    ) -> anyhow::Result<()> {
+        let open_orders = match side {
+            OrderSide::Buy => self.quote_instrument.get_open_buy_orders(false),
+            OrderSide::Sell => self.quote_instrument.get_open_sell_orders(false),
+        };
+        let (target_px, fair_value) = match (target_px, fair_value) {
+            (Some(target_px), Some(fair_value)) => (target_px, fair_value),
+            _ => {
+                for order in open_orders.iter() {
+                    self.cancel_order(order).await;
+                }
+                self.set_open_order(&side, None, None);
+                return Ok(());
+            }
+        };
+
+        let mut open_orders = open_orders.into_iter();
+        let open_order = open_orders.next();
+        // a single order per side, anything else is left over from a race
+        for order in open_orders {
+            self.cancel_order(&order).await;
+        }
+
+        match open_order {
+            None => {
+                let pending_cancels = match side {
+                    OrderSide::Buy => self.quote_instrument.get_open_buy_orders(true),
+                    OrderSide::Sell => self.quote_instrument.get_open_sell_orders(true),
+                };
+                self.set_open_order(&side, None, None);
+                if pending_cancels.is_empty() {
+                    self.quote_instrument
+                        .send_order(side, target_px, params.base_size, OrderType::Limit)
+                        .await?;
+                }
+            }
+            Some(open_order) => {
+                let client_id = open_order.client_id.clone().unwrap_or_default();
+                let queue_ahead = self
+                    .queue_positions
+                    .get(client_id.as_str())
+                    .map(|queue_position| queue_position.queue_ahead);
+                self.set_open_order(&side, Some(open_order.price), queue_ahead);
+                match open_order.status {
+                    OrderStatus::New | OrderStatus::Open => {}
+                    // not acknowledged yet
+                    _ => return Ok(()),
+                }
+                if !should_requote(
+                    params,
+                    &side,
+                    open_order.price,
+                    target_px,
+                    fair_value,
+                    queue_ahead,
+                ) {
+                    return Ok(());
+                }
+                self.strategy_state.lock().unwrap().requote_cnt += 1;
+                match self
+                    .quote_instrument
+                    .modify_order(client_id.as_str(), target_px, params.base_size)
+                    .await
+                {
+                    Ok(Some(_new_client_id)) => {
+                        self.queue_positions.remove(client_id.as_str());
+                    }
+                    Ok(None) => self.cancel_order(&open_order).await,
+                    Err(err) => {
+                        error!("modify {} failed: {}", client_id, err);
+                        self.cancel_order(&open_order).await;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn set_open_order(&self, side: &OrderSide, open_px: Option<f64>, queue_ahead: Option<f64>) {
+        let mut state = self.strategy_state.lock().unwrap();
+        match side {
+            OrderSide::Buy => {
+                state.open_bid_px = open_px;
+                state.bid_queue_ahead = queue_ahead;
+            }
+            OrderSide::Sell => {
+                state.open_ask_px = open_px;
+                state.ask_queue_ahead = queue_ahead;
+            }
+        }
+    }
+
+    async fn cancel_order(&self, order: &OrderUpdate) {
+        if let Some(ref client_id) = order.client_id {
+            if let Err(err) = self.quote_instrument.cancel_order(client_id.as_str()).await {
+                error!("cancel {} failed: {}", client_id, err);
+            }
+        }
+    }
+
+    fn is_instrument(instrument: &Instrument, exchange: &Exchanges, market: &str) -> bool {
+        *exchange == instrument.exchange && market == instrument.market
+    }
+}
+
+#[async_trait::async_trait]
+impl Strategy for Lambda {
+    async fn on_market_depth(&self, market_depth: &MarketDepth) -> anyhow::Result<()> {
+        let exchange = &market_depth.exchange;
+        let market = market_depth.market.as_str();
+        if Self::is_instrument(&self.lead_instrument, exchange, market) {
+            self.on_lead_depth(market_depth);
+        } else if Self::is_instrument(&self.quote_instrument, exchange, market) {
+            self.on_quote_depth(market_depth, &self.get_strategy_params()?);
+        } else {
+            return Ok(());
+        }
+        self.quote().await
+    }
+
+    fn coalesce_market_depth(&self) -> bool {
+        true
+    }
+
+    async fn on_order_update(&self, order_update: &OrderUpdate) -> anyhow::Result<()> {
+        if !Self::is_instrument(
+            &self.quote_instrument,
+            &order_update.exchange,
+            order_update.market.as_str(),
+        ) {
+            return Ok(());
+        }
+        match order_update.status {
+            OrderStatus::Closed | OrderStatus::Failed => {
+                if let Some(ref client_id) = order_update.client_id {
+                    self.queue_positions.remove(client_id.as_str());
+                }
+            }
+            _ => {}
+        }
+        self.quote().await
+    }
+
+    async fn on_fill(&self, order_fill: &OrderFill) -> anyhow::Result<()> {
+        if !Self::is_instrument(
+            &self.quote_instrument,
+            &order_fill.exchange,
+            order_fill.market.as_str(),
+        ) {
+            return Ok(());
+        }
+        let mut state = self.strategy_state.lock().unwrap();
+        match order_fill.side {
+            OrderSide::Buy => state.position += order_fill.size,
+            OrderSide::Sell => state.position -= order_fill.size,
+        }
+        Ok(())
+    }
+
+    fn timer_interval(&self) -> Option<Duration> {
+        Some(Duration::from_millis(500))
+    }
+
+    async fn on_timer(&self) -> anyhow::Result<()> {
+        // MarketDepthCache drops stale books, never quote on a stale fair value
+        let lead_depth = self
+            .market_depth
+            .get_clone(self.lead_instrument.market.as_str());
+        let quote_depth = self
+            .market_depth
+            .get_clone(self.quote_instrument.market.as_str());
+        if lead_depth.is_none() || quote_depth.is_none() {
+            self.clear_fair_value();
+        }
+        self.quote().await
+    }
+
+    fn param_schema(&self) -> ParamSchema {
+        params::param_schema()
+    }
+
+    async fn on_param_change(&self, _strategy_params: &Value) -> anyhow::Result<()> {
+        self.quote().await
+    }
+
+    async fn on_stop(&self) -> anyhow::Result<()> {
+        for order in self.quote_instrument.get_open_orders(false) {
+            self.cancel_order(&order).await;
+        }
+        Ok(())
+    }
+
+    async fn run(&self) -> anyhow::Result<()> {
+        tokio::select! {
+            result = self.context.period_publish_state(|| self.get_strategy_state()) => {
+                error!("period_publish_state completed: {:?}", result)
+            }
+            result = self.watchdog.subscribe() => {
+                error!("watchdog completed: {:?}", result)
+            }
+        }
+        Err(anyhow!("LatencyMM run uncaught"))
+    }
+}
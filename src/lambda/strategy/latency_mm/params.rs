@@ -0,0 +1,95 @@
+use crate::lambda::strategy::schema::{state_spec, ParamSchema, ParamSpec};
+use crate::lambda::LambdaState;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct LatencyMMInitParams {
+    /// instrument we quote, e.g. `ETH-PERP.FTX`
+    pub quote_symbol: String,
+    /// faster moving instrument the fair value is derived from, e.g. `ETH/USD.FTX`
+    pub lead_symbol: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct LatencyMMStrategyParams {
+    pub state: LambdaState,
+    pub base_size: f64,
+    pub tick_size: f64,
+    /// distance of each quote from the skewed fair value
+    pub half_spread_bp: f64,
+    /// weight of the latest quote/lead mid difference in the lead-lag offset
+    pub offset_ewma_alpha: f64,
+    /// leave an order alone while its price is within this distance of the target
+    pub requote_threshold_bp: f64,
+    /// an order that fell behind the target keeps its place while at most this size is queued
+    /// ahead of it
+    pub queue_keep_size: f64,
+    /// requote an order that fell behind the target regardless of its queue position
+    pub max_passive_gap_bp: f64,
+    /// stop quoting the side that would grow the position beyond this
+    pub max_position: f64,
+    /// shift of both quotes against the position, reached at `max_position`
+    pub inventory_skew_bp: f64,
+}
+impl Default for LatencyMMStrategyParams {
+    fn default() -> Self {
+        LatencyMMStrategyParams {
+            state: LambdaState::Init,
+            base_size: 0.001,
+            tick_size: 0.1,
+            half_spread_bp: 2.0,
+            offset_ewma_alpha: 0.01,
+            requote_threshold_bp: 0.5,
+            queue_keep_size: 10.0,
+            max_passive_gap_bp: 2.0,
+            max_position: 0.01,
+            inventory_skew_bp: 2.0,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct LatencyMMStrategyStateStruct {
+    pub lead_mid_px: Option<f64>,
+    pub quote_mid_px: Option<f64>,
+    /// EWMA of quote mid - lead mid
+    pub lead_lag_offset: Option<f64>,
+    pub fair_value: Option<f64>,
+    pub depth_bid_px: Option<f64>,
+    pub depth_ask_px: Option<f64>,
+    pub position: f64,
+    pub skew_bp: f64,
+    pub target_bid_px: Option<f64>,
+    pub target_ask_px: Option<f64>,
+    pub open_bid_px: Option<f64>,
+    pub open_ask_px: Option<f64>,
+    pub bid_queue_ahead: Option<f64>,
+    pub ask_queue_ahead: Option<f64>,
+    pub requote_cnt: u64,
+}
+
+pub fn param_schema() -> ParamSchema {
+    ParamSchema::new(vec![
+        state_spec(),
+        ParamSpec::float("base_size", "order size").min(0.0),
+        ParamSpec::float("tick_size", "price increment of the quote instrument")
+            .min(0.0)
+            .cold(),
+        ParamSpec::float("half_spread_bp", "distance of each quote from the fair value").min(0.0),
+        ParamSpec::float("offset_ewma_alpha", "weight of the latest basis in the lead-lag offset")
+            .min(0.0)
+            .max(1.0),
+        ParamSpec::float("requote_threshold_bp", "leave orders within this distance of the target")
+            .min(0.0),
+        ParamSpec::float("queue_keep_size", "queue ahead an order behind the target may keep")
+            .min(0.0),
+        ParamSpec::float("max_passive_gap_bp", "requote behind the target regardless of the queue")
+            .min(0.0),
+        ParamSpec::float("max_position", "stop quoting the side growing the position beyond this")
+            .min(0.0)
+            .admin(),
+        ParamSpec::float("inventory_skew_bp", "quote shift against the position at max_position")
+            .min(0.0),
+    ])
+    .parsed_as::<LatencyMMStrategyParams>()
+}
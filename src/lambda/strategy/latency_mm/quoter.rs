@@ -0,0 +1,95 @@
+use crate::lambda::strategy::latency_mm::params::LatencyMMStrategyParams;
+use crate::model::OrderSide;
+
+/// EWMA of the quote mid - lead mid difference. the fair value of the quote instrument is the
+/// lead mid plus this offset, so it moves with the lead book as soon as it updates.
+pub fn update_lead_lag_offset(
+    offset: Option<f64>,
+    quote_mid: f64,
+    lead_mid: f64,
+    alpha: f64,
+) -> f64 {
+    let diff = quote_mid - lead_mid;
+    match offset {
+        None => diff,
+        Some(offset) => offset + alpha * (diff - offset),
+    }
+}
+
+/// shift of the quotes in bp, negative while long so we buy less and sell more eagerly
+pub fn inventory_skew_bp(params: &LatencyMMStrategyParams, position: f64) -> f64 {
+    if params.max_position <= 0.0 {
+        return 0.0;
+    }
+    -(position / params.max_position).clamp(-1.0, 1.0) * params.inventory_skew_bp
+}
+
+fn round_down(price: f64, tick_size: f64) -> f64 {
+    (price / tick_size + 1e-9).floor() * tick_size
+}
+
+fn round_up(price: f64, tick_size: f64) -> f64 {
+    (price / tick_size - 1e-9).ceil() * tick_size
+}
+
+/// bid and ask to quote around `fair_value`, None for a side we should not quote.
+/// quotes never cross the book since orders are post only.
+pub fn target_quotes(
+    params: &LatencyMMStrategyParams,
+    fair_value: f64,
+    position: f64,
+    best_bid: f64,
+    best_ask: f64,
+) -> (Option<f64>, Option<f64>) {
+    let center = fair_value * (1.0 + inventory_skew_bp(params, position) / 10000.0);
+    let bid = round_down(
+        center * (1.0 - params.half_spread_bp / 10000.0),
+        params.tick_size,
+    )
+    .min(best_ask - params.tick_size);
+    let ask = round_up(
+        center * (1.0 + params.half_spread_bp / 10000.0),
+        params.tick_size,
+    )
+    .max(best_bid + params.tick_size);
+    let bid = if position + params.base_size > params.max_position {
+        None
+    } else {
+        Some(bid)
+    };
+    let ask = if position - params.base_size < -params.max_position {
+        None
+    } else {
+        Some(ask)
+    };
+    (bid, ask)
+}
+
+/// Whether an order resting at `open_px` should move to `target_px`.
+///
+/// An order priced more aggressively than the target is requoted as soon as the gap reaches
+/// `requote_threshold_bp`. An order that fell behind the target keeps its queue position while
+/// little size is ahead of it, until the gap reaches `max_passive_gap_bp`.
+pub fn should_requote(
+    params: &LatencyMMStrategyParams,
+    side: &OrderSide,
+    open_px: f64,
+    target_px: f64,
+    fair_value: f64,
+    queue_ahead: Option<f64>,
+) -> bool {
+    // positive when the target is more aggressive than the open order
+    let gap_bp = match side {
+        OrderSide::Buy => (target_px - open_px) / fair_value * 10000.0,
+        OrderSide::Sell => (open_px - target_px) / fair_value * 10000.0,
+    };
+    if gap_bp <= -params.requote_threshold_bp {
+        return true;
+    }
+    if gap_bp >= params.requote_threshold_bp {
+        let near_front =
+            matches!(queue_ahead, Some(queue_ahead) if queue_ahead <= params.queue_keep_size);
+        return !near_front || gap_bp >= params.max_passive_gap_bp;
+    }
+    false
+}
@@ -0,0 +1,90 @@
+pub use context::{restored_state, StrategyContext};
+pub use registry::{StrategyFactory, StrategyRegistry};
+pub use runner::StrategyRunner;
+pub use schema::{ParamSchema, ParamSpec, ParamType};
+
+mod context;
+mod registry;
+mod runner;
+pub mod schema;
+
+pub mod basis_arb;
+pub mod common;
+pub mod funding_carry;
+pub mod latency_mm;
+pub mod swap_mm;
+
+use crate::model::market_data_model::MarketDepth;
+use crate::model::{OrderFill, OrderUpdate};
+use async_trait::async_trait;
+use serde_json::Value;
+use std::time::Duration;
+
+/// A trading strategy run by the `StrategyRunner`.
+///
+/// Every hook has a no-op default so a strategy only implements the events it cares about.
+/// Hooks are called from a single runner; a strategy that needs its own background loops runs
+/// them from `run`.
+#[async_trait]
+pub trait Strategy: Send + Sync {
+    /// called once before any other hook
+    async fn on_init(&self) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    /// a new book for one of the instance's `lambda_params.market_depths`
+    async fn on_market_depth(&self, _market_depth: &MarketDepth) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    /// deliver only the latest book per market when `on_market_depth` falls behind the feed,
+    /// dropping the ones in between
+    fn coalesce_market_depth(&self) -> bool {
+        false
+    }
+
+    /// called once the OrderUpdateCache reflects `order_update`
+    async fn on_order_update(&self, _order_update: &OrderUpdate) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    async fn on_fill(&self, _order_fill: &OrderFill) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    /// period of `on_timer`, None disables the timer
+    fn timer_interval(&self) -> Option<Duration> {
+        None
+    }
+
+    async fn on_timer(&self) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    /// params `UpdateParam` may change and their bounds. the default keeps every param's JSON type
+    fn param_schema(&self) -> ParamSchema {
+        ParamSchema::default()
+    }
+
+    /// StrategyParams changed in the ValueCache, e.g. through `UpdateParam`
+    async fn on_param_change(&self, _strategy_params: &Value) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    /// Orders of the instance's markets found open on the exchange when the lambda starts, left
+    /// over from before a restart. Called once, before `on_init`. Returns the client ids the
+    /// strategy adopts and keeps managing, the engine cancels the others. The default adopts none.
+    async fn on_restore(&self, _open_orders: &[OrderUpdate]) -> anyhow::Result<Vec<String>> {
+        Ok(vec![])
+    }
+
+    /// the lambda moved to `LambdaState::Stopped`
+    async fn on_stop(&self) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    /// long running work of the strategy. runs next to the hooks and never completes by default
+    async fn run(&self) -> anyhow::Result<()> {
+        futures_util::future::pending().await
+    }
+}
@@ -0,0 +1,67 @@
+use crate::lambda::strategy::{basis_arb, funding_carry, latency_mm, swap_mm};
+use crate::lambda::strategy::{Strategy, StrategyContext};
+use dashmap::DashMap;
+use once_cell::sync::Lazy;
+use std::sync::Arc;
+
+pub type StrategyFactory = fn(&StrategyContext) -> anyhow::Result<Arc<dyn Strategy>>;
+
+static STRATEGY_REGISTRY: Lazy<DashMap<String, StrategyFactory>> = Lazy::new(|| {
+    let registry = DashMap::new();
+    registry.insert("SwapMM".to_string(), swap_mm as StrategyFactory);
+    registry.insert("LatencyMM".to_string(), latency_mm as StrategyFactory);
+    registry.insert("BasisArb".to_string(), basis_arb as StrategyFactory);
+    registry.insert("FundingCarry".to_string(), funding_carry as StrategyFactory);
+    registry
+});
+
+fn basis_arb(context: &StrategyContext) -> anyhow::Result<Arc<dyn Strategy>> {
+    Ok(Arc::new(basis_arb::lambda::Lambda::new(context)?))
+}
+
+fn funding_carry(context: &StrategyContext) -> anyhow::Result<Arc<dyn Strategy>> {
+    Ok(Arc::new(funding_carry::lambda::Lambda::new(context)?))
+}
+
+fn latency_mm(context: &StrategyContext) -> anyhow::Result<Arc<dyn Strategy>> {
+    Ok(Arc::new(latency_mm::lambda::Lambda::new(context)?))
+}
+
+fn swap_mm(context: &StrategyContext) -> anyhow::Result<Arc<dyn Strategy>> {
+    Ok(Arc::new(swap_mm::lambda::Lambda::new(context)?))
+}
+
+/// Strategies by the name instance configs refer to in `registry`.
+/// Built-in strategies are registered on first use, others plug in through `register`.
+pub struct StrategyRegistry;
+
+impl StrategyRegistry {
+    /// register `factory` under `name`, replacing any strategy registered under that name
+    pub fn register(name: &str, factory: StrategyFactory) {
+        STRATEGY_REGISTRY.insert(name.to_string(), factory);
+    }
+
+    pub fn names() -> Vec<String> {
+        let mut names: Vec<String> = STRATEGY_REGISTRY
+            .iter()
+            .map(|entry| entry.key().clone())
+            .collect();
+        names.sort();
+        names
+    }
+
+    pub fn create(name: &str, context: &StrategyContext) -> anyhow::Result<Arc<dyn Strategy>> {
+        // copy the fn pointer out so the factory runs without holding the map shard
+        let factory = match STRATEGY_REGISTRY.get(name) {
+            None => {
+                return Err(anyhow!(
+                    "unknown strategy {}, registered: {:?}",
+                    name,
+                    Self::names()
+                ))
+            }
+            Some(factory) => *factory.value(),
+        };
+        factory(context)
+    }
+}
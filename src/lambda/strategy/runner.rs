@@ -0,0 +1,259 @@
+use crate::cache::ValueCacheKey;
+use crate::lambda::strategy::{Strategy, StrategyContext};
+use crate::lambda::LambdaState;
+use crate::model::constants::PublishChannel;
+use crate::model::market_data_model::MarketDepth;
+use crate::model::OrderFill;
+use crate::pubsub::simple_message_bus::{MessageConsumer, RedisBackedMessageBus};
+use crate::pubsub::SubscribeMarketDepthRequest;
+use async_trait::async_trait;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::Notify;
+
+/// how often StrategyParams are checked for changes
+const PARAM_POLL_INTERVAL_MS: u64 = 100;
+
+/// Latest pending book per market, waiting for the strategy to catch up
+#[derive(Default)]
+struct MarketDepthCoalescer {
+    pending: Mutex<HashMap<String, MarketDepth>>,
+    notify: Notify,
+}
+
+/// Drives a `Strategy`: subscribes to the instance's market depths and fills, takes its order
+/// updates from the `OrderUpdateCache` once applied, and dispatches them to the hooks as they
+/// arrive, next to the timer and StrategyParams changes.
+///
+/// Hooks run one at a time. A hook returning an error is logged and does not stop the runner.
+pub struct StrategyRunner {
+    strategy: Arc<dyn Strategy>,
+    context: StrategyContext,
+    coalescer: MarketDepthCoalescer,
+    hook_lock: tokio::sync::Mutex<()>,
+}
+
+impl StrategyRunner {
+    pub fn new(strategy: Arc<dyn Strategy>, context: StrategyContext) -> Self {
+        StrategyRunner {
+            strategy,
+            context,
+            coalescer: MarketDepthCoalescer::default(),
+            hook_lock: tokio::sync::Mutex::new(()),
+        }
+    }
+
+    pub async fn run(&self) -> anyhow::Result<()> {
+        self.strategy.on_init().await?;
+        tokio::select! {
+            result = self.strategy.run() => {
+                error!("strategy run completed: {:?}", result)
+            }
+            Err(err) = self.subscribe_market_depth() => {
+                error!("strategy market_depth dispatch: {}", err)
+            }
+            Err(err) = self.dispatch_coalesced_market_depth(), if self.strategy.coalesce_market_depth() => {
+                error!("strategy coalesced market_depth dispatch: {}", err)
+            }
+            Err(err) = self.subscribe_order_update() => {
+                error!("strategy order_update dispatch: {}", err)
+            }
+            Err(err) = self.subscribe_order_fill() => {
+                error!("strategy order_fill dispatch: {}", err)
+            }
+            Err(err) = self.period_timer() => {
+                error!("strategy timer: {}", err)
+            }
+            Err(err) = self.period_watch_params() => {
+                error!("strategy param watch: {}", err)
+            }
+        }
+        Err(anyhow!("StrategyRunner uncaught"))
+    }
+
+    async fn subscribe_market_depth(&self) -> anyhow::Result<()> {
+        let channels: Vec<String> = self
+            .context
+            .instance_config
+            .lambda_params
+            .market_depths
+            .iter()
+            .map(|token| {
+                let request = SubscribeMarketDepthRequest::from_token(token.as_str());
+                format!(
+                    "{}:{}:{}",
+                    PublishChannel::MarketDepth,
+                    request.exchange,
+                    request.market
+                )
+            })
+            .collect();
+        let channels = channels.iter().map(AsRef::as_ref).collect();
+        let consumer = MarketDepthDispatch {
+            runner: self,
+            coalescer: if self.strategy.coalesce_market_depth() {
+                Some(&self.coalescer)
+            } else {
+                None
+            },
+        };
+        RedisBackedMessageBus::subscribe_channels(channels, &consumer).await?;
+        Err(anyhow!("MarketDepthDispatch subscribe uncaught"))
+    }
+
+    async fn dispatch_coalesced_market_depth(&self) -> anyhow::Result<()> {
+        loop {
+            self.coalescer.notify.notified().await;
+            let market_depths: Vec<MarketDepth> = self
+                .coalescer
+                .pending
+                .lock()
+                .unwrap()
+                .drain()
+                .map(|(_, market_depth)| market_depth)
+                .collect();
+            for market_depth in market_depths {
+                self.on_market_depth(&market_depth).await;
+            }
+        }
+    }
+
+    /// order updates of the instance, after the OrderUpdateCache applied them
+    async fn subscribe_order_update(&self) -> anyhow::Result<()> {
+        let owner = self.context.instance_config.name.as_str();
+        let mut applied = self.context.order_update_cache.subscribe_applied();
+        loop {
+            let order_update = match applied.recv().await {
+                Ok(order_update) => order_update,
+                Err(RecvError::Lagged(skipped)) => {
+                    warn!("strategy missed {} order updates", skipped);
+                    continue;
+                }
+                Err(RecvError::Closed) => {
+                    return Err(anyhow!("OrderUpdateCache closed"));
+                }
+            };
+            if !order_update.is_owned_by(owner) {
+                continue;
+            }
+            let _hook = self.hook_lock.lock().await;
+            if let Err(err) = self.strategy.on_order_update(&order_update).await {
+                error!("strategy on_order_update: {}", err);
+            }
+        }
+    }
+
+    async fn subscribe_order_fill(&self) -> anyhow::Result<()> {
+        RedisBackedMessageBus::subscribe_channels(
+            vec![PublishChannel::OrderFill.as_ref()],
+            &OrderFillDispatch(self),
+        )
+        .await?;
+        Err(anyhow!("OrderFillDispatch subscribe uncaught"))
+    }
+
+    async fn on_market_depth(&self, market_depth: &MarketDepth) {
+        let _hook = self.hook_lock.lock().await;
+        if let Err(err) = self.strategy.on_market_depth(market_depth).await {
+            error!("strategy on_market_depth: {}", err);
+        }
+    }
+
+    async fn period_timer(&self) -> anyhow::Result<()> {
+        let interval = match self.strategy.timer_interval() {
+            None => return futures_util::future::pending().await,
+            Some(interval) => interval,
+        };
+        loop {
+            self.context.clock.sleep(interval).await;
+            let _hook = self.hook_lock.lock().await;
+            if let Err(err) = self.strategy.on_timer().await {
+                error!("strategy on_timer: {}", err);
+            }
+        }
+    }
+
+    async fn period_watch_params(&self) -> anyhow::Result<()> {
+        let mut last_params = self.strategy_params();
+        loop {
+            self.context
+                .clock
+                .sleep(Duration::from_millis(PARAM_POLL_INTERVAL_MS))
+                .await;
+            let params = self.strategy_params();
+            if params == last_params {
+                continue;
+            }
+            if let Some(ref value) = params {
+                let _hook = self.hook_lock.lock().await;
+                let was_stopped = matches!(last_params, Some(ref last) if Self::is_stopped(last));
+                if Self::is_stopped(value) && !was_stopped {
+                    info!("lambda stopped");
+                    if let Err(err) = self.strategy.on_stop().await {
+                        error!("strategy on_stop: {}", err);
+                    }
+                }
+                if let Err(err) = self.strategy.on_param_change(value).await {
+                    error!("strategy on_param_change: {}", err);
+                }
+            }
+            last_params = params;
+        }
+    }
+
+    fn strategy_params(&self) -> Option<Value> {
+        self.context
+            .value_cache
+            .get_clone(ValueCacheKey::StrategyParams)
+    }
+
+    fn is_stopped(strategy_params: &Value) -> bool {
+        matches!(
+            strategy_params["state"]
+                .as_str()
+                .map(LambdaState::from_str),
+            Some(Ok(LambdaState::Stopped))
+        )
+    }
+}
+
+struct MarketDepthDispatch<'r> {
+    runner: &'r StrategyRunner,
+    coalescer: Option<&'r MarketDepthCoalescer>,
+}
+#[async_trait]
+impl<'r> MessageConsumer for MarketDepthDispatch<'r> {
+    async fn consume(&self, msg: &[u8]) -> anyhow::Result<()> {
+        let market_depth = serde_json::from_slice::<MarketDepth>(msg)?;
+        match self.coalescer {
+            Some(coalescer) => {
+                let key = format!("{}:{}", market_depth.exchange, market_depth.market);
+                coalescer.pending.lock().unwrap().insert(key, market_depth);
+                coalescer.notify.notify_one();
+            }
+            None => self.runner.on_market_depth(&market_depth).await,
+        }
+        Ok(())
+    }
+}
+
+/// fills of the orders of the runner's instance
+struct OrderFillDispatch<'r>(&'r StrategyRunner);
+#[async_trait]
+impl<'r> MessageConsumer for OrderFillDispatch<'r> {
+    async fn consume(&self, msg: &[u8]) -> anyhow::Result<()> {
+        let order_fill = serde_json::from_slice::<OrderFill>(msg)?;
+        if !order_fill.is_owned_by(self.0.context.instance_config.name.as_str()) {
+            return Ok(());
+        }
+        let _hook = self.0.hook_lock.lock().await;
+        if let Err(err) = self.0.strategy.on_fill(&order_fill).await {
+            error!("strategy on_fill: {}", err);
+        }
+        Ok(())
+    }
+}
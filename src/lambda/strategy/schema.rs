@@ -0,0 +1,289 @@
+use crate::core::auth::Role;
+use crate::lambda::LambdaState;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use std::str::FromStr;
+
+/// tolerance when checking a value against `step`
+const STEP_EPSILON: f64 = 1e-9;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum ParamType {
+    String,
+    Int,
+    Float,
+    Bool,
+    Enum,
+    /// nested params, e.g. the hedger of SwapMM, checked as a whole by the strategy's own type
+    Object,
+}
+
+/// Declaration of one strategy param, published for the frontend to render a control.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ParamSpec {
+    pub key: String,
+    #[serde(rename = "type")]
+    pub type_: ParamType,
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+    pub step: Option<f64>,
+    pub enum_values: Vec<String>,
+    pub description: String,
+    /// null is a valid value, e.g. for an `Option<f64>` param
+    pub nullable: bool,
+    /// false for params only read when the strategy starts, updates are rejected
+    pub hot_update: bool,
+    /// least role allowed to update the param
+    #[serde(default)]
+    pub role: Role,
+}
+
+impl ParamSpec {
+    fn new(key: &str, type_: ParamType, description: &str) -> Self {
+        ParamSpec {
+            key: key.to_string(),
+            type_,
+            min: None,
+            max: None,
+            step: None,
+            enum_values: vec![],
+            description: description.to_string(),
+            nullable: false,
+            hot_update: true,
+            role: Role::Trader,
+        }
+    }
+
+    pub fn string(key: &str, description: &str) -> Self {
+        Self::new(key, ParamType::String, description)
+    }
+
+    pub fn int(key: &str, description: &str) -> Self {
+        Self::new(key, ParamType::Int, description)
+    }
+
+    pub fn float(key: &str, description: &str) -> Self {
+        Self::new(key, ParamType::Float, description)
+    }
+
+    pub fn bool(key: &str, description: &str) -> Self {
+        Self::new(key, ParamType::Bool, description)
+    }
+
+    pub fn enumeration(key: &str, values: &[&str], description: &str) -> Self {
+        let mut spec = Self::new(key, ParamType::Enum, description);
+        spec.enum_values = values.iter().map(|value| value.to_string()).collect();
+        spec
+    }
+
+    pub fn object(key: &str, description: &str) -> Self {
+        Self::new(key, ParamType::Object, description)
+    }
+
+    pub fn min(mut self, min: f64) -> Self {
+        self.min = Some(min);
+        self
+    }
+
+    pub fn max(mut self, max: f64) -> Self {
+        self.max = Some(max);
+        self
+    }
+
+    pub fn step(mut self, step: f64) -> Self {
+        self.step = Some(step);
+        self
+    }
+
+    pub fn nullable(mut self) -> Self {
+        self.nullable = true;
+        self
+    }
+
+    pub fn cold(mut self) -> Self {
+        self.hot_update = false;
+        self
+    }
+
+    /// risk limits only admins may change
+    pub fn admin(mut self) -> Self {
+        self.role = Role::Admin;
+        self
+    }
+
+    pub fn validate(&self, value: &Value) -> Result<(), String> {
+        if value.is_null() {
+            return match self.nullable {
+                true => Ok(()),
+                false => Err(format!("{} must not be null", self.key)),
+            };
+        }
+        let type_ok = match self.type_ {
+            ParamType::String => value.is_string(),
+            ParamType::Int => value.is_i64() || value.is_u64(),
+            ParamType::Float => value.is_number(),
+            ParamType::Bool => value.is_boolean(),
+            ParamType::Enum => matches!(
+                value.as_str(),
+                Some(value) if self.enum_values.iter().any(|v| v == value)
+            ),
+            ParamType::Object => value.is_object(),
+        };
+        if !type_ok {
+            return match self.type_ {
+                ParamType::Enum => Err(format!(
+                    "{} must be one of {:?}, got {}",
+                    self.key, self.enum_values, value
+                )),
+                _ => Err(format!(
+                    "{} must be {:?}, got {}",
+                    self.key, self.type_, value
+                )),
+            };
+        }
+        if let Some(number) = value.as_f64() {
+            if let Some(min) = self.min {
+                if number < min {
+                    return Err(format!("{} must be >= {}, got {}", self.key, min, number));
+                }
+            }
+            if let Some(max) = self.max {
+                if number > max {
+                    return Err(format!("{} must be <= {}, got {}", self.key, max, number));
+                }
+            }
+            if let Some(step) = self.step {
+                let steps = (number - self.min.unwrap_or(0.0)) / step;
+                if (steps - steps.round()).abs() > STEP_EPSILON * steps.abs().max(1.0) {
+                    return Err(format!(
+                        "{} must be a multiple of {}, got {}",
+                        self.key, step, number
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Params a strategy accepts updates for.
+///
+/// Keys without a spec keep the JSON type of their current value. The updated params as a whole
+/// must still parse into the strategy's params type, see `parsed_as`.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct ParamSchema {
+    pub params: Vec<ParamSpec>,
+    #[serde(skip)]
+    parse: Option<ParseFn>,
+}
+
+type ParseFn = fn(&Value) -> Result<(), String>;
+
+fn parse_as<T: DeserializeOwned>(value: &Value) -> Result<(), String> {
+    serde_json::from_value::<T>(value.clone())
+        .map(|_| ())
+        .map_err(|err| err.to_string())
+}
+
+fn same_json_type(current: &Value, value: &Value) -> bool {
+    match current {
+        Value::Null => true,
+        Value::Bool(_) => value.is_boolean(),
+        Value::Number(_) => value.is_number(),
+        Value::String(_) => value.is_string(),
+        Value::Array(_) => value.is_array(),
+        Value::Object(_) => value.is_object(),
+    }
+}
+
+impl ParamSchema {
+    pub fn new(params: Vec<ParamSpec>) -> Self {
+        ParamSchema {
+            params,
+            parse: None,
+        }
+    }
+
+    /// reject updates after which the params no longer parse into `T`
+    pub fn parsed_as<T: DeserializeOwned>(mut self) -> Self {
+        self.parse = Some(parse_as::<T>);
+        self
+    }
+
+    pub fn spec(&self, key: &str) -> Option<&ParamSpec> {
+        self.params.iter().find(|spec| spec.key == key)
+    }
+
+    /// whether `role` may set `key` to `value`, state transitions are checked against the current state
+    pub fn authorize(
+        &self,
+        role: &Role,
+        params: &Value,
+        key: &str,
+        value: &Value,
+    ) -> Result<(), String> {
+        if *role < Role::Trader {
+            return Err(format!("{:?} may not update params", role));
+        }
+        if let Some(spec) = self.spec(key) {
+            if *role < spec.role {
+                return Err(format!("{} may only be updated by {:?}", key, spec.role));
+            }
+        }
+        if key == "state" {
+            let state = |value: &Value| {
+                value
+                    .as_str()
+                    .and_then(|value| LambdaState::from_str(value).ok())
+            };
+            // unknown states are left to the spec to reject
+            if let (Some(from), Some(to)) = (state(&params["state"]), state(value)) {
+                if !role.may_set_state(&from, &to) {
+                    return Err(format!(
+                        "{:?} may not move state from {} to {}",
+                        role, from, to
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// `params` with `key` set to `value`, or why the update is rejected
+    pub fn apply_update(&self, params: &Value, key: &str, value: &Value) -> Result<Value, String> {
+        let current = params
+            .get(key)
+            .ok_or_else(|| format!("unknown param {}", key))?;
+        match self.spec(key) {
+            Some(spec) => {
+                if !spec.hot_update {
+                    return Err(format!("{} is only read on start", key));
+                }
+                spec.validate(value)?;
+            }
+            None => {
+                if !same_json_type(current, value) {
+                    return Err(format!(
+                        "{} must keep the type of {}, got {}",
+                        key, current, value
+                    ));
+                }
+            }
+        }
+        let mut params = params.clone();
+        params[key] = value.clone();
+        if let Some(parse) = self.parse {
+            parse(&params).map_err(|err| format!("{} rejected: {}", key, err))?;
+        }
+        Ok(params)
+    }
+}
+
+/// `state` spec shared by all strategies
+pub fn state_spec() -> ParamSpec {
+    ParamSpec::enumeration(
+        "state",
+        &["Init", "Live", "Paused", "Stopped", "AutoPaused"],
+        "Live trades, any other state stops quoting",
+    )
+}
@@ -0,0 +1,311 @@
+use crate::cache::MarketDepthCache;
+use crate::core::clock::SharedClock;
+use crate::lambda::strategy::swap_mm::params::{HedgeOrderType, HedgerParams};
+use crate::model::global_measurement::HEDGE_SLIPPAGE;
+use crate::model::{Instrument, OrderFill, OrderSide, OrderStatus, OrderType, OrderUpdate};
+use crate::pubsub::simple_message_bus::TypedMessageConsumer;
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// signed size, positive for buys
+fn signed(side: &OrderSide, size: f64) -> f64 {
+    match side {
+        OrderSide::Buy => size,
+        OrderSide::Sell => -size,
+    }
+}
+
+/// Side and size of the next hedge, None while the rest is below threshold and min size.
+///
+/// `unhedged_delta` is the depth instrument delta not offset by hedge fills, `in_flight` the signed
+/// open size of hedge orders, which already offsets it.
+pub fn hedge_order(
+    params: &HedgerParams,
+    unhedged_delta: f64,
+    in_flight: f64,
+) -> Option<(OrderSide, f64)> {
+    let rest = unhedged_delta + in_flight;
+    let size = rest.abs();
+    if size == 0.0 || size < params.threshold.max(params.min_size) {
+        return None;
+    }
+    match rest > 0.0 {
+        true => Some((OrderSide::Sell, size)),
+        false => Some((OrderSide::Buy, size)),
+    }
+}
+
+/// limit price `max_slippage_bp` through `touch_px`, the best ask for buys and the best bid for sells
+pub fn hedge_price(params: &HedgerParams, side: &OrderSide, touch_px: f64) -> f64 {
+    let slippage = params.max_slippage_bp / 10000.0;
+    match side {
+        OrderSide::Buy => touch_px * (1.0 + slippage),
+        OrderSide::Sell => touch_px * (1.0 - slippage),
+    }
+}
+
+/// cost of a hedge fill against the touch it was sent at in bp, negative when filled better
+pub fn slippage_bp(side: &OrderSide, touch_px: f64, avg_fill_px: f64) -> f64 {
+    match side {
+        OrderSide::Buy => (avg_fill_px - touch_px) / touch_px * 10000.0,
+        OrderSide::Sell => (touch_px - avg_fill_px) / touch_px * 10000.0,
+    }
+}
+
+#[derive(Debug, Clone)]
+struct HedgeOrder {
+    side: OrderSide,
+    size: f64,
+    filled_size: f64,
+    touch_px: Option<f64>,
+    order_type: HedgeOrderType,
+    sent_ms: i64,
+}
+
+#[derive(Debug, Default)]
+struct HedgeBook {
+    unhedged_delta: f64,
+    orders: HashMap<String, HedgeOrder>,
+    last_slippage_bp: Option<f64>,
+}
+
+impl HedgeBook {
+    fn in_flight(&self) -> f64 {
+        self.orders
+            .values()
+            .map(|order| signed(&order.side, order.size - order.filled_size))
+            .sum()
+    }
+}
+
+/// Hedges fills of the depth instrument on the hedge instrument.
+///
+/// Fills accumulate into the unhedged delta, which is hedged once it exceeds the threshold, so
+/// fills below the min size of the hedge instrument are hedged together. Failed and partially
+/// filled hedges leave their rest in the delta and are retried every `retry_interval_ms`.
+pub struct Hedger {
+    pub depth_instrument: Arc<Instrument>,
+    pub hedge_instrument: Arc<Instrument>,
+    market_depth: Arc<MarketDepthCache>,
+    params: Mutex<HedgerParams>,
+    book: Mutex<HedgeBook>,
+    /// serializes sending hedges with order updates, so an update never misses its order
+    hedge_lock: tokio::sync::Mutex<()>,
+    clock: SharedClock,
+}
+
+impl Hedger {
+    pub fn new(
+        depth_instrument: Arc<Instrument>,
+        hedge_instrument: Arc<Instrument>,
+        market_depth: Arc<MarketDepthCache>,
+        clock: SharedClock,
+    ) -> Self {
+        Hedger {
+            depth_instrument,
+            hedge_instrument,
+            market_depth,
+            params: Mutex::new(HedgerParams::default()),
+            book: Mutex::new(HedgeBook::default()),
+            hedge_lock: tokio::sync::Mutex::new(()),
+            clock,
+        }
+    }
+
+    pub fn set_params(&self, params: HedgerParams) {
+        *self.params.lock().unwrap() = params;
+    }
+
+    fn params(&self) -> HedgerParams {
+        self.params.lock().unwrap().clone()
+    }
+
+    /// seed the delta left unhedged before a restart, its hedges were not adopted
+    pub fn restore_unhedged_delta(&self, unhedged_delta: f64) {
+        self.book.lock().unwrap().unhedged_delta = unhedged_delta;
+    }
+
+    pub fn unhedged_delta(&self) -> f64 {
+        self.book.lock().unwrap().unhedged_delta
+    }
+
+    pub fn in_flight(&self) -> f64 {
+        self.book.lock().unwrap().in_flight()
+    }
+
+    pub fn open_hedges(&self) -> usize {
+        self.book.lock().unwrap().orders.len()
+    }
+
+    pub fn last_slippage_bp(&self) -> Option<f64> {
+        self.book.lock().unwrap().last_slippage_bp
+    }
+
+    /// touch of the hedge instrument on the side a hedge takes, None while the book is stale
+    fn touch_px(&self, side: &OrderSide) -> Option<f64> {
+        let md = self
+            .market_depth
+            .get_clone(self.hedge_instrument.market.as_str())?;
+        let level = match side {
+            OrderSide::Buy => md.asks.first(),
+            OrderSide::Sell => md.bids.first(),
+        };
+        level.map(|level| level.price)
+    }
+
+    /// send the hedge for the current unhedged delta, if any
+    pub async fn hedge(&self) -> anyhow::Result<()> {
+        let _guard = self.hedge_lock.lock().await;
+        let params = self.params();
+        let (side, size) = {
+            let book = self.book.lock().unwrap();
+            match hedge_order(&params, book.unhedged_delta, book.in_flight()) {
+                None => return Ok(()),
+                Some(hedge) => hedge,
+            }
+        };
+        let touch_px = self.touch_px(&side);
+        let (price, type_, ioc) = match params.order_type {
+            HedgeOrderType::Market => (0.0, OrderType::Market, false),
+            HedgeOrderType::AggressiveLimit | HedgeOrderType::Ioc => match touch_px {
+                None => {
+                    warn!(
+                        "hedge {} {} skipped: no market depth for {}",
+                        side, size, self.hedge_instrument.market
+                    );
+                    return Ok(());
+                }
+                Some(touch_px) => (
+                    hedge_price(&params, &side, touch_px),
+                    OrderType::Limit,
+                    params.order_type == HedgeOrderType::Ioc,
+                ),
+            },
+        };
+        debug!("hedge {} {} @ {} {:?}", side, size, price, params.order_type);
+        let client_id = self
+            .hedge_instrument
+            .send_order_request(side.clone(), price, size, type_, ioc, false)
+            .await?;
+        if let Some(client_id) = client_id {
+            self.book.lock().unwrap().orders.insert(
+                client_id,
+                HedgeOrder {
+                    side,
+                    size,
+                    filled_size: 0.0,
+                    touch_px,
+                    order_type: params.order_type,
+                    sent_ms: self.clock.now_ms(),
+                },
+            );
+        }
+        Ok(())
+    }
+
+    /// cancel aggressive limits resting longer than the retry interval, the market moved away
+    async fn cancel_resting_hedges(&self) {
+        let params = self.params();
+        let now_ms = self.clock.now_ms();
+        let resting: Vec<String> = self
+            .book
+            .lock()
+            .unwrap()
+            .orders
+            .iter()
+            .filter(|(_, order)| {
+                order.order_type == HedgeOrderType::AggressiveLimit
+                    && now_ms - order.sent_ms >= params.retry_interval_ms as i64
+            })
+            .map(|(client_id, _)| client_id.clone())
+            .collect();
+        for client_id in resting {
+            if let Err(err) = self.hedge_instrument.cancel_order(client_id.as_str()).await {
+                error!("cancel hedge {} failed: {}", client_id, err);
+            }
+        }
+    }
+
+    async fn period_retry(&self) -> anyhow::Result<()> {
+        loop {
+            let retry_interval_ms = self.params().retry_interval_ms.max(1);
+            self.clock
+                .sleep(Duration::from_millis(retry_interval_ms))
+                .await;
+            self.cancel_resting_hedges().await;
+            if let Err(err) = self.hedge().await {
+                error!("hedge retry failed: {}", err);
+            }
+        }
+    }
+
+    pub async fn subscribe(&self) -> anyhow::Result<()> {
+        log::info!("hedger subscribing...");
+        tokio::select! {
+            Err(err) = self.depth_instrument.subscribe_order_fill(self) => {
+                error!("Hedge subscribe_order_fill: {}", err);
+            },
+            Err(err) = self.hedge_instrument.subscribe_order_update(self) => {
+                error!("Hedge subscribe_order_update: {}", err);
+            }
+            Err(err) = self.period_retry() => {
+                error!("Hedge period_retry: {}", err);
+            }
+        }
+        Err(anyhow!("Hedge subscribe uncaught"))
+    }
+}
+
+#[async_trait::async_trait]
+impl TypedMessageConsumer<OrderFill> for Hedger {
+    async fn consume(&self, order_fill: OrderFill) -> anyhow::Result<()> {
+        self.book.lock().unwrap().unhedged_delta += signed(&order_fill.side, order_fill.size);
+        self.hedge().await
+    }
+}
+
+/// Hedge fills are taken from the filled size of order updates, so fills and the close of a hedge
+/// order arrive in order on the same channel.
+#[async_trait::async_trait]
+impl TypedMessageConsumer<OrderUpdate> for Hedger {
+    async fn consume(&self, order_update: OrderUpdate) -> anyhow::Result<()> {
+        let client_id = match order_update.client_id {
+            None => return Ok(()),
+            Some(ref client_id) => client_id.clone(),
+        };
+        let _guard = self.hedge_lock.lock().await;
+        let mut book = self.book.lock().unwrap();
+        let order = match book.orders.get_mut(client_id.as_str()) {
+            None => return Ok(()),
+            Some(order) => order,
+        };
+        let filled = (order_update.filledSize - order.filled_size).max(0.0);
+        order.filled_size += filled;
+        let side = order.side.clone();
+        let touch_px = order.touch_px;
+        book.unhedged_delta += signed(&side, filled);
+
+        match order_update.status {
+            OrderStatus::New | OrderStatus::Open | OrderStatus::PendingNew => {}
+            OrderStatus::PendingCancel => {}
+            OrderStatus::Closed | OrderStatus::Failed => {
+                book.orders.remove(client_id.as_str());
+                if order_update.status == OrderStatus::Failed {
+                    warn!("hedge {} failed, retrying", client_id);
+                }
+                if let (Some(touch_px), Some(avg_fill_px)) = (touch_px, order_update.avgFillPrice) {
+                    if order_update.filledSize > 0.0 {
+                        let slippage = slippage_bp(&side, touch_px, avg_fill_px);
+                        book.last_slippage_bp = Some(slippage);
+                        self.hedge_instrument
+                            .measurement_cache
+                            .add_point_now(&HEDGE_SLIPPAGE, slippage);
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
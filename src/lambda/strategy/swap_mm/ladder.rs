@@ -0,0 +1,84 @@
+use crate::lambda::strategy::swap_mm::params::SwapMMStrategyParams;
+use crate::model::OrderSide;
+
+/// relative tolerance when comparing a computed price with the price echoed by the exchange
+const PRICE_EPSILON: f64 = 1e-9;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LadderLevel {
+    pub price: f64,
+    pub size: f64,
+}
+
+impl LadderLevel {
+    pub fn matches(&self, price: f64, size: f64) -> bool {
+        (self.price - price).abs() <= self.price.abs() * PRICE_EPSILON
+            && (self.size - size).abs() <= self.size.abs() * PRICE_EPSILON
+    }
+}
+
+/// size of ladder level `level`, the last entry of `level_sizes` repeats for deeper levels
+pub fn level_size(params: &SwapMMStrategyParams, level: usize) -> f64 {
+    match params.level_sizes.get(level).or_else(|| params.level_sizes.last()) {
+        None => params.base_size,
+        Some(size) => *size,
+    }
+}
+
+/// shift of both sides in bp, positive while long: the bid widens and the ask narrows
+pub fn inventory_skew_bp(params: &SwapMMStrategyParams, position: f64) -> f64 {
+    match params.max_position {
+        Some(max_position) if max_position > 0.0 => {
+            (position / max_position).clamp(-1.0, 1.0) * params.inventory_skew_bp
+        }
+        _ => 0.0,
+    }
+}
+
+fn round_to_tick(price: f64, tick_size: Option<f64>, side: &OrderSide) -> f64 {
+    match tick_size {
+        Some(tick_size) if tick_size > 0.0 => match side {
+            OrderSide::Buy => (price / tick_size + PRICE_EPSILON).floor() * tick_size,
+            OrderSide::Sell => (price / tick_size - PRICE_EPSILON).ceil() * tick_size,
+        },
+        _ => price,
+    }
+}
+
+/// Orders to keep on `side`, from the most aggressive level outwards.
+///
+/// The first level sits at `target_px`, every further level `level_spacing_bp` further from the
+/// touch. The inventory skew then moves the whole ladder, but never past `best_px`, the best price
+/// on our side of the book, so a narrowed side joins the touch instead of crossing.
+/// The side that would grow the position beyond `max_position` is not quoted.
+pub fn ladder(
+    params: &SwapMMStrategyParams,
+    side: &OrderSide,
+    target_px: f64,
+    best_px: f64,
+    position: f64,
+) -> Vec<LadderLevel> {
+    if let Some(max_position) = params.max_position {
+        let grows_beyond_max = match side {
+            OrderSide::Buy => position >= max_position,
+            OrderSide::Sell => position <= -max_position,
+        };
+        if grows_beyond_max {
+            return vec![];
+        }
+    }
+    let skew = 1.0 - inventory_skew_bp(params, position) / 10000.0;
+    (0..params.levels.max(1))
+        .map(|level| {
+            let spacing = level as f64 * params.level_spacing_bp / 10000.0;
+            let price = match side {
+                OrderSide::Buy => (target_px * (1.0 - spacing) * skew).min(best_px),
+                OrderSide::Sell => (target_px * (1.0 + spacing) * skew).max(best_px),
+            };
+            LadderLevel {
+                price: round_to_tick(price, params.tick_size, side),
+                size: level_size(params, level),
+            }
+        })
+        .collect()
+}
@@ -1,24 +1,22 @@
 use crate::cache::{MarketDepthCache, ValueCache, ValueCacheKey};
+use crate::lambda::strategy::swap_mm::hedger::Hedger;
+use crate::lambda::strategy::swap_mm::ladder::{inventory_skew_bp, ladder, LadderLevel};
 use crate::lambda::strategy::swap_mm::params::{
-    SwapMMInitParams, SwapMMStrategyParams, SwapMMStrategyStateStruct,
+    self, SwapMMInitParams, SwapMMStrategyParams, SwapMMStrategyStateStruct,
 };
-use crate::lambda::{GenericLambdaInstanceConfig, LambdaInstanceConfig, LambdaState};
+use crate::lambda::strategy::{ParamSchema, Strategy, StrategyContext};
+use crate::lambda::{LambdaState, Watchdog};
 
-use crate::model::{
-    Instrument, InstrumentSymbol, MeasurementCache, OrderFill, OrderSide, OrderStatus, OrderType,
-    OrderUpdate,
-};
-use crate::pubsub::PublishPayload;
-
-use crate::cache::OrderUpdateCache;
+use crate::model::constants::Exchanges;
+use crate::model::market_data_model::MarketDepth;
+use crate::model::{Instrument, OrderFill, OrderSide, OrderStatus, OrderType, OrderUpdate};
 
-use crate::pubsub::simple_message_bus::TypedMessageConsumer;
 use dashmap::mapref::one::RefMut;
-use dashmap::{DashMap, DashSet};
+use dashmap::DashMap;
+use serde_json::Value;
 
 use std::collections::hash_map::RandomState;
 
-use std::str::FromStr;
 use std::sync::Arc;
 use std::time::Duration;
 
@@ -31,72 +29,49 @@ static STRATEGY_STATE_KEY: &str = "STRATEGY_STATE_KEY";
 pub struct Lambda {
     market_depth: Arc<MarketDepthCache>,
     depth_instrument: Arc<Instrument>,
-    hedge_instrument: Arc<Instrument>,
+    hedger: Hedger,
     strategy_state: Arc<DashMap<String, StrategyState>>,
-    measurement_cache: Arc<MeasurementCache>,
     value_cache: Arc<ValueCache>,
+    context: StrategyContext,
+    watchdog: Watchdog,
 }
 
 impl Lambda {
-    pub fn new(
-        instance_config: GenericLambdaInstanceConfig,
-        market_depth: Arc<MarketDepthCache>,
-        order_cache: Arc<OrderUpdateCache>,
-        message_bus_sender: tokio::sync::mpsc::Sender<PublishPayload>,
-        measurement_cache: Arc<MeasurementCache>,
-        value_cache: Arc<ValueCache>,
-    ) -> Self {
-        // get init params
-        let lambda_instance_config = LambdaInstanceConfig::load(instance_config.name.as_str());
-        let init_params =
-            serde_json::from_value::<InitParams>(lambda_instance_config.init_params.clone())
-                .unwrap();
-
-        // depth_instrument
-        let depth_instrument_token = InstrumentSymbol::from_str(init_params.depth_symbol.as_str())
-            .expect("Cannot parse depth instrument from token");
-        let depth_instrument = match depth_instrument_token {
-            InstrumentSymbol(exchange, market) => Arc::new(Instrument {
-                exchange,
-                market,
-                order_cache: order_cache.clone(),
-                message_bus_sender: message_bus_sender.clone(),
-                measurement_cache: measurement_cache.clone(),
-            }),
-        };
-        // hedge_instrument
-        let hedge_instrument_token = InstrumentSymbol::from_str(init_params.hedge_symbol.as_str())
-            .expect("Cannot parse hedge instrumen from token");
-        let hedge_instrument = match hedge_instrument_token {
-            InstrumentSymbol(exchange, market) => Arc::new(Instrument {
-                exchange,
-                market,
-                order_cache: order_cache.clone(),
-                message_bus_sender: message_bus_sender.clone(),
-                measurement_cache: measurement_cache.clone(),
-            }),
-        };
-
+    pub fn new(context: &StrategyContext) -> anyhow::Result<Self> {
+        let init_params = context.init_params::<InitParams>()?;
+        let depth_instrument = context.instrument(init_params.depth_symbol.as_str())?;
+        let hedge_instrument = context.instrument(init_params.hedge_symbol.as_str())?;
+        let market_depth = context.market_depth_cache.clone();
+        let value_cache = context.value_cache.clone();
+
+        let restored_state = context.restored_state::<StrategyState>();
+        let unhedged_delta = restored_state.unhedged_delta;
         let strategy_state = DashMap::new();
-        strategy_state.insert(STRATEGY_STATE_KEY.to_string(), StrategyState::default());
+        strategy_state.insert(STRATEGY_STATE_KEY.to_string(), restored_state);
+
+        let watchdog = context.watchdog(vec![depth_instrument.clone(), hedge_instrument.clone()]);
+
+        let hedger = Hedger::new(
+            depth_instrument.clone(),
+            hedge_instrument.clone(),
+            market_depth.clone(),
+            context.clock.clone(),
+        );
+        hedger.restore_unhedged_delta(unhedged_delta);
 
-        Lambda {
+        Ok(Lambda {
             market_depth,
             depth_instrument,
-            hedge_instrument,
+            hedger,
             strategy_state: Arc::new(strategy_state),
-            measurement_cache,
             value_cache,
-        }
+            context: context.clone(),
+            watchdog,
+        })
     }
 
-    pub fn get_strategy_params(&self) -> StrategyParams {
-        match self.value_cache.get_clone(ValueCacheKey::StrategyParams) {
-            None => {
-                panic!("StrategyParams could not be None")
-            }
-            Some(value) => return serde_json::from_value::<StrategyParams>(value).unwrap(),
-        }
+    pub fn get_strategy_params(&self) -> anyhow::Result<StrategyParams> {
+        self.context.strategy_params()
     }
 
     pub fn get_strategy_state(&self) -> StrategyState {
@@ -112,350 +87,339 @@ impl Lambda {
     //     self.strategy_state.write().await
     // }
 
-    async fn publish_state(&self) -> anyhow::Result<()> {
-        let strategy_state = self.get_strategy_state();
-        let value = serde_json::to_value(strategy_state)?;
-        self.value_cache
-            .insert(ValueCacheKey::StrategyStates, value);
-        Ok(())
+    /// the state with the hedger's current delta
+    fn published_state(&self) -> StrategyState {
+        if let Some(mut state) = self.write_strategy_state() {
+            state.unhedged_delta = self.hedger.unhedged_delta();
+            state.hedge_in_flight = self.hedger.in_flight();
+            state.hedge_slippage_bp = self.hedger.last_slippage_bp();
+        }
+        self.get_strategy_state()
     }
 
-    async fn period_publish_state(&self) -> anyhow::Result<()> {
-        loop {
-            match self.publish_state().await {
-                Ok(_) => {}
-                Err(err) => error!("Error publishing lambda state: {}", err),
+    /// target quotes from the book of the depth instrument
+    fn update_targets(&self, md: &MarketDepth) -> anyhow::Result<()> {
+        let params = self.get_strategy_params()?;
+        let target_size = params.target_acc_size;
+
+        let mut sum_bid_size = 0.0f64;
+        let mut target_bid_price = 0.0f64;
+        let mut target_bid_level = 0i64;
+        for level in md.bids.iter() {
+            sum_bid_size += level.size;
+            if sum_bid_size >= target_size {
+                target_bid_price = level.price;
+                break;
             }
-            tokio::time::sleep(Duration::from_millis(500)).await;
+            target_bid_level += 1;
         }
-    }
 
-    async fn period_update(&self) -> anyhow::Result<()> {
-        loop {
-            let params = self.get_strategy_params();
-            if let Some(md) = self
-                .market_depth
-                .get_clone(self.depth_instrument.market.as_str())
-            {
-                let target_size = params.target_acc_size;
-
-                let mut sum_bid_size = 0.0f64;
-                let mut target_bid_price = 0.0f64;
-                let mut target_bid_level = 0i64;
-                for level in md.bids.iter() {
-                    sum_bid_size += level.size;
-                    if sum_bid_size >= target_size {
-                        target_bid_price = level.price;
-                        break;
-                    }
-                    target_bid_level += 1;
-                }
+        let mut sum_ask_size = 0.0f64;
+        let mut target_ask_price = 0.0f64;
+        let mut target_ask_level = 0i64;
+        for level in md.asks.iter() {
+            sum_ask_size += level.size;
+            if sum_ask_size >= target_size {
+                target_ask_price = level.price;
+                break;
+            }
+            target_ask_level += 1;
+        }
 
-                let mut sum_ask_size = 0.0f64;
-                let mut target_ask_price = 0.0f64;
-                let mut target_ask_level = 0i64;
-                for level in md.asks.iter() {
-                    sum_ask_size += level.size;
-                    if sum_ask_size >= target_size {
-                        target_ask_price = level.price;
-                        break;
-                    }
-                    target_ask_level += 1;
-                }
+        // debug!("bid[{}]: {}", target_bid_level, target_bid_price);
+        // debug!("ask[{}]: {}", target_ask_level, target_ask_price);
 
-                // debug!("bid[{}]: {}", target_bid_level, target_bid_price);
-                // debug!("ask[{}]: {}", target_ask_level, target_ask_price);
-
-                // measurement
-                let bid_basis_bp =
-                    ((target_bid_price - md.bids[0].price) / md.bids[0].price) * 10000.0;
-                let ask_basis_bp =
-                    ((target_ask_price - md.asks[0].price) / md.bids[0].price) * 10000.0;
-
-                let open_bid_orders = self.depth_instrument.get_open_buy_orders(true);
-
-                if let Some(mut state) = self.write_strategy_state() {
-                    state.target_bid_px = Some(target_bid_price);
-                    state.target_ask_px = Some(target_ask_price);
-                    state.target_bid_level = Some(target_bid_level);
-                    state.target_ask_level = Some(target_ask_level);
-                    state.bid_basis_bp = Some(bid_basis_bp);
-                    state.ask_basis_bp = Some(ask_basis_bp);
-                    state.open_bid_cnt = Some(open_bid_orders.len());
-                    match md.bids.get(0) {
-                        None => {
-                            state.depth_bid_px = None;
-                        }
-                        Some(bid_0) => state.depth_bid_px = Some(bid_0.price),
-                    }
-                    match md.asks.get(0) {
-                        None => {
-                            state.depth_ask_px = None;
-                        }
-                        Some(ask_0) => state.depth_ask_px = Some(ask_0.price),
-                    }
-                    match open_bid_orders.get(0) {
-                        None => {
-                            state.open_bid_px = None;
-                        }
-                        Some(open_bid) => {
-                            state.open_bid_px = Some(open_bid.price);
-                        }
-                    }
-                }
-            } else {
-                if let Some(mut state) = self.write_strategy_state() {
-                    state.depth_bid_px = None;
-                    state.depth_ask_px = None;
-                    state.bid_basis_bp = None;
-                    state.ask_basis_bp = None;
-                    state.target_bid_px = None;
-                    state.target_bid_level = None;
-                    state.target_ask_px = None;
-                    state.target_ask_level = None
-                }
+        let (bid_0, ask_0) = match (md.bids.first(), md.asks.first()) {
+            (Some(bid_0), Some(ask_0)) => (bid_0, ask_0),
+            _ => {
+                self.clear_targets();
+                return Ok(());
             }
-            tokio::time::sleep(Duration::from_millis(5)).await;
+        };
+
+        // measurement
+        let bid_basis_bp = ((target_bid_price - bid_0.price) / bid_0.price) * 10000.0;
+        let ask_basis_bp = ((target_ask_price - ask_0.price) / bid_0.price) * 10000.0;
+
+        if let Some(mut state) = self.write_strategy_state() {
+            state.target_bid_px = Some(target_bid_price);
+            state.target_ask_px = Some(target_ask_price);
+            state.target_bid_level = Some(target_bid_level);
+            state.target_ask_level = Some(target_ask_level);
+            state.bid_basis_bp = Some(bid_basis_bp);
+            state.ask_basis_bp = Some(ask_basis_bp);
+            state.depth_bid_px = Some(bid_0.price);
+            state.depth_ask_px = Some(ask_0.price);
         }
+        Ok(())
     }
 
-    async fn period_cancel_orders(&self) -> anyhow::Result<()> {
-        loop {
-            self.cancel_orders().await;
-            tokio::time::sleep(Duration::from_millis(5)).await;
+    fn clear_targets(&self) {
+        if let Some(mut state) = self.write_strategy_state() {
+            state.depth_bid_px = None;
+            state.depth_ask_px = None;
+            state.bid_basis_bp = None;
+            state.ask_basis_bp = None;
+            state.target_bid_px = None;
+            state.target_bid_level = None;
+            state.target_ask_px = None;
+            state.target_ask_level = None
         }
     }
 
-    async fn period_run_trading(&self) -> anyhow::Result<()> {
-        loop {
-            if self.should_run_trading() {
-                self.add_order().await?;
-            }
-            tokio::time::sleep(Duration::from_millis(500)).await;
-        }
+    /// bring the ladders of both sides to the targets
+    async fn quote(&self) -> anyhow::Result<()> {
+        self.manage_ladder(OrderSide::Buy).await?;
+        self.manage_ladder(OrderSide::Sell).await
     }
 
-    fn should_run_trading(&self) -> bool {
-        let params = self.get_strategy_params();
-        return match params.state {
-            LambdaState::Init
-            | LambdaState::Paused
-            | LambdaState::Stopped
-            | LambdaState::AutoPaused => false,
-            LambdaState::Live => true,
-        };
+    fn is_depth_instrument(&self, exchange: &Exchanges, market: &str) -> bool {
+        *exchange == self.depth_instrument.exchange && market == self.depth_instrument.market
     }
 
-    async fn cancel_orders(&self) {
-        let open_buy_orders = self.depth_instrument.get_open_buy_orders(false);
-        let open_sell_orders = self.depth_instrument.get_open_sell_orders(false);
-        if open_buy_orders.len() > 1 {
-            panic!("invalid state: depth_instrument open_buy_orders > 1");
-        }
-        if open_sell_orders.len() > 1 {
-            panic!("invalid state: depth_instrument open_sell_orders > 1");
+    fn should_run_trading(&self, params: &StrategyParams) -> bool {
+        matches!(params.state, LambdaState::Live)
+    }
+
+    fn can_quote_buy(&self, params: &StrategyParams, state: &StrategyState) -> bool {
+        match (state.target_bid_level, state.bid_basis_bp) {
+            (Some(target_bid_level), Some(bid_basis_bp)) => {
+                target_bid_level >= params.min_level && bid_basis_bp <= -params.min_basis
+            }
+            _ => false,
         }
+    }
 
-        let open_bid = open_buy_orders.get(0);
-        let open_ask = open_sell_orders.get(0);
+    fn can_quote_sell(&self, params: &StrategyParams, state: &StrategyState) -> bool {
+        match (state.target_ask_level, state.ask_basis_bp) {
+            (Some(target_ask_level), Some(ask_basis_bp)) => {
+                target_ask_level >= params.min_level && ask_basis_bp >= params.min_basis
+            }
+            _ => false,
+        }
+    }
 
+    /// the ladder `side` should have right now, empty while the side is not quoted
+    fn target_ladder(&self, side: &OrderSide) -> anyhow::Result<Vec<LadderLevel>> {
+        let params = self.get_strategy_params()?;
         let state = self.get_strategy_state();
-
-        // cancel buy
-        if let (Some(open_bid), Some(target_bid_px)) = (open_bid, state.target_bid_px) {
-            if !open_buy_orders.is_empty() && open_bid.price != target_bid_px {
-                for order in open_buy_orders {
-                    self.depth_instrument
-                        .cancel_order(order.client_id.unwrap_or_default().as_str())
-                        .await;
-                }
-                if let Some(mut write_state) = self.write_strategy_state() {
-                    write_state.enable_buy = true;
-                }
-            }
-        } else {
-            // no first active order
-            if open_buy_orders.is_empty() && !state.enable_buy {
-                // if no open orders
-                if let Some(mut write_state) = self.write_strategy_state() {
-                    write_state.enable_buy = true;
-                }
-            }
+        let (can_quote, target_px, best_px) = match side {
+            OrderSide::Buy => (
+                self.can_quote_buy(&params, &state),
+                state.target_bid_px,
+                state.depth_bid_px,
+            ),
+            OrderSide::Sell => (
+                self.can_quote_sell(&params, &state),
+                state.target_ask_px,
+                state.depth_ask_px,
+            ),
         };
-
-        // cancel sell
-        if let (Some(open_ask), Some(target_ask_px)) = (open_ask, state.target_ask_px) {
-            if !open_sell_orders.is_empty() && open_ask.price != target_ask_px {
-                for order in open_sell_orders {
-                    self.depth_instrument
-                        .cancel_order(order.client_id.unwrap_or_default().as_str())
-                        .await;
-                }
+        if let Some(mut write_state) = self.write_strategy_state() {
+            write_state.skew_bp = inventory_skew_bp(&params, state.position);
+            match side {
+                OrderSide::Buy => write_state.enable_buy = can_quote,
+                OrderSide::Sell => write_state.enable_sell = can_quote,
             }
-            if let Some(mut write_state) = self.write_strategy_state() {
-                write_state.enable_sell = true;
+        }
+        let quoting = can_quote && self.should_run_trading(&params);
+        Ok(match (quoting, target_px, best_px) {
+            (true, Some(target_px), Some(best_px)) => {
+                ladder(&params, side, target_px, best_px, state.position)
             }
-        } else {
-            if open_sell_orders.is_empty() && !state.enable_sell {
-                if let Some(mut write_state) = self.write_strategy_state() {
-                    write_state.enable_sell = true;
+            _ => vec![],
+        })
+    }
+
+    /// Match the open orders of `side`, most aggressive first, against the target ladder:
+    /// requote orders off their level, cancel orders beyond the ladder and place missing levels.
+    async fn manage_ladder(&self, side: OrderSide) -> anyhow::Result<()> {
+        let levels = self.target_ladder(&side)?;
+        let (mut open_orders, open_order_cnt) = match side {
+            OrderSide::Buy => (
+                self.depth_instrument.get_open_buy_orders(false),
+                self.depth_instrument.get_open_buy_orders(true).len(),
+            ),
+            OrderSide::Sell => (
+                self.depth_instrument.get_open_sell_orders(false),
+                self.depth_instrument.get_open_sell_orders(true).len(),
+            ),
+        };
+        open_orders.sort_by(|a, b| match side {
+            OrderSide::Buy => b.price.partial_cmp(&a.price).unwrap(),
+            OrderSide::Sell => a.price.partial_cmp(&b.price).unwrap(),
+        });
+
+        for (level, open_order) in open_orders.iter().enumerate() {
+            match levels.get(level) {
+                None => self.cancel_order(open_order).await,
+                Some(target) => {
+                    if !target.matches(open_order.price, open_order.size)
+                        && !self.requote_order(open_order, target).await
+                    {
+                        self.cancel_order(open_order).await;
+                    }
                 }
             }
         }
-        drop(state)
-    }
 
-    async fn add_order(&self) -> anyhow::Result<()> {
-        let open_orders = self.depth_instrument.get_open_orders(true);
-        let params = self.get_strategy_params();
-        if open_orders.is_empty() {
-            let state = self.get_strategy_state();
+        // orders still waiting for their cancel hold their level until closed
+        let placed = open_order_cnt.max(open_orders.len());
+        for target in levels.iter().skip(placed) {
+            self.depth_instrument
+                .send_order(side.clone(), target.price, target.size, OrderType::Limit)
+                .await?;
+        }
 
-            // place order buy
-            if let (enable_buy, Some(target_bid_px), Some(target_bid_level), Some(bid_basis_bp)) = (
-                state.enable_buy,
-                state.target_bid_px,
-                state.target_bid_level,
-                state.bid_basis_bp,
-            ) {
-                if enable_buy
-                    && target_bid_level >= params.min_level
-                    && bid_basis_bp <= -params.min_basis
-                {
-                    self.depth_instrument
-                        .send_order(
-                            OrderSide::Buy,
-                            target_bid_px,
-                            params.base_size,
-                            OrderType::Limit,
-                        )
-                        .await;
-                    if let Some(mut state) = self.write_strategy_state() {
-                        state.enable_buy = false;
-                    }
+        if let Some(mut state) = self.write_strategy_state() {
+            let open_px = open_orders.first().map(|order| order.price);
+            match side {
+                OrderSide::Buy => {
+                    state.open_bid_cnt = Some(open_orders.len());
+                    state.open_bid_px = open_px;
+                }
+                OrderSide::Sell => {
+                    state.open_ask_cnt = Some(open_orders.len());
+                    state.open_ask_px = open_px;
                 }
             }
+        }
+        Ok(())
+    }
 
-            // place order sell
-            if let (enable_sell, Some(target_ask_px), Some(target_ask_level), Some(ask_basis_bp)) = (
-                state.enable_sell,
-                state.target_ask_px,
-                state.target_ask_level,
-                state.ask_basis_bp,
-            ) {
-                if enable_sell
-                    && target_ask_level >= params.min_level
-                    && ask_basis_bp >= params.min_basis
-                {
-                    self.depth_instrument
-                        .send_order(
-                            OrderSide::Sell,
-                            target_ask_px,
-                            params.base_size,
-                            OrderType::Limit,
-                        )
-                        .await;
-                    if let Some(mut state) = self.write_strategy_state() {
-                        state.enable_sell = false;
-                    }
-                }
+    async fn cancel_order(&self, order: &OrderUpdate) {
+        if let Some(ref client_id) = order.client_id {
+            if let Err(err) = self.depth_instrument.cancel_order(client_id.as_str()).await {
+                error!("cancel {} failed: {}", client_id, err);
             }
+        }
+    }
 
+    /// Move `open_order` to the `target` level with a single modify request, keeping us in the
+    /// book. Returns false if the order should be cancelled instead.
+    async fn requote_order(&self, open_order: &OrderUpdate, target: &LadderLevel) -> bool {
+        let client_id = match open_order.client_id {
+            Some(ref client_id) => client_id,
+            None => return false,
         };
-        Ok(())
+        match open_order.status {
+            OrderStatus::New | OrderStatus::Open => {}
+            // not acknowledged yet, wait for the exchange before modifying
+            _ => return true,
+        }
+        match self
+            .depth_instrument
+            .modify_order(client_id, target.price, target.size)
+            .await
+        {
+            Ok(Some(_new_client_id)) => true,
+            Ok(None) => false,
+            Err(err) => {
+                error!("modify {} failed: {}", client_id, err);
+                false
+            }
+        }
     }
 
+    /// background work next to the event hooks: state publishing, hedging and the watchdog
     pub async fn subscribe(&self) -> anyhow::Result<()> {
-        let hedger =
-            SimpleHedger::new(self.depth_instrument.clone(), self.hedge_instrument.clone());
         tokio::select! {
-            result = self.period_update() => {
-                panic!("lambda update panic: {:?}", result)
-            }
-            result = self.period_run_trading() => {
-                panic!("lambda period_run_trading panic: {:?}", result)
-            }
-            result = self.period_publish_state() => {
-                panic!("period_publish_state panic: {:?}", result)
+            result = self.context.period_publish_state(|| self.published_state()) => {
+                error!("period_publish_state completed: {:?}", result)
             }
-            result = self.period_cancel_orders() => {
-                panic!("lambda cancel_orders panic: {:?}", result)
+            result = self.hedger.subscribe() => {
+                error!("hedger completed: {:?}", result)
             }
-            result = hedger.subscribe() => {
-                panic!("depth_instrument.subscribe_order_fill panic: {:?}", result)
+            result = self.watchdog.subscribe() => {
+                error!("watchdog completed: {:?}", result)
             }
         }
-        panic!("Lambda subscribe uncaught")
+        Err(anyhow!("SwapMM subscribe uncaught"))
     }
 }
-pub struct SimpleHedger {
-    pub hedge_orders: Arc<DashSet<String>>,
-    pub depth_instrument: Arc<Instrument>,
-    pub hedge_instrument: Arc<Instrument>,
-}
-impl SimpleHedger {
-    pub fn new(depth_instrument: Arc<Instrument>, hedge_instrument: Arc<Instrument>) -> Self {
-        SimpleHedger {
-            hedge_orders: Arc::new(DashSet::new()),
-            depth_instrument,
-            hedge_instrument,
+
+/// SwapMM requotes on every book and order update of the depth instrument. The timer catches
+/// state changes without events, e.g. going Live or the book going stale.
+#[async_trait::async_trait]
+impl Strategy for Lambda {
+    async fn on_market_depth(&self, market_depth: &MarketDepth) -> anyhow::Result<()> {
+        if !self.is_depth_instrument(&market_depth.exchange, market_depth.market.as_str()) {
+            return Ok(());
         }
+        self.update_targets(market_depth)?;
+        self.quote().await
     }
-    pub async fn subscribe(&self) -> anyhow::Result<()> {
-        log::info!("simple_hedger subscribing...");
-        tokio::select! {
-            Err(err) = self.depth_instrument.subscribe_order_fill(self) => {
-                error!("Hedge subscribe_order_fill: {}", err);
-            },
-            Err(err) = self.hedge_instrument.subscribe_order_update(self) => {
-                error!("Hedge subscribe_order_update: {}", err);
-            }
+
+    fn coalesce_market_depth(&self) -> bool {
+        true
+    }
+
+    async fn on_order_update(&self, order_update: &OrderUpdate) -> anyhow::Result<()> {
+        if !self.is_depth_instrument(&order_update.exchange, order_update.market.as_str()) {
+            return Ok(());
         }
-        Err(anyhow!("Hedge subscribe uncaught"))
+        self.quote().await
     }
-}
-#[async_trait::async_trait]
-impl TypedMessageConsumer<OrderFill> for SimpleHedger {
-    async fn consume(&self, order_fill: OrderFill) -> anyhow::Result<()> {
-        let hedge_order = self.hedge_instrument.send_order(
-            OrderSide::flip_side(&order_fill.side),
-            0.0,
-            order_fill.size.clone(),
-            OrderType::Market,
-        );
-        if let Some(client_id) = hedge_order.await? {
-            self.hedge_orders.insert(client_id);
+
+    fn timer_interval(&self) -> Option<Duration> {
+        Some(Duration::from_millis(500))
+    }
+
+    async fn on_timer(&self) -> anyhow::Result<()> {
+        // MarketDepthCache drops stale books
+        match self
+            .market_depth
+            .get_clone(self.depth_instrument.market.as_str())
+        {
+            None => self.clear_targets(),
+            Some(md) => self.update_targets(&md)?,
         }
-        Ok(())
+        self.quote().await
     }
-}
 
-#[async_trait::async_trait]
-impl TypedMessageConsumer<OrderUpdate> for SimpleHedger {
-    async fn consume(&self, order_update: OrderUpdate) -> anyhow::Result<()> {
-        if let Some(client_id) = order_update.client_id {
-            match order_update.status {
-                OrderStatus::New => {}
-                OrderStatus::Open => {}
-                OrderStatus::PendingNew => {}
-                OrderStatus::PendingCancel => {}
-                OrderStatus::Closed => {
-                    if order_update.filledSize >= order_update.size {
-                        self.hedge_orders.remove(client_id.as_str());
-                    }
-                }
-                OrderStatus::Failed => {
-                    self.hedge_orders.remove(client_id.as_str());
-                    // resend order
-                    self.hedge_instrument.send_order(
-                        order_update.side.clone(),
-                        0.0,
-                        order_update.size.clone(),
-                        OrderType::Market,
-                    );
-                }
+    async fn on_fill(&self, order_fill: &OrderFill) -> anyhow::Result<()> {
+        if !self.is_depth_instrument(&order_fill.exchange, order_fill.market.as_str()) {
+            return Ok(());
+        }
+        if let Some(mut state) = self.write_strategy_state() {
+            match order_fill.side {
+                OrderSide::Buy => state.position += order_fill.size,
+                OrderSide::Sell => state.position -= order_fill.size,
             }
-        };
+        }
+        Ok(())
+    }
+
+    /// the ladder picks up its own orders of the depth instrument, hedges start over from the
+    /// restored unhedged delta
+    async fn on_restore(&self, open_orders: &[OrderUpdate]) -> anyhow::Result<Vec<String>> {
+        Ok(open_orders
+            .iter()
+            .filter(|order| order.is_owned_by(self.depth_instrument.owner.as_str()))
+            .filter(|order| self.is_depth_instrument(&order.exchange, order.market.as_str()))
+            .filter_map(|order| order.client_id.clone())
+            .collect())
+    }
+
+    async fn on_init(&self) -> anyhow::Result<()> {
+        if let Some(value) = self.value_cache.get_clone(ValueCacheKey::StrategyParams) {
+            self.hedger
+                .set_params(serde_json::from_value::<StrategyParams>(value)?.hedger);
+        }
         Ok(())
     }
+
+    fn param_schema(&self) -> ParamSchema {
+        params::param_schema()
+    }
+
+    async fn on_param_change(&self, strategy_params: &Value) -> anyhow::Result<()> {
+        self.hedger
+            .set_params(serde_json::from_value::<StrategyParams>(strategy_params.clone())?.hedger);
+        self.quote().await
+    }
+
+    async fn run(&self) -> anyhow::Result<()> {
+        self.subscribe().await
+    }
 }
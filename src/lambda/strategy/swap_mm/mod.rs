@@ -1,2 +1,4 @@
+pub mod hedger;
+pub mod ladder;
 pub mod params;
 pub mod lambda;
@@ -1,3 +1,4 @@
+use crate::lambda::strategy::schema::{state_spec, ParamSchema, ParamSpec};
 use crate::lambda::LambdaState;
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -13,6 +14,64 @@ pub struct SwapMMStrategyParams {
     pub min_basis: f64,
     pub base_size: f64,
     pub target_acc_size: f64,
+    /// orders per side
+    #[serde(default = "default_levels")]
+    pub levels: usize,
+    /// distance between two levels of the ladder
+    #[serde(default)]
+    pub level_spacing_bp: f64,
+    /// size per level, the last entry repeats for deeper levels. `base_size` when empty
+    #[serde(default)]
+    pub level_sizes: Vec<f64>,
+    /// ladder prices are rounded to the tick, bids down and asks up
+    #[serde(default)]
+    pub tick_size: Option<f64>,
+    /// the side that would grow the position of the depth instrument beyond this is not quoted
+    #[serde(default)]
+    pub max_position: Option<f64>,
+    /// shift of both sides against the position, reached at `max_position`
+    #[serde(default)]
+    pub inventory_skew_bp: f64,
+    #[serde(default)]
+    pub hedger: HedgerParams,
+}
+fn default_levels() -> usize {
+    1
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+pub enum HedgeOrderType {
+    #[default]
+    Market,
+    /// limit order through the touch, capped at `max_slippage_bp`, rests until `retry_interval_ms`
+    AggressiveLimit,
+    /// same price cap as `AggressiveLimit`, the unfilled rest is hedged on the next retry
+    Ioc,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct HedgerParams {
+    pub order_type: HedgeOrderType,
+    /// unhedged delta of the depth instrument that triggers a hedge
+    pub threshold: f64,
+    /// smallest order size the hedge instrument accepts
+    pub min_size: f64,
+    /// limit price distance through the touch of the hedge instrument
+    pub max_slippage_bp: f64,
+    /// period of retrying failed or partially filled hedges
+    pub retry_interval_ms: u64,
+}
+impl Default for HedgerParams {
+    fn default() -> Self {
+        HedgerParams {
+            order_type: HedgeOrderType::Market,
+            threshold: 0.0,
+            min_size: 0.0,
+            max_slippage_bp: 10.0,
+            retry_interval_ms: 1000,
+        }
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, Default)]
@@ -33,4 +92,36 @@ pub struct SwapMMStrategyStateStruct {
     pub depth_ask_px: Option<f64>,
     pub bid_basis_bp: Option<f64>,
     pub ask_basis_bp: Option<f64>,
+    /// net position of the depth instrument
+    pub position: f64,
+    pub skew_bp: f64,
+    /// delta of depth fills not yet offset by hedge fills, positive while long
+    pub unhedged_delta: f64,
+    /// signed size of open hedge orders
+    pub hedge_in_flight: f64,
+    pub hedge_slippage_bp: Option<f64>,
+}
+
+pub fn param_schema() -> ParamSchema {
+    ParamSchema::new(vec![
+        state_spec(),
+        ParamSpec::int("min_level", "depth level of the target quote").min(0.0),
+        ParamSpec::float("min_basis", "minimum perp/spot basis in bp to quote a side"),
+        ParamSpec::float("base_size", "order size").min(0.0),
+        ParamSpec::float("target_acc_size", "accumulated book size the target quote sits behind")
+            .min(0.0),
+        ParamSpec::int("levels", "orders per side").min(1.0).max(20.0),
+        ParamSpec::float("level_spacing_bp", "distance between two ladder levels").min(0.0),
+        ParamSpec::float("tick_size", "ladder prices are rounded to the tick")
+            .min(0.0)
+            .nullable(),
+        ParamSpec::float("max_position", "no quotes growing the position beyond this")
+            .min(0.0)
+            .nullable()
+            .admin(),
+        ParamSpec::float("inventory_skew_bp", "quote shift against the position at max_position")
+            .min(0.0),
+        ParamSpec::object("hedger", "hedge order type, threshold and price cap"),
+    ])
+    .parsed_as::<SwapMMStrategyParams>()
 }
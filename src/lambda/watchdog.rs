@@ -0,0 +1,272 @@
+use crate::cache::{MarketDepthCache, ValueCache, ValueCacheKey};
+use crate::core::clock::SharedClock;
+use crate::lambda::LambdaState;
+use crate::model::global_measurement::ORDER_LATENCY;
+use crate::model::{Instrument, MeasurementCache, OrderFill, OrderSide, OrderStatus, OrderUpdate};
+use crate::pubsub::simple_message_bus::TypedMessageConsumer;
+use dashmap::DashMap;
+use futures_util::FutureExt;
+use std::collections::VecDeque;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, AtomicI64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct WatchdogParams {
+    pub enabled: bool,
+    pub check_interval_ms: u64,
+    /// AutoPause when any subscribed market depth is stale (see `MarketDepthCache::get_clone`)
+    pub stale_market_depth: bool,
+    pub max_order_latency_ms: Option<f64>,
+    /// latencies older than this are ignored, a quiet lambda keeps no stale trigger
+    pub order_latency_window_ms: i64,
+    pub max_failed_orders: Option<usize>,
+    pub failed_orders_window_ms: i64,
+    pub max_position: Option<f64>,
+    /// resume to Live once no trigger fired for `resume_after_ms`, otherwise wait for a manual reset
+    pub auto_resume: bool,
+    pub resume_after_ms: i64,
+}
+impl Default for WatchdogParams {
+    fn default() -> Self {
+        WatchdogParams {
+            enabled: false,
+            check_interval_ms: 100,
+            stale_market_depth: true,
+            max_order_latency_ms: None,
+            order_latency_window_ms: 10000,
+            max_failed_orders: None,
+            failed_orders_window_ms: 60000,
+            max_position: None,
+            auto_resume: false,
+            resume_after_ms: 30000,
+        }
+    }
+}
+
+#[derive(Debug, Clone, strum_macros::Display)]
+pub enum WatchdogTrigger {
+    StaleMarketDepth(String),
+    OrderLatency(f64),
+    FailedOrders(usize),
+    PositionLimit(String, f64),
+    BusDisconnected,
+}
+
+pub struct Watchdog {
+    params: WatchdogParams,
+    market_depths: Vec<String>,
+    market_depth_cache: Arc<MarketDepthCache>,
+    measurement_cache: Arc<MeasurementCache>,
+    value_cache: Arc<ValueCache>,
+    instruments: Vec<Arc<Instrument>>,
+    positions: DashMap<String, f64>,
+    failed_orders: Mutex<VecDeque<i64>>,
+    bus_connected: AtomicBool,
+    paused_by_watchdog: AtomicBool,
+    last_trigger_ms: AtomicI64,
+    clock: SharedClock,
+}
+
+impl Watchdog {
+    pub fn new(
+        params: WatchdogParams,
+        market_depths: Vec<String>,
+        market_depth_cache: Arc<MarketDepthCache>,
+        measurement_cache: Arc<MeasurementCache>,
+        value_cache: Arc<ValueCache>,
+        instruments: Vec<Arc<Instrument>>,
+        clock: SharedClock,
+    ) -> Self {
+        Watchdog {
+            params,
+            market_depths,
+            market_depth_cache,
+            measurement_cache,
+            value_cache,
+            instruments,
+            positions: DashMap::new(),
+            failed_orders: Mutex::new(VecDeque::new()),
+            bus_connected: AtomicBool::new(true),
+            paused_by_watchdog: AtomicBool::new(false),
+            last_trigger_ms: AtomicI64::new(0),
+            clock,
+        }
+    }
+
+    fn time_now(&self) -> i64 {
+        self.clock.now_ms()
+    }
+
+    pub fn get_position(&self, market: &str) -> f64 {
+        self.positions
+            .get(market)
+            .map(|position| *position.value())
+            .unwrap_or(0.0)
+    }
+
+    pub fn triggers(&self) -> Vec<WatchdogTrigger> {
+        let mut triggers = vec![];
+        if self.params.stale_market_depth {
+            for market in self.market_depths.iter() {
+                if self.market_depth_cache.get_clone(market).is_none() {
+                    triggers.push(WatchdogTrigger::StaleMarketDepth(market.clone()));
+                }
+            }
+        }
+        if let Some(max_order_latency_ms) = self.params.max_order_latency_ms {
+            if let Some(latency) = self
+                .measurement_cache
+                .get_latest_point(&ORDER_LATENCY, self.params.order_latency_window_ms)
+            {
+                if latency > max_order_latency_ms {
+                    triggers.push(WatchdogTrigger::OrderLatency(latency));
+                }
+            }
+        }
+        if let Some(max_failed_orders) = self.params.max_failed_orders {
+            let window_start = self.time_now() - self.params.failed_orders_window_ms;
+            let mut failed_orders = self.failed_orders.lock().unwrap();
+            while let Some(ts) = failed_orders.front() {
+                if *ts >= window_start {
+                    break;
+                }
+                failed_orders.pop_front();
+            }
+            if failed_orders.len() > max_failed_orders {
+                triggers.push(WatchdogTrigger::FailedOrders(failed_orders.len()));
+            }
+        }
+        if let Some(max_position) = self.params.max_position {
+            for position in self.positions.iter() {
+                if position.value().abs() > max_position {
+                    triggers.push(WatchdogTrigger::PositionLimit(
+                        position.key().clone(),
+                        *position.value(),
+                    ));
+                }
+            }
+        }
+        if !self.bus_connected.load(Ordering::SeqCst) {
+            triggers.push(WatchdogTrigger::BusDisconnected);
+        }
+        triggers
+    }
+
+    fn get_state(&self) -> Option<LambdaState> {
+        let params = self.value_cache.get_clone(ValueCacheKey::StrategyParams)?;
+        LambdaState::from_str(params["state"].as_str()?).ok()
+    }
+
+    async fn cancel_open_orders(&self) {
+        for instrument in self.instruments.iter() {
+            for order in instrument.get_open_orders(false) {
+                if let Some(client_id) = order.client_id {
+                    if let Err(err) = instrument.cancel_order(client_id.as_str()).await {
+                        error!("watchdog failed to cancel {}: {}", client_id, err);
+                    }
+                }
+            }
+        }
+    }
+
+    /// one round of the periodic check: pause on a trigger, resume once they cleared
+    pub async fn check(&self) {
+        let triggers = self.triggers();
+        let now = self.time_now();
+        let state = self.get_state();
+        if !matches!(state, Some(LambdaState::AutoPaused)) {
+            // operator moved the lambda out of AutoPaused, hand control back
+            self.paused_by_watchdog.store(false, Ordering::SeqCst);
+        }
+        if !triggers.is_empty() {
+            self.last_trigger_ms.store(now, Ordering::SeqCst);
+            let paused = self
+                .value_cache
+                .transition_state(&[LambdaState::Live], LambdaState::AutoPaused);
+            if matches!(paused, Some(LambdaState::Live)) {
+                warn!("watchdog auto pausing lambda: {:?}", triggers);
+                self.paused_by_watchdog.store(true, Ordering::SeqCst);
+                self.cancel_open_orders().await;
+            }
+        } else if self.params.auto_resume
+            && self.paused_by_watchdog.load(Ordering::SeqCst)
+            && now - self.last_trigger_ms.load(Ordering::SeqCst) >= self.params.resume_after_ms
+        {
+            let resumed = self
+                .value_cache
+                .transition_state(&[LambdaState::AutoPaused], LambdaState::Live);
+            if matches!(resumed, Some(LambdaState::AutoPaused)) {
+                info!("watchdog triggers cleared. resuming lambda");
+            }
+            self.paused_by_watchdog.store(false, Ordering::SeqCst);
+        }
+    }
+
+    async fn period_check(&self) -> anyhow::Result<()> {
+        loop {
+            self.check().await;
+            self.clock
+                .sleep(Duration::from_millis(self.params.check_interval_ms))
+                .await;
+        }
+    }
+
+    async fn subscribe_orders(&self) -> anyhow::Result<()> {
+        loop {
+            let mut subscriptions = vec![];
+            for instrument in self.instruments.iter() {
+                subscriptions.push(instrument.subscribe_order_fill(self).boxed());
+                subscriptions.push(instrument.subscribe_order_update(self).boxed());
+            }
+            self.bus_connected.store(true, Ordering::SeqCst);
+            let (result, _, _) = futures_util::future::select_all(subscriptions).await;
+            error!("watchdog lost message bus subscription: {:?}", result);
+            self.bus_connected.store(false, Ordering::SeqCst);
+            self.clock.sleep(Duration::from_millis(1000)).await;
+        }
+    }
+
+    pub async fn subscribe(&self) -> anyhow::Result<()> {
+        if !self.params.enabled {
+            info!("watchdog disabled");
+            return futures_util::future::pending().await;
+        }
+        tokio::select! {
+            result = self.period_check() => {
+                error!("watchdog period_check completed: {:?}", result);
+            }
+            result = self.subscribe_orders() => {
+                error!("watchdog subscribe_orders completed: {:?}", result);
+            }
+        }
+        Err(anyhow!("Watchdog subscribe uncaught"))
+    }
+}
+
+#[async_trait::async_trait]
+impl TypedMessageConsumer<OrderFill> for Watchdog {
+    async fn consume(&self, order_fill: OrderFill) -> anyhow::Result<()> {
+        let delta = match order_fill.side {
+            OrderSide::Buy => order_fill.size,
+            OrderSide::Sell => -order_fill.size,
+        };
+        *self.positions.entry(order_fill.market).or_insert(0.0) += delta;
+        Ok(())
+    }
+}
+
+#[async_trait::async_trait]
+impl TypedMessageConsumer<OrderUpdate> for Watchdog {
+    async fn consume(&self, order_update: OrderUpdate) -> anyhow::Result<()> {
+        if order_update.status == OrderStatus::Failed {
+            self.failed_orders
+                .lock()
+                .unwrap()
+                .push_back(self.time_now());
+        }
+        Ok(())
+    }
+}
@@ -5,6 +5,7 @@ extern crate serde;
 #[macro_use]
 extern crate anyhow;
 
+pub mod backtest;
 pub mod cache;
 pub mod conn;
 pub mod core;
@@ -12,4 +13,5 @@ pub mod ftx;
 pub mod lambda;
 pub mod model;
 pub mod pubsub;
+pub mod sim;
 pub mod view;
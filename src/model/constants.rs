@@ -30,8 +30,21 @@ pub enum PublishChannel {
     OrderFill,
     OrderRequest,
     MarketDepth,
+    Trades,
     CancelOrder,
+    ModifyOrder,
     StrategyStates,
     StrategyParams,
     UpdateParam,
+    UpdateParamResult,
+    ParamSchema,
+    QueryAuditLog,
+    AuditLog,
+    OrderBlotter,
+    Fills,
+    QueryBlotter,
+    KillSwitch,
+    LambdaControl,
+    LambdaControlResult,
+    SupervisorEvent,
 }
@@ -1,6 +1,9 @@
 use crate::cache::OrderUpdateCache;
 use crate::model::constants::{Exchanges, PublishChannel};
-use crate::model::{MeasurementCache, OrderFill, OrderRequest, OrderSide, OrderStatus, OrderType, OrderUpdate};
+use crate::model::{
+    MeasurementCache, ModifyOrderRequest, OrderFill, OrderRequest, OrderSide, OrderStatus,
+    OrderType, OrderUpdate,
+};
 
 use crate::pubsub::PublishPayload;
 
@@ -10,7 +13,10 @@ use crate::pubsub::simple_message_bus::{
 pub use std::str::FromStr;
 use std::sync::Arc;
 
+/// A market traded by one lambda instance. Orders are sent under client ids of `owner`, open
+/// orders, updates and fills of other instances on the same market are not seen.
 pub struct Instrument {
+    pub owner: String,
     pub exchange: Exchanges,
     pub market: String,
     pub order_cache: Arc<OrderUpdateCache>,
@@ -25,7 +31,7 @@ impl FromStr for InstrumentSymbol {
 
     fn from_str(token: &str) -> Result<Self, Self::Err> {
         let split: Vec<&str> = token.split(".").collect();
-        let market = split.get(0).expect("Invalid token.").to_owned();
+        let market = split.first().expect("Invalid token.").to_owned();
         let exchange = split.get(1).expect("Invalid token.").to_owned();
         let exchange = Exchanges::from_str(exchange).expect("Unknown exchange");
         Ok(InstrumentSymbol(exchange, market.to_string()))
@@ -34,6 +40,7 @@ impl FromStr for InstrumentSymbol {
 
 impl Instrument {
     pub fn new(
+        owner: &str,
         exchange: Exchanges,
         market: &str,
         order_cache: Arc<OrderUpdateCache>,
@@ -41,6 +48,7 @@ impl Instrument {
         measurement_cache: Arc<MeasurementCache>,
     ) -> Self {
         Instrument {
+            owner: owner.to_string(),
             exchange,
             market: market.to_string(),
             order_cache,
@@ -51,7 +59,7 @@ impl Instrument {
 
     pub fn instrument_symbol(token: &str) -> InstrumentSymbol {
         let split: Vec<&str> = token.split(".").collect();
-        let market = split.get(0).expect("Invalid token.").to_owned();
+        let market = split.first().expect("Invalid token.").to_owned();
         let exchange = split.get(1).expect("Invalid token.").to_owned();
         let exchange = Exchanges::from_str(exchange).expect("Unknown exchange");
         InstrumentSymbol(exchange, market.to_string())
@@ -60,14 +68,14 @@ impl Instrument {
     pub fn get_open_orders(&self, include_pending_cancels: bool) -> Vec<OrderUpdate> {
         let mut open_orders: Vec<OrderUpdate> = vec![];
         for ou in self.order_cache.cache.iter() {
-            if ou.exchange == self.exchange && ou.market == self.market {
+            if self.is_own_order(&ou) {
                 match ou.status {
                     OrderStatus::New | OrderStatus::Open | OrderStatus::PendingNew => {
-                        open_orders.push(Clone::clone(&ou.value()));
+                        open_orders.push(Clone::clone(ou.value()));
                     }
                     OrderStatus::PendingCancel => {
                         if include_pending_cancels {
-                            open_orders.push(Clone::clone(&ou.value()));
+                            open_orders.push(Clone::clone(ou.value()));
                         }
                     }
                     OrderStatus::Closed | OrderStatus::Failed => {}
@@ -77,12 +85,18 @@ impl Instrument {
         open_orders
     }
 
+    /// an order of this instrument sent by its owner
+    pub fn is_own_order(&self, order_update: &OrderUpdate) -> bool {
+        order_update.exchange == self.exchange
+            && order_update.market == self.market
+            && order_update.is_owned_by(self.owner.as_str())
+    }
+
     pub fn get_open_buy_orders(&self, include_pending_cancels: bool) -> Vec<OrderUpdate> {
         let open_orders = self.get_open_orders(include_pending_cancels);
         open_orders
             .into_iter()
             .filter(|order| order.side == OrderSide::Buy)
-            .map(|order| order.clone())
             .collect()
     }
 
@@ -91,7 +105,6 @@ impl Instrument {
         open_orders
             .into_iter()
             .filter(|order| order.side == OrderSide::Sell)
-            .map(|order| order.clone())
             .collect()
     }
 
@@ -101,6 +114,20 @@ impl Instrument {
         price: f64,
         size: f64,
         type_: OrderType,
+    ) -> anyhow::Result<Option<String>> {
+        self.send_order_request(side, price, size, type_, false, true)
+            .await
+    }
+
+    /// `send_order` for orders taking liquidity, e.g. hedges
+    pub async fn send_order_request(
+        &self,
+        side: OrderSide,
+        price: f64,
+        size: f64,
+        type_: OrderType,
+        ioc: bool,
+        post_only: bool,
     ) -> anyhow::Result<Option<String>> {
         let mut order_request = OrderRequest {
             exchange: self.exchange.clone(),
@@ -108,12 +135,14 @@ impl Instrument {
             side,
             price,
             size,
-            type_: type_,
-            ioc: false,
-            post_only: true,
+            type_,
+            ioc,
+            post_only,
             client_id: None,
         };
-        let client_id = order_request.generate_client_id().clone();
+        let client_id = order_request
+            .generate_client_id(self.owner.as_str())
+            .clone();
         OrderRequest::send_order(
             &self.order_cache.cache,
             &self.message_bus_sender,
@@ -128,6 +157,40 @@ impl Instrument {
         Ok(client_id)
     }
 
+    /// Modify price and size of an open order in a single request instead of cancel + new.
+    /// Returns the client id of the replacement order, or None if the order is not modifiable.
+    pub async fn modify_order(
+        &self,
+        client_id: &str,
+        price: f64,
+        size: f64,
+    ) -> anyhow::Result<Option<String>> {
+        let side = match self.order_cache.cache.get(client_id) {
+            None => return Ok(None),
+            Some(order_update) => order_update.side.clone(),
+        };
+        let new_client_id =
+            OrderRequest::new_client_id(self.owner.as_str(), &self.exchange, &self.market, &side);
+        let modify_order_request = ModifyOrderRequest {
+            exchange: self.exchange.clone(),
+            market: self.market.clone(),
+            client_id: client_id.to_string(),
+            new_client_id: new_client_id.clone(),
+            price,
+            size,
+        };
+        let accepted = OrderRequest::modify_order(
+            &self.order_cache.cache,
+            &self.message_bus_sender,
+            modify_order_request,
+        )
+        .await?;
+        if !accepted {
+            return Ok(None);
+        }
+        Ok(Some(new_client_id))
+    }
+
     pub async fn cancel_order(&self, client_id: &str) -> anyhow::Result<()> {
         OrderRequest::cancel_order(
             &self.order_cache.cache,
@@ -142,8 +205,7 @@ impl Instrument {
     where
         T: TypedMessageConsumer<OrderFill> + Sync,
     {
-        let order_fill_filter =
-            OrderFillFilter(self.exchange.to_owned(), self.market.to_owned(), consumer);
+        let order_fill_filter = OrderFillFilter(self, consumer);
         RedisBackedMessageBus::subscribe_channels(
             vec![PublishChannel::OrderFill.as_ref()],
             &order_fill_filter,
@@ -155,8 +217,7 @@ impl Instrument {
     where
         T: TypedMessageConsumer<OrderUpdate> + Sync,
     {
-        let order_update_filter =
-            OrderUpdateFilter(self.exchange.to_owned(), self.market.to_owned(), consumer);
+        let order_update_filter = OrderUpdateFilter(self, consumer);
         RedisBackedMessageBus::subscribe_channels(
             vec![PublishChannel::OrderUpdate.as_ref()],
             &order_update_filter,
@@ -165,7 +226,7 @@ impl Instrument {
     }
 }
 
-pub struct OrderFillFilter<'r, ResultConsumer>(Exchanges, String, &'r ResultConsumer);
+pub struct OrderFillFilter<'r, ResultConsumer>(&'r Instrument, &'r ResultConsumer);
 
 #[async_trait::async_trait]
 impl<'r, ResultConsumer> MessageConsumer for OrderFillFilter<'r, ResultConsumer>
@@ -174,15 +235,19 @@ where
 {
     async fn consume(&self, msg: &[u8]) -> anyhow::Result<()> {
         let order_fill: OrderFill = serde_json::from_slice(msg)?;
-        if order_fill.exchange == self.0 && order_fill.market == self.1 {
-            self.2.consume(order_fill).await
+        let instrument = self.0;
+        if order_fill.exchange == instrument.exchange
+            && order_fill.market == instrument.market
+            && order_fill.is_owned_by(instrument.owner.as_str())
+        {
+            self.1.consume(order_fill).await
         } else {
             Ok(())
         }
     }
 }
 
-pub struct OrderUpdateFilter<'r, ResultConsumer>(Exchanges, String, &'r ResultConsumer);
+pub struct OrderUpdateFilter<'r, ResultConsumer>(&'r Instrument, &'r ResultConsumer);
 
 #[async_trait::async_trait]
 impl<'r, ResultConsumer> MessageConsumer for OrderUpdateFilter<'r, ResultConsumer>
@@ -191,8 +256,8 @@ where
 {
     async fn consume(&self, msg: &[u8]) -> anyhow::Result<()> {
         let order_update: OrderUpdate = serde_json::from_slice(msg)?;
-        if order_update.exchange == self.0 && order_update.market == self.1 {
-            self.2.consume(order_update).await
+        if self.0.is_own_order(&order_update) {
+            self.1.consume(order_update).await
         } else {
             Ok(())
         }
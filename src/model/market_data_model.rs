@@ -1,4 +1,5 @@
-use crate::model::constants::Exchanges;
+use crate::model::constants::{Exchanges, PublishChannel};
+use crate::model::OrderSide;
 use serde::{Deserialize, Serialize};
 use std::hash::{Hash, Hasher};
 pub use std::str::FromStr;
@@ -46,3 +47,21 @@ pub struct MarketDepth {
     pub bids: Vec<PriceLevel>,
     pub asks: Vec<PriceLevel>,
 }
+
+/// a trade printed on the exchange, `side` is the side of the taker
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Trade {
+    pub timestamp: i64,
+    pub exchange: Exchanges,
+    pub market: String,
+    pub side: OrderSide,
+    pub price: f64,
+    pub size: f64,
+}
+
+impl Trade {
+    /// channel the trades of `market` on `exchange` are published on
+    pub fn channel(exchange: &Exchanges, market: &str) -> String {
+        format!("{}:{}:{}", PublishChannel::Trades, exchange, market)
+    }
+}
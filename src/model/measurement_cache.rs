@@ -1,18 +1,15 @@
 use std::sync::Arc;
+use crate::core::clock::{SharedClock, WallClock};
 use crate::core::config::ConfigStore;
+use crate::core::in_flight::InFlight;
 use crate::model::constants::Exchanges;
 use crate::model::OrderSide;
 use redis::AsyncCommands;
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
 pub struct TSOptions {
     pub retention: i64,
 }
-impl Default for TSOptions {
-    fn default() -> Self {
-        TSOptions { retention: 0 }
-    }
-}
 
 #[derive(Serialize, Deserialize, Debug, strum_macros::Display, Clone)]
 pub enum Measurement {
@@ -27,7 +24,14 @@ pub enum Measurement {
     },
     ToAck {
         options: TSOptions,
-    }
+    },
+    RateLimitSaturation {
+        options: TSOptions,
+    },
+    /// average hedge fill price against the touch when the hedge was sent, in bp
+    HedgeSlippage {
+        options: TSOptions,
+    },
 }
 
 trait FillRedisArgs {
@@ -57,7 +61,7 @@ impl FillRedisArgs for Measurement {
                     "side".to_string(),
                     side.to_string(),
                 ];
-                vec![options_args, args].concat()
+                [options_args, args].concat()
             },
             Measurement::OrderLatency { options } => {
                 options.redis_args()
@@ -65,6 +69,12 @@ impl FillRedisArgs for Measurement {
             Measurement::ToAck { options } => {
                 options.redis_args()
             }
+            Measurement::RateLimitSaturation { options } => {
+                options.redis_args()
+            }
+            Measurement::HedgeSlippage { options } => {
+                options.redis_args()
+            }
         }
     }
 }
@@ -75,13 +85,21 @@ pub struct TimerStamp {
 }
 
 pub struct MeasurementCache {
-    client: redis::Client,
-    shared_conn: redis::aio::MultiplexedConnection,
+    /// None for a cache kept in memory only, e.g. in a backtest
+    shared_conn: Option<redis::aio::MultiplexedConnection>,
     timer_cache: Arc<dashmap::DashMap<String, TimerStamp>>,
+    /// time and value of the last point of each measurement
+    latest_points: Arc<dashmap::DashMap<String, (i64, f64)>>,
+    in_flight: InFlight,
+    clock: SharedClock,
 }
 
 impl MeasurementCache {
     pub async fn new() -> Self {
+        Self::with_clock(WallClock::shared()).await
+    }
+
+    pub async fn with_clock(clock: SharedClock) -> Self {
         let config = ConfigStore::load();
         let redis = redis::Client::open(config.redis_ts_url).expect("Failed to connect redis_ts");
         let conn = redis
@@ -89,14 +107,30 @@ impl MeasurementCache {
             .await
             .expect("redis_ts: Failed to get multiplexed connection");
         MeasurementCache {
-            client: redis,
-            shared_conn: conn,
+            shared_conn: Some(conn),
             timer_cache: Arc::new(dashmap::DashMap::new()),
+            latest_points: Arc::new(dashmap::DashMap::new()),
+            in_flight: InFlight::new(),
+            clock,
+        }
+    }
+
+    /// timers and latest points without writing to redis_ts
+    pub fn in_memory(clock: SharedClock) -> Self {
+        MeasurementCache {
+            shared_conn: None,
+            timer_cache: Arc::new(dashmap::DashMap::new()),
+            latest_points: Arc::new(dashmap::DashMap::new()),
+            in_flight: InFlight::new(),
+            clock,
         }
     }
 
     pub async fn measurement(&self, measurement: &Measurement) -> &Self {
-        let mut conn = self.shared_conn.clone();
+        let mut conn = match self.shared_conn {
+            None => return self,
+            Some(ref conn) => conn.clone(),
+        };
         let measurement_name = measurement.to_string();
         match conn
             .keys::<&str, Vec<redis::Value>>(&measurement_name)
@@ -114,11 +148,14 @@ impl MeasurementCache {
                         .map(AsRef::as_ref)
                         .collect::<Vec<&str>>();
                     log::info!("{:?}", args);
-                    redis::cmd("TS.CREATE")
+                    if let Err(err) = redis::cmd("TS.CREATE")
                         .arg(measurement_name.as_str())
                         .arg(&args)
                         .query_async::<redis::aio::MultiplexedConnection, redis::Value>(&mut conn)
-                        .await;
+                        .await
+                    {
+                        error!("TS.CREATE {}: {}", measurement_name, err);
+                    }
                 }
                 false => {
                     info!("measurement {:?} exists. altering...", measurement_name);
@@ -127,11 +164,14 @@ impl MeasurementCache {
                         .iter()
                         .map(AsRef::as_ref)
                         .collect::<Vec<&str>>();
-                    redis::cmd("TS.ALTER")
+                    if let Err(err) = redis::cmd("TS.ALTER")
                         .arg(measurement_name.as_str())
                         .arg(&args)
                         .query_async::<redis::aio::MultiplexedConnection, redis::Value>(&mut conn)
-                        .await;
+                        .await
+                    {
+                        error!("TS.ALTER {}: {}", measurement_name, err);
+                    }
                 }
             },
             Err(err) => error!("{}", err),
@@ -141,7 +181,10 @@ impl MeasurementCache {
 
     #[deprecated]
     pub async fn add_point(&self, measurement: &Measurement, time_ms: i64, point: f64) {
-        let mut conn = self.shared_conn.clone();
+        let mut conn = match self.shared_conn {
+            None => return,
+            Some(ref conn) => conn.clone(),
+        };
         let measurement = measurement.clone();
         let result = redis::cmd("ts.add")
             .arg(measurement.to_string())
@@ -156,9 +199,16 @@ impl MeasurementCache {
     }
 
     pub fn add_point_now(&self, measurement: &'static Measurement, point: f64) {
-        let mut conn = self.shared_conn.clone();
-        let time_now = chrono::Utc::now().timestamp_millis();
+        let time_now = self.time_now();
+        self.latest_points
+            .insert(measurement.to_string(), (time_now, point));
+        let mut conn = match self.shared_conn {
+            None => return,
+            Some(ref conn) => conn.clone(),
+        };
+        let in_flight = self.in_flight.start();
         tokio::spawn(async move {
+            let _in_flight = in_flight;
             let result = redis::cmd("ts.add")
                 .arg(measurement.to_string())
                 .arg(time_now)
@@ -172,8 +222,26 @@ impl MeasurementCache {
         });
     }
 
-    fn time_now() -> i64 {
-        chrono::Utc::now().timestamp_millis()
+    /// wait for the points of `add_point_now` to be written, returns the number still unwritten
+    pub async fn flush(&self, timeout: std::time::Duration) -> usize {
+        self.in_flight.wait(timeout).await
+    }
+
+    /// last point added through `add_point_now` in this process, None when it is older than
+    /// `max_age_ms`
+    pub fn get_latest_point(&self, measurement: &Measurement, max_age_ms: i64) -> Option<f64> {
+        let (time_ms, point) = *self
+            .latest_points
+            .get(measurement.to_string().as_str())?
+            .value();
+        if self.time_now() - time_ms > max_age_ms {
+            return None;
+        }
+        Some(point)
+    }
+
+    fn time_now(&self) -> i64 {
+        self.clock.now_ms()
     }
 
     pub fn time_start(&self, event: &str) {
@@ -181,13 +249,13 @@ impl MeasurementCache {
         match self.timer_cache.get_mut(event) {
             None => {
                 let timer_stamp = TimerStamp {
-                    start: MeasurementCache::time_now(),
+                    start: self.time_now(),
                     end: None,
                 };
                 self.timer_cache.insert(event.to_string(), timer_stamp);
             }
             Some(mut timer_stamp) => {
-                timer_stamp.start = MeasurementCache::time_now();
+                timer_stamp.start = self.time_now();
             }
         };
     }
@@ -200,7 +268,7 @@ impl MeasurementCache {
                 None
             }
             Some(mut timer_stamp) => {
-                let end = MeasurementCache::time_now();
+                let end = self.time_now();
                 timer_stamp.end = Some(end);
                 Some(end - timer_stamp.start)
             }
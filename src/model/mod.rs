@@ -3,17 +3,21 @@ mod instrument;
 pub mod market_data_model;
 mod measurement_cache;
 mod order_data_model;
+mod queue_position;
 
 pub use instrument::{Instrument, InstrumentSymbol, OrderFillFilter};
 pub use measurement_cache::*;
 pub use order_data_model::{
-    CancelOrderRequest, OrderFill, OrderRequest, OrderSide, OrderStatus, OrderType, OrderUpdate,
-    OrderUpdateCacheInner,
+    CancelOrderRequest, ModifyOrderRequest, OrderFill, OrderRequest, OrderSide, OrderStatus,
+    OrderType, OrderUpdate, OrderUpdateCacheInner,
 };
+pub use queue_position::{level_size, QueuePosition};
 
 pub mod global_measurement {
     use crate::model::{Measurement, TSOptions};
 
     pub static ORDER_LATENCY: Measurement = Measurement::OrderLatency { options: TSOptions { retention: 0 } };
     pub static TO_ACK: Measurement = Measurement::ToAck { options: TSOptions { retention: 0 }};
+    pub static RATE_LIMIT_SATURATION: Measurement = Measurement::RateLimitSaturation { options: TSOptions { retention: 0 }};
+    pub static HEDGE_SLIPPAGE: Measurement = Measurement::HedgeSlippage { options: TSOptions { retention: 0 }};
 }
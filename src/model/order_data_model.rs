@@ -3,6 +3,7 @@ use crate::model::constants::{Exchanges, PublishChannel};
 use dashmap::DashMap;
 use serde::{Deserialize, Serialize};
 
+use crate::cache::OrderUpdateCache;
 use crate::pubsub::simple_message_bus::RedisBackedMessageBus;
 use crate::pubsub::PublishPayload;
 use uuid::Uuid;
@@ -64,14 +65,15 @@ impl OrderUpdate {
             None => {
                 panic!("Error getting OrderUpdate Key");
             }
-            Some(cid) => return Clone::clone(cid),
+            Some(cid) => Clone::clone(cid),
         }
     }
     pub fn has_cache_key(&self) -> bool {
-        match self.client_id {
-            None => false,
-            Some(_) => true,
-        }
+        self.client_id.is_some()
+    }
+    /// whether the order was sent by the lambda instance `owner`
+    pub fn is_owned_by(&self, owner: &str) -> bool {
+        OrderRequest::is_owned_by(self.client_id.as_deref(), owner)
     }
 }
 impl Default for OrderUpdate {
@@ -96,7 +98,7 @@ impl Default for OrderUpdate {
     }
 }
 
-#[derive(Deserialize, Serialize, Debug)]
+#[derive(Deserialize, Serialize, Debug, Clone)]
 #[allow(non_camel_case_types, non_snake_case)]
 pub struct OrderFill {
     pub exchange: Exchanges,
@@ -113,6 +115,15 @@ pub struct OrderFill {
     pub time: String,
     #[serde(rename = "type")]
     pub type_: String,
+    /// client id of the filled order, None when the gateway could not resolve it
+    #[serde(default)]
+    pub client_id: Option<String>,
+}
+impl OrderFill {
+    /// whether the filled order was sent by the lambda instance `owner`
+    pub fn is_owned_by(&self, owner: &str) -> bool {
+        OrderRequest::is_owned_by(self.client_id.as_deref(), owner)
+    }
 }
 impl Default for OrderFill {
     fn default() -> Self {
@@ -130,6 +141,7 @@ impl Default for OrderFill {
             size: 0.0,
             time: "".to_string(),
             type_: OrderType::Limit.to_string(),
+            client_id: None,
         }
     }
 }
@@ -147,15 +159,41 @@ pub struct OrderRequest {
     pub client_id: Option<String>,
 }
 impl OrderRequest {
-    pub fn generate_client_id(&mut self) -> &Option<String> {
-        self.client_id = Option::from(format!(
-            "{}:{}:{}:{}",
-            &self.exchange.to_string(),
+    pub fn generate_client_id(&mut self, owner: &str) -> &Option<String> {
+        self.client_id = Option::from(Self::new_client_id(
+            owner,
+            &self.exchange,
             &self.market,
-            &self.side.to_string(),
-            Uuid::new_v4().to_string(),
+            &self.side,
         ));
-        return &self.client_id;
+        &self.client_id
+    }
+
+    /// Client ids start with the name of the lambda instance sending the order, so lambdas
+    /// trading the same market tell their orders and fills apart.
+    pub fn new_client_id(
+        owner: &str,
+        exchange: &Exchanges,
+        market: &str,
+        side: &OrderSide,
+    ) -> String {
+        format!(
+            "{}:{}:{}:{}:{}",
+            owner,
+            exchange,
+            market,
+            side,
+            Uuid::new_v4(),
+        )
+    }
+
+    /// lambda instance a client id was generated for, None for orders sent elsewhere
+    pub fn client_id_owner(client_id: &str) -> Option<&str> {
+        client_id.find(':').map(|end| &client_id[..end])
+    }
+
+    pub fn is_owned_by(client_id: Option<&str>, owner: &str) -> bool {
+        client_id.and_then(Self::client_id_owner) == Some(owner)
     }
 }
 
@@ -166,6 +204,18 @@ pub struct CancelOrderRequest {
     pub client_id: String,
 }
 
+/// Replace price and size of an open order. The exchange closes `client_id` and opens the
+/// replacement order under `new_client_id`.
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct ModifyOrderRequest {
+    pub exchange: Exchanges,
+    pub market: String,
+    pub client_id: String,
+    pub new_client_id: String,
+    pub price: f64,
+    pub size: f64,
+}
+
 impl OrderRequest {
     pub async fn send_order(
         order_update_cache: &OrderUpdateCacheInner,
@@ -179,7 +229,7 @@ impl OrderRequest {
         let pending_order_update = OrderUpdate {
             exchange: order_request.exchange.clone(),
             id: -1,
-            client_id: Option::from(order_request.client_id),
+            client_id: order_request.client_id,
             market: order_request.market,
             type_: order_request.type_,
             side: order_request.side,
@@ -197,6 +247,21 @@ impl OrderRequest {
         message_bus_sender.send(payload).await?;
         Ok(())
     }
+    pub async fn modify_order(
+        order_update_cache: &OrderUpdateCacheInner,
+        message_bus_sender: &tokio::sync::mpsc::Sender<PublishPayload>,
+        modify_order_request: ModifyOrderRequest,
+    ) -> anyhow::Result<bool> {
+        if !OrderUpdateCache::accept_modify_order(order_update_cache, &modify_order_request) {
+            return Ok(false);
+        }
+        let payload = PublishPayload {
+            channel: PublishChannel::ModifyOrder.to_string(),
+            payload: RedisBackedMessageBus::pack_json(&modify_order_request)?,
+        };
+        message_bus_sender.send(payload).await?;
+        Ok(true)
+    }
     pub async fn cancel_order(
         order_update_cache: &OrderUpdateCacheInner,
         message_bus_sender: &tokio::sync::mpsc::Sender<PublishPayload>,
@@ -209,7 +274,7 @@ impl OrderRequest {
                     order_update.status = OrderStatus::PendingCancel
                 }
                 ref status => {
-                    debug!("order status {}. skipping cancel_order", status.to_string());
+                    debug!("order status {}. skipping cancel_order", status);
                     return Ok(())
                 }
             }
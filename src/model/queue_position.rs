@@ -0,0 +1,46 @@
+use crate::model::market_data_model::MarketDepth;
+use crate::model::OrderSide;
+
+/// size resting at `price` on the `side` of the book
+pub fn level_size(market_depth: &MarketDepth, side: &OrderSide, price: f64) -> f64 {
+    let levels = match side {
+        OrderSide::Buy => &market_depth.bids,
+        OrderSide::Sell => &market_depth.asks,
+    };
+    levels
+        .iter()
+        .find(|level| level.price == price)
+        .map(|level| level.size)
+        .unwrap_or(0.0)
+}
+
+/// Estimated size queued ahead of a resting order. The depth feed has no trades, so a size
+/// decrease at our level is assumed to come from the front of the queue.
+#[derive(Debug, Clone)]
+pub struct QueuePosition {
+    pub queue_ahead: f64,
+    level_size: f64,
+}
+
+impl QueuePosition {
+    /// `own_size` is the part of `level_size` that is our own order, zero while the book does not
+    /// show it, e.g. in the simulated exchange
+    pub fn new(level_size: f64, own_size: f64) -> Self {
+        QueuePosition {
+            queue_ahead: (level_size - own_size).max(0.0),
+            level_size,
+        }
+    }
+
+    /// Move up the queue by the decrease of our level. Returns the part of the decrease beyond the
+    /// size that was ahead of us, which reached our order.
+    pub fn on_level_size(&mut self, level_size: f64, own_size: f64) -> f64 {
+        let decrease = (self.level_size - level_size).max(0.0);
+        let traded = (decrease - self.queue_ahead).max(0.0);
+        self.queue_ahead = (self.queue_ahead - decrease)
+            .max(0.0)
+            .min((level_size - own_size).max(0.0));
+        self.level_size = level_size;
+        traded
+    }
+}
@@ -1,6 +1,7 @@
 use crate::model::constants::Exchanges;
 use async_trait::async_trait;
 
+pub mod local_message_bus;
 pub mod simple_message_bus;
 pub use std::str::FromStr;
 
@@ -39,11 +40,11 @@ impl SubscribeMarketDepthRequest {
     }
     pub fn from_token(token: &str) -> SubscribeMarketDepthRequest {
         let splitted: Vec<&str> = token.split(".").collect();
-        let market = splitted.get(0).expect("Invalid token.").to_owned();
+        let market = splitted.first().expect("Invalid token.").to_owned();
         let exchange = splitted.get(1).expect("Invalid token.").to_owned();
         let exchange = Exchanges::from_str(exchange).expect("Unknown exchange");
         SubscribeMarketDepthRequest {
-            exchange: exchange,
+            exchange,
             market: market.to_string(),
         }
     }
@@ -0,0 +1,64 @@
+use crate::pubsub::simple_message_bus::MessageConsumer;
+use dashmap::DashMap;
+use std::future::Future;
+use std::sync::Arc;
+use tokio::sync::mpsc::{unbounded_channel, UnboundedSender};
+
+tokio::task_local! {
+    static LOCAL_MESSAGE_BUS: Arc<LocalMessageBus>;
+}
+
+/// In-process replacement for redis pubsub. Within `scope`, `RedisBackedMessageBus::subscribe_channels`
+/// subscribes here instead of redis, so a whole engine can run inside one process, e.g. a backtest.
+/// Messages are delivered to every subscriber in publish order.
+pub struct LocalMessageBus {
+    subscribers: DashMap<String, Vec<UnboundedSender<Vec<u8>>>>,
+}
+
+impl LocalMessageBus {
+    pub fn new() -> Arc<LocalMessageBus> {
+        Arc::new(LocalMessageBus {
+            subscribers: DashMap::new(),
+        })
+    }
+
+    /// Run `future` with `bus` as the message bus of its subscriptions. Only subscriptions polled
+    /// by `future` itself are redirected, tasks it spawns and everything outside keep using redis.
+    pub async fn scope<F: Future>(bus: Arc<LocalMessageBus>, future: F) -> F::Output {
+        LOCAL_MESSAGE_BUS.scope(bus, future).await
+    }
+
+    /// bus of the enclosing `scope`, None outside of one
+    pub fn current() -> Option<Arc<LocalMessageBus>> {
+        LOCAL_MESSAGE_BUS.try_with(|bus| bus.clone()).ok()
+    }
+
+    pub fn publish(&self, channel: &str, payload: &[u8]) {
+        if let Some(mut subscribers) = self.subscribers.get_mut(channel) {
+            subscribers.retain(|subscriber| subscriber.send(payload.to_vec()).is_ok());
+        }
+    }
+
+    pub async fn subscribe_channels<T>(
+        &self,
+        channels: Vec<&str>,
+        consumer: &T,
+    ) -> anyhow::Result<()>
+    where
+        T: MessageConsumer,
+    {
+        let (tx, mut rx) = unbounded_channel::<Vec<u8>>();
+        for channel in channels {
+            log::info!("subscribing local channel {}", channel);
+            self.subscribers
+                .entry(channel.to_string())
+                .or_default()
+                .push(tx.clone());
+        }
+        drop(tx);
+        while let Some(msg) = rx.recv().await {
+            consumer.consume(msg.as_slice()).await?;
+        }
+        Err(anyhow!("local subscribe_channels uncaught error"))
+    }
+}
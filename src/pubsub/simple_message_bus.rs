@@ -1,4 +1,5 @@
 use crate::core::config::ConfigStore;
+use crate::pubsub::local_message_bus::LocalMessageBus;
 use crate::pubsub::{MessageBus, PublishPayload};
 use async_trait::async_trait;
 use futures_util::StreamExt;
@@ -35,12 +36,6 @@ impl RedisBackedMessageBus {
         Ok(instance)
     }
 
-    fn pack_value<T: Serialize>(value: &T) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
-        let mut buf = Vec::new();
-        value.serialize(&mut serde_json::Serializer::new(&mut buf))?;
-        Ok(buf)
-    }
-
     pub fn pack_json<T: Serialize>(value: &T) -> anyhow::Result<String> {
         Ok(serde_json::to_string(&value)?)
     }
@@ -48,7 +43,7 @@ impl RedisBackedMessageBus {
     pub async fn publish<T: Serialize>(&self, channel: &str, message: &T) -> anyhow::Result<()> {
         let packed = Self::pack_json(message)?;
         let mut conn = self.publish_conn.clone();
-        conn.publish::<&str, &str, i32>(channel.clone(), packed.as_str())
+        conn.publish::<&str, &str, i32>(channel, packed.as_str())
             .await?;
         Ok(())
     }
@@ -78,6 +73,9 @@ impl RedisBackedMessageBus {
     where
         T: MessageConsumer,
     {
+        if let Some(local_message_bus) = LocalMessageBus::current() {
+            return local_message_bus.subscribe_channels(channels, consumer).await;
+        }
         let cfg = ConfigStore::load();
         let redis_client = redis::Client::open(cfg.redis_url)?;
         let conn = redis_client.get_async_connection().await?;
@@ -101,8 +99,12 @@ impl RedisBackedMessageBus {
         let mut conn = self.publish_conn.clone();
         while let Some(msg) = rx.recv().await {
             // let time_start = chrono::Utc::now().timestamp_nanos();
-            conn.publish::<&str, &str, i32>(msg.channel.as_str(), msg.payload.as_str())
-                .await;
+            if let Err(err) = conn
+                .publish::<&str, &str, i32>(msg.channel.as_str(), msg.payload.as_str())
+                .await
+            {
+                log::error!("publish on {} failed: {}", msg.channel, err);
+            }
             // let time_end = chrono::Utc::now().timestamp_nanos();
             // log::info!("INFO: {}ms", (time_end - time_start) as f64 * 0.000001);
         }
@@ -0,0 +1,367 @@
+use crate::model::constants::Exchanges;
+use crate::model::market_data_model::{MarketDepth, PriceLevel};
+use crate::model::{
+    level_size, CancelOrderRequest, ModifyOrderRequest, OrderFill, OrderRequest, OrderSide,
+    OrderStatus, OrderType, OrderUpdate, QueuePosition,
+};
+use crate::sim::SimParams;
+use chrono::{TimeZone, Utc};
+use std::collections::HashMap;
+
+const SIZE_EPSILON: f64 = 1e-9;
+
+/// Order events emitted by the simulated exchange, published like the FTX gateway does
+#[derive(Debug, Clone)]
+pub enum SimEvent {
+    OrderUpdate(OrderUpdate),
+    OrderFill(OrderFill),
+}
+
+struct RestingOrder {
+    order: OrderUpdate,
+    /// the depth feed does not show our order, so none of the level is our own
+    queue: QueuePosition,
+}
+
+/// Local matching engine for `Exchanges::SIM`.
+///
+/// Marketable orders take liquidity from the last seen `MarketDepth` as taker. Resting orders
+/// join the back of their price level and move up its `QueuePosition`, which fills us with the
+/// decrease beyond the queue while we are at the touch. Orders are filled completely as maker
+/// once the opposite side trades through their price.
+pub struct MatchingEngine {
+    maker_fee_rate: f64,
+    taker_fee_rate: f64,
+    depths: HashMap<String, MarketDepth>,
+    orders: HashMap<String, RestingOrder>,
+    next_order_id: i64,
+    next_fill_id: i64,
+}
+
+impl MatchingEngine {
+    pub fn new(params: &SimParams) -> Self {
+        MatchingEngine {
+            maker_fee_rate: params.maker_fee_rate,
+            taker_fee_rate: params.taker_fee_rate,
+            depths: HashMap::new(),
+            orders: HashMap::new(),
+            next_order_id: 1,
+            next_fill_id: 1,
+        }
+    }
+
+    pub fn get_open_orders(&self) -> Vec<OrderUpdate> {
+        self.orders
+            .values()
+            .map(|resting| resting.order.clone())
+            .collect()
+    }
+
+    pub fn get_queue_ahead(&self, client_id: &str) -> Option<f64> {
+        self.orders
+            .get(client_id)
+            .map(|resting| resting.queue.queue_ahead)
+    }
+
+    fn same_side<'a>(depth: &'a MarketDepth, side: &OrderSide) -> &'a Vec<PriceLevel> {
+        match side {
+            OrderSide::Buy => &depth.bids,
+            OrderSide::Sell => &depth.asks,
+        }
+    }
+
+    fn opposite_side<'a>(depth: &'a MarketDepth, side: &OrderSide) -> &'a Vec<PriceLevel> {
+        match side {
+            OrderSide::Buy => &depth.asks,
+            OrderSide::Sell => &depth.bids,
+        }
+    }
+
+    /// whether a `side` order at `price` would take liquidity from `level`
+    fn crosses(side: &OrderSide, price: f64, level: &PriceLevel) -> bool {
+        match side {
+            OrderSide::Buy => level.price <= price,
+            OrderSide::Sell => level.price >= price,
+        }
+    }
+
+    /// whether an order at `price` is at or better than the best price of its own side
+    fn at_touch(depth: &MarketDepth, side: &OrderSide, price: f64) -> bool {
+        match Self::same_side(depth, side).first() {
+            None => true,
+            Some(best) => match side {
+                OrderSide::Buy => price >= best.price,
+                OrderSide::Sell => price <= best.price,
+            },
+        }
+    }
+
+    fn fill(
+        &mut self,
+        order: &mut OrderUpdate,
+        price: f64,
+        size: f64,
+        taker: bool,
+        now: i64,
+    ) -> OrderFill {
+        let filled_value = order.avgFillPrice.unwrap_or(0.0) * order.filledSize + price * size;
+        order.filledSize += size;
+        order.remainingSize = (order.size - order.filledSize).max(0.0);
+        order.avgFillPrice = Some(filled_value / order.filledSize);
+        let fee_rate = if taker {
+            self.taker_fee_rate
+        } else {
+            self.maker_fee_rate
+        };
+        let fill = OrderFill {
+            exchange: Exchanges::SIM,
+            fee: price * size * fee_rate,
+            fee_rate,
+            id: self.next_fill_id,
+            liquidity: if taker { "taker" } else { "maker" }.to_string(),
+            market: order.market.clone(),
+            orderId: order.id,
+            tradeId: self.next_fill_id,
+            price,
+            side: order.side.clone(),
+            size,
+            time: Utc
+                .timestamp_millis_opt(now)
+                .single()
+                .map(|time| time.to_rfc3339())
+                .unwrap_or_default(),
+            type_: "order".to_string(),
+            client_id: order.client_id.clone(),
+        };
+        self.next_fill_id += 1;
+        fill
+    }
+
+    /// `Failed` update of an order the exchange did not accept
+    pub fn failed(order_request: &OrderRequest) -> SimEvent {
+        SimEvent::OrderUpdate(OrderUpdate {
+            exchange: Exchanges::SIM,
+            id: -1,
+            client_id: order_request.client_id.clone(),
+            market: order_request.market.clone(),
+            type_: order_request.type_.clone(),
+            side: order_request.side.clone(),
+            size: order_request.size,
+            price: order_request.price,
+            reduceOnly: false,
+            ioc: order_request.ioc,
+            postOnly: order_request.post_only,
+            status: OrderStatus::Failed,
+            filledSize: 0.0,
+            remainingSize: 0.0,
+            avgFillPrice: None,
+        })
+    }
+
+    pub fn place_order(&mut self, order_request: &OrderRequest, now: i64) -> Vec<SimEvent> {
+        if order_request.client_id.is_none() || order_request.size <= 0.0 {
+            return vec![Self::failed(order_request)];
+        }
+        let mut depth = match self.depths.remove(order_request.market.as_str()) {
+            None => {
+                warn!("sim has no market depth for {}", order_request.market);
+                return vec![Self::failed(order_request)];
+            }
+            Some(depth) => depth,
+        };
+        let events = self.match_order(order_request, &mut depth, now);
+        self.depths.insert(order_request.market.clone(), depth);
+        events
+    }
+
+    fn match_order(
+        &mut self,
+        order_request: &OrderRequest,
+        depth: &mut MarketDepth,
+        now: i64,
+    ) -> Vec<SimEvent> {
+        let side = &order_request.side;
+        let is_market = order_request.type_ == OrderType::Market;
+        let marketable = match Self::opposite_side(depth, side).first() {
+            None => false,
+            Some(best) => is_market || Self::crosses(side, order_request.price, best),
+        };
+        if marketable && order_request.post_only && !is_market {
+            // FTX rejects post only orders that would cross
+            return vec![Self::failed(order_request)];
+        }
+
+        let mut order = OrderUpdate {
+            exchange: Exchanges::SIM,
+            id: self.next_order_id,
+            client_id: order_request.client_id.clone(),
+            market: order_request.market.clone(),
+            type_: order_request.type_.clone(),
+            side: side.clone(),
+            size: order_request.size,
+            price: order_request.price,
+            reduceOnly: false,
+            ioc: order_request.ioc,
+            postOnly: order_request.post_only,
+            status: OrderStatus::New,
+            filledSize: 0.0,
+            remainingSize: order_request.size,
+            avgFillPrice: None,
+        };
+        self.next_order_id += 1;
+        let mut events = vec![SimEvent::OrderUpdate(order.clone())];
+
+        if marketable {
+            // take liquidity, consuming it from our copy of the book until the next depth update
+            let levels = match side {
+                OrderSide::Buy => &mut depth.asks,
+                OrderSide::Sell => &mut depth.bids,
+            };
+            let mut taken = vec![];
+            for level in levels.iter_mut() {
+                if order.remainingSize <= SIZE_EPSILON {
+                    break;
+                }
+                if !is_market && !Self::crosses(side, order_request.price, level) {
+                    break;
+                }
+                let size = order.remainingSize.min(level.size);
+                level.size -= size;
+                order.remainingSize -= size;
+                taken.push((level.price, size));
+            }
+            levels.retain(|level| level.size > SIZE_EPSILON);
+            order.remainingSize = order_request.size;
+            for (price, size) in taken {
+                let fill = self.fill(&mut order, price, size, true, now);
+                events.push(SimEvent::OrderFill(fill));
+            }
+        }
+
+        if order.remainingSize <= SIZE_EPSILON || is_market || order_request.ioc {
+            order.status = OrderStatus::Closed;
+            events.push(SimEvent::OrderUpdate(order));
+            return events;
+        }
+
+        order.status = OrderStatus::Open;
+        events.push(SimEvent::OrderUpdate(order.clone()));
+        let queue = QueuePosition::new(level_size(depth, side, order.price), 0.0);
+        self.orders
+            .insert(order.cache_key(), RestingOrder { order, queue });
+        events
+    }
+
+    pub fn cancel_order(&mut self, cancel_order_request: &CancelOrderRequest) -> Vec<SimEvent> {
+        let order = match self.orders.remove(cancel_order_request.client_id.as_str()) {
+            Some(resting) => resting.order,
+            None => {
+                // unknown or already closed, let the OrderUpdateCache drop the PendingCancel entry
+                OrderUpdate {
+                    exchange: Exchanges::SIM,
+                    client_id: Some(cancel_order_request.client_id.clone()),
+                    market: cancel_order_request.market.clone(),
+                    ..Default::default()
+                }
+            }
+        };
+        let mut order = order;
+        order.status = OrderStatus::Closed;
+        vec![SimEvent::OrderUpdate(order)]
+    }
+
+    fn failed_modify(modify_order_request: &ModifyOrderRequest) -> SimEvent {
+        SimEvent::OrderUpdate(OrderUpdate {
+            exchange: Exchanges::SIM,
+            id: -1,
+            client_id: Some(modify_order_request.new_client_id.clone()),
+            market: modify_order_request.market.clone(),
+            price: modify_order_request.price,
+            size: modify_order_request.size,
+            status: OrderStatus::Failed,
+            ..Default::default()
+        })
+    }
+
+    /// fail the replacement and cancel the original, as a gateway refusing a modify does
+    pub fn reject_modify_order(
+        &mut self,
+        modify_order_request: &ModifyOrderRequest,
+    ) -> Vec<SimEvent> {
+        let mut events = vec![Self::failed_modify(modify_order_request)];
+        events.append(&mut self.cancel_order(&CancelOrderRequest {
+            exchange: Exchanges::SIM,
+            market: modify_order_request.market.clone(),
+            client_id: modify_order_request.client_id.clone(),
+        }));
+        events
+    }
+
+    pub fn modify_order(
+        &mut self,
+        modify_order_request: &ModifyOrderRequest,
+        now: i64,
+    ) -> Vec<SimEvent> {
+        let mut original = match self.orders.remove(modify_order_request.client_id.as_str()) {
+            Some(resting) => resting.order,
+            None => return vec![Self::failed_modify(modify_order_request)],
+        };
+        original.status = OrderStatus::Closed;
+        let order_request = OrderRequest {
+            exchange: Exchanges::SIM,
+            market: original.market.clone(),
+            side: original.side.clone(),
+            price: modify_order_request.price,
+            size: modify_order_request.size,
+            type_: original.type_.clone(),
+            ioc: original.ioc,
+            post_only: original.postOnly,
+            client_id: Some(modify_order_request.new_client_id.clone()),
+        };
+        let mut events = vec![SimEvent::OrderUpdate(original)];
+        events.append(&mut self.place_order(&order_request, now));
+        events
+    }
+
+    pub fn on_market_depth(&mut self, depth: MarketDepth, now: i64) -> Vec<SimEvent> {
+        let mut events = vec![];
+        let client_ids: Vec<String> = self
+            .orders
+            .iter()
+            .filter(|(_, resting)| resting.order.market == depth.market)
+            .map(|(client_id, _)| client_id.clone())
+            .collect();
+        for client_id in client_ids {
+            let mut resting = self.orders.remove(client_id.as_str()).unwrap();
+            let side = resting.order.side.clone();
+            let price = resting.order.price;
+            let traded_through = Self::opposite_side(&depth, &side)
+                .first()
+                .map(|best| Self::crosses(&side, price, best))
+                .unwrap_or(false);
+            let traded = resting
+                .queue
+                .on_level_size(level_size(&depth, &side, price), 0.0);
+            let fill_size = if traded_through {
+                resting.order.remainingSize
+            } else if Self::at_touch(&depth, &side, price) && traded > 0.0 {
+                traded.min(resting.order.remainingSize)
+            } else {
+                0.0
+            };
+            if fill_size > SIZE_EPSILON {
+                let fill = self.fill(&mut resting.order, price, fill_size, false, now);
+                events.push(SimEvent::OrderFill(fill));
+                if resting.order.remainingSize <= SIZE_EPSILON {
+                    resting.order.status = OrderStatus::Closed;
+                    events.push(SimEvent::OrderUpdate(resting.order));
+                    continue;
+                }
+                events.push(SimEvent::OrderUpdate(resting.order.clone()));
+            }
+            self.orders.insert(client_id, resting);
+        }
+        self.depths.insert(depth.market.clone(), depth);
+        events
+    }
+}
@@ -0,0 +1,29 @@
+use crate::model::constants::Exchanges;
+
+pub mod matching_engine;
+pub mod sim_order_gateway;
+
+pub use matching_engine::{MatchingEngine, SimEvent};
+pub use sim_order_gateway::SimOrderGateway;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct SimParams {
+    /// exchange whose MarketDepth feed SIM orders are matched against. The feed is re-published
+    /// as SIM depth for the lambda. Use SIM when a replay publishes SIM depth directly.
+    pub source_exchange: Exchanges,
+    /// delay before an order, cancel or modify request reaches the matching engine
+    pub latency_ms: u64,
+    pub maker_fee_rate: f64,
+    pub taker_fee_rate: f64,
+}
+impl Default for SimParams {
+    fn default() -> Self {
+        SimParams {
+            source_exchange: Exchanges::FTX,
+            latency_ms: 20,
+            maker_fee_rate: 0.0002,
+            taker_fee_rate: 0.0007,
+        }
+    }
+}
@@ -0,0 +1,353 @@
+use crate::core::clock::SharedClock;
+use crate::core::kill_switch::KillSwitch;
+use crate::core::OrderGateway;
+use crate::model::constants::{Exchanges, PublishChannel};
+use crate::model::market_data_model::{MarketDepth, Trade};
+use crate::model::{CancelOrderRequest, ModifyOrderRequest, OrderRequest};
+use crate::pubsub::simple_message_bus::{MessageBusSender, MessageConsumer, RedisBackedMessageBus};
+use crate::pubsub::PublishPayload;
+use crate::sim::{MatchingEngine, SimEvent, SimParams};
+use async_trait::async_trait;
+
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// State shared by the SIM services
+struct SimExchange {
+    message_bus_sender: MessageBusSender,
+    params: SimParams,
+    matching_engine: Mutex<MatchingEngine>,
+    kill_switch: Arc<KillSwitch>,
+    clock: SharedClock,
+}
+
+impl SimExchange {
+    fn time_now(&self) -> i64 {
+        self.clock.now_ms()
+    }
+
+    async fn latency(&self) {
+        if self.params.latency_ms > 0 {
+            self.clock
+                .sleep(Duration::from_millis(self.params.latency_ms))
+                .await;
+        }
+    }
+
+    async fn publish_events(&self, events: Vec<SimEvent>) {
+        for event in events {
+            let payload = match event {
+                SimEvent::OrderUpdate(order_update) => PublishPayload {
+                    channel: PublishChannel::OrderUpdate.to_string(),
+                    payload: RedisBackedMessageBus::pack_json(&order_update).unwrap(),
+                },
+                SimEvent::OrderFill(order_fill) => PublishPayload {
+                    channel: PublishChannel::OrderFill.to_string(),
+                    payload: RedisBackedMessageBus::pack_json(&order_fill).unwrap(),
+                },
+            };
+            if let Err(err) = self.message_bus_sender.send(payload).await {
+                log::error!("sim publish error: {}", err);
+            }
+        }
+    }
+
+    async fn accept_order_request(self: Arc<Self>, order_request: OrderRequest) {
+        self.latency().await;
+        if self
+            .kill_switch
+            .is_engaged(&Exchanges::SIM, order_request.market.as_str())
+        {
+            warn!(
+                "kill switch engaged. rejecting {:?}",
+                order_request.client_id
+            );
+            self.publish_events(vec![MatchingEngine::failed(&order_request)])
+                .await;
+            return;
+        }
+        let events = self
+            .matching_engine
+            .lock()
+            .unwrap()
+            .place_order(&order_request, self.time_now());
+        self.publish_events(events).await;
+    }
+
+    async fn accept_cancel_order_request(
+        self: Arc<Self>,
+        cancel_order_request: CancelOrderRequest,
+    ) {
+        self.latency().await;
+        let events = self
+            .matching_engine
+            .lock()
+            .unwrap()
+            .cancel_order(&cancel_order_request);
+        self.publish_events(events).await;
+    }
+
+    async fn accept_modify_order_request(
+        self: Arc<Self>,
+        modify_order_request: ModifyOrderRequest,
+    ) {
+        self.latency().await;
+        let engaged = self
+            .kill_switch
+            .is_engaged(&Exchanges::SIM, modify_order_request.market.as_str());
+        let events = if engaged {
+            warn!(
+                "kill switch engaged. rejecting modify {}",
+                modify_order_request.client_id
+            );
+            self.matching_engine
+                .lock()
+                .unwrap()
+                .reject_modify_order(&modify_order_request)
+        } else {
+            self.matching_engine
+                .lock()
+                .unwrap()
+                .modify_order(&modify_order_request, self.time_now())
+        };
+        self.publish_events(events).await;
+    }
+
+    async fn accept_market_depth(&self, market_depth: MarketDepth) {
+        if self.params.source_exchange != Exchanges::SIM {
+            // mirror the source feed so the lambda quotes on the book SIM orders match against
+            let mut sim_market_depth = market_depth.clone();
+            sim_market_depth.exchange = Exchanges::SIM;
+            let payload = PublishPayload {
+                channel: format!(
+                    "{}:{}:{}",
+                    PublishChannel::MarketDepth,
+                    Exchanges::SIM,
+                    sim_market_depth.market
+                ),
+                payload: RedisBackedMessageBus::pack_json(&sim_market_depth).unwrap(),
+            };
+            if let Err(err) = self.message_bus_sender.send(payload).await {
+                log::error!("sim publish error: {}", err);
+            }
+        }
+        let events = self
+            .matching_engine
+            .lock()
+            .unwrap()
+            .on_market_depth(market_depth, self.time_now());
+        self.publish_events(events).await;
+    }
+
+    /// mirror a trade of the source feed, for executors following the market volume
+    async fn accept_trade(&self, mut trade: Trade) {
+        trade.exchange = Exchanges::SIM;
+        let payload = PublishPayload {
+            channel: Trade::channel(&Exchanges::SIM, trade.market.as_str()),
+            payload: RedisBackedMessageBus::pack_json(&trade).unwrap(),
+        };
+        if let Err(err) = self.message_bus_sender.send(payload).await {
+            log::error!("sim publish error: {}", err);
+        }
+    }
+}
+
+/// Order gateway for `Exchanges::SIM`, matching orders locally against the MarketDepth feed
+/// of `SimParams::source_exchange`. Orders and modifies are rejected while `kill_switch` is engaged.
+pub struct SimOrderGateway {
+    exchange: Arc<SimExchange>,
+    markets: Vec<String>,
+}
+
+impl SimOrderGateway {
+    pub fn new(
+        message_bus_sender: MessageBusSender,
+        params: SimParams,
+        markets: Vec<String>,
+        kill_switch: Arc<KillSwitch>,
+        clock: SharedClock,
+    ) -> Self {
+        SimOrderGateway {
+            exchange: Arc::new(SimExchange {
+                message_bus_sender,
+                matching_engine: Mutex::new(MatchingEngine::new(&params)),
+                params,
+                kill_switch,
+                clock,
+            }),
+            markets,
+        }
+    }
+
+    async fn subscribe_market_depth(&self) -> anyhow::Result<()> {
+        let channels: Vec<String> = self
+            .markets
+            .iter()
+            .map(|market| {
+                format!(
+                    "{}:{}:{}",
+                    PublishChannel::MarketDepth,
+                    self.exchange.params.source_exchange,
+                    market
+                )
+            })
+            .collect();
+        let channels = channels.iter().map(AsRef::as_ref).collect();
+        let consumer = SimMarketDepthService(self.exchange.clone());
+        RedisBackedMessageBus::subscribe_channels(channels, &consumer).await?;
+        Err(anyhow!("SimMarketDepthService subscribe uncaught"))
+    }
+
+    async fn subscribe_trades(&self) -> anyhow::Result<()> {
+        let source_exchange = &self.exchange.params.source_exchange;
+        if *source_exchange == Exchanges::SIM {
+            return futures_util::future::pending().await;
+        }
+        let channels: Vec<String> = self
+            .markets
+            .iter()
+            .map(|market| Trade::channel(source_exchange, market))
+            .collect();
+        let channels = channels.iter().map(AsRef::as_ref).collect();
+        let consumer = SimTradeService(self.exchange.clone());
+        RedisBackedMessageBus::subscribe_channels(channels, &consumer).await?;
+        Err(anyhow!("SimTradeService subscribe uncaught"))
+    }
+
+    async fn subscribe_order_request(&self) -> anyhow::Result<()> {
+        let consumer = SimOrderRequestService(self.exchange.clone());
+        RedisBackedMessageBus::subscribe_channels(
+            vec![PublishChannel::OrderRequest.as_ref()],
+            &consumer,
+        )
+        .await?;
+        Err(anyhow!("SimOrderRequestService subscribe uncaught"))
+    }
+
+    async fn subscribe_cancel_order(&self) -> anyhow::Result<()> {
+        let consumer = SimCancelOrderService(self.exchange.clone());
+        RedisBackedMessageBus::subscribe_channels(
+            vec![PublishChannel::CancelOrder.as_ref()],
+            &consumer,
+        )
+        .await?;
+        Err(anyhow!("SimCancelOrderService subscribe uncaught"))
+    }
+
+    async fn subscribe_modify_order(&self) -> anyhow::Result<()> {
+        let consumer = SimModifyOrderService(self.exchange.clone());
+        RedisBackedMessageBus::subscribe_channels(
+            vec![PublishChannel::ModifyOrder.as_ref()],
+            &consumer,
+        )
+        .await?;
+        Err(anyhow!("SimModifyOrderService subscribe uncaught"))
+    }
+}
+
+#[async_trait]
+impl OrderGateway for SimOrderGateway {
+    async fn subscribe(&self) -> anyhow::Result<()> {
+        tokio::select! {
+            Err(err) = self.subscribe_market_depth() => {
+                log::error!("sim market_depth_service panic: {}", err)
+            },
+            Err(err) = self.subscribe_trades() => {
+                log::error!("sim trade_service panic: {}", err)
+            },
+            Err(err) = self.subscribe_order_request() => {
+                log::error!("sim order_request_service panic: {}", err)
+            },
+            Err(err) = self.subscribe_cancel_order() => {
+                log::error!("sim cancel_order_service panic: {}", err)
+            },
+            Err(err) = self.subscribe_modify_order() => {
+                log::error!("sim modify_order_service panic: {}", err)
+            },
+        }
+        Err(anyhow!("SimOrderGateway subscribe uncaught"))
+    }
+}
+
+struct SimMarketDepthService(Arc<SimExchange>);
+#[async_trait]
+impl MessageConsumer for SimMarketDepthService {
+    async fn consume(&self, msg: &[u8]) -> anyhow::Result<()> {
+        match serde_json::from_slice::<MarketDepth>(msg) {
+            Ok(market_depth) => self.0.accept_market_depth(market_depth).await,
+            Err(err) => log::error!("{}", err),
+        }
+        Ok(())
+    }
+}
+
+struct SimTradeService(Arc<SimExchange>);
+#[async_trait]
+impl MessageConsumer for SimTradeService {
+    async fn consume(&self, msg: &[u8]) -> anyhow::Result<()> {
+        match serde_json::from_slice::<Trade>(msg) {
+            Ok(trade) => self.0.accept_trade(trade).await,
+            Err(err) => log::error!("{}", err),
+        }
+        Ok(())
+    }
+}
+
+struct SimOrderRequestService(Arc<SimExchange>);
+#[async_trait]
+impl MessageConsumer for SimOrderRequestService {
+    async fn consume(&self, msg: &[u8]) -> anyhow::Result<()> {
+        match serde_json::from_slice::<OrderRequest>(msg) {
+            Ok(order_request) => {
+                if order_request.exchange == Exchanges::SIM {
+                    log::info!("SimOrderRequestService: {:?}", order_request);
+                    tokio::spawn(self.0.clone().accept_order_request(order_request));
+                }
+            }
+            Err(err) => log::error!("{}", err),
+        }
+        Ok(())
+    }
+}
+
+struct SimCancelOrderService(Arc<SimExchange>);
+#[async_trait]
+impl MessageConsumer for SimCancelOrderService {
+    async fn consume(&self, msg: &[u8]) -> anyhow::Result<()> {
+        match serde_json::from_slice::<CancelOrderRequest>(msg) {
+            Ok(cancel_order_request) => {
+                if cancel_order_request.exchange == Exchanges::SIM {
+                    log::info!("SimCancelOrderService: {:?}", cancel_order_request);
+                    tokio::spawn(
+                        self.0
+                            .clone()
+                            .accept_cancel_order_request(cancel_order_request),
+                    );
+                }
+            }
+            Err(err) => log::error!("{}", err),
+        }
+        Ok(())
+    }
+}
+
+struct SimModifyOrderService(Arc<SimExchange>);
+#[async_trait]
+impl MessageConsumer for SimModifyOrderService {
+    async fn consume(&self, msg: &[u8]) -> anyhow::Result<()> {
+        match serde_json::from_slice::<ModifyOrderRequest>(msg) {
+            Ok(modify_order_request) => {
+                if modify_order_request.exchange == Exchanges::SIM {
+                    log::info!("SimModifyOrderService: {:?}", modify_order_request);
+                    tokio::spawn(
+                        self.0
+                            .clone()
+                            .accept_modify_order_request(modify_order_request),
+                    );
+                }
+            }
+            Err(err) => log::error!("{}", err),
+        }
+        Ok(())
+    }
+}
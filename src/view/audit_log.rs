@@ -0,0 +1,91 @@
+use serde_json::Value;
+use std::fs::OpenOptions;
+use std::io::{BufRead, BufReader, Write};
+
+/// one accepted param change
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct AuditEntry {
+    pub time_ms: i64,
+    pub user: String,
+    pub key: String,
+    pub old_value: Value,
+    pub new_value: Value,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct AuditQuery {
+    /// only changes of this param
+    pub key: Option<String>,
+    pub since_ms: Option<i64>,
+    /// the latest `limit` entries, all when None
+    pub limit: Option<usize>,
+}
+
+/// Append-only log of param changes, one JSON entry per line.
+pub struct AuditLog {
+    path: String,
+}
+
+impl AuditLog {
+    pub fn new(path: &str) -> Self {
+        AuditLog {
+            path: path.to_string(),
+        }
+    }
+
+    pub fn instance_path(instance_name: &str) -> String {
+        format!("./instance/{}.audit.jsonl", instance_name)
+    }
+
+    pub fn append(&self, entry: &AuditEntry) -> anyhow::Result<()> {
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(self.path.as_str())?;
+        let mut line = serde_json::to_string(entry)?;
+        line.push('\n');
+        file.write_all(line.as_bytes())?;
+        file.sync_data()?;
+        Ok(())
+    }
+
+    /// entries matching `query` in the order they were appended
+    pub fn query(&self, query: &AuditQuery) -> anyhow::Result<Vec<AuditEntry>> {
+        let file = match OpenOptions::new().read(true).open(self.path.as_str()) {
+            Ok(file) => file,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(vec![]),
+            Err(err) => return Err(err.into()),
+        };
+        let mut entries = vec![];
+        for line in BufReader::new(file).lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let entry = match serde_json::from_str::<AuditEntry>(line.as_str()) {
+                Ok(entry) => entry,
+                Err(err) => {
+                    // e.g. a line cut short by a crash, the rest of the log is still valid
+                    warn!("skipping audit log line: {}", err);
+                    continue;
+                }
+            };
+            if let Some(ref key) = query.key {
+                if entry.key != *key {
+                    continue;
+                }
+            }
+            if let Some(since_ms) = query.since_ms {
+                if entry.time_ms < since_ms {
+                    continue;
+                }
+            }
+            entries.push(entry);
+        }
+        if let Some(limit) = query.limit {
+            let skip = entries.len().saturating_sub(limit);
+            entries.drain(..skip);
+        }
+        Ok(entries)
+    }
+}
@@ -0,0 +1,235 @@
+use crate::model::constants::Exchanges;
+use crate::model::{OrderFill, OrderSide, OrderStatus, OrderType, OrderUpdate};
+use std::collections::{HashMap, HashSet, VecDeque};
+
+/// closed orders kept in the blotter after their last update
+const MAX_CLOSED_ORDERS: usize = 200;
+/// fills kept in the rolling fills table
+const MAX_FILLS: usize = 500;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct StatusChange {
+    pub status: OrderStatus,
+    pub time_ms: i64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct BlotterRow {
+    pub client_id: String,
+    pub exchange: Exchanges,
+    pub market: String,
+    pub side: OrderSide,
+    #[serde(rename = "type")]
+    pub type_: OrderType,
+    pub price: f64,
+    pub size: f64,
+    pub filled_size: f64,
+    pub avg_fill_px: Option<f64>,
+    pub status: OrderStatus,
+    pub updated_ms: i64,
+    pub history: Vec<StatusChange>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct FillRow {
+    pub id: String,
+    pub exchange: Exchanges,
+    pub market: String,
+    pub side: OrderSide,
+    pub price: f64,
+    pub size: f64,
+    pub fee: f64,
+    pub liquidity: String,
+    pub time: String,
+}
+
+impl From<&OrderFill> for FillRow {
+    fn from(fill: &OrderFill) -> Self {
+        FillRow {
+            id: fill.id.to_string(),
+            exchange: fill.exchange.clone(),
+            market: fill.market.clone(),
+            side: fill.side.clone(),
+            price: fill.price,
+            size: fill.size,
+            fee: fill.fee,
+            liquidity: fill.liquidity.clone(),
+            time: fill.time.clone(),
+        }
+    }
+}
+
+/// Rows to add or replace and ids to drop since the previous update of a table.
+///
+/// `seq` increases by one per update, a client seeing a gap asks for a snapshot through
+/// `QueryBlotter`. A snapshot carries every row and the `seq` of the last update before it, upserts
+/// are idempotent so rows in both the snapshot and the next update are harmless.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct TableUpdate<T> {
+    pub seq: u64,
+    pub snapshot: bool,
+    pub upsert: Vec<T>,
+    pub remove: Vec<String>,
+}
+
+/// Orders and fills of one instance, published as incremental `TableUpdate`s.
+pub struct Blotter {
+    owner: String,
+    orders: HashMap<String, BlotterRow>,
+    /// closed orders, oldest first
+    closed: VecDeque<String>,
+    fills: VecDeque<FillRow>,
+    changed_orders: HashSet<String>,
+    removed_orders: Vec<String>,
+    new_fills: Vec<FillRow>,
+    removed_fills: Vec<String>,
+    order_seq: u64,
+    fill_seq: u64,
+}
+
+impl Blotter {
+    pub fn new(owner: &str) -> Self {
+        Blotter {
+            owner: owner.to_string(),
+            orders: HashMap::new(),
+            closed: VecDeque::new(),
+            fills: VecDeque::new(),
+            changed_orders: HashSet::new(),
+            removed_orders: vec![],
+            new_fills: vec![],
+            removed_fills: vec![],
+            order_seq: 0,
+            fill_seq: 0,
+        }
+    }
+
+    pub fn on_order_update(&mut self, order_update: &OrderUpdate, time_ms: i64) {
+        if !order_update.is_owned_by(self.owner.as_str()) {
+            return;
+        }
+        let client_id = match order_update.client_id {
+            None => return,
+            Some(ref client_id) => client_id.clone(),
+        };
+        let row = self
+            .orders
+            .entry(client_id.clone())
+            .or_insert_with(|| BlotterRow {
+                client_id: client_id.clone(),
+                exchange: order_update.exchange.clone(),
+                market: order_update.market.clone(),
+                side: order_update.side.clone(),
+                type_: order_update.type_.clone(),
+                price: order_update.price,
+                size: order_update.size,
+                filled_size: 0.0,
+                avg_fill_px: None,
+                status: order_update.status.clone(),
+                updated_ms: time_ms,
+                history: vec![],
+            });
+        if row.history.last().map(|change| &change.status) != Some(&order_update.status) {
+            row.history.push(StatusChange {
+                status: order_update.status.clone(),
+                time_ms,
+            });
+        }
+        row.price = order_update.price;
+        row.size = order_update.size;
+        row.filled_size = order_update.filledSize;
+        row.avg_fill_px = order_update.avgFillPrice.or(row.avg_fill_px);
+        row.status = order_update.status.clone();
+        row.updated_ms = time_ms;
+        self.changed_orders.insert(client_id.clone());
+
+        match order_update.status {
+            OrderStatus::Closed | OrderStatus::Failed => {
+                if !self.closed.contains(&client_id) {
+                    self.closed.push_back(client_id);
+                }
+                while self.closed.len() > MAX_CLOSED_ORDERS {
+                    if let Some(evicted) = self.closed.pop_front() {
+                        self.orders.remove(evicted.as_str());
+                        self.changed_orders.remove(evicted.as_str());
+                        self.removed_orders.push(evicted);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    pub fn on_fill(&mut self, order_fill: &OrderFill) {
+        if !order_fill.is_owned_by(self.owner.as_str()) {
+            return;
+        }
+        let row = FillRow::from(order_fill);
+        self.fills.push_back(row.clone());
+        self.new_fills.push(row);
+        while self.fills.len() > MAX_FILLS {
+            if let Some(evicted) = self.fills.pop_front() {
+                match self.new_fills.iter().position(|fill| fill.id == evicted.id) {
+                    // never published, drop it quietly
+                    Some(index) => {
+                        self.new_fills.remove(index);
+                    }
+                    None => self.removed_fills.push(evicted.id),
+                }
+            }
+        }
+    }
+
+    /// orders changed since the previous call, None when nothing changed
+    pub fn take_order_update(&mut self) -> Option<TableUpdate<BlotterRow>> {
+        if self.changed_orders.is_empty() && self.removed_orders.is_empty() {
+            return None;
+        }
+        self.order_seq += 1;
+        let orders = &self.orders;
+        let upsert = self
+            .changed_orders
+            .drain()
+            .filter_map(|client_id| orders.get(client_id.as_str()).cloned())
+            .collect();
+        Some(TableUpdate {
+            seq: self.order_seq,
+            snapshot: false,
+            upsert,
+            remove: std::mem::take(&mut self.removed_orders),
+        })
+    }
+
+    /// fills since the previous call, None when there are none
+    pub fn take_fill_update(&mut self) -> Option<TableUpdate<FillRow>> {
+        if self.new_fills.is_empty() && self.removed_fills.is_empty() {
+            return None;
+        }
+        self.fill_seq += 1;
+        Some(TableUpdate {
+            seq: self.fill_seq,
+            snapshot: false,
+            upsert: std::mem::take(&mut self.new_fills),
+            remove: std::mem::take(&mut self.removed_fills),
+        })
+    }
+
+    /// every order, rows changed since the last update are repeated by the next one
+    pub fn order_snapshot(&self) -> TableUpdate<BlotterRow> {
+        TableUpdate {
+            seq: self.order_seq,
+            snapshot: true,
+            upsert: self.orders.values().cloned().collect(),
+            remove: vec![],
+        }
+    }
+
+    /// every fill in the window, fills since the last update are repeated by the next one
+    pub fn fill_snapshot(&self) -> TableUpdate<FillRow> {
+        TableUpdate {
+            seq: self.fill_seq,
+            snapshot: true,
+            upsert: self.fills.iter().cloned().collect(),
+            remove: vec![],
+        }
+    }
+}
@@ -1,2 +1,4 @@
+pub mod audit_log;
+pub mod blotter;
 pub mod utils;
 pub mod view_service;
\ No newline at end of file
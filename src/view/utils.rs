@@ -8,6 +8,9 @@ pub struct KeyValueEntry {
     #[serde(rename = "type")]
     type_: KeyValueType,
     pub group: String,
+    /// who sent an `UpdateParam`, recorded in the audit log
+    #[serde(default)]
+    pub user: Option<String>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -18,6 +21,15 @@ pub enum KeyValueType {
     Bool,
 }
 
+/// reply to an `UpdateParam` entry
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ParamUpdateResult {
+    pub key: String,
+    pub value: Value,
+    pub accepted: bool,
+    pub reason: Option<String>,
+}
+
 pub fn value_to_entries(value: &Value, group: &str) -> Vec<KeyValueEntry> {
     let mut entries: Vec<KeyValueEntry> = Vec::new();
     if let Some(obj) = value.as_object() {
@@ -27,6 +39,7 @@ pub fn value_to_entries(value: &Value, group: &str) -> Vec<KeyValueEntry> {
                 value: value.clone(),
                 type_: KeyValueType::String,
                 group: group.to_string(),
+                user: None,
             };
             if value.is_boolean() {
                 entry.type_ = KeyValueType::Bool;
@@ -1,37 +1,81 @@
-use crate::cache::{ValueCache, ValueCacheKey};
-use crate::lambda::GenericLambdaInstanceConfig;
+use crate::cache::{OrderUpdateCache, ValueCache, ValueCacheKey};
+use crate::core::auth::{ApiUser, Authenticator, SignedCommand};
+use crate::core::clock::SharedClock;
+use crate::lambda::strategy::ParamSchema;
+use crate::lambda::{GenericLambdaInstanceConfig, LambdaInstanceConfig};
 use crate::model::constants::PublishChannel;
+use crate::model::{OrderFill, OrderUpdate};
 use crate::pubsub::simple_message_bus::{MessageConsumer, RedisBackedMessageBus};
-use crate::view::utils::{value_to_entries, KeyValueEntry};
+use crate::view::audit_log::{AuditEntry, AuditLog, AuditQuery};
+use crate::view::blotter::Blotter;
+use crate::view::utils::{value_to_entries, KeyValueEntry, ParamUpdateResult};
 use serde_json::Value;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 use std::time::Duration;
 
 pub struct ViewService {
     instance_config: GenericLambdaInstanceConfig,
     message_bus: Arc<RedisBackedMessageBus>,
     value_cache: Arc<ValueCache>,
+    param_updater: Arc<ParamUpdater>,
+    blotter: Arc<Mutex<Blotter>>,
+    clock: SharedClock,
 }
 impl ViewService {
     pub fn new(
         instance_config: GenericLambdaInstanceConfig,
         message_bus: Arc<RedisBackedMessageBus>,
         value_cache: Arc<ValueCache>,
+        param_schema: ParamSchema,
+        authenticator: Arc<Authenticator>,
+        clock: SharedClock,
     ) -> Self {
+        let audit_log =
+            AuditLog::new(AuditLog::instance_path(instance_config.name.as_str()).as_str());
+        let param_updater = ParamUpdater {
+            instance_name: instance_config.name.clone(),
+            value_cache: value_cache.clone(),
+            param_schema: Arc::new(param_schema),
+            audit_log: Arc::new(audit_log),
+            authenticator,
+            clock: clock.clone(),
+        };
+        let blotter = Blotter::new(instance_config.name.as_str());
         ViewService {
             instance_config,
             message_bus,
             value_cache,
+            param_updater: Arc::new(param_updater),
+            blotter: Arc::new(Mutex::new(blotter)),
+            clock,
         }
     }
 
+    /// Add the orders of the instance already in `order_update_cache`, i.e. the ones restored on a
+    /// warm restart, which no order update announces. Called once the lambda adopted its orders.
+    pub fn seed_blotter(&self, order_update_cache: &OrderUpdateCache) {
+        let now_ms = self.clock.now_ms();
+        let mut blotter = self.blotter.lock().unwrap();
+        for order_update in order_update_cache.cache.iter() {
+            blotter.on_order_update(order_update.value(), now_ms);
+        }
+    }
+
+    pub fn param_updater(&self) -> Arc<ParamUpdater> {
+        self.param_updater.clone()
+    }
+
+    fn channel(&self, channel: PublishChannel) -> String {
+        format!("{}:{}", channel, self.instance_config.name)
+    }
+
     pub async fn publish_strategy_states(&self) -> anyhow::Result<()> {
         match self.value_cache.get_clone(ValueCacheKey::StrategyStates) {
             None => {}
             Some(value) => {
                 let channel = format!(
                     "{}:{}",
-                    PublishChannel::StrategyStates.to_string(),
+                    PublishChannel::StrategyStates,
                     self.instance_config.name
                 );
                 let entries = value_to_entries(&value, "states");
@@ -47,7 +91,7 @@ impl ViewService {
             Some(value) => {
                 let channel = format!(
                     "{}:{}",
-                    PublishChannel::StrategyParams.to_string(),
+                    PublishChannel::StrategyParams,
                     self.instance_config.name,
                 );
                 let entries = value_to_entries(&value, "params");
@@ -57,18 +101,85 @@ impl ViewService {
         Ok(())
     }
 
+    /// published next to the params so late subscribers receive it too
+    pub async fn publish_param_schema(&self) -> anyhow::Result<()> {
+        let channel = self.channel(PublishChannel::ParamSchema);
+        self.message_bus
+            .publish(&channel, &self.param_updater.param_schema().params)
+            .await?;
+        Ok(())
+    }
+
+    /// orders and fills changed since the previous publish
+    pub async fn publish_blotter(&self) -> anyhow::Result<()> {
+        let (order_update, fill_update) = {
+            let mut blotter = self.blotter.lock().unwrap();
+            (blotter.take_order_update(), blotter.take_fill_update())
+        };
+        if let Some(order_update) = order_update {
+            let channel = self.channel(PublishChannel::OrderBlotter);
+            self.message_bus.publish(&channel, &order_update).await?;
+        }
+        if let Some(fill_update) = fill_update {
+            let channel = self.channel(PublishChannel::Fills);
+            self.message_bus.publish(&channel, &fill_update).await?;
+        }
+        Ok(())
+    }
+
+    pub async fn subscribe_blotter(&self) -> anyhow::Result<()> {
+        let order_consumer = BlotterConsumer {
+            blotter: self.blotter.clone(),
+            channel: PublishChannel::OrderUpdate,
+            clock: self.clock.clone(),
+        };
+        let fill_consumer = BlotterConsumer {
+            blotter: self.blotter.clone(),
+            channel: PublishChannel::OrderFill,
+            clock: self.clock.clone(),
+        };
+        let query_consumer = BlotterQueryConsumer {
+            blotter: self.blotter.clone(),
+            message_bus: self.message_bus.clone(),
+            order_channel: self.channel(PublishChannel::OrderBlotter),
+            fill_channel: self.channel(PublishChannel::Fills),
+        };
+        let query_channel = self.channel(PublishChannel::QueryBlotter);
+        tokio::select! {
+            result = RedisBackedMessageBus::subscribe_channels(vec![PublishChannel::OrderUpdate.as_ref()], &order_consumer) => {
+                error!("blotter order updates completed: {:?}", result);
+            }
+            result = RedisBackedMessageBus::subscribe_channels(vec![PublishChannel::OrderFill.as_ref()], &fill_consumer) => {
+                error!("blotter fills completed: {:?}", result);
+            }
+            result = RedisBackedMessageBus::subscribe_channels(vec![query_channel.as_str()], &query_consumer) => {
+                error!("blotter queries completed: {:?}", result);
+            }
+        }
+        Err(anyhow!("subscribe_blotter uncaught"))
+    }
+
     pub async fn update_params(&self) -> anyhow::Result<()> {
-        let consumer = ParamUpdateConsumer(self.value_cache.clone());
+        let consumer = ParamUpdateConsumer {
+            param_updater: self.param_updater.clone(),
+            message_bus: self.message_bus.clone(),
+            channel: self.channel(PublishChannel::UpdateParam),
+            result_channel: self.channel(PublishChannel::UpdateParamResult),
+        };
+        RedisBackedMessageBus::subscribe_channels(vec![consumer.channel.as_str()], &consumer).await
+    }
+
+    pub async fn query_audit_log(&self) -> anyhow::Result<()> {
+        let consumer = AuditQueryConsumer {
+            message_bus: self.message_bus.clone(),
+            param_updater: self.param_updater.clone(),
+            result_channel: self.channel(PublishChannel::AuditLog),
+        };
         RedisBackedMessageBus::subscribe_channels(
-            vec![format!(
-                "{}:{}",
-                PublishChannel::UpdateParam.to_string(),
-                self.instance_config.name
-            )
-            .as_str()],
+            vec![self.channel(PublishChannel::QueryAuditLog).as_str()],
             &consumer,
         )
-        .await;
+        .await?;
         Ok(())
     }
 
@@ -76,9 +187,10 @@ impl ViewService {
         loop {
             self.publish_strategy_states().await?;
             self.publish_strategy_params().await?;
-            tokio::time::sleep(Duration::from_millis(500)).await;
+            self.publish_param_schema().await?;
+            self.publish_blotter().await?;
+            self.clock.sleep(Duration::from_millis(500)).await;
         }
-        Ok(())
     }
 
     pub async fn subscribe(&self) -> anyhow::Result<()> {
@@ -89,32 +201,254 @@ impl ViewService {
             result = self.update_params() => {
                 error!("update_params completed: {:?}", result);
             }
+            result = self.query_audit_log() => {
+                error!("query_audit_log completed: {:?}", result);
+            }
+            result = self.subscribe_blotter() => {
+                error!("subscribe_blotter completed: {:?}", result);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Validates, applies and persists param updates, shared by `UpdateParam` and the HTTP api.
+pub struct ParamUpdater {
+    instance_name: String,
+    value_cache: Arc<ValueCache>,
+    param_schema: Arc<ParamSchema>,
+    audit_log: Arc<AuditLog>,
+    authenticator: Arc<Authenticator>,
+    clock: SharedClock,
+}
+
+impl ParamUpdater {
+    /// apply `entry` to the cached params, returns the params before and after
+    fn apply(&self, entry: &KeyValueEntry, user: &ApiUser) -> Result<(Value, Value), String> {
+        // validated and applied under the entry lock, a concurrent state change is never lost
+        self.value_cache
+            .update(ValueCacheKey::StrategyParams, |params| {
+                self.param_schema.authorize(
+                    &user.role,
+                    params,
+                    entry.key.as_str(),
+                    &entry.value,
+                )?;
+                let updated =
+                    self.param_schema
+                        .apply_update(params, entry.key.as_str(), &entry.value)?;
+                let old_params = std::mem::replace(params, updated.clone());
+                Ok((old_params, updated))
+            })
+            .unwrap_or_else(|| Err("StrategyParams not in ValueCache".to_string()))
+    }
+
+    /// Keep an accepted update across restarts and record who made it. The files are written on
+    /// the blocking pool, `Err` when the config could not be written.
+    async fn persist(
+        &self,
+        entry: &KeyValueEntry,
+        old_params: &Value,
+        params: Value,
+    ) -> anyhow::Result<()> {
+        let instance_name = self.instance_name.clone();
+        tokio::task::spawn_blocking(move || {
+            LambdaInstanceConfig::save_strategy_params(instance_name.as_str(), params)
+        })
+        .await??;
+        let audit_entry = AuditEntry {
+            time_ms: self.clock.now_ms(),
+            user: entry.user.clone().unwrap_or_else(|| "unknown".to_string()),
+            key: entry.key.clone(),
+            old_value: old_params[entry.key.as_str()].clone(),
+            new_value: entry.value.clone(),
+        };
+        let audit_log = self.audit_log.clone();
+        match tokio::task::spawn_blocking(move || audit_log.append(&audit_entry)).await {
+            Ok(Ok(())) => {}
+            Ok(Err(err)) => error!(
+                "Failed to append audit log of {}: {}",
+                self.instance_name, err
+            ),
+            Err(err) => error!(
+                "Failed to append audit log of {}: {}",
+                self.instance_name, err
+            ),
         }
         Ok(())
     }
+
+    /// undo `entry` in the cached params, unless the param changed again since
+    fn revert(&self, entry: &KeyValueEntry, old_params: &Value, params: &Value) {
+        let key = entry.key.as_str();
+        self.value_cache
+            .update(ValueCacheKey::StrategyParams, |current| {
+                if current[key] == params[key] {
+                    current[key] = old_params[key].clone();
+                }
+            });
+    }
+
+    /// Apply `entry` on behalf of `user`, who is recorded as the author of the change. An update
+    /// that cannot be persisted is reverted and rejected.
+    pub async fn update(&self, mut entry: KeyValueEntry, user: &ApiUser) -> ParamUpdateResult {
+        entry.user = Some(user.name.clone());
+        info!("Receive UpdateParma {:?}", entry);
+        let (old_params, params) = match self.apply(&entry, user) {
+            Ok(update) => update,
+            Err(reason) => return rejected(entry.key, entry.value, reason),
+        };
+        if let Err(err) = self.persist(&entry, &old_params, params.clone()).await {
+            error!(
+                "Failed to persist {} of {}: {}",
+                entry.key, self.instance_name, err
+            );
+            self.revert(&entry, &old_params, &params);
+            return rejected(entry.key, entry.value, format!("not persisted: {}", err));
+        }
+        ParamUpdateResult {
+            key: entry.key,
+            value: entry.value,
+            accepted: true,
+            reason: None,
+        }
+    }
+
+    pub fn param_schema(&self) -> &ParamSchema {
+        &self.param_schema
+    }
+
+    pub fn audit_log(&self) -> &AuditLog {
+        &self.audit_log
+    }
+
+    pub fn authenticator(&self) -> &Authenticator {
+        &self.authenticator
+    }
+}
+
+fn rejected(key: String, value: Value, reason: String) -> ParamUpdateResult {
+    warn!("Rejected UpdateParam: {}", reason);
+    ParamUpdateResult {
+        key,
+        value,
+        accepted: false,
+        reason: Some(reason),
+    }
+}
+
+struct ParamUpdateConsumer {
+    param_updater: Arc<ParamUpdater>,
+    message_bus: Arc<RedisBackedMessageBus>,
+    /// signed into every command
+    channel: String,
+    result_channel: String,
 }
 
-struct ParamUpdateConsumer(Arc<ValueCache>);
 #[async_trait::async_trait]
 impl MessageConsumer for ParamUpdateConsumer {
     async fn consume(&self, msg: &[u8]) -> anyhow::Result<()> {
-        let entry = serde_json::from_slice::<KeyValueEntry>(msg)
-            .expect("ParamUpdateConsumer Failed to consumer message");
-        info!("Receive UpdateParma {:?}", entry);
-        if let Some(mut params) = self.0.get_clone(ValueCacheKey::StrategyParams) {
-            match params.get(&entry.key) {
-                None => {
-                    warn!(
-                        "Error updating StrategyParams, key not found : {}",
-                        entry.key
-                    );
-                }
-                Some(_) => params[entry.key.clone()] = entry.value.clone(),
+        let command = match serde_json::from_slice::<SignedCommand>(msg) {
+            Ok(command) => command,
+            Err(err) => {
+                warn!("Rejected UpdateParam, not a signed command: {}", err);
+                return Ok(());
+            }
+        };
+        let entry = match serde_json::from_str::<KeyValueEntry>(command.payload.as_str()) {
+            Ok(entry) => entry,
+            Err(err) => {
+                warn!("Rejected UpdateParam, invalid entry: {}", err);
+                return Ok(());
+            }
+        };
+        let now_ms = self.param_updater.clock.now_ms();
+        let result = match self.param_updater.authenticator().verify(
+            self.channel.as_str(),
+            &command,
+            now_ms,
+        ) {
+            Ok(user) => self.param_updater.update(entry, user).await,
+            Err(err) => rejected(entry.key, entry.value, err.to_string()),
+        };
+        self.message_bus
+            .publish(&self.result_channel, &result)
+            .await?;
+        Ok(())
+    }
+}
+
+struct AuditQueryConsumer {
+    message_bus: Arc<RedisBackedMessageBus>,
+    param_updater: Arc<ParamUpdater>,
+    result_channel: String,
+}
+
+#[async_trait::async_trait]
+impl MessageConsumer for AuditQueryConsumer {
+    async fn consume(&self, msg: &[u8]) -> anyhow::Result<()> {
+        let query = match serde_json::from_slice::<AuditQuery>(msg) {
+            Ok(query) => query,
+            Err(err) => {
+                warn!("Rejected QueryAuditLog, invalid query: {}", err);
+                return Ok(());
+            }
+        };
+        let entries = self.param_updater.audit_log().query(&query)?;
+        self.message_bus
+            .publish(&self.result_channel, &entries)
+            .await?;
+        Ok(())
+    }
+}
+
+struct BlotterConsumer {
+    blotter: Arc<Mutex<Blotter>>,
+    /// `OrderUpdate` or `OrderFill`
+    channel: PublishChannel,
+    clock: SharedClock,
+}
+
+#[async_trait::async_trait]
+impl MessageConsumer for BlotterConsumer {
+    async fn consume(&self, msg: &[u8]) -> anyhow::Result<()> {
+        match self.channel {
+            PublishChannel::OrderFill => {
+                let order_fill = serde_json::from_slice::<OrderFill>(msg)?;
+                self.blotter.lock().unwrap().on_fill(&order_fill);
+            }
+            _ => {
+                let order_update = serde_json::from_slice::<OrderUpdate>(msg)?;
+                let now_ms = self.clock.now_ms();
+                self.blotter
+                    .lock()
+                    .unwrap()
+                    .on_order_update(&order_update, now_ms);
             }
-            self.0.insert(ValueCacheKey::StrategyParams, params);
-        } else {
-            error!("Cannot get StrategyParams from ValueCache")
         }
         Ok(())
     }
 }
+
+/// replies to `QueryBlotter` with snapshots of both tables
+struct BlotterQueryConsumer {
+    blotter: Arc<Mutex<Blotter>>,
+    message_bus: Arc<RedisBackedMessageBus>,
+    order_channel: String,
+    fill_channel: String,
+}
+
+#[async_trait::async_trait]
+impl MessageConsumer for BlotterQueryConsumer {
+    async fn consume(&self, _msg: &[u8]) -> anyhow::Result<()> {
+        let (orders, fills) = {
+            let blotter = self.blotter.lock().unwrap();
+            (blotter.order_snapshot(), blotter.fill_snapshot())
+        };
+        self.message_bus
+            .publish(&self.order_channel, &orders)
+            .await?;
+        self.message_bus.publish(&self.fill_channel, &fills).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,53 @@
+#[cfg(test)]
+mod api_service_test {
+    use rust_quant::lambda::{ApiParams, LambdaParams, PositionBook};
+    use rust_quant::model::constants::Exchanges;
+    use rust_quant::model::{OrderFill, OrderSide};
+
+    fn order_fill(owner: &str, market: &str, side: OrderSide, size: f64) -> OrderFill {
+        OrderFill {
+            exchange: Exchanges::SIM,
+            market: market.to_string(),
+            side,
+            size,
+            client_id: Some(format!("{}:SIM:{}:id", owner, market)),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn positions_of_instance_markets() {
+        let positions = PositionBook::new(
+            "swap-mm",
+            vec![
+                (Exchanges::SIM, "ETH-PERP".to_string()),
+                (Exchanges::SIM, "ETH/USD".to_string()),
+            ],
+        );
+        let own = |market, side, size| order_fill("swap-mm", market, side, size);
+        assert!(positions.apply(&own("ETH-PERP", OrderSide::Buy, 1.5)));
+        assert!(positions.apply(&own("ETH-PERP", OrderSide::Sell, 0.5)));
+        assert!(positions.apply(&own("ETH/USD", OrderSide::Sell, 1.0)));
+        assert!(!positions.apply(&own("BTC-PERP", OrderSide::Buy, 1.0)));
+        // another lambda quoting the same market
+        assert!(!positions.apply(&order_fill("latency-mm", "ETH-PERP", OrderSide::Buy, 1.0)));
+
+        let snapshot = positions.snapshot();
+        assert_eq!(snapshot.len(), 2);
+        assert_eq!(snapshot["ETH-PERP"], 1.0);
+        assert_eq!(snapshot["ETH/USD"], -1.0);
+    }
+
+    #[test]
+    fn api_params_default_to_engine_port() {
+        let params: ApiParams = serde_json::from_str("{}").unwrap();
+        assert!(params.enabled);
+        assert_eq!(params.port, 6008);
+
+        let lambda_params: LambdaParams =
+            serde_json::from_str(r#"{"book": "", "market_depths": [], "api": {"port": 6009}}"#)
+                .unwrap();
+        assert_eq!(lambda_params.api.port, 6009);
+        assert_eq!(lambda_params.api.ws_interval_ms, 500);
+    }
+}
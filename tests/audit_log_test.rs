@@ -0,0 +1,69 @@
+#[cfg(test)]
+mod audit_log_test {
+    use rust_quant::lambda::LambdaInstanceConfig;
+    use rust_quant::view::audit_log::{AuditEntry, AuditLog, AuditQuery};
+    use serde_json::json;
+
+    fn temp_path(name: &str) -> String {
+        let path = std::env::temp_dir().join(format!("{}-{}", uuid::Uuid::new_v4(), name));
+        path.to_str().unwrap().to_string()
+    }
+
+    fn entry(time_ms: i64, key: &str, old_value: f64, new_value: f64) -> AuditEntry {
+        AuditEntry {
+            time_ms,
+            user: "alice".to_string(),
+            key: key.to_string(),
+            old_value: json!(old_value),
+            new_value: json!(new_value),
+        }
+    }
+
+    #[test]
+    fn append_and_query() {
+        let log = AuditLog::new(temp_path("audit.jsonl").as_str());
+        assert!(log.query(&AuditQuery::default()).unwrap().is_empty());
+        log.append(&entry(1, "size", 0.1, 0.2)).unwrap();
+        log.append(&entry(2, "spread", 5.0, 6.0)).unwrap();
+        log.append(&entry(3, "size", 0.2, 0.3)).unwrap();
+
+        let all = log.query(&AuditQuery::default()).unwrap();
+        assert_eq!(all.len(), 3);
+        assert_eq!(all[0], entry(1, "size", 0.1, 0.2));
+
+        let size = log
+            .query(&AuditQuery {
+                key: Some("size".to_string()),
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(size.len(), 2);
+
+        let latest = log
+            .query(&AuditQuery {
+                since_ms: Some(2),
+                limit: Some(1),
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(latest, vec![entry(3, "size", 0.2, 0.3)]);
+    }
+
+    #[test]
+    fn save_to_replaces_config() {
+        let path = temp_path("instance.toml");
+        let mut config = LambdaInstanceConfig {
+            name: "audit".to_string(),
+            init_params: json!({"symbol": "ETH-PERP.FTX"}),
+            strategy_params: json!({"size": 0.1}),
+            ..Default::default()
+        };
+        config.save_to(path.as_str()).unwrap();
+
+        config.strategy_params = json!({"size": 0.2});
+        config.save_to(path.as_str()).unwrap();
+        let saved: LambdaInstanceConfig = confy::load_path(path.as_str()).unwrap();
+        assert_eq!(saved.strategy_params, json!({"size": 0.2}));
+        assert!(!std::path::Path::new(format!("{}.tmp", path).as_str()).exists());
+    }
+}
@@ -0,0 +1,161 @@
+#[cfg(test)]
+mod auth_test {
+    use rust_quant::core::auth::{ApiUser, AuthError, Authenticator, Role, SignedCommand};
+    use rust_quant::core::kill_switch::{KillSwitchAction, KillSwitchCommand};
+    use rust_quant::lambda::strategy::latency_mm::params::{
+        param_schema, LatencyMMStrategyParams,
+    };
+    use rust_quant::lambda::LambdaState;
+    use serde_json::json;
+
+    fn user(name: &str, role: Role) -> ApiUser {
+        ApiUser {
+            name: name.to_string(),
+            api_key: format!("{}-key", name),
+            api_secret: format!("{}-secret", name),
+            role,
+        }
+    }
+
+    const CHANNEL: &str = "UpdateParam:latency-mm";
+
+    fn signed(user: &ApiUser, time: i64, payload: &str) -> SignedCommand {
+        SignedCommand::new(
+            CHANNEL,
+            user.api_key.as_str(),
+            user.api_secret.as_str(),
+            time,
+            payload.to_string(),
+        )
+    }
+
+    #[test]
+    fn verify_signed_command() {
+        let alice = user("alice", Role::Trader);
+        let authenticator = Authenticator::new(vec![alice.clone()]);
+        let payload = r#"{"key":"base_size","value":0.1,"type":"Float","group":"params"}"#;
+
+        let command = signed(&alice, 1000, payload);
+        assert_eq!(
+            authenticator.verify(CHANNEL, &command, 1500).unwrap().name,
+            "alice"
+        );
+        assert_eq!(
+            authenticator.verify(CHANNEL, &command, 10000).unwrap_err(),
+            AuthError::Expired(1000)
+        );
+
+        let mut tampered = command.clone();
+        tampered.payload = payload.replace("0.1", "10");
+        assert_eq!(
+            authenticator.verify(CHANNEL, &tampered, 1500).unwrap_err(),
+            AuthError::InvalidSignature
+        );
+
+        let mallory = user("mallory", Role::Admin);
+        assert_eq!(
+            authenticator
+                .verify(CHANNEL, &signed(&mallory, 1000, payload), 1500)
+                .unwrap_err(),
+            AuthError::UnknownKey
+        );
+    }
+
+    #[test]
+    fn commands_are_bound_to_their_channel_and_accepted_once() {
+        let alice = user("alice", Role::Trader);
+        let authenticator = Authenticator::new(vec![alice.clone()]);
+        let payload = r#"{"key":"base_size","value":0.1,"type":"Float","group":"params"}"#;
+        let command = signed(&alice, 1000, payload);
+
+        assert_eq!(
+            authenticator
+                .verify("UpdateParam:swap-mm", &command, 1500)
+                .unwrap_err(),
+            AuthError::InvalidSignature
+        );
+        assert!(authenticator.verify(CHANNEL, &command, 1500).is_ok());
+        assert_eq!(
+            authenticator.verify(CHANNEL, &command, 2000).unwrap_err(),
+            AuthError::Replayed
+        );
+        // a new command with the same payload has a new time and signature
+        assert!(authenticator
+            .verify(CHANNEL, &signed(&alice, 1001, payload), 2000)
+            .is_ok());
+    }
+
+    #[test]
+    fn rearm_needs_a_command_signed_by_an_admin() {
+        let trader = user("trader", Role::Trader);
+        let admin = user("admin", Role::Admin);
+        let authenticator = Authenticator::new(vec![trader.clone(), admin.clone()]);
+        let command = |action| KillSwitchCommand {
+            action,
+            exchange: None,
+            market: None,
+        };
+
+        let engage = serde_json::to_vec(&command(KillSwitchAction::Engage)).unwrap();
+        assert_eq!(
+            KillSwitchCommand::authorize(&engage, &authenticator, 1000)
+                .unwrap()
+                .action,
+            KillSwitchAction::Engage
+        );
+        let rearm = serde_json::to_vec(&command(KillSwitchAction::Rearm)).unwrap();
+        assert!(KillSwitchCommand::authorize(&rearm, &authenticator, 1000).is_err());
+
+        let sign = |user: &ApiUser| {
+            let signed = command(KillSwitchAction::Rearm)
+                .sign(user.api_key.as_str(), user.api_secret.as_str(), 1000)
+                .unwrap();
+            serde_json::to_vec(&signed).unwrap()
+        };
+        assert!(KillSwitchCommand::authorize(&sign(&trader), &authenticator, 1000).is_err());
+        assert_eq!(
+            KillSwitchCommand::authorize(&sign(&admin), &authenticator, 1000)
+                .unwrap()
+                .action,
+            KillSwitchAction::Rearm
+        );
+    }
+
+    #[test]
+    fn trader_state_transitions() {
+        let trader = Role::Trader;
+        assert!(trader.may_set_state(&LambdaState::Live, &LambdaState::Paused));
+        assert!(trader.may_set_state(&LambdaState::Paused, &LambdaState::Live));
+        assert!(trader.may_set_state(&LambdaState::AutoPaused, &LambdaState::Live));
+        assert!(!trader.may_set_state(&LambdaState::Stopped, &LambdaState::Live));
+        assert!(!trader.may_set_state(&LambdaState::Init, &LambdaState::Live));
+        assert!(Role::Admin.may_set_state(&LambdaState::Stopped, &LambdaState::Live));
+        assert!(!Role::Viewer.may_set_state(&LambdaState::Live, &LambdaState::Paused));
+    }
+
+    #[test]
+    fn roles_gate_params() {
+        let schema = param_schema();
+        let mut params = serde_json::to_value(LatencyMMStrategyParams::default()).unwrap();
+        params["state"] = json!("Stopped");
+
+        assert!(schema
+            .authorize(&Role::Viewer, &params, "base_size", &json!(0.1))
+            .is_err());
+        assert!(schema
+            .authorize(&Role::Trader, &params, "base_size", &json!(0.1))
+            .is_ok());
+        assert!(schema
+            .authorize(&Role::Trader, &params, "max_position", &json!(10.0))
+            .is_err());
+        assert!(schema
+            .authorize(&Role::Admin, &params, "max_position", &json!(10.0))
+            .is_ok());
+        assert!(schema
+            .authorize(&Role::Trader, &params, "state", &json!("Live"))
+            .is_err());
+        assert!(schema
+            .authorize(&Role::Admin, &params, "state", &json!("Live"))
+            .is_ok());
+    }
+}
@@ -0,0 +1,103 @@
+#[cfg(test)]
+mod backtest_test {
+    use rust_quant::backtest::{MarketDataSource, ReplayClock, ReportCollector};
+    use rust_quant::model::constants::Exchanges;
+    use rust_quant::model::{OrderFill, OrderSide};
+    use rust_quant::pubsub::local_message_bus::LocalMessageBus;
+    use rust_quant::pubsub::simple_message_bus::{MessageConsumer, RedisBackedMessageBus};
+    use std::sync::Mutex;
+
+    fn order_fill(side: OrderSide, price: f64, size: f64, fee: f64) -> OrderFill {
+        OrderFill {
+            exchange: Exchanges::SIM,
+            market: "ETH-PERP".to_string(),
+            side,
+            price,
+            size,
+            fee,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn synthetic_market_depth_around_trade_price() {
+        let market_depth = MarketDataSource::synthetic_market_depth(
+            0,
+            Exchanges::FTX,
+            "ETH-PERP",
+            100.0,
+            0.5,
+            3,
+            2.0,
+        );
+        let bids: Vec<f64> = market_depth.bids.iter().map(|level| level.price).collect();
+        let asks: Vec<f64> = market_depth.asks.iter().map(|level| level.price).collect();
+        assert_eq!(bids, vec![99.5, 99.0, 98.5]);
+        assert_eq!(asks, vec![100.5, 101.0, 101.5]);
+    }
+
+    #[tokio::test]
+    async fn report_marks_inventory_to_mid() {
+        let collector = ReportCollector::new(ReplayClock::new(0), vec!["ETH-PERP".to_string()]);
+        collector.on_market_depth(&MarketDataSource::synthetic_market_depth(
+            0,
+            Exchanges::SIM,
+            "ETH-PERP",
+            100.0,
+            1.0,
+            1,
+            1.0,
+        ));
+        let buy = order_fill(OrderSide::Buy, 99.0, 2.0, 0.1);
+        let sell = order_fill(OrderSide::Sell, 101.0, 1.0, 0.1);
+        for fill in [buy, sell].iter() {
+            collector
+                .consume(RedisBackedMessageBus::pack_json(fill).unwrap().as_bytes())
+                .await
+                .unwrap();
+        }
+        collector.on_publish("OrderRequest");
+        collector.on_publish("CancelOrder");
+
+        let report = collector.report();
+        assert_eq!(report.fills.len(), 2);
+        assert_eq!(report.order_count, 1);
+        assert_eq!(report.cancel_count, 1);
+        assert_eq!(report.positions["ETH-PERP"], 1.0);
+        assert_eq!(report.inventory_path.len(), 2);
+        // -198 + 101 + 1 * 100 - 0.2
+        assert!((report.pnl - 2.8).abs() < 1e-9);
+    }
+
+    struct Collect(Mutex<Vec<Vec<u8>>>);
+    #[async_trait::async_trait]
+    impl MessageConsumer for Collect {
+        async fn consume(&self, msg: &[u8]) -> anyhow::Result<()> {
+            self.0.lock().unwrap().push(msg.to_vec());
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn local_message_bus_delivers_in_order() {
+        let local_message_bus = LocalMessageBus::new();
+        let consumer = Collect(Mutex::new(vec![]));
+        LocalMessageBus::scope(local_message_bus.clone(), async {
+            let subscription = RedisBackedMessageBus::subscribe_channels(vec!["a", "b"], &consumer);
+            futures_util::pin_mut!(subscription);
+            // the first poll subscribes, the second one drains what was published since
+            assert!(futures_util::poll!(&mut subscription).is_pending());
+            local_message_bus.publish("a", b"1");
+            local_message_bus.publish("c", b"2");
+            local_message_bus.publish("b", b"3");
+            assert!(futures_util::poll!(&mut subscription).is_pending());
+        })
+        .await;
+        assert_eq!(
+            *consumer.0.lock().unwrap(),
+            vec![b"1".to_vec(), b"3".to_vec()]
+        );
+        // the bus ends with its scope
+        assert!(LocalMessageBus::current().is_none());
+    }
+}
@@ -0,0 +1,115 @@
+#[cfg(test)]
+mod basis_arb_test {
+    use rust_quant::lambda::strategy::basis_arb::params::{
+        BasisArbInitParams, BasisArbStrategyParams,
+    };
+    use rust_quant::lambda::strategy::basis_arb::pricing::{
+        find_opportunity, unwind_order, update_fill_ratio, vwap, Leg,
+    };
+    use rust_quant::lambda::{EngineMarkets, GenericLambdaInstanceConfig, LambdaInstanceConfig};
+    use rust_quant::model::constants::Exchanges;
+    use rust_quant::model::market_data_model::{MarketDepth, PriceLevel};
+    use rust_quant::model::OrderSide;
+
+    fn params() -> BasisArbStrategyParams {
+        BasisArbStrategyParams {
+            size: 2.0,
+            fee_a_bp: 5.0,
+            fee_b_bp: 5.0,
+            min_basis: 5.0,
+            min_unwind_size: 0.1,
+            ..Default::default()
+        }
+    }
+
+    fn levels(levels: &[(f64, f64)]) -> Vec<PriceLevel> {
+        levels
+            .iter()
+            .map(|(price, size)| PriceLevel {
+                price: *price,
+                size: *size,
+            })
+            .collect()
+    }
+
+    fn book(exchange: Exchanges, bids: &[(f64, f64)], asks: &[(f64, f64)]) -> MarketDepth {
+        MarketDepth {
+            timestamp: 0,
+            exchange,
+            market: "BTC".to_string(),
+            bids: levels(bids),
+            asks: levels(asks),
+        }
+    }
+
+    #[test]
+    fn vwap_walks_the_book() {
+        let asks = levels(&[(100.0, 1.0), (102.0, 3.0)]);
+        assert_eq!(vwap(&asks, 2.0), Some((101.0, 102.0)));
+        assert_eq!(vwap(&asks, 1.0), Some((100.0, 100.0)));
+        assert_eq!(vwap(&asks, 5.0), None);
+    }
+
+    #[test]
+    fn fires_only_when_the_vwap_spread_beats_fees_and_min_basis() {
+        let book_a = book(Exchanges::FTX, &[(9990.0, 5.0)], &[(10000.0, 5.0)]);
+        // 20bp over a's ask at the touch, but only 10bp at the vwap of 2.0
+        let thin = book(
+            Exchanges::BINANCE,
+            &[(10020.0, 1.0), (10000.0, 5.0)],
+            &[(10030.0, 5.0)],
+        );
+        assert_eq!(find_opportunity(&params(), &book_a, &thin), None);
+
+        let deep = book(Exchanges::BINANCE, &[(10020.0, 5.0)], &[(10030.0, 5.0)]);
+        let opportunity = find_opportunity(&params(), &book_a, &deep).unwrap();
+        assert_eq!(opportunity.buy_leg, Leg::A);
+        assert_eq!(opportunity.sell_leg(), Leg::B);
+        assert_eq!(opportunity.buy_px, 10000.0);
+        assert_eq!(opportunity.sell_px, 10020.0);
+        assert!((opportunity.edge_bp - 10.0).abs() < 1e-9);
+
+        // and the other way round
+        let opportunity = find_opportunity(&params(), &deep, &book_a).unwrap();
+        assert_eq!(opportunity.buy_leg, Leg::B);
+    }
+
+    #[test]
+    fn legging_is_unwound_on_the_overfilled_leg() {
+        assert_eq!(unwind_order(&params(), 2.0, -2.0), None);
+        assert_eq!(unwind_order(&params(), 2.0, -2.05), None);
+        assert_eq!(
+            unwind_order(&params(), 2.0, -0.5),
+            Some((Leg::A, OrderSide::Sell, 1.5))
+        );
+        assert_eq!(
+            unwind_order(&params(), 0.5, -2.0),
+            Some((Leg::B, OrderSide::Buy, 1.5))
+        );
+        assert_eq!(
+            unwind_order(&params(), -2.0, 0.5),
+            Some((Leg::A, OrderSide::Buy, 1.5))
+        );
+    }
+
+    #[test]
+    fn fill_ratio_tracks_partial_legs() {
+        let ratio = update_fill_ratio(None, 1.0, 2.0, 0.5);
+        assert_eq!(ratio, 0.5);
+        assert_eq!(update_fill_ratio(Some(ratio), 2.0, 2.0, 0.5), 0.75);
+    }
+
+    #[test]
+    fn instance_file_runs_on_the_sim_gateway() {
+        let config = LambdaInstanceConfig::load("basis-arb-btc");
+        let init_params = serde_json::from_value::<BasisArbInitParams>(config.init_params).unwrap();
+        assert_eq!(init_params.symbol_a, "BTC-PERP.SIM");
+        assert_eq!(init_params.symbol_b, "BTC/USD.SIM");
+        serde_json::from_value::<BasisArbStrategyParams>(config.strategy_params).unwrap();
+
+        let instance_config = GenericLambdaInstanceConfig::load("basis-arb-btc");
+        let markets = EngineMarkets::from_configs(&[instance_config.clone()]);
+        assert!(markets.covers(&instance_config).is_ok());
+        assert_eq!(markets.sim_params.unwrap().source_exchange, Exchanges::FTX);
+    }
+}
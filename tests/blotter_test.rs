@@ -0,0 +1,105 @@
+#[cfg(test)]
+mod blotter_test {
+    use rust_quant::model::constants::Exchanges;
+    use rust_quant::model::{OrderFill, OrderSide, OrderStatus, OrderType, OrderUpdate};
+    use rust_quant::view::blotter::Blotter;
+
+    fn blotter() -> Blotter {
+        Blotter::new("swap-mm")
+    }
+
+    fn order_update(client_id: &str, status: OrderStatus, filled_size: f64) -> OrderUpdate {
+        OrderUpdate {
+            exchange: Exchanges::SIM,
+            id: 1,
+            client_id: Some(format!("swap-mm:{}", client_id)),
+            market: "ETH-PERP".to_string(),
+            type_: OrderType::Limit,
+            side: OrderSide::Buy,
+            size: 1.0,
+            price: 1000.0,
+            reduceOnly: false,
+            ioc: false,
+            postOnly: true,
+            status,
+            filledSize: filled_size,
+            remainingSize: 1.0 - filled_size,
+            avgFillPrice: None,
+        }
+    }
+
+    fn order_fill(id: i64, owner: &str) -> OrderFill {
+        OrderFill {
+            exchange: Exchanges::SIM,
+            id,
+            market: "ETH-PERP".to_string(),
+            client_id: Some(format!("{}:SIM:ETH-PERP:Buy:{}", owner, id)),
+            side: OrderSide::Buy,
+            price: 1000.0,
+            size: 0.5,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn incremental_order_updates() {
+        let mut blotter = blotter();
+        assert!(blotter.take_order_update().is_none());
+
+        blotter.on_order_update(&order_update("a", OrderStatus::PendingNew, 0.0), 1);
+        blotter.on_order_update(&order_update("a", OrderStatus::New, 0.0), 2);
+        blotter.on_order_update(&order_update("b", OrderStatus::New, 0.0), 2);
+        let update = blotter.take_order_update().unwrap();
+        assert_eq!(update.seq, 1);
+        assert_eq!(update.upsert.len(), 2);
+        assert!(blotter.take_order_update().is_none());
+
+        // only the changed order is published
+        blotter.on_order_update(&order_update("a", OrderStatus::Open, 0.5), 3);
+        blotter.on_order_update(&order_update("a", OrderStatus::Closed, 1.0), 4);
+        let update = blotter.take_order_update().unwrap();
+        assert_eq!(update.seq, 2);
+        assert_eq!(update.upsert.len(), 1);
+        let row = &update.upsert[0];
+        assert_eq!(row.status, OrderStatus::Closed);
+        assert_eq!(row.filled_size, 1.0);
+        let history: Vec<OrderStatus> = row.history.iter().map(|c| c.status.clone()).collect();
+        assert_eq!(
+            history,
+            vec![
+                OrderStatus::PendingNew,
+                OrderStatus::New,
+                OrderStatus::Open,
+                OrderStatus::Closed
+            ]
+        );
+
+        // closed orders stay in the snapshot
+        let snapshot = blotter.order_snapshot();
+        assert!(snapshot.snapshot);
+        assert_eq!(snapshot.seq, 2);
+        assert_eq!(snapshot.upsert.len(), 2);
+    }
+
+    #[test]
+    fn rolling_fills_of_the_instance() {
+        let mut blotter = blotter();
+        blotter.on_fill(&order_fill(1, "swap-mm"));
+        // same market, other lambda
+        blotter.on_fill(&order_fill(2, "latency-mm"));
+        let update = blotter.take_fill_update().unwrap();
+        assert_eq!(update.upsert.len(), 1);
+        assert_eq!(update.upsert[0].id, "1");
+
+        for id in 3..504 {
+            blotter.on_fill(&order_fill(id, "swap-mm"));
+        }
+        let update = blotter.take_fill_update().unwrap();
+        assert_eq!(update.seq, 2);
+        // fill 1 was published before leaving the window, fill 3 never was
+        assert_eq!(update.remove, vec!["1".to_string()]);
+        assert_eq!(update.upsert.len(), 500);
+        assert_eq!(update.upsert[0].id, "4");
+        assert_eq!(blotter.fill_snapshot().upsert.len(), 500);
+    }
+}
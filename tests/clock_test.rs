@@ -0,0 +1,40 @@
+#[cfg(test)]
+mod clock_test {
+    use rust_quant::core::clock::{Clock, ManualClock};
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    #[test]
+    fn manual_clock_never_moves_backwards() {
+        let clock = ManualClock::new(1000);
+        assert_eq!(clock.now_ms(), 1000);
+        clock.advance(Duration::from_millis(250));
+        assert_eq!(clock.now_ms(), 1250);
+        clock.set_ms(1100);
+        assert_eq!(clock.now_ms(), 1250);
+        clock.set_ms(2000);
+        assert_eq!(clock.now_ns(), 2_000_000_000);
+    }
+
+    #[tokio::test]
+    async fn manual_clock_sleep_wakes_on_advance() {
+        let clock = Arc::new(ManualClock::new(0));
+        let woken = Arc::new(AtomicBool::new(false));
+        let sleeper = {
+            let clock = clock.clone();
+            let woken = woken.clone();
+            tokio::spawn(async move {
+                clock.sleep(Duration::from_millis(100)).await;
+                woken.store(true, Ordering::SeqCst);
+                clock.now_ms()
+            })
+        };
+        tokio::task::yield_now().await;
+        clock.advance(Duration::from_millis(60));
+        tokio::task::yield_now().await;
+        assert!(!woken.load(Ordering::SeqCst));
+        clock.advance(Duration::from_millis(60));
+        assert_eq!(sleeper.await.unwrap(), 120);
+    }
+}
@@ -0,0 +1,164 @@
+#[cfg(test)]
+mod engine_test {
+    use rust_quant::cache::{MarketDepthCache, OrderUpdateCache, ValueCache};
+    use rust_quant::core::clock::{ManualClock, WallClock};
+    use rust_quant::lambda::strategy::StrategyContext;
+    use rust_quant::lambda::{
+        EngineMarkets, GenericLambdaInstanceConfig, LambdaEngine, LambdaParams,
+    };
+    use rust_quant::model::{MeasurementCache, OrderFill, OrderSide, OrderType};
+    use rust_quant::pubsub::local_message_bus::LocalMessageBus;
+    use rust_quant::pubsub::PublishPayload;
+    use std::collections::HashSet;
+    use std::sync::Arc;
+
+    fn instance_config(name: &str, market_depths: &[&str]) -> GenericLambdaInstanceConfig {
+        GenericLambdaInstanceConfig {
+            name: name.to_string(),
+            lambda_params: LambdaParams {
+                market_depths: market_depths
+                    .iter()
+                    .map(|token| token.to_string())
+                    .collect(),
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn shared_gateway_markets() {
+        let markets = EngineMarkets::from_configs(&[
+            instance_config("latency-mm", &["ETH-PERP.FTX", "ETH/USD.FTX"]),
+            instance_config("swap-mm-sim", &["ETH-PERP.SIM", "ETH/USD.SIM"]),
+            instance_config("basis-sim", &["ETH-PERP.SIM", "BTC-PERP.SIM"]),
+        ]);
+        assert!(markets.ftx);
+        assert_eq!(
+            markets.sim_markets,
+            vec![
+                "ETH-PERP".to_string(),
+                "ETH/USD".to_string(),
+                "BTC-PERP".to_string()
+            ]
+        );
+        assert!(markets.sim_params.is_some());
+
+        assert!(markets
+            .covers(&instance_config("other", &["BTC-PERP.FTX", "BTC-PERP.SIM"]))
+            .is_ok());
+        assert!(markets
+            .covers(&instance_config("other", &["SOL-PERP.SIM"]))
+            .is_err());
+        assert!(markets
+            .covers(&instance_config(
+                "other",
+                &["BTC-PERP.FTX", "BTCUSDT.BINANCE"]
+            ))
+            .is_err());
+    }
+
+    #[test]
+    fn no_ftx_gateway_without_ftx_instances() {
+        let markets =
+            EngineMarkets::from_configs(&[instance_config("swap-mm-sim", &["ETH-PERP.SIM"])]);
+        assert!(!markets.ftx);
+        assert!(markets
+            .covers(&instance_config("latency-mm", &["ETH-PERP.FTX"]))
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn lambdas_on_one_market_only_see_their_own_orders() {
+        let order_update_cache = Arc::new(OrderUpdateCache::new());
+        let (message_bus_sender, _message_bus_receiver) =
+            tokio::sync::mpsc::channel::<PublishPayload>(10);
+        let context = |name: &str| {
+            let instance_config = instance_config(name, &["ETH-PERP.FTX"]);
+            StrategyContext {
+                instance_config: instance_config.clone(),
+                market_depth_cache: Arc::new(MarketDepthCache::new()),
+                order_update_cache: order_update_cache.clone(),
+                message_bus_sender: message_bus_sender.clone(),
+                measurement_cache: Arc::new(MeasurementCache::in_memory(WallClock::shared())),
+                value_cache: Arc::new(ValueCache::in_memory(instance_config)),
+                ftx_client: None,
+                clock: WallClock::shared(),
+            }
+        };
+        let swap_mm = context("swap-mm-ethusd")
+            .instrument("ETH-PERP.FTX")
+            .unwrap();
+        let latency_mm = context("latency-mm").instrument("ETH-PERP.FTX").unwrap();
+
+        let swap_mm_order = swap_mm
+            .send_order(OrderSide::Buy, 3000.0, 0.1, OrderType::Limit)
+            .await
+            .unwrap()
+            .unwrap();
+        latency_mm
+            .send_order(OrderSide::Sell, 3010.0, 0.2, OrderType::Limit)
+            .await
+            .unwrap();
+        assert_eq!(order_update_cache.cache.len(), 2);
+
+        let open_orders = swap_mm.get_open_orders(true);
+        assert_eq!(open_orders.len(), 1);
+        assert_eq!(open_orders[0].client_id, Some(swap_mm_order.clone()));
+        assert!(swap_mm.get_open_sell_orders(true).is_empty());
+        assert_eq!(latency_mm.get_open_orders(true).len(), 1);
+        assert!(latency_mm.get_open_buy_orders(true).is_empty());
+
+        let order_fill = OrderFill {
+            market: "ETH-PERP".to_string(),
+            client_id: Some(swap_mm_order),
+            ..Default::default()
+        };
+        assert!(order_fill.is_owned_by("swap-mm-ethusd"));
+        assert!(!order_fill.is_owned_by("latency-mm"));
+        // a fill the gateway could not resolve belongs to no lambda
+        assert!(!OrderFill::default().is_owned_by("swap-mm-ethusd"));
+    }
+
+    #[test]
+    fn instance_files_serve_the_api_on_their_own_ports() {
+        let mut ports = HashSet::new();
+        for entry in std::fs::read_dir("./instance").unwrap() {
+            let path = entry.unwrap().path();
+            let instance_name = path.file_stem().unwrap().to_str().unwrap();
+            let api = GenericLambdaInstanceConfig::read(instance_name)
+                .unwrap()
+                .lambda_params
+                .api;
+            if api.enabled {
+                assert!(
+                    ports.insert(api.port),
+                    "{} port {}",
+                    instance_name,
+                    api.port
+                );
+            }
+        }
+        assert!(ports.len() > 1);
+    }
+
+    #[tokio::test]
+    async fn ftx_instances_start_side_by_side() {
+        let instance_names = ["latency-mm", "swap-mm-ethusd"];
+        let instance_configs = instance_names
+            .iter()
+            .map(|instance_name| GenericLambdaInstanceConfig::read(instance_name).unwrap())
+            .collect();
+        let (engine, _receiver) = LambdaEngine::in_memory(
+            instance_configs,
+            LocalMessageBus::new(),
+            Arc::new(ManualClock::new(0)),
+        );
+        for instance_name in instance_names {
+            engine.start_lambda(instance_name).await.unwrap();
+        }
+        let mut running = engine.running();
+        running.sort();
+        assert_eq!(running, instance_names);
+    }
+}
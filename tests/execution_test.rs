@@ -0,0 +1,302 @@
+#[cfg(test)]
+mod execution_test {
+    use rust_quant::cache::{MarketDepthCache, OrderUpdateCache};
+    use rust_quant::core::clock::{Clock, ManualClock, SharedClock};
+    use rust_quant::lambda::execution::algo::{child_price, next_child_size, scheduled_size};
+    use rust_quant::lambda::execution::{
+        execution_owner, ExecutionAlgo, ExecutionStatus, Executor, ParentOrder,
+    };
+    use rust_quant::model::constants::{Exchanges, PublishChannel};
+    use rust_quant::model::market_data_model::{MarketDepth, PriceLevel, Trade};
+    use rust_quant::model::{
+        CancelOrderRequest, Instrument, MeasurementCache, OrderRequest, OrderSide, OrderStatus,
+        OrderUpdate,
+    };
+    use rust_quant::pubsub::PublishPayload;
+    use std::sync::Arc;
+    use std::time::Duration;
+    use tokio::sync::mpsc::Receiver;
+
+    fn parent(algo: ExecutionAlgo) -> ParentOrder {
+        ParentOrder {
+            side: OrderSide::Buy,
+            size: 4.0,
+            limit_px: None,
+            max_slippage_bp: 10.0,
+            min_child_size: 0.5,
+            algo,
+        }
+    }
+
+    #[test]
+    fn twap_releases_equal_slices_over_the_duration() {
+        let twap = parent(ExecutionAlgo::Twap {
+            duration_ms: 4000,
+            slices: 4,
+        });
+        assert_eq!(scheduled_size(&twap, 0), Some(1.0));
+        assert_eq!(scheduled_size(&twap, 1999), Some(2.0));
+        assert_eq!(scheduled_size(&twap, 10000), Some(4.0));
+
+        assert_eq!(next_child_size(&twap, 0, 0.0, 0.0, 100.0), Some(1.0));
+        assert_eq!(next_child_size(&twap, 500, 1.0, 0.0, 100.0), None);
+        // a missed slice is caught up
+        assert_eq!(next_child_size(&twap, 2000, 1.0, 0.0, 100.0), Some(2.0));
+        assert_eq!(next_child_size(&twap, 5000, 4.0, 0.0, 100.0), None);
+    }
+
+    #[test]
+    fn vwap_keeps_up_with_the_traded_volume() {
+        let vwap = parent(ExecutionAlgo::Vwap {
+            participation_rate: 0.25,
+        });
+        assert_eq!(next_child_size(&vwap, 0, 0.0, 0.0, 8.0), Some(2.0));
+        // what was executed or is still open counts against the share
+        assert_eq!(next_child_size(&vwap, 0, 1.5, 0.5, 8.0), None);
+        assert_eq!(next_child_size(&vwap, 0, 1.5, 0.0, 12.0), Some(1.5));
+        // below the min child size nothing is sent
+        assert_eq!(next_child_size(&vwap, 0, 0.0, 0.0, 1.0), None);
+        // unless it is the rest
+        assert_eq!(next_child_size(&vwap, 0, 3.75, 0.0, 100.0), Some(0.25));
+    }
+
+    #[test]
+    fn iceberg_shows_the_display_size() {
+        let iceberg = parent(ExecutionAlgo::Iceberg { display_size: 1.5 });
+        assert_eq!(next_child_size(&iceberg, 0, 0.0, 0.0, 0.0), Some(1.5));
+        assert_eq!(next_child_size(&iceberg, 0, 3.0, 0.0, 0.0), Some(1.0));
+        // topped up once the resting child filled more than the min child size
+        assert_eq!(next_child_size(&iceberg, 0, 0.2, 1.3, 0.0), None);
+        assert_eq!(next_child_size(&iceberg, 0, 1.0, 0.5, 0.0), Some(1.0));
+    }
+
+    #[test]
+    fn children_are_capped_at_the_limit() {
+        let mut twap = parent(ExecutionAlgo::Twap {
+            duration_ms: 1000,
+            slices: 1,
+        });
+        assert!((child_price(&twap, 1000.0) - 1001.0).abs() < 1e-9);
+        twap.limit_px = Some(1000.5);
+        assert_eq!(child_price(&twap, 1000.0), 1000.5);
+        twap.side = OrderSide::Sell;
+        twap.limit_px = Some(999.5);
+        assert_eq!(child_price(&twap, 1000.0), 999.5);
+    }
+
+    struct Fixture {
+        clock: Arc<ManualClock>,
+        market_depth_cache: Arc<MarketDepthCache>,
+        order_update_cache: Arc<OrderUpdateCache>,
+        receiver: Receiver<PublishPayload>,
+        executor: Executor,
+    }
+
+    fn fixture() -> Fixture {
+        let clock = Arc::new(ManualClock::new(1_000_000));
+        let shared_clock: SharedClock = clock.clone();
+        let market_depth_cache = Arc::new(MarketDepthCache::with_clock(shared_clock.clone()));
+        let order_update_cache = Arc::new(OrderUpdateCache::new());
+        let (sender, receiver) = tokio::sync::mpsc::channel(100);
+        let instrument = Arc::new(Instrument::new(
+            execution_owner("ops").as_str(),
+            Exchanges::FTX,
+            "ETH-PERP",
+            order_update_cache.clone(),
+            sender,
+            Arc::new(MeasurementCache::in_memory(shared_clock.clone())),
+        ));
+        let executor = Executor::new(instrument, market_depth_cache.clone(), shared_clock);
+        let fixture = Fixture {
+            clock,
+            market_depth_cache,
+            order_update_cache,
+            receiver,
+            executor,
+        };
+        fixture.refresh_depth();
+        fixture
+    }
+
+    impl Fixture {
+        fn refresh_depth(&self) {
+            self.market_depth_cache.cache.insert(
+                "ETH-PERP".to_string(),
+                MarketDepth {
+                    timestamp: self.clock.now_ms(),
+                    exchange: Exchanges::FTX,
+                    market: "ETH-PERP".to_string(),
+                    bids: vec![PriceLevel {
+                        price: 99.0,
+                        size: 10.0,
+                    }],
+                    asks: vec![PriceLevel {
+                        price: 101.0,
+                        size: 10.0,
+                    }],
+                },
+            );
+        }
+
+        fn child(&mut self) -> OrderRequest {
+            let payload = self.receiver.try_recv().expect("no child sent");
+            assert_eq!(payload.channel, PublishChannel::OrderRequest.as_ref());
+            serde_json::from_str(payload.payload.as_str()).unwrap()
+        }
+
+        /// the update of a child as the order update cache and then the executor see it
+        async fn update(&self, child: &OrderRequest, status: OrderStatus, filled_size: f64) {
+            let order_update = OrderUpdate {
+                exchange: Exchanges::FTX,
+                client_id: child.client_id.clone(),
+                market: child.market.clone(),
+                side: child.side.clone(),
+                size: child.size,
+                price: child.price,
+                status,
+                filledSize: filled_size,
+                remainingSize: child.size - filled_size,
+                avgFillPrice: Some(child.price),
+                ..Default::default()
+            };
+            self.order_update_cache
+                .cache
+                .insert(order_update.cache_key(), order_update.clone());
+            assert!(self.executor.on_order_update(&order_update).await);
+        }
+    }
+
+    #[tokio::test]
+    async fn twap_children_are_tracked_until_done() {
+        let mut fixture = fixture();
+        let id = fixture
+            .executor
+            .submit(ParentOrder {
+                limit_px: Some(101.05),
+                ..parent(ExecutionAlgo::Twap {
+                    duration_ms: 2000,
+                    slices: 2,
+                })
+            })
+            .await
+            .unwrap();
+        let child = fixture.child();
+        assert!(OrderRequest::is_owned_by(
+            child.client_id.as_deref(),
+            execution_owner("ops").as_str()
+        ));
+        assert_eq!((child.size, child.price, child.ioc), (2.0, 101.05, true));
+        let report = fixture.executor.report(id.as_str()).unwrap();
+        assert_eq!((report.open_size, report.child_cnt), (2.0, 1));
+
+        fixture.update(&child, OrderStatus::Closed, 2.0).await;
+        let report = fixture.executor.report(id.as_str()).unwrap();
+        assert_eq!((report.filled_size, report.open_size), (2.0, 0.0));
+        assert_eq!(report.status, ExecutionStatus::Working);
+        // the next slice is not due yet
+        fixture.executor.work().await.unwrap();
+        assert!(fixture.receiver.try_recv().is_err());
+
+        fixture.clock.advance(Duration::from_millis(1000));
+        fixture.refresh_depth();
+        fixture.executor.work().await.unwrap();
+        let child = fixture.child();
+        assert_eq!(child.size, 2.0);
+        fixture.update(&child, OrderStatus::Closed, 2.0).await;
+        let report = fixture.executor.report(id.as_str()).unwrap();
+        assert_eq!(report.status, ExecutionStatus::Done);
+        assert!((report.avg_fill_px.unwrap() - 101.05).abs() < 1e-9);
+        assert_eq!(report.child_cnt, 2);
+    }
+
+    #[tokio::test]
+    async fn vwap_children_follow_the_trades_of_the_market() {
+        let mut fixture = fixture();
+        let trade = |market: &str, size: f64| Trade {
+            timestamp: 1_000_000,
+            exchange: Exchanges::FTX,
+            market: market.to_string(),
+            side: OrderSide::Sell,
+            price: 101.0,
+            size,
+        };
+        let id = fixture
+            .executor
+            .submit(parent(ExecutionAlgo::Vwap {
+                participation_rate: 0.5,
+            }))
+            .await
+            .unwrap();
+        // nothing traded yet
+        assert!(fixture.receiver.try_recv().is_err());
+
+        assert!(!fixture.executor.on_trade(&trade("BTC-PERP", 10.0)));
+        assert!(fixture.executor.on_trade(&trade("ETH-PERP", 3.0)));
+        fixture.executor.work().await.unwrap();
+        let child = fixture.child();
+        assert_eq!((child.size, child.ioc), (1.5, true));
+        fixture.update(&child, OrderStatus::Closed, 1.5).await;
+
+        assert!(fixture.executor.on_trade(&trade("ETH-PERP", 0.5)));
+        fixture.executor.work().await.unwrap();
+        assert!(fixture.receiver.try_recv().is_err());
+
+        assert!(fixture.executor.on_trade(&trade("ETH-PERP", 20.0)));
+        fixture.executor.work().await.unwrap();
+        let child = fixture.child();
+        assert_eq!(child.size, 2.5);
+        fixture.update(&child, OrderStatus::Closed, 2.5).await;
+        let report = fixture.executor.report(id.as_str()).unwrap();
+        assert_eq!(report.status, ExecutionStatus::Done);
+        assert!(!fixture.executor.on_trade(&trade("ETH-PERP", 1.0)));
+    }
+
+    #[tokio::test]
+    async fn iceberg_is_topped_up_as_it_fills_until_cancelled() {
+        let mut fixture = fixture();
+        let id = fixture
+            .executor
+            .submit(ParentOrder {
+                side: OrderSide::Sell,
+                limit_px: Some(102.0),
+                ..parent(ExecutionAlgo::Iceberg { display_size: 1.5 })
+            })
+            .await
+            .unwrap();
+        let resting = fixture.child();
+        assert_eq!(
+            (resting.size, resting.price, resting.ioc),
+            (1.5, 102.0, false)
+        );
+        fixture.update(&resting, OrderStatus::Open, 0.0).await;
+        fixture.executor.work().await.unwrap();
+        assert!(fixture.receiver.try_recv().is_err());
+
+        fixture.update(&resting, OrderStatus::Open, 1.0).await;
+        fixture.executor.work().await.unwrap();
+        let top_up = fixture.child();
+        assert_eq!((top_up.size, top_up.price), (1.0, 102.0));
+        fixture.update(&top_up, OrderStatus::Open, 0.0).await;
+        let report = fixture.executor.report(id.as_str()).unwrap();
+        assert_eq!((report.filled_size, report.open_size), (1.0, 1.5));
+
+        fixture.executor.cancel(id.as_str()).await.unwrap();
+        let mut cancelled = vec![];
+        while let Ok(payload) = fixture.receiver.try_recv() {
+            assert_eq!(payload.channel, PublishChannel::CancelOrder.as_ref());
+            let request: CancelOrderRequest =
+                serde_json::from_str(payload.payload.as_str()).unwrap();
+            cancelled.push(request.client_id);
+        }
+        cancelled.sort();
+        let mut children = vec![resting.client_id.unwrap(), top_up.client_id.unwrap()];
+        children.sort();
+        assert_eq!(cancelled, children);
+        assert_eq!(
+            fixture.executor.report(id.as_str()).unwrap().status,
+            ExecutionStatus::Cancelled
+        );
+        fixture.executor.work().await.unwrap();
+        assert!(fixture.receiver.try_recv().is_err());
+    }
+}
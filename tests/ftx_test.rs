@@ -5,7 +5,8 @@ mod test_common;
 mod ftx_test {
     use rust_quant::model::{OrderRequest, OrderSide, OrderType};
     use rust_quant::model::constants::Exchanges;
-    use rust_quant::ftx::{FtxPlaceOrder, FtxOrderStatus, FtxOrderSide, FtxOrderType};
+    use rust_quant::ftx::{FtxPlaceOrder, FtxOrderSide, FtxOrderType, FtxApiError, ApiResponse, FtxOrderData};
+    use reqwest::StatusCode;
 
     #[test]
     pub fn transform_place_market_order() {
@@ -48,4 +49,36 @@ mod ftx_test {
         assert_eq!(fo.price, Some(10.0));
         assert!(matches!(fo.side, FtxOrderSide::sell))
     }
-}
\ No newline at end of file
+
+    #[test]
+    pub fn classify_api_errors() {
+        let error = FtxApiError::from_response(StatusCode::BAD_REQUEST, "Post only order would cross");
+        assert!(matches!(error, FtxApiError::PostOnlyWouldCross(_)));
+        let error = FtxApiError::from_response(StatusCode::BAD_REQUEST, "Not enough balances");
+        assert!(matches!(error, FtxApiError::InsufficientMargin(_)));
+        let error = FtxApiError::from_response(StatusCode::BAD_REQUEST, "Account does not have enough margin for order.");
+        assert!(matches!(error, FtxApiError::InsufficientMargin(_)));
+        let error = FtxApiError::from_response(StatusCode::BAD_REQUEST, "Invalid margin mode");
+        assert!(matches!(error, FtxApiError::Other(_, _)));
+        let error = FtxApiError::from_response(StatusCode::BAD_REQUEST, "Order already closed");
+        assert!(matches!(error, FtxApiError::OrderAlreadyClosed(_)));
+        let error = FtxApiError::from_response(StatusCode::TOO_MANY_REQUESTS, "Do not send more than 30 requests per 200ms");
+        assert!(matches!(error, FtxApiError::RateLimited(_)));
+        assert!(error.is_retryable());
+        let error = FtxApiError::from_response(StatusCode::UNAUTHORIZED, "Not logged in");
+        assert!(matches!(error, FtxApiError::AuthFailure(_)));
+        assert!(!error.is_retryable());
+        let error = FtxApiError::from_response(StatusCode::BAD_REQUEST, "Invalid price");
+        assert!(matches!(error, FtxApiError::Other(StatusCode::BAD_REQUEST, _)));
+    }
+
+    #[test]
+    pub fn parse_place_order_response() {
+        let body = r#"{"success": true, "result": {"id": 9596912, "clientId": "FTX:ETH-PERP:Buy:1", "market": "ETH-PERP", "type": "limit", "side": "buy", "size": 0.001, "price": 1000.0, "reduceOnly": false, "ioc": false, "postOnly": true, "status": "new", "filledSize": 0.0, "remainingSize": 0.001, "avgFillPrice": null, "createdAt": "2021-10-01T00:00:00.000000+00:00", "future": "ETH-PERP"}}"#;
+        let response = serde_json::from_str::<ApiResponse<FtxOrderData>>(body).unwrap();
+        assert!(response.success);
+        let order_update = response.result.unwrap().to_order_update();
+        assert_eq!(order_update.id, 9596912);
+        assert_eq!(order_update.client_id, Some("FTX:ETH-PERP:Buy:1".to_string()));
+    }
+}
@@ -0,0 +1,51 @@
+#[cfg(test)]
+mod funding_carry_test {
+    use rust_quant::lambda::strategy::funding_carry::carry::{
+        basis_bp, expected_carry_bp, rebalance_order, target_position,
+    };
+    use rust_quant::lambda::strategy::funding_carry::params::FundingCarryStrategyParams;
+    use rust_quant::model::OrderSide;
+
+    fn params() -> FundingCarryStrategyParams {
+        FundingCarryStrategyParams {
+            max_size: 1.0,
+            clip_size: 0.25,
+            min_carry_bp: 10.0,
+            full_carry_bp: 50.0,
+            exit_carry_bp: 0.0,
+            horizon_hours: 24.0,
+            fee_perp_bp: 2.0,
+            fee_spot_bp: 3.0,
+            rebalance_threshold: 0.01,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn carry_counts_funding_basis_and_fees() {
+        assert!((basis_bp(1001.0, 1000.0) - 10.0).abs() < 1e-9);
+        // 1bp an hour over a day, 10bp premium, 10bp round trip fees
+        let carry = expected_carry_bp(&params(), 0.0001, 10.0);
+        assert!((carry - 24.0).abs() < 1e-9);
+        assert!(expected_carry_bp(&params(), -0.0001, 10.0) < 0.0);
+    }
+
+    #[test]
+    fn position_scales_with_carry_and_exits_when_negative() {
+        assert_eq!(target_position(&params(), 5.0, 0.0), 0.0);
+        assert_eq!(target_position(&params(), 30.0, 0.0), -0.5);
+        assert_eq!(target_position(&params(), 80.0, 0.0), -1.0);
+        // barely worth opening still opens a clip
+        assert_eq!(target_position(&params(), 10.0, 0.0), -0.25);
+        // kept between exit and entry, closed below exit
+        assert_eq!(target_position(&params(), 5.0, -0.5), -0.5);
+        assert_eq!(target_position(&params(), -1.0, -0.5), 0.0);
+    }
+
+    #[test]
+    fn rebalance_in_clips_beyond_threshold() {
+        assert_eq!(rebalance_order(&params(), -0.5, -0.505), None);
+        assert_eq!(rebalance_order(&params(), 0.0, -1.0), Some((OrderSide::Sell, 0.25)));
+        assert_eq!(rebalance_order(&params(), -0.5, -0.375), Some((OrderSide::Buy, 0.125)));
+    }
+}
@@ -0,0 +1,127 @@
+#[cfg(test)]
+use std::sync::Arc;
+use rust_quant::lambda::strategy::swap_mm::hedger::Hedger;
+
+#[cfg(test)]
+mod test_common;
+
+#[cfg(test)]
+fn spawn_thread_hedger_subscribe(hedger: Arc<Hedger>) {
+    tokio::spawn(async move {
+        log::info!("spawn_thread_hedger_subscribe");
+        hedger.subscribe().await
+    });
+}
+
+#[cfg(test)]
+mod hedger_test {
+    use super::*;
+    use rust_quant::cache::{MarketDepthCache, OrderUpdateCache};
+    use rust_quant::core::clock::WallClock;
+    use rust_quant::lambda::strategy::swap_mm::hedger::{hedge_order, hedge_price, slippage_bp};
+    use rust_quant::lambda::strategy::swap_mm::params::HedgerParams;
+    use rust_quant::model::constants::Exchanges;
+    use rust_quant::model::{Instrument, MeasurementCache, OrderFill, OrderSide};
+    use rust_quant::pubsub::simple_message_bus::RedisBackedMessageBus;
+    use std::sync::Arc;
+    use test_common::common::*;
+
+    fn params() -> HedgerParams {
+        HedgerParams {
+            threshold: 0.2,
+            min_size: 0.5,
+            max_slippage_bp: 10.0,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn small_fills_accumulate_until_min_size() {
+        assert_eq!(hedge_order(&params(), 0.25, 0.0), None);
+        assert_eq!(hedge_order(&params(), 0.5, 0.0), Some((OrderSide::Sell, 0.5)));
+        assert_eq!(hedge_order(&params(), -0.75, 0.0), Some((OrderSide::Buy, 0.75)));
+    }
+
+    #[test]
+    fn open_hedges_offset_the_delta() {
+        assert_eq!(hedge_order(&params(), 0.5, -0.5), None);
+        assert_eq!(hedge_order(&params(), 1.0, -0.5), Some((OrderSide::Sell, 0.5)));
+        // an overhedge is taken back
+        assert_eq!(hedge_order(&params(), 0.0, -0.5), Some((OrderSide::Buy, 0.5)));
+    }
+
+    #[test]
+    fn price_cap_and_slippage() {
+        assert!((hedge_price(&params(), &OrderSide::Buy, 1000.0) - 1001.0).abs() < 1e-9);
+        assert!((hedge_price(&params(), &OrderSide::Sell, 1000.0) - 999.0).abs() < 1e-9);
+        assert!((slippage_bp(&OrderSide::Buy, 1000.0, 1000.5) - 5.0).abs() < 1e-9);
+        assert!((slippage_bp(&OrderSide::Sell, 1000.0, 1000.5) + 5.0).abs() < 1e-9);
+    }
+
+    #[tokio::test]
+    async fn can_init() {
+        before_each();
+        let message_bus = Arc::new(RedisBackedMessageBus::new().await.unwrap());
+        spawn_thread_message_bus(message_bus.clone());
+        sleep(100).await;
+
+        let order_update_cache = Arc::new(OrderUpdateCache::new());
+        spawn_thread_order_update_cache(order_update_cache.clone());
+        sleep(100).await;
+
+        let measurement_cache = Arc::new(MeasurementCache::new().await);
+
+        let depth_instrument = Arc::new(Instrument::new(
+            "hedger-test",
+            Exchanges::Unknown,
+            "ETH-PERP",
+            order_update_cache.clone(),
+            message_bus.publish_tx.clone(),
+            measurement_cache.clone(),
+        ));
+
+        let hedge_instrument = Arc::new(Instrument::new(
+            "hedger-test",
+            Exchanges::Unknown,
+            "ETH/USD",
+            order_update_cache.clone(),
+            message_bus.publish_tx.clone(),
+            measurement_cache.clone(),
+        ));
+
+        let hedger = Arc::new(Hedger::new(
+            depth_instrument.clone(),
+            hedge_instrument.clone(),
+            Arc::new(MarketDepthCache::new()),
+            WallClock::shared(),
+        ));
+        hedger.set_params(params());
+        spawn_thread_hedger_subscribe(hedger.clone());
+        sleep(100).await;
+
+        let count = 10;
+        for _i in 0..count {
+            let order_fill = OrderFill {
+                exchange: Exchanges::Unknown,
+                market: "ETH-PERP".to_string(),
+                side: OrderSide::Buy,
+                size: 0.25,
+                client_id: Some("hedger-test:order".to_string()),
+                ..Default::default()
+            };
+            message_bus
+                .publish_spawn(
+                    rust_quant::model::constants::PublishChannel::OrderFill.to_string(),
+                    order_fill,
+                )
+                .unwrap();
+        }
+
+        sleep(100).await;
+
+        // two fills per hedge at min size 0.5
+        assert_eq!(hedger.open_hedges(), 5);
+        assert_eq!(hedger.unhedged_delta(), 2.5);
+        assert_eq!(hedger.in_flight(), -2.5);
+    }
+}
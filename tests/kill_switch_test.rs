@@ -0,0 +1,169 @@
+#[cfg(test)]
+mod kill_switch_test {
+    use rust_quant::core::clock::{ManualClock, SharedClock};
+    use rust_quant::core::kill_switch::{KillSwitch, KillSwitchScope};
+    use rust_quant::core::OrderGateway;
+    use rust_quant::model::constants::{Exchanges, PublishChannel};
+    use rust_quant::model::market_data_model::{MarketDepth, PriceLevel};
+    use rust_quant::model::{OrderRequest, OrderSide, OrderStatus, OrderType, OrderUpdate};
+    use rust_quant::pubsub::local_message_bus::LocalMessageBus;
+    use rust_quant::pubsub::PublishPayload;
+    use rust_quant::sim::{SimOrderGateway, SimParams};
+    use std::sync::Arc;
+    use std::time::Duration;
+    use tokio::sync::mpsc::Receiver;
+
+    fn kill_switch_path() -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("kill_switch-{}.engaged", uuid::Uuid::new_v4()))
+    }
+
+    fn order_request(client_id: &str) -> OrderRequest {
+        OrderRequest {
+            exchange: Exchanges::SIM,
+            market: "ETH-PERP".to_string(),
+            side: OrderSide::Buy,
+            price: 99.0,
+            size: 1.0,
+            type_: OrderType::Limit,
+            ioc: false,
+            post_only: false,
+            client_id: Some(client_id.to_string()),
+        }
+    }
+
+    async fn next_order_update(receiver: &mut Receiver<PublishPayload>) -> OrderUpdate {
+        loop {
+            let payload = tokio::time::timeout(Duration::from_secs(1), receiver.recv())
+                .await
+                .expect("no order update")
+                .unwrap();
+            if payload.channel == PublishChannel::OrderUpdate.to_string() {
+                return serde_json::from_str(payload.payload.as_str()).unwrap();
+            }
+        }
+    }
+
+    #[test]
+    fn engage_and_rearm_survive_a_restart() {
+        let path = kill_switch_path();
+        let kill_switch = KillSwitch::persistent(&path);
+        assert!(!kill_switch.is_engaged(&Exchanges::FTX, "ETH-PERP"));
+
+        kill_switch.engage(&KillSwitchScope::all());
+        assert!(kill_switch.is_engaged(&Exchanges::FTX, "ETH-PERP"));
+        assert!(KillSwitch::persistent(&path).is_engaged(&Exchanges::FTX, "ETH-PERP"));
+
+        kill_switch.rearm(&KillSwitchScope::all());
+        assert!(!kill_switch.is_engaged(&Exchanges::FTX, "ETH-PERP"));
+        assert!(!path.exists());
+        assert!(!KillSwitch::persistent(&path).is_engaged(&Exchanges::FTX, "ETH-PERP"));
+    }
+
+    #[test]
+    fn engage_and_rearm_only_their_scope() {
+        let path = kill_switch_path();
+        let kill_switch = KillSwitch::persistent(&path);
+        let sim_eth = KillSwitchScope {
+            exchange: Some(Exchanges::SIM),
+            market: Some("ETH-PERP".to_string()),
+        };
+        kill_switch.engage(&sim_eth);
+        assert!(kill_switch.is_engaged(&Exchanges::SIM, "ETH-PERP"));
+        assert!(!kill_switch.is_engaged(&Exchanges::SIM, "BTC-PERP"));
+        assert!(!kill_switch.is_engaged(&Exchanges::FTX, "ETH-PERP"));
+        assert_eq!(
+            KillSwitch::persistent(&path).engaged(),
+            vec![sim_eth.clone()]
+        );
+
+        // engaging the exchange takes over its markets
+        kill_switch.engage(&KillSwitchScope::exchange(Exchanges::SIM));
+        assert_eq!(
+            kill_switch.engaged(),
+            vec![KillSwitchScope::exchange(Exchanges::SIM)]
+        );
+        assert!(kill_switch.is_engaged(&Exchanges::SIM, "BTC-PERP"));
+
+        // a market re-arm leaves its exchange engaged
+        kill_switch.rearm(&sim_eth);
+        assert!(kill_switch.is_engaged(&Exchanges::SIM, "ETH-PERP"));
+
+        kill_switch.engage(&KillSwitchScope::exchange(Exchanges::FTX));
+        kill_switch.rearm(&KillSwitchScope::exchange(Exchanges::SIM));
+        assert!(!kill_switch.is_engaged(&Exchanges::SIM, "ETH-PERP"));
+        assert!(kill_switch.is_engaged(&Exchanges::FTX, "ETH-PERP"));
+
+        kill_switch.rearm(&KillSwitchScope::all());
+        assert!(kill_switch.engaged().is_empty());
+        assert!(!path.exists());
+    }
+
+    #[tokio::test]
+    async fn sim_gateway_rejects_orders_while_engaged() {
+        let kill_switch = Arc::new(KillSwitch::new());
+        let (message_bus_sender, mut receiver) = tokio::sync::mpsc::channel(100);
+        let clock: SharedClock = Arc::new(ManualClock::new(0));
+        let sim_params = SimParams {
+            source_exchange: Exchanges::SIM,
+            latency_ms: 0,
+            ..Default::default()
+        };
+        let sim_order_gateway = SimOrderGateway::new(
+            message_bus_sender,
+            sim_params,
+            vec!["ETH-PERP".to_string()],
+            kill_switch.clone(),
+            clock,
+        );
+        let bus = LocalMessageBus::new();
+        let publish = |channel: String, payload: Vec<u8>| bus.publish(channel.as_str(), &payload);
+
+        let test = async {
+            // the gateway subscribes on its first poll
+            tokio::task::yield_now().await;
+            let market_depth = MarketDepth {
+                timestamp: 0,
+                exchange: Exchanges::SIM,
+                market: "ETH-PERP".to_string(),
+                bids: vec![PriceLevel {
+                    price: 99.0,
+                    size: 1.0,
+                }],
+                asks: vec![PriceLevel {
+                    price: 101.0,
+                    size: 1.0,
+                }],
+            };
+            publish(
+                format!("{}:SIM:ETH-PERP", PublishChannel::MarketDepth),
+                serde_json::to_vec(&market_depth).unwrap(),
+            );
+            tokio::task::yield_now().await;
+
+            kill_switch.engage(&KillSwitchScope::exchange(Exchanges::SIM));
+            publish(
+                PublishChannel::OrderRequest.to_string(),
+                serde_json::to_vec(&order_request("1")).unwrap(),
+            );
+            let rejected = next_order_update(&mut receiver).await;
+            assert_eq!(rejected.client_id, Some("1".to_string()));
+            assert_eq!(rejected.status, OrderStatus::Failed);
+
+            kill_switch.rearm(&KillSwitchScope::exchange(Exchanges::SIM));
+            publish(
+                PublishChannel::OrderRequest.to_string(),
+                serde_json::to_vec(&order_request("2")).unwrap(),
+            );
+            let accepted = next_order_update(&mut receiver).await;
+            assert_eq!(accepted.client_id, Some("2".to_string()));
+            assert_eq!(accepted.status, OrderStatus::New);
+        };
+        let run = async {
+            tokio::select! {
+                result = sim_order_gateway.subscribe() => panic!("sim gateway completed: {:?}", result),
+                _ = test => {}
+            }
+        };
+        LocalMessageBus::scope(bus.clone(), run).await;
+    }
+}
@@ -0,0 +1,89 @@
+#[cfg(test)]
+mod latency_mm_test {
+    use rust_quant::lambda::strategy::latency_mm::params::LatencyMMStrategyParams;
+    use rust_quant::lambda::strategy::latency_mm::quoter::{
+        inventory_skew_bp, should_requote, target_quotes, update_lead_lag_offset,
+    };
+    use rust_quant::model::{OrderSide, QueuePosition};
+
+    fn params() -> LatencyMMStrategyParams {
+        LatencyMMStrategyParams {
+            tick_size: 0.1,
+            half_spread_bp: 10.0,
+            base_size: 1.0,
+            max_position: 4.0,
+            inventory_skew_bp: 20.0,
+            requote_threshold_bp: 1.0,
+            max_passive_gap_bp: 5.0,
+            queue_keep_size: 2.0,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn lead_lag_offset_follows_the_basis() {
+        let offset = update_lead_lag_offset(None, 1001.0, 1000.0, 0.5);
+        assert_eq!(offset, 1.0);
+        let offset = update_lead_lag_offset(Some(offset), 1003.0, 1000.0, 0.5);
+        assert_eq!(offset, 2.0);
+    }
+
+    #[test]
+    fn quotes_around_fair_value_without_crossing() {
+        let (bid, ask) = target_quotes(&params(), 1000.0, 0.0, 990.0, 1010.0);
+        assert_eq!(bid, Some(999.0));
+        assert_eq!(ask, Some(1001.0));
+
+        // a tight book keeps the quotes post only
+        let (bid, ask) = target_quotes(&params(), 1000.0, 0.0, 999.9, 1000.1);
+        assert_eq!(bid, Some(999.0));
+        assert_eq!(ask, Some(1001.0));
+        let (bid, _ask) = target_quotes(&params(), 1000.0, 0.0, 998.0, 998.5);
+        assert!((bid.unwrap() - 998.4).abs() < 1e-9);
+    }
+
+    #[test]
+    fn inventory_skews_quotes_and_stops_the_growing_side() {
+        assert_eq!(inventory_skew_bp(&params(), 2.0), -10.0);
+        assert_eq!(inventory_skew_bp(&params(), -8.0), 20.0);
+
+        let (bid, ask) = target_quotes(&params(), 1000.0, 2.0, 990.0, 1010.0);
+        assert_eq!(bid, Some(998.0));
+        assert_eq!(ask, Some(1000.0));
+
+        let (bid, ask) = target_quotes(&params(), 1000.0, 4.0, 990.0, 1010.0);
+        assert_eq!(bid, None);
+        assert!(ask.is_some());
+    }
+
+    #[test]
+    fn requote_keeps_queue_position_for_small_passive_gaps() {
+        let params = params();
+        // within threshold
+        assert!(!should_requote(&params, &OrderSide::Buy, 999.0, 999.05, 1000.0, None));
+        // resting bid above the target is requoted immediately
+        assert!(should_requote(&params, &OrderSide::Buy, 999.2, 999.0, 1000.0, Some(0.0)));
+        // target moved away from the resting ask, same rule
+        assert!(should_requote(&params, &OrderSide::Sell, 1000.8, 1001.0, 1000.0, Some(0.0)));
+        // bid fell behind: keep while near the front of the queue
+        assert!(!should_requote(&params, &OrderSide::Buy, 999.0, 999.2, 1000.0, Some(1.0)));
+        assert!(should_requote(&params, &OrderSide::Buy, 999.0, 999.2, 1000.0, Some(5.0)));
+        assert!(should_requote(&params, &OrderSide::Buy, 999.0, 999.2, 1000.0, None));
+        // unless the gap grows too large
+        assert!(should_requote(&params, &OrderSide::Buy, 999.0, 999.6, 1000.0, Some(1.0)));
+    }
+
+    #[test]
+    fn queue_position_shrinks_from_the_front() {
+        let mut queue_position = QueuePosition::new(5.0, 1.0);
+        assert_eq!(queue_position.queue_ahead, 4.0);
+        assert_eq!(queue_position.on_level_size(3.0, 1.0), 0.0);
+        assert_eq!(queue_position.queue_ahead, 2.0);
+        // size joining behind us does not move us back
+        queue_position.on_level_size(6.0, 1.0);
+        assert_eq!(queue_position.queue_ahead, 2.0);
+        // the decrease beyond the queue reached us
+        assert_eq!(queue_position.on_level_size(1.0, 1.0), 3.0);
+        assert_eq!(queue_position.queue_ahead, 0.0);
+    }
+}
@@ -8,7 +8,8 @@ mod order_update_cache_test {
     use rust_quant::cache::OrderUpdateCache;
 
     use rust_quant::model::constants::PublishChannel;
-    use rust_quant::model::OrderStatus;
+    use rust_quant::model::constants::Exchanges;
+    use rust_quant::model::{ModifyOrderRequest, OrderStatus};
     use rust_quant::pubsub::simple_message_bus::RedisBackedMessageBus;
     use rust_quant::pubsub::PublishPayload;
 
@@ -23,6 +24,32 @@ mod order_update_cache_test {
         assert_eq!(order_update_cache.cache.len(), 0)
     }
 
+    #[tokio::test]
+    async fn applied_updates_follow_the_cache() {
+        use rust_quant::pubsub::simple_message_bus::MessageConsumer;
+
+        let order_update_cache = OrderUpdateCache::new();
+        let mut applied = order_update_cache.subscribe_applied();
+
+        for status in [OrderStatus::New, OrderStatus::Closed] {
+            let order_update = rust_quant::model::OrderUpdate {
+                client_id: Some("order-1".to_string()),
+                status,
+                ..Default::default()
+            };
+            let msg = serde_json::to_vec(&order_update).unwrap();
+            order_update_cache.consume(&msg).await.unwrap();
+        }
+
+        // each update is seen once the cache applied it, in order
+        let first = applied.recv().await.unwrap();
+        assert_eq!(first.status, OrderStatus::New);
+        let second = applied.recv().await.unwrap();
+        assert_eq!(second.status, OrderStatus::Closed);
+        assert_eq!(order_update_cache.cache.len(), 0);
+        assert!(applied.try_recv().is_err());
+    }
+
     #[tokio::test]
     async fn insert_cache() {
         before_each();
@@ -111,4 +138,44 @@ mod order_update_cache_test {
         sleep(4000).await;
         assert_eq!(order_update_cache.cache.len(), 0)
     }
+
+    #[test]
+    fn accept_modify_order() {
+        let order_update_cache = OrderUpdateCache::new();
+        let order_update = rust_quant::model::OrderUpdate {
+            client_id: Some("order-1".to_string()),
+            status: OrderStatus::Open,
+            price: 100.0,
+            size: 1.0,
+            ..Default::default()
+        };
+        order_update_cache
+            .cache
+            .insert("order-1".to_string(), order_update);
+
+        let modify_order_request = ModifyOrderRequest {
+            exchange: Exchanges::FTX,
+            market: "".to_string(),
+            client_id: "order-1".to_string(),
+            new_client_id: "order-2".to_string(),
+            price: 101.0,
+            size: 2.0,
+        };
+        assert!(OrderUpdateCache::accept_modify_order(
+            &order_update_cache.cache,
+            &modify_order_request
+        ));
+        let original = order_update_cache.cache.get("order-1").unwrap().clone();
+        assert_eq!(original.status, OrderStatus::PendingCancel);
+        let replacement = order_update_cache.cache.get("order-2").unwrap().clone();
+        assert_eq!(replacement.status, OrderStatus::PendingNew);
+        assert_eq!(replacement.price, 101.0);
+        assert_eq!(replacement.size, 2.0);
+
+        // a PendingCancel order cannot be modified again
+        assert!(!OrderUpdateCache::accept_modify_order(
+            &order_update_cache.cache,
+            &modify_order_request
+        ));
+    }
 }
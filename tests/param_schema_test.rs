@@ -0,0 +1,53 @@
+#[cfg(test)]
+mod param_schema_test {
+    use rust_quant::lambda::strategy::latency_mm::params::{
+        param_schema, LatencyMMStrategyParams,
+    };
+    use rust_quant::lambda::strategy::{ParamSchema, ParamSpec};
+    use serde_json::json;
+
+    fn params() -> serde_json::Value {
+        serde_json::to_value(LatencyMMStrategyParams::default()).unwrap()
+    }
+
+    #[test]
+    fn string_into_a_float_is_rejected() {
+        let schema = param_schema();
+        let result = schema.apply_update(&params(), "base_size", &json!("0.1"));
+        assert!(result.is_err());
+        let params = schema
+            .apply_update(&params(), "base_size", &json!(0.1))
+            .unwrap();
+        assert_eq!(params["base_size"], json!(0.1));
+    }
+
+    #[test]
+    fn bounds_enum_and_cold_params() {
+        let schema = param_schema();
+        assert!(schema
+            .apply_update(&params(), "offset_ewma_alpha", &json!(1.5))
+            .is_err());
+        assert!(schema.apply_update(&params(), "state", &json!("Live")).is_ok());
+        assert!(schema.apply_update(&params(), "state", &json!("Running")).is_err());
+        assert!(schema.apply_update(&params(), "tick_size", &json!(0.5)).is_err());
+        assert!(schema.apply_update(&params(), "unknown", &json!(1)).is_err());
+    }
+
+    #[test]
+    fn step_and_nullable() {
+        let spec = ParamSpec::float("size", "").min(0.0).step(0.001);
+        assert!(spec.validate(&json!(0.003)).is_ok());
+        assert!(spec.validate(&json!(0.0035)).is_err());
+        assert!(spec.validate(&json!(null)).is_err());
+        assert!(spec.nullable().validate(&json!(null)).is_ok());
+    }
+
+    #[test]
+    fn without_a_spec_the_json_type_is_kept() {
+        let schema = ParamSchema::default();
+        let params = json!({"base_size": 1.0, "name": "a"});
+        assert!(schema.apply_update(&params, "base_size", &json!("x")).is_err());
+        assert!(schema.apply_update(&params, "base_size", &json!(2)).is_ok());
+        assert!(schema.apply_update(&params, "name", &json!("b")).is_ok());
+    }
+}
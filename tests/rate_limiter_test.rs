@@ -0,0 +1,113 @@
+#[cfg(test)]
+mod rate_limiter_test {
+    use rust_quant::ftx::rate_limiter::{
+        BucketParams, EndpointClass, RateLimiter, RateLimiterParams,
+    };
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+    use tokio::time::Instant;
+
+    fn params() -> RateLimiterParams {
+        RateLimiterParams {
+            global: BucketParams {
+                capacity: 4.0,
+                refill_per_sec: 20.0,
+            },
+            place_order: BucketParams {
+                capacity: 2.0,
+                refill_per_sec: 10.0,
+            },
+            cancel_order: BucketParams {
+                capacity: 4.0,
+                refill_per_sec: 20.0,
+            },
+            other: BucketParams {
+                capacity: 1.0,
+                refill_per_sec: 10.0,
+            },
+            cancel_reserve: 1.0,
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn waits_when_class_budget_exhausted() {
+        let rate_limiter = RateLimiter::new(params());
+        let start = Instant::now();
+        rate_limiter.acquire(EndpointClass::PlaceOrder).await;
+        rate_limiter.acquire(EndpointClass::PlaceOrder).await;
+        assert!(start.elapsed() < Duration::from_millis(20));
+        rate_limiter.acquire(EndpointClass::PlaceOrder).await;
+        assert!(start.elapsed() >= Duration::from_millis(90));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn cancel_reserve_is_kept_for_cancels() {
+        let rate_limiter = RateLimiter::new(params());
+        rate_limiter.acquire(EndpointClass::PlaceOrder).await;
+        rate_limiter.acquire(EndpointClass::PlaceOrder).await;
+        rate_limiter.acquire(EndpointClass::Other).await;
+        // one global token left, reserved for cancels
+        let start = Instant::now();
+        rate_limiter.acquire(EndpointClass::CancelOrder).await;
+        assert!(start.elapsed() < Duration::from_millis(20));
+        assert!(rate_limiter.saturation() > 0.9);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn blocks_after_rate_limited() {
+        let rate_limiter = RateLimiter::new(params());
+        rate_limiter.on_rate_limited(Duration::from_millis(100));
+        assert_eq!(rate_limiter.saturation(), 1.0);
+        let start = Instant::now();
+        rate_limiter.acquire(EndpointClass::CancelOrder).await;
+        assert!(start.elapsed() >= Duration::from_millis(90));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn dropped_cancel_stops_blocking_other_classes() {
+        let rate_limiter = RateLimiter::new(params());
+        rate_limiter.on_rate_limited(Duration::from_millis(100));
+        let cancel = rate_limiter.acquire(EndpointClass::CancelOrder);
+        assert!(tokio::time::timeout(Duration::from_millis(10), cancel)
+            .await
+            .is_err());
+
+        let start = Instant::now();
+        let place_order = rate_limiter.acquire(EndpointClass::PlaceOrder);
+        assert!(tokio::time::timeout(Duration::from_secs(1), place_order)
+            .await
+            .is_ok());
+        assert!(start.elapsed() < Duration::from_millis(100));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn other_classes_wait_for_a_waiting_cancel() {
+        let rate_limiter = Arc::new(RateLimiter::new(params()));
+        rate_limiter.on_rate_limited(Duration::from_millis(100));
+        let admitted = Arc::new(Mutex::new(vec![]));
+
+        let cancel = {
+            let rate_limiter = rate_limiter.clone();
+            let admitted = admitted.clone();
+            tokio::spawn(async move {
+                rate_limiter.acquire(EndpointClass::CancelOrder).await;
+                admitted.lock().unwrap().push(EndpointClass::CancelOrder);
+            })
+        };
+        tokio::time::sleep(Duration::from_millis(1)).await;
+        let place_order = {
+            let rate_limiter = rate_limiter.clone();
+            let admitted = admitted.clone();
+            tokio::spawn(async move {
+                rate_limiter.acquire(EndpointClass::PlaceOrder).await;
+                admitted.lock().unwrap().push(EndpointClass::PlaceOrder);
+            })
+        };
+        cancel.await.unwrap();
+        place_order.await.unwrap();
+        assert_eq!(
+            *admitted.lock().unwrap(),
+            vec![EndpointClass::CancelOrder, EndpointClass::PlaceOrder]
+        );
+    }
+}
@@ -0,0 +1,126 @@
+#[cfg(test)]
+mod shutdown_test {
+    use rust_quant::cache::OrderUpdateCache;
+    use rust_quant::core::clock::ManualClock;
+    use rust_quant::core::in_flight::InFlight;
+    use rust_quant::lambda::{
+        working_orders, GenericLambdaInstanceConfig, LambdaEngine, ShutdownReport,
+    };
+    use rust_quant::model::constants::PublishChannel;
+    use rust_quant::model::{CancelOrderRequest, OrderStatus, OrderUpdate};
+    use rust_quant::pubsub::local_message_bus::LocalMessageBus;
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    fn order_update(client_id: &str, status: OrderStatus) -> OrderUpdate {
+        OrderUpdate {
+            client_id: Some(client_id.to_string()),
+            market: "ETH-PERP".to_string(),
+            status,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn working_orders_exclude_closed_orders() {
+        let order_update_cache = OrderUpdateCache::new();
+        for (client_id, status) in [
+            ("a", OrderStatus::PendingNew),
+            ("b", OrderStatus::Open),
+            ("c", OrderStatus::PendingCancel),
+            ("d", OrderStatus::Closed),
+        ] {
+            order_update_cache
+                .cache
+                .insert(client_id.to_string(), order_update(client_id, status));
+        }
+        let mut client_ids: Vec<String> = working_orders(&order_update_cache)
+            .iter()
+            .map(|order| order.cache_key())
+            .collect();
+        client_ids.sort();
+        assert_eq!(client_ids, vec!["a", "b", "c"]);
+    }
+
+    #[test]
+    fn report_summarizes_what_is_left() {
+        let mut report = ShutdownReport {
+            signal: "SIGTERM".to_string(),
+            stopped: vec!["swap-mm-sim".to_string()],
+            cancelled: vec!["a".to_string()],
+            ..Default::default()
+        };
+        assert!(report.is_clean());
+        assert_eq!(
+            report.summary(),
+            "shutdown on SIGTERM: stopped [\"swap-mm-sim\"], cancelled 1 orders"
+        );
+
+        report.open_orders.push("FTX ETH-PERP b".to_string());
+        report.unflushed_values = 2;
+        assert!(!report.is_clean());
+        assert!(report
+            .summary()
+            .ends_with(", orders left open [\"FTX ETH-PERP b\"], 2 value writes lost"));
+    }
+
+    #[tokio::test]
+    async fn in_flight_wait() {
+        let in_flight = InFlight::new();
+        let guard = in_flight.start();
+        assert_eq!(in_flight.wait(Duration::from_millis(20)).await, 1);
+
+        let writer = tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            drop(guard);
+        });
+        assert_eq!(in_flight.wait(Duration::from_millis(1000)).await, 0);
+        writer.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn shutdown_cancels_only_the_orders_of_its_lambdas() {
+        let instance_config = GenericLambdaInstanceConfig::read("swap-mm-sim").unwrap();
+        let clock = Arc::new(ManualClock::new(0));
+        let (engine, mut receiver) =
+            LambdaEngine::in_memory(vec![instance_config], LocalMessageBus::new(), clock.clone());
+        engine.start_lambda("swap-mm-sim").await.unwrap();
+        let owned = "swap-mm-sim:SIM:ETH-PERP:Buy:1";
+        let restored = "swap-mm-other:SIM:ETH-PERP:Buy:2";
+        let order_update_cache = engine.order_update_cache();
+        for client_id in [owned, restored] {
+            order_update_cache.cache.insert(
+                client_id.to_string(),
+                order_update(client_id, OrderStatus::Open),
+            );
+        }
+
+        let shutdown = engine.shutdown("SIGTERM");
+        tokio::pin!(shutdown);
+        let mut cancelled = vec![];
+        let report = loop {
+            tokio::select! {
+                biased;
+                report = &mut shutdown => break report,
+                Some(payload) = receiver.recv() => {
+                    if payload.channel == PublishChannel::CancelOrder.as_ref() {
+                        let request: CancelOrderRequest =
+                            serde_json::from_str(payload.payload.as_str()).unwrap();
+                        // the gateway confirms the cancel
+                        order_update_cache.cache.remove(&request.client_id);
+                        cancelled.push(request.client_id);
+                    }
+                }
+                _ = tokio::task::yield_now() => clock.advance(Duration::from_millis(50)),
+            }
+        };
+
+        assert!(cancelled.contains(&owned.to_string()));
+        assert!(!cancelled.contains(&restored.to_string()));
+        assert!(report.is_clean(), "{}", report.summary());
+        assert_eq!(report.stopped, vec!["swap-mm-sim"]);
+        assert_eq!(report.cancelled, vec![owned]);
+        assert!(order_update_cache.cache.contains_key(restored));
+        assert!(engine.running().is_empty());
+    }
+}
@@ -0,0 +1,177 @@
+#[cfg(test)]
+mod sim_matching_engine_test {
+    use rust_quant::model::constants::Exchanges;
+    use rust_quant::model::market_data_model::{MarketDepth, PriceLevel};
+    use rust_quant::model::{CancelOrderRequest, OrderRequest, OrderSide, OrderStatus, OrderType};
+    use rust_quant::sim::{MatchingEngine, SimEvent, SimParams};
+
+    fn market_depth(bids: Vec<(f64, f64)>, asks: Vec<(f64, f64)>) -> MarketDepth {
+        let levels = |levels: Vec<(f64, f64)>| {
+            levels
+                .into_iter()
+                .map(|(price, size)| PriceLevel { price, size })
+                .collect()
+        };
+        MarketDepth {
+            timestamp: 0,
+            exchange: Exchanges::SIM,
+            market: "ETH-PERP".to_string(),
+            bids: levels(bids),
+            asks: levels(asks),
+        }
+    }
+
+    fn order_request(
+        client_id: &str,
+        side: OrderSide,
+        price: f64,
+        size: f64,
+        type_: OrderType,
+        post_only: bool,
+    ) -> OrderRequest {
+        OrderRequest {
+            exchange: Exchanges::SIM,
+            market: "ETH-PERP".to_string(),
+            side,
+            price,
+            size,
+            type_,
+            ioc: false,
+            post_only,
+            client_id: Some(client_id.to_string()),
+        }
+    }
+
+    fn fills(events: &[SimEvent]) -> Vec<(f64, f64, String)> {
+        events
+            .iter()
+            .filter_map(|event| match event {
+                SimEvent::OrderFill(fill) => Some((fill.price, fill.size, fill.liquidity.clone())),
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn last_status(events: &[SimEvent]) -> Option<OrderStatus> {
+        events.iter().rev().find_map(|event| match event {
+            SimEvent::OrderUpdate(order_update) => Some(order_update.status.clone()),
+            _ => None,
+        })
+    }
+
+    fn engine() -> MatchingEngine {
+        let mut engine = MatchingEngine::new(&SimParams::default());
+        engine.on_market_depth(
+            market_depth(vec![(99.0, 1.0), (98.0, 2.0)], vec![(101.0, 1.0), (102.0, 2.0)]),
+            0,
+        );
+        engine
+    }
+
+    #[test]
+    fn fails_without_market_depth() {
+        let mut engine = MatchingEngine::new(&SimParams::default());
+        let events = engine.place_order(
+            &order_request("1", OrderSide::Buy, 100.0, 1.0, OrderType::Limit, true),
+            0,
+        );
+        assert_eq!(last_status(&events), Some(OrderStatus::Failed));
+    }
+
+    #[test]
+    fn market_order_walks_the_book() {
+        let mut engine = engine();
+        let events = engine.place_order(
+            &order_request("1", OrderSide::Buy, 0.0, 2.0, OrderType::Market, false),
+            0,
+        );
+        assert_eq!(
+            fills(&events),
+            vec![
+                (101.0, 1.0, "taker".to_string()),
+                (102.0, 1.0, "taker".to_string())
+            ]
+        );
+        // fills carry the client id their owner is told apart by
+        assert!(events.iter().all(|event| match event {
+            SimEvent::OrderFill(fill) => fill.client_id == Some("1".to_string()),
+            _ => true,
+        }));
+        assert_eq!(last_status(&events), Some(OrderStatus::Closed));
+    }
+
+    #[test]
+    fn post_only_rejected_when_crossing() {
+        let mut engine = engine();
+        let events = engine.place_order(
+            &order_request("1", OrderSide::Buy, 101.0, 1.0, OrderType::Limit, true),
+            0,
+        );
+        assert_eq!(last_status(&events), Some(OrderStatus::Failed));
+        assert!(engine.get_open_orders().is_empty());
+    }
+
+    #[test]
+    fn resting_order_fills_after_queue_ahead() {
+        let mut engine = engine();
+        let events = engine.place_order(
+            &order_request("1", OrderSide::Buy, 99.0, 1.0, OrderType::Limit, true),
+            0,
+        );
+        assert_eq!(last_status(&events), Some(OrderStatus::Open));
+        assert_eq!(engine.get_queue_ahead("1"), Some(1.0));
+
+        // 0.5 traded ahead of us
+        let events = engine.on_market_depth(
+            market_depth(vec![(99.0, 0.5), (98.0, 2.0)], vec![(101.0, 1.0)]),
+            1,
+        );
+        assert!(fills(&events).is_empty());
+        assert_eq!(engine.get_queue_ahead("1"), Some(0.5));
+
+        // the rest of the queue is gone, we are first in line
+        let events = engine.on_market_depth(
+            market_depth(vec![(98.0, 2.0)], vec![(101.0, 1.0)]),
+            2,
+        );
+        assert!(fills(&events).is_empty());
+        assert_eq!(engine.get_queue_ahead("1"), Some(0.0));
+
+        // size joins behind us and 0.5 trades at our level
+        engine.on_market_depth(
+            market_depth(vec![(99.0, 1.0), (98.0, 2.0)], vec![(101.0, 1.0)]),
+            3,
+        );
+        let events = engine.on_market_depth(
+            market_depth(vec![(99.0, 0.5), (98.0, 2.0)], vec![(101.0, 1.0)]),
+            4,
+        );
+        assert_eq!(fills(&events), vec![(99.0, 0.5, "maker".to_string())]);
+        assert_eq!(last_status(&events), Some(OrderStatus::Open));
+
+        // asks trade through our price
+        let events = engine.on_market_depth(
+            market_depth(vec![(98.0, 2.0)], vec![(99.0, 1.0)]),
+            5,
+        );
+        assert_eq!(fills(&events), vec![(99.0, 0.5, "maker".to_string())]);
+        assert_eq!(last_status(&events), Some(OrderStatus::Closed));
+        assert!(engine.get_open_orders().is_empty());
+    }
+
+    #[test]
+    fn cancel_closes_resting_order() {
+        let mut engine = engine();
+        engine.place_order(
+            &order_request("1", OrderSide::Sell, 103.0, 1.0, OrderType::Limit, true),
+            0,
+        );
+        let events = engine.cancel_order(&CancelOrderRequest {
+            exchange: Exchanges::SIM,
+            market: "ETH-PERP".to_string(),
+            client_id: "1".to_string(),
+        });
+        assert_eq!(last_status(&events), Some(OrderStatus::Closed));
+        assert!(engine.get_open_orders().is_empty());
+    }
+}
@@ -7,6 +7,7 @@ use mockall::{automock, mock, predicate::*};
 mod lambda_test {
     use super::*;
     use rust_quant::cache::{MarketDepthCache, OrderUpdateCache, ValueCache};
+    use rust_quant::core::clock::{ManualClock, SharedClock};
     use rust_quant::lambda::{GenericLambdaInstanceConfig, LambdaState};
     use rust_quant::model::{InstrumentSymbol, MeasurementCache, Instrument};
     use rust_quant::pubsub::simple_message_bus::RedisBackedMessageBus;
@@ -17,6 +18,7 @@ mod lambda_test {
     use rust_quant::model::market_data_model::{MarketDepth, PriceLevel};
     use rust_quant::model::constants::Exchanges;
     use rust_quant::lambda::strategy::swap_mm::lambda::Lambda;
+    use rust_quant::lambda::strategy::{Strategy, StrategyContext};
 
     #[tokio::test]
     pub async fn it_init() {
@@ -31,37 +33,41 @@ mod lambda_test {
             .map(|token| InstrumentSymbol::from_str(token.as_str()).unwrap())
             .map(|symbol| SubscribeMarketDepthRequest::new(symbol.0, symbol.1.as_str()))
             .collect::<Vec<SubscribeMarketDepthRequest>>();
-        let market_depth_cache = Arc::new(MarketDepthCache::new());
+        let clock: SharedClock = Arc::new(ManualClock::new(1_000_000));
+        let market_depth_cache = Arc::new(MarketDepthCache::with_clock(clock.clone()));
         let order_update_cache = Arc::new(OrderUpdateCache::new());
         let message_bus = Arc::new(RedisBackedMessageBus::new().await.unwrap());
-        let measurement_cache = Arc::new(MeasurementCache::new().await);
+        let measurement_cache = Arc::new(MeasurementCache::with_clock(clock.clone()).await);
         let value_cache = Arc::new(ValueCache::new(instance_config.clone()).await);
-        let lambda = Arc::new(Lambda::new(
+        let context = StrategyContext {
             instance_config,
-            market_depth_cache.clone(),
-            order_update_cache.clone(),
-            message_bus.publish_tx.clone(),
-            measurement_cache.clone(),
-            value_cache.clone(),
-        ));
+            market_depth_cache: market_depth_cache.clone(),
+            order_update_cache: order_update_cache.clone(),
+            message_bus_sender: message_bus.publish_tx.clone(),
+            measurement_cache: measurement_cache.clone(),
+            value_cache: value_cache.clone(),
+            ftx_client: None,
+            clock: clock.clone(),
+        };
+        let lambda = Arc::new(Lambda::new(&context).unwrap());
 
         spawn_thread_market_depth_cache(market_depth_cache.clone(), subscribe_md_requests);
         let handle = spawn_thread_lambda(lambda.clone());
 
-        sleep(1000).await;
-        
+        // let the spawned lambda subscribe, its clock does not move
+        tokio::task::yield_now().await;
+
         let md_0 = MarketDepth {
-            timestamp: chrono::Utc::now().timestamp_millis(),
+            timestamp: clock.now_ms(),
             exchange: Exchanges::SIM,
             market: "ETH-PERP".to_string(),
             bids: vec![PriceLevel { price: 99.0, size: 1.0 }],
             asks: vec![PriceLevel { price: 101.0, size: 1.0 }]
         };
-        market_depth_cache.cache.insert("ETH-PERP".to_string(), md_0);
-
-        sleep(20).await;
+        market_depth_cache.cache.insert("ETH-PERP".to_string(), md_0.clone());
+        lambda.on_market_depth(&md_0).await.unwrap();
 
-        assert!(matches!(lambda.get_strategy_params().state, LambdaState::Init));
+        assert!(matches!(lambda.get_strategy_params().unwrap().state, LambdaState::Init));
         assert_eq!(lambda.get_strategy_state().depth_bid_px, Some(99.0));
 
         drop(handle);
@@ -0,0 +1,23 @@
+#[cfg(test)]
+mod strategy_registry_test {
+    use rust_quant::lambda::strategy::{Strategy, StrategyContext, StrategyRegistry};
+    use std::sync::Arc;
+
+    fn noop(_context: &StrategyContext) -> anyhow::Result<Arc<dyn Strategy>> {
+        Err(anyhow::anyhow!("noop"))
+    }
+
+    #[test]
+    fn builtin_strategies_are_registered() {
+        assert!(StrategyRegistry::names().contains(&"SwapMM".to_string()));
+        assert!(StrategyRegistry::names().contains(&"LatencyMM".to_string()));
+        assert!(StrategyRegistry::names().contains(&"BasisArb".to_string()));
+        assert!(StrategyRegistry::names().contains(&"FundingCarry".to_string()));
+    }
+
+    #[test]
+    fn register_by_name() {
+        StrategyRegistry::register("Noop", noop);
+        assert!(StrategyRegistry::names().contains(&"Noop".to_string()));
+    }
+}
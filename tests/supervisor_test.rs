@@ -0,0 +1,155 @@
+#[cfg(test)]
+mod supervisor_test {
+    use futures_util::FutureExt;
+    use rust_quant::core::clock::WallClock;
+    use rust_quant::core::supervisor::{
+        Backoff, RestartPolicy, Supervisor, SupervisorEventKind, WorldPause,
+    };
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::{Arc, Mutex};
+    use tokio::sync::Notify;
+
+    fn backoff() -> Backoff {
+        Backoff {
+            initial_ms: 1,
+            max_ms: 4,
+            reset_after_ms: 60_000,
+        }
+    }
+
+    struct RecordingWorld(Mutex<Vec<String>>);
+
+    #[async_trait::async_trait]
+    impl WorldPause for RecordingWorld {
+        async fn pause_world(&self, component: &str) -> anyhow::Result<()> {
+            self.0.lock().unwrap().push(component.to_string());
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn backoff_doubles_up_to_max() {
+        let backoff = Backoff {
+            initial_ms: 100,
+            max_ms: 1000,
+            reset_after_ms: 60_000,
+        };
+        let delays: Vec<u64> = (1..=6).map(|attempt| backoff.delay_ms(attempt)).collect();
+        assert_eq!(delays, vec![100, 200, 400, 800, 1000, 1000]);
+        assert_eq!(backoff.delay_ms(1000), 1000);
+    }
+
+    #[tokio::test]
+    async fn restarts_failed_and_panicked_components_until_escalation() {
+        let starts = Arc::new(AtomicUsize::new(0));
+        let recovered = Arc::new(Notify::new());
+        let mut supervisor = Supervisor::new(WallClock::shared(), None);
+        {
+            let starts = starts.clone();
+            let recovered = recovered.clone();
+            supervisor.supervise("flaky", RestartPolicy::Restart(backoff()), move || {
+                let start = starts.fetch_add(1, Ordering::SeqCst);
+                let recovered = recovered.clone();
+                async move {
+                    match start {
+                        0 => Err(anyhow::anyhow!("disconnected")),
+                        1 => panic!("boom"),
+                        _ => {
+                            recovered.notify_one();
+                            futures_util::future::pending().await
+                        }
+                    }
+                }
+                .boxed()
+            });
+        }
+        supervisor.supervise("fatal", RestartPolicy::Escalate, move || {
+            let recovered = recovered.clone();
+            async move {
+                recovered.notified().await;
+                Err(anyhow::anyhow!("gone"))
+            }
+            .boxed()
+        });
+
+        let result = supervisor.run().await;
+        assert_eq!(result.unwrap_err().to_string(), "fatal escalated: gone");
+        assert_eq!(starts.load(Ordering::SeqCst), 3);
+
+        let flaky: Vec<(SupervisorEventKind, u32)> = supervisor
+            .events()
+            .into_iter()
+            .filter(|event| event.component == "flaky")
+            .map(|event| (event.kind, event.attempt))
+            .collect();
+        assert_eq!(
+            flaky,
+            vec![
+                (SupervisorEventKind::Started, 0),
+                (SupervisorEventKind::Exited, 0),
+                (SupervisorEventKind::Restarting, 1),
+                (SupervisorEventKind::Started, 1),
+                (SupervisorEventKind::Exited, 1),
+                (SupervisorEventKind::Restarting, 2),
+                (SupervisorEventKind::Started, 2),
+            ]
+        );
+        let panicked = supervisor
+            .events()
+            .into_iter()
+            .find(|event| {
+                event.component == "flaky"
+                    && event.kind == SupervisorEventKind::Exited
+                    && event.attempt == 1
+            })
+            .unwrap();
+        assert_eq!(panicked.reason, Some("panicked: boom".to_string()));
+    }
+
+    #[tokio::test]
+    async fn pauses_the_world_before_restarting() {
+        let world = Arc::new(RecordingWorld(Mutex::new(vec![])));
+        let starts = Arc::new(AtomicUsize::new(0));
+        let mut supervisor = Supervisor::new(WallClock::shared(), None);
+        supervisor.set_world_pause(world.clone());
+        {
+            let starts = starts.clone();
+            supervisor.supervise(
+                "gateway",
+                RestartPolicy::StopTheWorld(backoff()),
+                move || {
+                    starts.fetch_add(1, Ordering::SeqCst);
+                    async { Err(anyhow::anyhow!("ws closed")) }.boxed()
+                },
+            );
+        }
+        supervisor.supervise("watcher", RestartPolicy::Escalate, || {
+            async {
+                tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+                Ok(())
+            }
+            .boxed()
+        });
+        assert!(supervisor.run().await.is_err());
+        let paused = world.0.lock().unwrap().clone();
+        assert!(paused.len() >= 2);
+        assert!(starts.load(Ordering::SeqCst) >= paused.len());
+        assert!(paused.iter().all(|component| component == "gateway"));
+        let kinds: Vec<SupervisorEventKind> = supervisor
+            .events()
+            .into_iter()
+            .filter(|event| event.component == "gateway")
+            .take(4)
+            .map(|event| event.kind)
+            .collect();
+        assert_eq!(
+            kinds,
+            vec![
+                SupervisorEventKind::Started,
+                SupervisorEventKind::Exited,
+                SupervisorEventKind::WorldPaused,
+                SupervisorEventKind::Restarting,
+            ]
+        );
+    }
+}
@@ -0,0 +1,70 @@
+#[cfg(test)]
+mod swap_mm_ladder_test {
+    use rust_quant::lambda::strategy::swap_mm::ladder::{inventory_skew_bp, ladder, LadderLevel};
+    use rust_quant::lambda::strategy::swap_mm::params::SwapMMStrategyParams;
+    use rust_quant::model::OrderSide;
+
+    fn params() -> SwapMMStrategyParams {
+        SwapMMStrategyParams {
+            base_size: 0.1,
+            levels: 3,
+            level_spacing_bp: 10.0,
+            level_sizes: vec![0.1, 0.2],
+            tick_size: Some(0.1),
+            max_position: Some(1.0),
+            inventory_skew_bp: 20.0,
+            ..Default::default()
+        }
+    }
+
+    fn prices(levels: &[LadderLevel]) -> Vec<f64> {
+        levels
+            .iter()
+            .map(|level| (level.price * 10.0).round() / 10.0)
+            .collect()
+    }
+
+    #[test]
+    fn single_level_by_default() {
+        let params = SwapMMStrategyParams {
+            base_size: 0.5,
+            levels: 1,
+            ..Default::default()
+        };
+        let levels = ladder(&params, &OrderSide::Buy, 990.0, 1000.0, 0.0);
+        assert_eq!(
+            levels,
+            vec![LadderLevel {
+                price: 990.0,
+                size: 0.5
+            }]
+        );
+    }
+
+    #[test]
+    fn levels_spread_away_from_the_touch() {
+        let bids = ladder(&params(), &OrderSide::Buy, 1000.0, 1005.0, 0.0);
+        assert_eq!(prices(&bids), vec![1000.0, 999.0, 998.0]);
+        let sizes: Vec<f64> = bids.iter().map(|level| level.size).collect();
+        assert_eq!(sizes, vec![0.1, 0.2, 0.2]);
+
+        let asks = ladder(&params(), &OrderSide::Sell, 1000.0, 995.0, 0.0);
+        assert_eq!(prices(&asks), vec![1000.0, 1001.0, 1002.0]);
+    }
+
+    #[test]
+    fn long_position_widens_bids_and_narrows_asks() {
+        assert_eq!(inventory_skew_bp(&params(), 0.5), 10.0);
+        let bids = ladder(&params(), &OrderSide::Buy, 1000.0, 1005.0, 0.5);
+        assert_eq!(prices(&bids), vec![999.0, 998.0, 997.0]);
+        // the narrowed ask joins the touch instead of crossing
+        let asks = ladder(&params(), &OrderSide::Sell, 1000.0, 999.5, 0.5);
+        assert_eq!(prices(&asks), vec![999.5, 1000.0, 1001.0]);
+    }
+
+    #[test]
+    fn side_growing_the_position_beyond_max_is_not_quoted() {
+        assert!(ladder(&params(), &OrderSide::Buy, 1000.0, 1005.0, 1.0).is_empty());
+        assert_eq!(ladder(&params(), &OrderSide::Sell, 1000.0, 995.0, 1.0).len(), 3);
+    }
+}
@@ -0,0 +1,95 @@
+#[cfg(test)]
+mod warm_restart_test {
+    use rust_quant::cache::{MarketDepthCache, OrderUpdateCache, ValueCache};
+    use rust_quant::core::clock::{ManualClock, SharedClock};
+    use rust_quant::ftx::{ApiResponse, FtxOrderData};
+    use rust_quant::lambda::strategy::{StrategyContext, StrategyRegistry};
+    use rust_quant::lambda::{working_orders, GenericLambdaInstanceConfig, RestoredOrders};
+    use rust_quant::model::constants::Exchanges;
+    use rust_quant::model::{MeasurementCache, OrderStatus};
+    use std::sync::Arc;
+
+    const OPEN_ORDERS: &str = r#"{"success": true, "result": [
+        {"id": 9596912, "clientId": "swap-mm-ethusd:FTX:ETH-PERP:Buy:1", "market": "ETH-PERP",
+         "type": "limit", "side": "buy", "size": 0.1, "price": 3000.0, "reduceOnly": false,
+         "ioc": false, "postOnly": true, "status": "open", "filledSize": 0.0,
+         "remainingSize": 0.1, "avgFillPrice": null,
+         "createdAt": "2021-10-19T07:51:00.000000+00:00"},
+        {"id": 9596913, "clientId": null, "market": "ETH-PERP", "type": "limit",
+         "side": "sell", "size": 0.2, "price": 3100.0, "reduceOnly": false, "ioc": false,
+         "postOnly": false, "status": "open", "filledSize": 0.05, "remainingSize": 0.15,
+         "avgFillPrice": 3100.0, "createdAt": "2021-10-19T07:52:00.000000+00:00"},
+        {"id": 9596914, "clientId": "swap-mm-ethusd:FTX:ETH/USD:Sell:2", "market": "ETH/USD",
+         "type": "limit", "side": "sell", "size": 0.1, "price": 2990.0, "reduceOnly": false,
+         "ioc": false, "postOnly": false, "status": "open", "filledSize": 0.0,
+         "remainingSize": 0.1, "avgFillPrice": null,
+         "createdAt": "2021-10-19T07:53:00.000000+00:00"},
+        {"id": 9596915, "clientId": "latency-mm:FTX:ETH-PERP:Sell:3", "market": "ETH-PERP",
+         "type": "limit", "side": "sell", "size": 0.1, "price": 3010.0, "reduceOnly": false,
+         "ioc": false, "postOnly": true, "status": "open", "filledSize": 0.0,
+         "remainingSize": 0.1, "avgFillPrice": null,
+         "createdAt": "2021-10-19T07:54:00.000000+00:00"}
+    ]}"#;
+
+    fn open_orders() -> Vec<FtxOrderData> {
+        let response = serde_json::from_str::<ApiResponse<Vec<FtxOrderData>>>(OPEN_ORDERS).unwrap();
+        assert!(response.success);
+        response.result.unwrap()
+    }
+
+    #[test]
+    fn open_orders_rebuild_order_update_cache() {
+        let order_update_cache = OrderUpdateCache::new();
+        let restored_orders = RestoredOrders::new();
+        assert_eq!(
+            restored_orders.restore_open_orders(&order_update_cache, &open_orders()),
+            3
+        );
+
+        let restored = working_orders(&order_update_cache);
+        assert_eq!(restored.len(), 3);
+        let order = order_update_cache
+            .cache
+            .get("swap-mm-ethusd:FTX:ETH-PERP:Buy:1")
+            .unwrap();
+        assert_eq!(order.exchange, Exchanges::FTX);
+        assert_eq!(order.status, OrderStatus::Open);
+        assert_eq!(order.remainingSize, 0.1);
+    }
+
+    #[tokio::test]
+    async fn lambda_adopts_its_quotes_and_leaves_other_orders() {
+        let order_update_cache = Arc::new(OrderUpdateCache::new());
+        let restored_orders = RestoredOrders::new();
+        restored_orders.restore_open_orders(&order_update_cache, &open_orders());
+
+        let instance_config = GenericLambdaInstanceConfig::read("swap-mm-ethusd").unwrap();
+        let clock: SharedClock = Arc::new(ManualClock::new(0));
+        let (message_bus_sender, _receiver) = tokio::sync::mpsc::channel(100);
+        let context = StrategyContext {
+            instance_config: instance_config.clone(),
+            market_depth_cache: Arc::new(MarketDepthCache::with_clock(clock.clone())),
+            order_update_cache: order_update_cache.clone(),
+            message_bus_sender,
+            measurement_cache: Arc::new(MeasurementCache::in_memory(clock.clone())),
+            value_cache: Arc::new(ValueCache::in_memory(instance_config.clone())),
+            ftx_client: None,
+            clock,
+        };
+        let strategy =
+            StrategyRegistry::create(instance_config.registry.as_str(), &context).unwrap();
+
+        // the hedge is not adopted, the quote of another lambda is not offered
+        let unadopted = restored_orders
+            .restore_lambda_orders(&instance_config, &strategy)
+            .await;
+        let unadopted: Vec<String> = unadopted.iter().map(|order| order.cache_key()).collect();
+        assert_eq!(unadopted, vec!["swap-mm-ethusd:FTX:ETH/USD:Sell:2"]);
+        let unclaimed: Vec<String> = restored_orders
+            .unclaimed()
+            .iter()
+            .map(|order| order.cache_key())
+            .collect();
+        assert_eq!(unclaimed, vec!["latency-mm:FTX:ETH-PERP:Sell:3"]);
+    }
+}
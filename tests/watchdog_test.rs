@@ -0,0 +1,109 @@
+#[cfg(test)]
+mod watchdog_test {
+    use rust_quant::cache::{MarketDepthCache, ValueCache, ValueCacheKey};
+    use rust_quant::core::clock::{ManualClock, SharedClock};
+    use rust_quant::lambda::{GenericLambdaInstanceConfig, LambdaState, Watchdog, WatchdogParams};
+    use rust_quant::model::global_measurement::ORDER_LATENCY;
+    use rust_quant::model::MeasurementCache;
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    struct Fixture {
+        clock: Arc<ManualClock>,
+        measurement_cache: Arc<MeasurementCache>,
+        value_cache: Arc<ValueCache>,
+        watchdog: Watchdog,
+    }
+
+    fn fixture(auto_resume: bool) -> Fixture {
+        let clock = Arc::new(ManualClock::new(1_000_000));
+        let shared_clock: SharedClock = clock.clone();
+        let measurement_cache = Arc::new(MeasurementCache::in_memory(shared_clock.clone()));
+        let value_cache = Arc::new(ValueCache::in_memory(GenericLambdaInstanceConfig::default()));
+        value_cache.insert(
+            ValueCacheKey::StrategyParams,
+            serde_json::json!({ "state": LambdaState::Live.to_string() }),
+        );
+        let params = WatchdogParams {
+            enabled: true,
+            stale_market_depth: false,
+            max_order_latency_ms: Some(1000.0),
+            order_latency_window_ms: 5000,
+            auto_resume,
+            resume_after_ms: 30000,
+            ..Default::default()
+        };
+        let watchdog = Watchdog::new(
+            params,
+            vec![],
+            Arc::new(MarketDepthCache::with_clock(shared_clock.clone())),
+            measurement_cache.clone(),
+            value_cache.clone(),
+            vec![],
+            shared_clock,
+        );
+        Fixture {
+            clock,
+            measurement_cache,
+            value_cache,
+            watchdog,
+        }
+    }
+
+    fn state(value_cache: &ValueCache) -> String {
+        value_cache
+            .get_clone(ValueCacheKey::StrategyParams)
+            .unwrap()["state"]
+            .as_str()
+            .unwrap()
+            .to_string()
+    }
+
+    #[tokio::test]
+    async fn slow_orders_pause_until_the_latency_ages_out() {
+        let fixture = fixture(true);
+        fixture
+            .measurement_cache
+            .add_point_now(&ORDER_LATENCY, 200.0);
+        fixture.watchdog.check().await;
+        assert!(fixture.watchdog.triggers().is_empty());
+        assert_eq!(state(&fixture.value_cache), "Live");
+
+        fixture
+            .measurement_cache
+            .add_point_now(&ORDER_LATENCY, 1500.0);
+        assert_eq!(fixture.watchdog.triggers().len(), 1);
+        fixture.watchdog.check().await;
+        assert_eq!(state(&fixture.value_cache), "AutoPaused");
+
+        // no order since, the old latency no longer counts
+        fixture.clock.advance(Duration::from_millis(5001));
+        assert!(fixture.watchdog.triggers().is_empty());
+        fixture.watchdog.check().await;
+        assert_eq!(state(&fixture.value_cache), "AutoPaused");
+
+        fixture.clock.advance(Duration::from_millis(25000));
+        fixture.watchdog.check().await;
+        assert_eq!(state(&fixture.value_cache), "Live");
+    }
+
+    #[tokio::test]
+    async fn without_auto_resume_the_pause_waits_for_an_operator() {
+        let fixture = fixture(false);
+        fixture
+            .measurement_cache
+            .add_point_now(&ORDER_LATENCY, 1500.0);
+        fixture.watchdog.check().await;
+        assert_eq!(state(&fixture.value_cache), "AutoPaused");
+
+        fixture.clock.advance(Duration::from_millis(60000));
+        fixture.watchdog.check().await;
+        assert_eq!(state(&fixture.value_cache), "AutoPaused");
+
+        fixture
+            .value_cache
+            .transition_state(&[LambdaState::AutoPaused], LambdaState::Live);
+        fixture.watchdog.check().await;
+        assert_eq!(state(&fixture.value_cache), "Live");
+    }
+}
@@ -17,6 +17,18 @@ module.exports = {
       args: "ftx marketdepth ETH-PERP",
       env_production,
     },
+    {
+      name: "trades-ftx-btcperp",
+      script: "./target/release/market_data",
+      args: "ftx trades BTC-PERP",
+      env_production,
+    },
+    {
+      name: "trades-ftx-ethperp",
+      script: "./target/release/market_data",
+      args: "ftx trades ETH-PERP",
+      env_production,
+    },
     {
       name: "latency-mm",
       script: "./target/release/container",